@@ -2,6 +2,8 @@
 #![cfg(feature = "cluster")]
 use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
 use redis::cluster::cluster_pipe;
+use redis::cluster_routing::RoutingInfo;
+use redis::cluster_topology::get_slot;
 
 use support::*;
 
@@ -76,6 +78,45 @@ fn bench_pipeline(c: &mut Criterion, con: &mut redis::cluster::ClusterConnection
     group.finish();
 }
 
+// Hashes the keys of MGET commands, which doesn't need a running cluster. `bytewise_crc16` is the CRC16 that the
+// slots were computed with before the slice-by-8 tables, for comparison.
+fn bench_slot(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cluster_slot");
+
+    for key_len in [8, 32, 128] {
+        let keys: Vec<Vec<u8>> = (0..PIPELINE_QUERIES)
+            .map(|i| format!("{i:0>key_len$}").into_bytes())
+            .collect();
+        let bytes = keys.iter().map(|key| key.len()).sum::<usize>();
+        group.throughput(Throughput::Bytes(bytes as u64));
+
+        group.bench_function(format!("bytewise_crc16/{key_len}"), |b| {
+            b.iter(|| {
+                for key in &keys {
+                    black_box(crc16::State::<crc16::XMODEM>::calculate(key) % 16384);
+                }
+            })
+        });
+        group.bench_function(format!("get_slot/{key_len}"), |b| {
+            b.iter(|| {
+                for key in &keys {
+                    black_box(get_slot(key));
+                }
+            })
+        });
+
+        let mut mget = redis::cmd("MGET");
+        for key in &keys {
+            mget.arg(key);
+        }
+        group.bench_function(format!("route_mget/{key_len}"), |b| {
+            b.iter(|| black_box(RoutingInfo::for_routable(&mget)))
+        });
+    }
+
+    group.finish();
+}
+
 fn bench_cluster_setup(c: &mut Criterion) {
     let cluster = TestClusterContext::new(6, 1);
     cluster.wait_for_cluster_up();
@@ -102,6 +143,7 @@ fn bench_cluster_read_from_replicas_setup(c: &mut Criterion) {
 
 criterion_group!(
     cluster_bench,
+    bench_slot,
     bench_cluster_setup,
     // bench_cluster_read_from_replicas_setup
 );
@@ -16,7 +16,7 @@ macro_rules! not_convertible_error {
 
 /// ACL rules are used in order to activate or remove a flag, or to perform a
 /// given change to the user ACL, which under the hood are just single words.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Rule {
     /// Enable the user: it is possible to authenticate as this user.
     On,
@@ -106,6 +106,134 @@ impl ToRedisArgs for Rule {
     }
 }
 
+/// A builder for the list of [`Rule`]s that is passed to `ACL SETUSER`.
+///
+/// # Example
+/// ```rust
+/// use redis::acl::AclRules;
+///
+/// let rules = AclRules::new()
+///     .reset()
+///     .on()
+///     .add_pass("secret")
+///     .pattern("cache:*")
+///     .add_category("read");
+/// assert_eq!(rules.rules().len(), 5);
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct AclRules {
+    rules: Vec<Rule>,
+}
+
+impl AclRules {
+    /// Creates an empty list of rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends the given rule.
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Appends [`Rule::On`].
+    pub fn on(self) -> Self {
+        self.rule(Rule::On)
+    }
+
+    /// Appends [`Rule::Off`].
+    pub fn off(self) -> Self {
+        self.rule(Rule::Off)
+    }
+
+    /// Appends [`Rule::AddCommand`].
+    pub fn add_command(self, cmd: impl Into<String>) -> Self {
+        self.rule(Rule::AddCommand(cmd.into()))
+    }
+
+    /// Appends [`Rule::RemoveCommand`].
+    pub fn remove_command(self, cmd: impl Into<String>) -> Self {
+        self.rule(Rule::RemoveCommand(cmd.into()))
+    }
+
+    /// Appends [`Rule::AddCategory`].
+    pub fn add_category(self, category: impl Into<String>) -> Self {
+        self.rule(Rule::AddCategory(category.into()))
+    }
+
+    /// Appends [`Rule::RemoveCategory`].
+    pub fn remove_category(self, category: impl Into<String>) -> Self {
+        self.rule(Rule::RemoveCategory(category.into()))
+    }
+
+    /// Appends [`Rule::AllCommands`].
+    pub fn all_commands(self) -> Self {
+        self.rule(Rule::AllCommands)
+    }
+
+    /// Appends [`Rule::NoCommands`].
+    pub fn no_commands(self) -> Self {
+        self.rule(Rule::NoCommands)
+    }
+
+    /// Appends [`Rule::AddPass`].
+    pub fn add_pass(self, pass: impl Into<String>) -> Self {
+        self.rule(Rule::AddPass(pass.into()))
+    }
+
+    /// Appends [`Rule::RemovePass`].
+    pub fn remove_pass(self, pass: impl Into<String>) -> Self {
+        self.rule(Rule::RemovePass(pass.into()))
+    }
+
+    /// Appends [`Rule::NoPass`].
+    pub fn no_pass(self) -> Self {
+        self.rule(Rule::NoPass)
+    }
+
+    /// Appends [`Rule::ResetPass`].
+    pub fn reset_pass(self) -> Self {
+        self.rule(Rule::ResetPass)
+    }
+
+    /// Appends [`Rule::Pattern`].
+    pub fn pattern(self, pattern: impl Into<String>) -> Self {
+        self.rule(Rule::Pattern(pattern.into()))
+    }
+
+    /// Appends [`Rule::AllKeys`].
+    pub fn all_keys(self) -> Self {
+        self.rule(Rule::AllKeys)
+    }
+
+    /// Appends [`Rule::ResetKeys`].
+    pub fn reset_keys(self) -> Self {
+        self.rule(Rule::ResetKeys)
+    }
+
+    /// Appends [`Rule::Reset`].
+    pub fn reset(self) -> Self {
+        self.rule(Rule::Reset)
+    }
+
+    /// Returns the rules, in the order in which they were appended.
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+}
+
+impl ToRedisArgs for AclRules {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        for rule in &self.rules {
+            rule.write_redis_args(out);
+        }
+    }
+}
+
 /// An info dictionary type storing Redis ACL information as multiple `Rule`.
 /// This type collects key/value data returned by the [`ACL GETUSER`][1] command.
 ///
@@ -279,6 +407,29 @@ mod tests {
         assert_args!(Other("resetchannels".to_owned()), b"resetchannels");
     }
 
+    #[test]
+    fn test_rules_builder_to_args() {
+        let rules = AclRules::new()
+            .reset()
+            .on()
+            .add_pass("mypass")
+            .pattern("pat:*")
+            .add_category("read")
+            .remove_command("keys");
+        assert_eq!(
+            rules.to_redis_args(),
+            vec![
+                b"reset".to_vec(),
+                b"on".to_vec(),
+                b">mypass".to_vec(),
+                b"~pat:*".to_vec(),
+                b"+@read".to_vec(),
+                b"-keys".to_vec(),
+            ]
+        );
+        assert_eq!(rules.rules()[1], Rule::On);
+    }
+
     #[test]
     fn test_from_redis_value() {
         let redis_value = Value::Array(vec![
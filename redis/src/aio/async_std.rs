@@ -79,6 +79,18 @@ where
         async_std::io::Write::poll_write(self.project().inner, cx, buf)
     }
 
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut core::task::Context,
+        bufs: &[std::io::IoSlice<'_>],
+    ) -> std::task::Poll<Result<usize, tokio::io::Error>> {
+        async_std::io::Write::poll_write_vectored(self.project().inner, cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+
     fn poll_flush(
         self: Pin<&mut Self>,
         cx: &mut core::task::Context,
@@ -146,6 +158,36 @@ impl AsyncWrite for AsyncStd {
         }
     }
 
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match &mut *self {
+            AsyncStd::Tcp(r) => Pin::new(r).poll_write_vectored(cx, bufs),
+            #[cfg(any(
+                feature = "async-std-native-tls-comp",
+                feature = "async-std-rustls-comp"
+            ))]
+            AsyncStd::TcpTls(r) => Pin::new(r).poll_write_vectored(cx, bufs),
+            #[cfg(unix)]
+            AsyncStd::Unix(r) => Pin::new(r).poll_write_vectored(cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            AsyncStd::Tcp(r) => r.is_write_vectored(),
+            #[cfg(any(
+                feature = "async-std-native-tls-comp",
+                feature = "async-std-rustls-comp"
+            ))]
+            AsyncStd::TcpTls(r) => r.is_write_vectored(),
+            #[cfg(unix)]
+            AsyncStd::Unix(r) => r.is_write_vectored(),
+        }
+    }
+
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<io::Result<()>> {
         match &mut *self {
             AsyncStd::Tcp(r) => Pin::new(r).poll_flush(cx),
@@ -4,7 +4,7 @@
 use super::async_std;
 use super::ConnectionLike;
 use super::{setup_connection, AsyncStream, RedisRuntime};
-use crate::cmd::{cmd, Cmd};
+use crate::cmd::{cmd, write_all_vectored_async, Cmd, CommandFrame};
 use crate::connection::{
     resp2_is_pub_sub_state_cleared, resp3_is_pub_sub_state_cleared, ConnectionAddr, ConnectionInfo,
     Msg, RedisConnectionInfo,
@@ -33,7 +33,6 @@ use tokio_util::codec::Decoder;
 #[deprecated(note = "aio::Connection is deprecated. Use aio::MultiplexedConnection instead.")]
 pub struct Connection<C = Pin<Box<dyn AsyncStream + Send + Sync>>> {
     con: C,
-    buf: Vec<u8>,
     decoder: combine::stream::Decoder<AnySendSyncPartialState, PointerOffset<[u8]>>,
     db: i64,
 
@@ -58,7 +57,6 @@ impl<C> Connection<C> {
     pub(crate) fn map<D>(self, f: impl FnOnce(C) -> D) -> Connection<D> {
         let Self {
             con,
-            buf,
             decoder,
             db,
             pubsub,
@@ -66,7 +64,6 @@ impl<C> Connection<C> {
         } = self;
         Connection {
             con: f(con),
-            buf,
             decoder,
             db,
             pubsub,
@@ -84,7 +81,6 @@ where
     pub async fn new(connection_info: &RedisConnectionInfo, con: C) -> RedisResult<Self> {
         let mut rv = Connection {
             con,
-            buf: Vec::new(),
             decoder: combine::stream::Decoder::new(),
             db: connection_info.db,
             pubsub: false,
@@ -226,9 +222,8 @@ where
             if self.pubsub {
                 self.exit_pubsub().await?;
             }
-            self.buf.clear();
-            cmd.write_packed_command(&mut self.buf);
-            self.con.write_all(&self.buf).await?;
+            let frame = CommandFrame::new(cmd);
+            write_all_vectored_async(&mut self.con, &frame.slices()).await?;
             if cmd.is_no_response() {
                 return Ok(Value::Nil);
             }
@@ -249,13 +244,15 @@ where
         count: usize,
     ) -> RedisFuture<'a, Vec<Value>> {
         (async move {
+            if cmd.is_empty() {
+                return Ok(vec![]);
+            }
             if self.pubsub {
                 self.exit_pubsub().await?;
             }
 
-            self.buf.clear();
-            cmd.write_packed_pipeline(&mut self.buf);
-            self.con.write_all(&self.buf).await?;
+            let frame = cmd.frame();
+            write_all_vectored_async(&mut self.con, &frame.slices()).await?;
 
             let mut first_err = None;
 
@@ -445,10 +442,16 @@ pub(crate) async fn get_socket_addrs(
 
 pub(crate) async fn connect_simple<T: RedisRuntime>(
     connection_info: &ConnectionInfo,
-    _socket_addr: Option<SocketAddr>,
+    socket_addr: Option<SocketAddr>,
 ) -> RedisResult<(T, Option<IpAddr>)> {
     Ok(match connection_info.addr {
         ConnectionAddr::Tcp(ref host, port) => {
+            if let Some(socket_addr) = socket_addr {
+                return Ok::<_, RedisError>((
+                    <T>::connect_tcp(socket_addr).await?,
+                    Some(socket_addr.ip()),
+                ));
+            }
             let socket_addrs = get_socket_addrs(host, port).await?;
             select_ok(socket_addrs.map(|socket_addr| {
                 Box::pin(async move {
@@ -469,7 +472,7 @@ pub(crate) async fn connect_simple<T: RedisRuntime>(
             insecure,
             ref tls_params,
         } => {
-            if let Some(socket_addr) = _socket_addr {
+            if let Some(socket_addr) = socket_addr {
                 return Ok::<_, RedisError>((
                     <T>::connect_tcp_tls(host, socket_addr, insecure, tls_params).await?,
                     Some(socket_addr.ip()),
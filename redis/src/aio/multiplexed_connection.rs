@@ -2,17 +2,19 @@ use super::{ConnectionLike, Runtime};
 use crate::aio::setup_connection;
 use crate::cmd::Cmd;
 #[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+use crate::cmd::{BORROWED_ARG_MIN_LEN, MAX_SLICES_PER_WRITE};
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
 use crate::parser::ValueCodec;
 use crate::push_manager::PushManager;
-use crate::types::{RedisError, RedisFuture, RedisResult, Value};
-use crate::{cmd, ConnectionInfo, ProtocolVersion, PushInfo, PushKind};
+use crate::types::{ErrorKind, RedisError, RedisFuture, RedisResult, Value};
+use crate::{cmd, ConnectionInfo, PipelineChunking, ProtocolVersion, PushInfo, PushKind};
 use ::tokio::{
     io::{AsyncRead, AsyncWrite},
-    sync::{mpsc, oneshot},
+    sync::{mpsc, oneshot, OwnedSemaphorePermit, Semaphore},
 };
-use arc_swap::ArcSwap;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use futures_util::{
-    future::{Future, FutureExt},
+    future::{BoxFuture, Future, FutureExt},
     ready,
     sink::Sink,
     stream::{self, Stream, StreamExt, TryStreamExt as _},
@@ -23,11 +25,12 @@ use std::fmt;
 use std::fmt::Debug;
 use std::io;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::task::{self, Poll};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 #[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
-use tokio_util::codec::Decoder;
+use tokio_util::codec::{Decoder, Framed};
 
 // Senders which the result of a single request are sent through
 type PipelineOutput = oneshot::Sender<RedisResult<Value>>;
@@ -59,6 +62,8 @@ impl ResponseAggregate {
 struct InFlight {
     output: PipelineOutput,
     response_aggregate: ResponseAggregate,
+    is_subscription: bool,
+    _outstanding_bytes: OutstandingBytesGuard,
 }
 
 // A single message sent through the pipeline
@@ -67,6 +72,89 @@ struct PipelineMessage<S> {
     output: PipelineOutput,
     // If `None`, this is a single request, not a pipeline of multiple requests.
     pipeline_response_count: Option<usize>,
+    // Whether the request contains subscription commands, which are replied with pushes.
+    is_subscription: bool,
+    outstanding_bytes: OutstandingBytesGuard,
+}
+
+/// What a [`MultiplexedConnection`] does with a request that would take its outstanding bytes above the limit that
+/// was set with [`MultiplexedConnection::set_max_outstanding_bytes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutstandingBytesPolicy {
+    /// Fail the request with a [`ClientError`](ErrorKind::ClientError).
+    Fail,
+    /// Wait until enough of the outstanding requests are replied, at most for the response timeout.
+    Wait,
+}
+
+struct OutstandingBytesLimit {
+    semaphore: Arc<Semaphore>,
+    max_bytes: u32,
+    policy: OutstandingBytesPolicy,
+}
+
+// The bytes of the encoded requests that are queued or in flight, shared by the clones of a connection.
+#[derive(Default)]
+struct OutstandingBytes {
+    bytes: AtomicUsize,
+    limit: ArcSwapOption<OutstandingBytesLimit>,
+}
+
+impl OutstandingBytes {
+    async fn reserve(self: &Arc<Self>, bytes: usize) -> RedisResult<OutstandingBytesGuard> {
+        let permit = match self.limit.load_full() {
+            None => None,
+            Some(limit) => {
+                let permits = u32::try_from(bytes)
+                    .ok()
+                    .filter(|permits| *permits <= limit.max_bytes)
+                    .ok_or_else(|| {
+                        RedisError::from((
+                            ErrorKind::ClientError,
+                            "The request is bigger than the outstanding bytes limit",
+                            format!("{bytes} bytes, limit of {} bytes", limit.max_bytes),
+                        ))
+                    })?;
+                let exceeded = || {
+                    RedisError::from((
+                        ErrorKind::ClientError,
+                        "The outstanding bytes limit was reached",
+                    ))
+                };
+                let semaphore = limit.semaphore.clone();
+                Some(match limit.policy {
+                    OutstandingBytesPolicy::Fail => semaphore
+                        .try_acquire_many_owned(permits)
+                        .map_err(|_| exceeded())?,
+                    OutstandingBytesPolicy::Wait => semaphore
+                        .acquire_many_owned(permits)
+                        .await
+                        .map_err(|_| exceeded())?,
+                })
+            }
+        };
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        Ok(OutstandingBytesGuard {
+            outstanding: self.clone(),
+            bytes,
+            _permit: permit,
+        })
+    }
+}
+
+// The bytes of a request, which stay outstanding until the request is replied or dropped.
+struct OutstandingBytesGuard {
+    outstanding: Arc<OutstandingBytes>,
+    bytes: usize,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for OutstandingBytesGuard {
+    fn drop(&mut self) {
+        self.outstanding
+            .bytes
+            .fetch_sub(self.bytes, Ordering::Relaxed);
+    }
 }
 
 /// Wrapper around a `Stream + Sink` where each item sent through the `Sink` results in one or more
@@ -77,6 +165,8 @@ struct Pipeline<SinkItem> {
     sender: mpsc::Sender<PipelineMessage<SinkItem>>,
 
     push_manager: Arc<ArcSwap<PushManager>>,
+    outstanding_bytes: Arc<OutstandingBytes>,
+    flush_policy: Arc<ArcSwap<FlushPolicy>>,
 }
 
 impl<SinkItem> Clone for Pipeline<SinkItem> {
@@ -84,6 +174,8 @@ impl<SinkItem> Clone for Pipeline<SinkItem> {
         Pipeline {
             sender: self.sender.clone(),
             push_manager: self.push_manager.clone(),
+            outstanding_bytes: self.outstanding_bytes.clone(),
+            flush_policy: self.flush_policy.clone(),
         }
     }
 }
@@ -97,6 +189,150 @@ where
     }
 }
 
+/// When a [`MultiplexedConnection`] flushes the requests that it wrote to the socket, which trades the latency of the
+/// requests for the number of write syscalls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FlushPolicy {
+    /// Flush each request before the next one is written.
+    PerRequest,
+    /// Flush once no more requests are queued, so that the requests that are sent concurrently are written together.
+    /// This is the default.
+    #[default]
+    OnYield,
+    /// Like [`OnYield`](FlushPolicy::OnYield), but wait up to the given duration after the first unflushed request,
+    /// so that more requests are written together. The requests are flushed earlier if the write buffer fills up.
+    Interval(Duration),
+}
+
+// Like the write buffer of `Framed`, the queued requests are written before more are accepted once they reach this size.
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+const WRITE_BACKPRESSURE_BOUNDARY: usize = 8 * 1024;
+
+/// The socket of a connection, which reads the replies with the codec, and writes the requests from the buffers they
+/// were packed into with vectored writes, instead of copying them into a write buffer first. Only the requests shorter
+/// than [`BORROWED_ARG_MIN_LEN`] are copied, into a buffer that stages them together.
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+struct VectoredFramed<T> {
+    framed: Framed<T, ValueCodec>,
+    // The requests that weren't written yet.
+    queue: VecDeque<Vec<u8>>,
+    // The number of bytes of the first queued request that were already written.
+    written: usize,
+    queued_bytes: usize,
+    // Whether the last queued buffer stages short requests, so that the next short request is appended to it.
+    staging: bool,
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+impl<T> VectoredFramed<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    fn new(framed: Framed<T, ValueCodec>) -> Self {
+        VectoredFramed {
+            framed,
+            queue: VecDeque::new(),
+            written: 0,
+            queued_bytes: 0,
+            staging: false,
+        }
+    }
+
+    fn poll_write_queue(&mut self, cx: &mut task::Context) -> Poll<io::Result<()>> {
+        while let Some(first) = self.queue.front() {
+            let slices: Vec<_> = std::iter::once(&first[self.written..])
+                .chain(self.queue.iter().skip(1).map(Vec::as_slice))
+                .take(MAX_SLICES_PER_WRITE)
+                .map(io::IoSlice::new)
+                .collect();
+            let written = ready!(Pin::new(self.framed.get_mut()).poll_write_vectored(cx, &slices))?;
+            if written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.advance(written);
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn advance(&mut self, mut written: usize) {
+        self.queued_bytes -= written;
+        while let Some(first) = self.queue.front() {
+            let remaining = first.len() - self.written;
+            if written < remaining {
+                self.written += written;
+                return;
+            }
+            written -= remaining;
+            self.queue.pop_front();
+            self.written = 0;
+        }
+        self.staging = false;
+    }
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+impl<T> Stream for VectoredFramed<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    type Item = <Framed<T, ValueCodec> as Stream>::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.framed).poll_next(cx)
+    }
+}
+
+#[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+impl<T> Sink<Vec<u8>> for VectoredFramed<T>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    type Error = RedisError;
+
+    fn poll_ready(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context,
+    ) -> Poll<Result<(), Self::Error>> {
+        if self.queued_bytes >= WRITE_BACKPRESSURE_BOUNDARY {
+            ready!(self.poll_write_queue(cx))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Vec<u8>) -> Result<(), Self::Error> {
+        if item.is_empty() {
+            return Ok(());
+        }
+        let this = &mut *self;
+        this.queued_bytes += item.len();
+        let short = item.len() < BORROWED_ARG_MIN_LEN;
+        match this.queue.back_mut() {
+            Some(staged) if this.staging && short => staged.extend_from_slice(&item),
+            _ => {
+                this.queue.push_back(item);
+                this.staging = short;
+            }
+        }
+        Ok(())
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context,
+    ) -> Poll<Result<(), Self::Error>> {
+        ready!(self.poll_write_queue(cx))?;
+        Poll::Ready(ready!(Pin::new(self.framed.get_mut()).poll_flush(cx)).map_err(Into::into))
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context,
+    ) -> Poll<Result<(), Self::Error>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        Poll::Ready(ready!(Pin::new(self.framed.get_mut()).poll_shutdown(cx)).map_err(Into::into))
+    }
+}
+
 pin_project! {
     struct PipelineSink<T> {
         #[pin]
@@ -104,6 +340,11 @@ pin_project! {
         in_flight: VecDeque<InFlight>,
         error: Option<RedisError>,
         push_manager: Arc<ArcSwap<PushManager>>,
+        flush_policy: Arc<ArcSwap<FlushPolicy>>,
+        // Whether requests were written since the last flush.
+        unflushed: bool,
+        // The timer of `FlushPolicy::Interval`, which is set while there are unflushed requests.
+        flush_timer: Option<BoxFuture<'static, ()>>,
     }
 }
 
@@ -111,7 +352,11 @@ impl<T> PipelineSink<T>
 where
     T: Stream<Item = RedisResult<Value>> + 'static,
 {
-    fn new<SinkItem>(sink_stream: T, push_manager: Arc<ArcSwap<PushManager>>) -> Self
+    fn new<SinkItem>(
+        sink_stream: T,
+        push_manager: Arc<ArcSwap<PushManager>>,
+        flush_policy: Arc<ArcSwap<FlushPolicy>>,
+    ) -> Self
     where
         T: Sink<SinkItem, Error = RedisError> + Stream<Item = RedisResult<Value>> + 'static,
     {
@@ -120,6 +365,9 @@ where
             in_flight: VecDeque::new(),
             error: None,
             push_manager,
+            flush_policy,
+            unflushed: false,
+            flush_timer: None,
         }
     }
 
@@ -142,8 +390,15 @@ where
         if let Ok(res) = &result {
             if let Value::Push { kind, data: _data } = res {
                 self_.push_manager.load().try_send_raw(res);
-                if !kind.has_reply() {
-                    // If it's not true then push kind is converted to reply of a command
+                // Subscription pushes are the replies of subscription commands, but the server also sends them
+                // unsolicited, e.g. when a shard channel's slot is migrated. Those mustn't be taken as the reply of
+                // an unrelated command.
+                let is_reply = kind.has_reply()
+                    && self_
+                        .in_flight
+                        .front()
+                        .map_or(false, |entry| entry.is_subscription);
+                if !is_reply {
                     skip_value = true;
                 }
             }
@@ -212,6 +467,16 @@ where
         mut self: Pin<&mut Self>,
         cx: &mut task::Context,
     ) -> Poll<Result<(), Self::Error>> {
+        let this = self.as_mut().project();
+        if *this.unflushed && **this.flush_policy.load() == FlushPolicy::PerRequest {
+            match ready!(this.sink_stream.poll_flush(cx)) {
+                Ok(()) => *this.unflushed = false,
+                Err(err) => {
+                    *this.error = Some(err);
+                    return Ok(()).into();
+                }
+            }
+        }
         match ready!(self.as_mut().project().sink_stream.poll_ready(cx)) {
             Ok(()) => Ok(()).into(),
             Err(err) => {
@@ -227,6 +492,8 @@ where
             input,
             output,
             pipeline_response_count,
+            is_subscription,
+            outstanding_bytes,
         }: PipelineMessage<SinkItem>,
     ) -> Result<(), Self::Error> {
         // If there is nothing to receive our output we do not need to send the message as it is
@@ -249,9 +516,12 @@ where
                 let entry = InFlight {
                     output,
                     response_aggregate,
+                    is_subscription,
+                    _outstanding_bytes: outstanding_bytes,
                 };
 
                 self_.in_flight.push_back(entry);
+                *self_.unflushed = true;
                 Ok(())
             }
             Err(err) => {
@@ -265,6 +535,19 @@ where
         mut self: Pin<&mut Self>,
         cx: &mut task::Context,
     ) -> Poll<Result<(), Self::Error>> {
+        let this = self.as_mut().project();
+        if let FlushPolicy::Interval(interval) = **this.flush_policy.load() {
+            if *this.unflushed {
+                let timer = this
+                    .flush_timer
+                    .get_or_insert_with(|| Runtime::locate().sleep(interval));
+                if timer.as_mut().poll(cx).is_pending() {
+                    // The responses of the flushed requests are still read in the meantime.
+                    return self.poll_read(cx);
+                }
+                *this.flush_timer = None;
+            }
+        }
         ready!(self
             .as_mut()
             .project()
@@ -273,6 +556,7 @@ where
             .map_err(|err| {
                 self.as_mut().send_result(Err(err));
             }))?;
+        *self.as_mut().project().unflushed = false;
         self.poll_read(cx)
     }
 
@@ -294,7 +578,7 @@ where
 
 impl<SinkItem> Pipeline<SinkItem>
 where
-    SinkItem: Send + 'static,
+    SinkItem: AsRef<[u8]> + Send + 'static,
 {
     fn new<T>(sink_stream: T) -> (Self, impl Future<Output = ()>)
     where
@@ -308,7 +592,9 @@ where
         let (sender, mut receiver) = mpsc::channel(BUFFER_SIZE);
         let push_manager: Arc<ArcSwap<PushManager>> =
             Arc::new(ArcSwap::new(Arc::new(PushManager::default())));
-        let sink = PipelineSink::new::<SinkItem>(sink_stream, push_manager.clone());
+        let flush_policy: Arc<ArcSwap<FlushPolicy>> = Default::default();
+        let sink =
+            PipelineSink::new::<SinkItem>(sink_stream, push_manager.clone(), flush_policy.clone());
         let f = stream::poll_fn(move |cx| receiver.poll_recv(cx))
             .map(Ok)
             .forward(sink)
@@ -317,6 +603,8 @@ where
             Pipeline {
                 sender,
                 push_manager,
+                outstanding_bytes: Default::default(),
+                flush_policy,
             },
             f,
         )
@@ -326,9 +614,10 @@ where
     async fn send_single(
         &mut self,
         item: SinkItem,
+        is_subscription: bool,
         timeout: Duration,
     ) -> Result<Value, Option<RedisError>> {
-        self.send_recv(item, None, timeout).await
+        self.send_recv(item, None, is_subscription, timeout).await
     }
 
     async fn send_recv(
@@ -336,19 +625,39 @@ where
         input: SinkItem,
         // If `None`, this is a single request, not a pipeline of multiple requests.
         pipeline_response_count: Option<usize>,
+        is_subscription: bool,
         timeout: Duration,
     ) -> Result<Value, Option<RedisError>> {
         let (sender, receiver) = oneshot::channel();
+        // The timeout covers both the wait for the outstanding bytes and the wait for the response.
+        let deadline = Instant::now().checked_add(timeout);
+        let remaining = || {
+            deadline.map_or(Duration::MAX, |deadline| {
+                deadline.saturating_duration_since(Instant::now())
+            })
+        };
 
+        let outstanding_bytes = match Runtime::locate()
+            .timeout(
+                remaining(),
+                self.outstanding_bytes.reserve(input.as_ref().len()),
+            )
+            .await
+        {
+            Ok(result) => result.map_err(Some)?,
+            Err(elapsed) => return Err(Some(elapsed.into())),
+        };
         self.sender
             .send(PipelineMessage {
                 input,
                 pipeline_response_count,
+                is_subscription,
                 output: sender,
+                outstanding_bytes,
             })
             .await
             .map_err(|_| None)?;
-        match Runtime::locate().timeout(timeout, receiver).await {
+        match Runtime::locate().timeout(remaining(), receiver).await {
             Ok(Ok(result)) => result.map_err(Some),
             Ok(Err(_)) => {
                 // The `sender` was dropped which likely means that the stream part
@@ -365,6 +674,35 @@ where
     }
 }
 
+/// The time of the last successful response of a connection, shared by its clones. It's kept as the microseconds
+/// since the connection was created, plus one, so that it can be updated atomically, and 0 means no response.
+#[derive(Clone)]
+struct ResponseClock {
+    start: Instant,
+    last_response: Arc<AtomicU64>,
+}
+
+impl ResponseClock {
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            last_response: Default::default(),
+        }
+    }
+
+    fn record(&self) {
+        let micros = u64::try_from(self.start.elapsed().as_micros()).unwrap_or(u64::MAX - 1);
+        self.last_response.fetch_max(micros + 1, Ordering::Relaxed);
+    }
+
+    fn last_response(&self) -> Option<Instant> {
+        match self.last_response.load(Ordering::Relaxed) {
+            0 => None,
+            micros => Some(self.start + Duration::from_micros(micros - 1)),
+        }
+    }
+}
+
 /// A connection object which can be cloned, allowing requests to be be sent concurrently
 /// on the same underlying connection (tcp/unix socket).
 #[derive(Clone)]
@@ -374,6 +712,10 @@ pub struct MultiplexedConnection {
     response_timeout: Duration,
     protocol: ProtocolVersion,
     push_manager: PushManager,
+    pipeline_chunking: PipelineChunking,
+    response_clock: ResponseClock,
+    // The size above which the replies fail, `usize::MAX` when they aren't limited.
+    max_reply_size: Arc<AtomicUsize>,
 }
 
 impl Debug for MultiplexedConnection {
@@ -426,9 +768,11 @@ impl MultiplexedConnection {
         compile_error!("tokio-comp or async-std-comp features required for aio feature");
 
         let redis_connection_info = &connection_info.redis;
-        let codec = ValueCodec::default()
-            .framed(stream)
-            .and_then(|msg| async move { msg });
+        let max_reply_size = Arc::new(AtomicUsize::new(usize::MAX));
+        let codec = VectoredFramed::new(
+            ValueCodec::with_max_reply_size(max_reply_size.clone()).framed(stream),
+        )
+        .and_then(|msg| async move { msg });
         let (mut pipeline, driver) = Pipeline::new(codec);
         let driver = boxed(driver);
         let pm = PushManager::default();
@@ -443,6 +787,9 @@ impl MultiplexedConnection {
             response_timeout,
             push_manager: pm,
             protocol: redis_connection_info.protocol,
+            pipeline_chunking: PipelineChunking::default(),
+            response_clock: ResponseClock::new(),
+            max_reply_size,
         };
         let driver = {
             let auth = setup_connection(&connection_info.redis, &mut con);
@@ -470,12 +817,69 @@ impl MultiplexedConnection {
         self.response_timeout = timeout;
     }
 
+    /// Sets when the requests that are written to the socket are flushed. The policy is shared by the clones of the
+    /// connection. See [`FlushPolicy`].
+    pub fn set_flush_policy(&mut self, policy: FlushPolicy) {
+        self.pipeline.flush_policy.store(Arc::new(policy));
+    }
+
+    /// Limits the bytes of the encoded requests that are queued or in flight on the connection, which protects the
+    /// process from running out of memory when the server slows down while requests keep being sent.
+    ///
+    /// A request that would take the outstanding bytes above `max_bytes` fails or waits, according to `policy`, and a
+    /// request that is bigger than `max_bytes` always fails. The limit is shared by the clones of the connection, and
+    /// applies to the requests that are sent after it's set. It can't be above 4 GiB.
+    pub fn set_max_outstanding_bytes(&mut self, max_bytes: usize, policy: OutstandingBytesPolicy) {
+        let max_bytes = u32::try_from(max_bytes).unwrap_or(u32::MAX);
+        self.pipeline
+            .outstanding_bytes
+            .limit
+            .store(Some(Arc::new(OutstandingBytesLimit {
+                semaphore: Arc::new(Semaphore::new(max_bytes as usize)),
+                max_bytes,
+                policy,
+            })));
+    }
+
+    /// Limits the size of the replies that the connection receives, which protects the process from accidentally
+    /// reading huge values. The limit is shared by the clones of the connection.
+    ///
+    /// A reply that is larger than `max_bytes` fails with a [`ValueTooLarge`](crate::ErrorKind::ValueTooLarge)
+    /// error. When the reply exceeds the limit before it's fully received, the connection is closed, because the rest
+    /// of the reply can't be skipped without reading it, so the other requests in flight fail too.
+    pub fn set_max_reply_size(&mut self, max_bytes: usize) {
+        self.max_reply_size.store(max_bytes, Ordering::Relaxed);
+    }
+
+    /// Returns the bytes of the encoded requests that are queued or in flight on the connection and its clones.
+    pub fn outstanding_bytes(&self) -> usize {
+        self.pipeline
+            .outstanding_bytes
+            .bytes
+            .load(Ordering::Relaxed)
+    }
+
+    /// Returns when the connection or one of its clones last received a successful response, or `None` if it
+    /// didn't receive any yet.
+    pub fn last_successful_response(&self) -> Option<Instant> {
+        self.response_clock.last_response()
+    }
+
+    /// Sets the limits above which pipelines are split into several chunks. See [`PipelineChunking`].
+    pub fn set_pipeline_chunking(&mut self, chunking: PipelineChunking) {
+        self.pipeline_chunking = chunking;
+    }
+
     /// Sends an already encoded (packed) command into the TCP socket and
     /// reads the single response from it.
     pub async fn send_packed_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
         let result = self
             .pipeline
-            .send_single(cmd.get_packed_command(), self.response_timeout)
+            .send_single(
+                cmd.get_packed_command(),
+                cmd.is_subscription(),
+                self.response_timeout,
+            )
             .await
             .map_err(|err| {
                 err.unwrap_or_else(|| RedisError::from(io::Error::from(io::ErrorKind::BrokenPipe)))
@@ -491,23 +895,46 @@ impl MultiplexedConnection {
                 }
             }
         }
+        if result.is_ok() {
+            self.response_clock.record();
+        }
         result
     }
 
     /// Sends multiple already encoded (packed) command into the TCP socket
     /// and reads `count` responses from it.  This is used to implement
     /// pipelining.
+    ///
+    /// Pipelines that exceed the [`PipelineChunking`] limits of the connection are sent in several chunks.
     pub async fn send_packed_commands(
         &mut self,
         cmd: &crate::Pipeline,
         offset: usize,
         count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        if cmd.is_empty() {
+            return Ok(vec![]);
+        }
+        match self.pipeline_chunking.split(cmd, offset, count) {
+            Some(chunks) => {
+                crate::pipeline::send_chunks(&mut UnchunkedConnection(self), chunks).await
+            }
+            None => self.send_packed_pipeline(cmd, offset, count).await,
+        }
+    }
+
+    async fn send_packed_pipeline(
+        &mut self,
+        cmd: &crate::Pipeline,
+        offset: usize,
+        count: usize,
     ) -> RedisResult<Vec<Value>> {
         let result = self
             .pipeline
             .send_recv(
                 cmd.get_packed_pipeline(),
                 Some(offset + count),
+                cmd.cmd_iter().any(Cmd::is_subscription),
                 self.response_timeout,
             )
             .await
@@ -527,6 +954,7 @@ impl MultiplexedConnection {
             }
         }
         let value = result?;
+        self.response_clock.record();
         match value {
             Value::Array(mut values) => {
                 values.drain(..offset);
@@ -543,6 +971,28 @@ impl MultiplexedConnection {
     }
 }
 
+/// Sends each pipeline as is, for the chunks of a pipeline that was already split.
+struct UnchunkedConnection<'a>(&'a mut MultiplexedConnection);
+
+impl ConnectionLike for UnchunkedConnection<'_> {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        self.0.req_packed_command(cmd)
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a crate::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        (async move { self.0.send_packed_pipeline(cmd, offset, count).await }).boxed()
+    }
+
+    fn get_db(&self) -> i64 {
+        self.0.db
+    }
+}
+
 impl ConnectionLike for MultiplexedConnection {
     fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
         (async move { self.send_packed_command(cmd).await }).boxed()
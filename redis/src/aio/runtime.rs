@@ -1,6 +1,6 @@
 use std::{io, time::Duration};
 
-use futures_util::Future;
+use futures_util::{future::BoxFuture, Future};
 
 #[cfg(feature = "async-std-comp")]
 use super::async_std;
@@ -45,7 +45,7 @@ impl Runtime {
     }
 
     #[allow(dead_code)]
-    pub(super) fn spawn(&self, f: impl Future<Output = ()> + Send + 'static) {
+    pub(crate) fn spawn(&self, f: impl Future<Output = ()> + Send + 'static) {
         match self {
             #[cfg(feature = "tokio-comp")]
             Runtime::Tokio => tokio::Tokio::spawn(f),
@@ -54,6 +54,15 @@ impl Runtime {
         }
     }
 
+    pub(crate) fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        match self {
+            #[cfg(feature = "tokio-comp")]
+            Runtime::Tokio => Box::pin(::tokio::time::sleep(duration)),
+            #[cfg(feature = "async-std-comp")]
+            Runtime::AsyncStd => Box::pin(::async_std::task::sleep(duration)),
+        }
+    }
+
     pub(crate) async fn timeout<F: Future>(
         &self,
         duration: Duration,
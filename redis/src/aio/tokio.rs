@@ -91,6 +91,30 @@ impl AsyncWrite for Tokio {
         }
     }
 
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut task::Context,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match &mut *self {
+            Tokio::Tcp(r) => Pin::new(r).poll_write_vectored(cx, bufs),
+            #[cfg(any(feature = "tokio-native-tls-comp", feature = "tokio-rustls-comp"))]
+            Tokio::TcpTls(r) => Pin::new(r).poll_write_vectored(cx, bufs),
+            #[cfg(unix)]
+            Tokio::Unix(r) => Pin::new(r).poll_write_vectored(cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            Tokio::Tcp(r) => r.is_write_vectored(),
+            #[cfg(any(feature = "tokio-native-tls-comp", feature = "tokio-rustls-comp"))]
+            Tokio::TcpTls(r) => r.is_write_vectored(),
+            #[cfg(unix)]
+            Tokio::Unix(r) => r.is_write_vectored(),
+        }
+    }
+
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut task::Context) -> Poll<io::Result<()>> {
         match &mut *self {
             Tokio::Tcp(r) => Pin::new(r).poll_flush(cx),
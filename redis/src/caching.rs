@@ -0,0 +1,400 @@
+//! Client-side caching of `GET` and `MGET` replies, kept consistent by the server with `CLIENT TRACKING`.
+//!
+//! A cached connection enables tracking on the server, which then sends an invalidation push message whenever a key
+//! that the connection read is changed. The cached replies are dropped once their invalidation arrives, so reads
+//! may be served from the cache until then. Since the invalidations are push messages, RESP3 is required.
+//!
+//! Cached connections are returned by [`Client::get_cached_multiplexed_async_connection`](crate::Client::get_cached_multiplexed_async_connection)
+//! and, for clusters, by `ClusterClient::get_cached_async_connection`.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures_util::FutureExt;
+use tokio::sync::mpsc;
+
+use crate::aio::{ConnectionLike, Runtime};
+use crate::cmd::{cmd, Cmd};
+use crate::types::{RedisFuture, RedisResult, Value};
+use crate::{PushInfo, PushKind};
+
+const DEFAULT_MAX_ENTRIES: usize = 10_000;
+
+/// The configuration of a client-side cache.
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    max_entries: usize,
+    ttl: Option<Duration>,
+    prefixes: Vec<String>,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: DEFAULT_MAX_ENTRIES,
+            ttl: None,
+            prefixes: Vec::new(),
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Creates a configuration that caches up to 10,000 keys, until they're invalidated by the server.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximal number of cached keys. Once it's reached, the keys that were cached first are evicted.
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Sets how long a key is cached for, even if it isn't invalidated by the server.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Caches only the keys that start with `prefix`, and can be called again to cache several prefixes.
+    ///
+    /// With prefixes, the server tracks the keys in broadcasting mode, which invalidates every change of a key with
+    /// one of the prefixes, whether it was read by the connection or not.
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefixes.push(prefix.into());
+        self
+    }
+
+    pub(crate) fn tracking_command(&self) -> Cmd {
+        let mut command = cmd("CLIENT");
+        command.arg("TRACKING").arg("ON");
+        if !self.prefixes.is_empty() {
+            command.arg("BCAST");
+            for prefix in &self.prefixes {
+                command.arg("PREFIX").arg(prefix);
+            }
+        }
+        command
+    }
+
+    fn is_cacheable(&self, key: &[u8]) -> bool {
+        self.prefixes.is_empty()
+            || self
+                .prefixes
+                .iter()
+                .any(|prefix| key.starts_with(prefix.as_bytes()))
+    }
+}
+
+struct Entry {
+    value: Value,
+    cached_at: Instant,
+    generation: u64,
+}
+
+pub(crate) struct LocalCache {
+    config: CacheConfig,
+    entries: HashMap<Vec<u8>, Entry>,
+    // The cached keys by the order of their caching, with the generation of their entry. Keys whose entry was
+    // replaced or removed since are skipped on eviction.
+    insertion_order: VecDeque<(Vec<u8>, u64)>,
+    next_generation: u64,
+    // Incremented by every invalidation, so that a reply that was read before an invalidation isn't cached after it.
+    invalidations: u64,
+}
+
+pub(crate) type SharedCache = Arc<Mutex<LocalCache>>;
+
+impl LocalCache {
+    fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            entries: HashMap::new(),
+            insertion_order: VecDeque::new(),
+            next_generation: 0,
+            invalidations: 0,
+        }
+    }
+
+    fn get(&mut self, key: &[u8]) -> Option<Value> {
+        let entry = self.entries.get(key)?;
+        if self
+            .config
+            .ttl
+            .map_or(false, |ttl| entry.cached_at.elapsed() >= ttl)
+        {
+            self.entries.remove(key);
+            return None;
+        }
+        Some(entry.value.clone())
+    }
+
+    /// Caches the value of `key`, unless the cache was invalidated since `invalidations` was read.
+    fn insert(&mut self, key: &[u8], value: Value, invalidations: u64) {
+        if invalidations != self.invalidations
+            || self.config.max_entries == 0
+            || !self.config.is_cacheable(key)
+        {
+            return;
+        }
+        let generation = self.next_generation;
+        self.next_generation += 1;
+        self.entries.insert(
+            key.to_vec(),
+            Entry {
+                value,
+                cached_at: Instant::now(),
+                generation,
+            },
+        );
+        self.insertion_order.push_back((key.to_vec(), generation));
+
+        while self.entries.len() > self.config.max_entries {
+            let Some((key, generation)) = self.insertion_order.pop_front() else {
+                break;
+            };
+            if self
+                .entries
+                .get(&key)
+                .map_or(false, |entry| entry.generation == generation)
+            {
+                self.entries.remove(&key);
+            }
+        }
+        if self.insertion_order.len() > 2 * self.config.max_entries {
+            let entries = &self.entries;
+            self.insertion_order.retain(|(key, generation)| {
+                entries
+                    .get(key)
+                    .map_or(false, |entry| entry.generation == *generation)
+            });
+        }
+    }
+
+    /// Removes the given keys, or all of them if `keys` is `None`.
+    fn invalidate(&mut self, keys: Option<&[Value]>) {
+        self.invalidations += 1;
+        match keys {
+            Some(keys) => {
+                for key in keys {
+                    if let Value::BulkString(key) = key {
+                        self.entries.remove(key);
+                    }
+                }
+            }
+            None => {
+                self.entries.clear();
+                self.insertion_order.clear();
+            }
+        }
+    }
+
+    fn handle_push(&mut self, push: &PushInfo) {
+        match (&push.kind, push.data.first()) {
+            (PushKind::Invalidate, Some(Value::Array(keys))) => self.invalidate(Some(keys)),
+            // The server invalidates all the keys at once with a nil, e.g. on `FLUSHALL`.
+            (PushKind::Invalidate, _) => self.invalidate(None),
+            // The invalidations of a lost connection are lost with it.
+            (PushKind::Disconnection, _) => self.invalidate(None),
+            _ => {}
+        }
+    }
+}
+
+/// Creates a cache, and the push sender that its connections should deliver their push messages to. The push
+/// messages update the cache, and are then forwarded to `push_sender`.
+pub(crate) fn create_cache(
+    config: CacheConfig,
+    push_sender: Option<mpsc::UnboundedSender<PushInfo>>,
+) -> (SharedCache, mpsc::UnboundedSender<PushInfo>) {
+    let cache = Arc::new(Mutex::new(LocalCache::new(config)));
+    let (sender, mut receiver) = mpsc::unbounded_channel::<PushInfo>();
+    let forwarded_cache = cache.clone();
+    Runtime::locate().spawn(async move {
+        while let Some(push) = receiver.recv().await {
+            forwarded_cache.lock().unwrap().handle_push(&push);
+            if let Some(push_sender) = &push_sender {
+                let _ = push_sender.send(push);
+            }
+        }
+    });
+    (cache, sender)
+}
+
+/// A connection that serves `GET` and `MGET` from a client-side cache, and sends them to the server only on a cache
+/// miss. The other commands, including pipelines, are sent to the server as is.
+#[derive(Clone)]
+pub struct CachedConnection<C> {
+    connection: C,
+    cache: SharedCache,
+}
+
+impl<C> CachedConnection<C>
+where
+    C: ConnectionLike + Send,
+{
+    pub(crate) fn new(connection: C, cache: SharedCache) -> Self {
+        Self { connection, cache }
+    }
+
+    /// Returns the underlying connection, whose commands bypass the cache.
+    pub fn connection(&mut self) -> &mut C {
+        &mut self.connection
+    }
+
+    /// Returns the number of cached keys.
+    pub fn cached_keys(&self) -> usize {
+        self.cache.lock().unwrap().entries.len()
+    }
+
+    /// Drops all the cached keys.
+    pub fn clear_cache(&self) {
+        self.cache.lock().unwrap().invalidate(None);
+    }
+
+    async fn query(&mut self, cmd: &Cmd) -> RedisResult<Value> {
+        let name = cmd.arg_idx(0).map(<[u8]>::to_ascii_uppercase);
+        match name.as_deref() {
+            Some(b"GET") if cmd.arg_idx(2).is_none() => match cmd.arg_idx(1) {
+                Some(key) => self.get(cmd, key).await,
+                None => self.connection.req_packed_command(cmd).await,
+            },
+            Some(b"MGET") if cmd.arg_idx(1).is_some() => self.mget(cmd).await,
+            _ => self.connection.req_packed_command(cmd).await,
+        }
+    }
+
+    async fn get(&mut self, cmd: &Cmd, key: &[u8]) -> RedisResult<Value> {
+        let invalidations = {
+            let mut cache = self.cache.lock().unwrap();
+            if let Some(value) = cache.get(key) {
+                return Ok(value);
+            }
+            cache.invalidations
+        };
+        let value = self.connection.req_packed_command(cmd).await?;
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(key, value.clone(), invalidations);
+        Ok(value)
+    }
+
+    async fn mget(&mut self, cmd: &Cmd) -> RedisResult<Value> {
+        let keys: Vec<&[u8]> = (1..).map_while(|index| cmd.arg_idx(index)).collect();
+        let invalidations = {
+            let mut cache = self.cache.lock().unwrap();
+            let cached: Option<Vec<Value>> = keys.iter().map(|key| cache.get(key)).collect();
+            if let Some(values) = cached {
+                return Ok(Value::Array(values));
+            }
+            cache.invalidations
+        };
+        let value = self.connection.req_packed_command(cmd).await?;
+        if let Value::Array(values) = &value {
+            if values.len() == keys.len() {
+                let mut cache = self.cache.lock().unwrap();
+                for (key, value) in keys.into_iter().zip(values) {
+                    cache.insert(key, value.clone(), invalidations);
+                }
+            }
+        }
+        Ok(value)
+    }
+}
+
+impl<C> ConnectionLike for CachedConnection<C>
+where
+    C: ConnectionLike + Send,
+{
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        (async move { self.query(cmd).await }).boxed()
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a crate::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        self.connection.req_packed_commands(cmd, offset, count)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.connection.get_db()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bulk(value: &str) -> Value {
+        Value::BulkString(value.as_bytes().to_vec())
+    }
+
+    fn invalidation(keys: Option<&[&str]>) -> PushInfo {
+        PushInfo {
+            kind: PushKind::Invalidate,
+            data: vec![keys.map_or(Value::Nil, |keys| {
+                Value::Array(keys.iter().map(|key| bulk(key)).collect())
+            })],
+        }
+    }
+
+    #[test]
+    fn test_invalidation_pushes_remove_the_keys() {
+        let mut cache = LocalCache::new(CacheConfig::new());
+        cache.insert(b"foo", bulk("1"), 0);
+        cache.insert(b"bar", bulk("2"), 0);
+
+        cache.handle_push(&invalidation(Some(&["foo"])));
+        assert_eq!(cache.get(b"foo"), None);
+        assert_eq!(cache.get(b"bar"), Some(bulk("2")));
+
+        cache.handle_push(&invalidation(None));
+        assert_eq!(cache.get(b"bar"), None);
+    }
+
+    #[test]
+    fn test_replies_read_before_an_invalidation_are_not_cached() {
+        let mut cache = LocalCache::new(CacheConfig::new());
+        let invalidations = cache.invalidations;
+        cache.handle_push(&invalidation(Some(&["foo"])));
+        cache.insert(b"foo", bulk("stale"), invalidations);
+        assert_eq!(cache.get(b"foo"), None);
+    }
+
+    #[test]
+    fn test_the_first_cached_keys_are_evicted() {
+        let mut cache = LocalCache::new(CacheConfig::new().max_entries(2));
+        cache.insert(b"a", bulk("1"), 0);
+        cache.insert(b"b", bulk("2"), 0);
+        cache.insert(b"a", bulk("3"), 0);
+        cache.insert(b"c", bulk("4"), 0);
+
+        assert_eq!(cache.get(b"a"), Some(bulk("3")));
+        assert_eq!(cache.get(b"b"), None);
+        assert_eq!(cache.get(b"c"), Some(bulk("4")));
+    }
+
+    #[test]
+    fn test_only_keys_with_the_prefixes_are_cached() {
+        let config = CacheConfig::new()
+            .prefix("user:")
+            .ttl(Duration::from_secs(60));
+        assert_eq!(
+            config.tracking_command().get_packed_command(),
+            cmd("CLIENT")
+                .arg(&["TRACKING", "ON", "BCAST", "PREFIX", "user:"])
+                .get_packed_command()
+        );
+        let mut cache = LocalCache::new(config);
+        cache.insert(b"user:1", bulk("1"), 0);
+        cache.insert(b"order:1", bulk("2"), 0);
+
+        assert_eq!(cache.get(b"user:1"), Some(bulk("1")));
+        assert_eq!(cache.get(b"order:1"), None);
+    }
+}
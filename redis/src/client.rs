@@ -1,5 +1,9 @@
 use std::time::Duration;
 
+#[cfg(feature = "aio")]
+use crate::caching::{self, CacheConfig, CachedConnection};
+#[cfg(feature = "aio")]
+use crate::types::{ErrorKind, RedisError};
 use crate::{
     connection::{connect, Connection, ConnectionInfo, ConnectionLike, IntoConnectionInfo},
     push_manager::PushInfo,
@@ -20,6 +24,8 @@ use crate::tls::{inner_build_with_tls, TlsCertificates};
 #[derive(Debug, Clone)]
 pub struct Client {
     pub(crate) connection_info: ConnectionInfo,
+    #[cfg(feature = "aio")]
+    pub(crate) cache_config: Option<CacheConfig>,
 }
 
 /// The client acts as connector to the redis server.  By itself it does not
@@ -45,6 +51,8 @@ impl Client {
     pub fn open<T: IntoConnectionInfo>(params: T) -> RedisResult<Client> {
         Ok(Client {
             connection_info: params.into_connection_info()?,
+            #[cfg(feature = "aio")]
+            cache_config: None,
         })
     }
 
@@ -203,6 +211,46 @@ impl Client {
         .map(|(conn, _ip)| conn)
     }
 
+    /// Sets the configuration of the client-side cache of the connections that are returned by
+    /// [`Client::get_cached_multiplexed_async_connection`].
+    pub fn with_cache(mut self, config: CacheConfig) -> Client {
+        self.cache_config = Some(config);
+        self
+    }
+
+    /// Returns an async multiplexed connection that serves `GET` and `MGET` from a client-side cache, which is
+    /// invalidated by the server with `CLIENT TRACKING`. See the [`caching`](crate::caching) module.
+    ///
+    /// The cache is configured by [`Client::with_cache`], or has the default configuration otherwise. Invalidations
+    /// are push messages, so the client must use RESP3. The push messages are forwarded to `push_sender` once the
+    /// cache handled them.
+    #[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(any(feature = "tokio-comp", feature = "async-std-comp")))
+    )]
+    pub async fn get_cached_multiplexed_async_connection(
+        &self,
+        push_sender: Option<mpsc::UnboundedSender<PushInfo>>,
+    ) -> RedisResult<CachedConnection<crate::aio::MultiplexedConnection>> {
+        if self.connection_info.redis.protocol != crate::ProtocolVersion::RESP3 {
+            return Err(RedisError::from((
+                ErrorKind::InvalidClientConfig,
+                "Client-side caching requires RESP3",
+            )));
+        }
+        let config = self.cache_config.clone().unwrap_or_default();
+        let tracking_command = config.tracking_command();
+        let (cache, push_sender) = caching::create_cache(config, push_sender);
+        let mut connection = self
+            .get_multiplexed_async_connection(Some(push_sender))
+            .await?;
+        tracking_command
+            .query_async::<_, ()>(&mut connection)
+            .await?;
+        Ok(CachedConnection::new(connection, cache))
+    }
+
     /// For TCP connections: returns (async connection, Some(the direct IP address))
     /// For Unix connections, returns (async connection, None)
     #[cfg(any(feature = "tokio-comp", feature = "async-std-comp"))]
@@ -48,7 +48,7 @@ use crate::cluster_routing::{
     MultipleNodeRoutingInfo, ResponsePolicy, Routable, SingleNodeRoutingInfo, SlotAddr,
 };
 use crate::cluster_slotmap::SlotMap;
-use crate::cluster_topology::{parse_and_count_slots, SLOT_SIZE};
+use crate::cluster_topology::{parse_and_count_slots, resolve_addresses, SLOT_SIZE};
 use crate::cmd::{cmd, Cmd};
 use crate::connection::{
     connect, Connection, ConnectionAddr, ConnectionInfo, ConnectionLike, RedisConnectionInfo,
@@ -62,7 +62,7 @@ use crate::{
     IntoConnectionInfo, PushInfo,
 };
 
-pub use crate::cluster_client::{ClusterClient, ClusterClientBuilder};
+pub use crate::cluster_client::{ClusterClient, ClusterClientBuilder, CommandFilter};
 pub use crate::cluster_pipeline::{cluster_pipe, ClusterPipeline};
 
 use tokio::sync::mpsc;
@@ -305,6 +305,23 @@ where
         self.send_recv_and_retry_cmds(pipe.commands())
     }
 
+    /// Returns the address of the node that each of the pipeline's commands is routed to, by the current slot map.
+    pub(crate) fn route_pipeline(&self, pipe: &ClusterPipeline) -> RedisResult<Vec<String>> {
+        pipe.commands()
+            .iter()
+            .enumerate()
+            .map(|(idx, cmd)| {
+                self.get_addr_for_cmd(cmd).map_err(|err| {
+                    RedisError::from((
+                        err.kind(),
+                        "Pipeline command can't be routed",
+                        format!("command {idx}: {err}"),
+                    ))
+                })
+            })
+            .collect()
+    }
+
     /// Returns the connection status.
     ///
     /// The connection is open until any `read_response` call recieved an
@@ -382,13 +399,21 @@ where
             "didn't get any slots from server".to_string(),
         )));
         for (addr, conn) in samples {
-            let value = conn.req_command(&slot_cmd())?;
+            let topology_command = self.cluster_params.topology_command;
+            let value = match conn.req_command(&topology_command.cmd()) {
+                Err(err) if topology_command.should_fall_back(&err) => {
+                    conn.req_command(&slot_cmd())?
+                }
+                value => value?,
+            };
             let addr = addr.split(':').next().ok_or(RedisError::from((
                 ErrorKind::ClientError,
                 "can't parse node address",
             )))?;
             match parse_and_count_slots(&value, self.cluster_params.tls, addr).map(|slots_data| {
-                SlotMap::new(slots_data.1, self.cluster_params.read_from_replicas)
+                let (_, slots) =
+                    resolve_addresses(slots_data, self.cluster_params.address_resolver.as_ref());
+                SlotMap::new(slots, self.cluster_params.read_from_replicas)
             }) {
                 Ok(new_slots) => {
                     result = Ok(new_slots);
@@ -415,6 +440,40 @@ where
         Ok(conn)
     }
 
+    // Reconnects to the node, with the retries' wait time between the attempts, and returns the last error once all
+    // the attempts fail.
+    fn reconnect(&self, addr: String) -> RedisResult<()> {
+        let retry_params = &self.cluster_params.retry_params;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let result = self.connect(&addr).and_then(|mut conn| {
+                if conn.check_connection() {
+                    Ok(conn)
+                } else {
+                    Err(RedisError::from((
+                        ErrorKind::IoError,
+                        "Connection check failed",
+                    )))
+                }
+            });
+            match result {
+                Ok(conn) => {
+                    self.connections.borrow_mut().insert(addr, conn);
+                    return Ok(());
+                }
+                Err(err) if attempt >= retry_params.max_reconnect_attempts => {
+                    return Err(RedisError::from((
+                        err.kind(),
+                        "Failed to reconnect",
+                        format!("to {addr} after {attempt} attempts: {err}"),
+                    )));
+                }
+                Err(_) => thread::sleep(retry_params.wait_time_for_retry(attempt)),
+            }
+        }
+    }
+
     fn get_connection<'a>(
         &self,
         connections: &'a mut HashMap<String, C>,
@@ -458,6 +517,10 @@ where
             Ok(slot_addr.to_string())
         };
 
+        self.cluster_params.check_command_allowed(cmd)?;
+        if self.cluster_params.strict_routing {
+            RoutingInfo::for_routable_strict(cmd)?;
+        }
         match RoutingInfo::for_routable(cmd) {
             Some(RoutingInfo::SingleNode(SingleNodeRoutingInfo::Random)) => {
                 let mut rng = thread_rng();
@@ -497,26 +560,27 @@ where
         input: Input,
         addresses: HashSet<&'a str>,
         connections: &'a mut HashMap<String, C>,
-    ) -> Vec<RedisResult<(&'a str, Value)>> {
+    ) -> Vec<(Option<&'a str>, RedisResult<Value>)> {
         addresses
             .into_iter()
             .map(|addr| {
-                let connection = self.get_connection_by_addr(connections, addr)?;
-                match input {
-                    Input::Slice { cmd, routable: _ } => connection.req_packed_command(cmd),
-                    Input::Cmd(cmd) => connection.req_command(cmd),
-                    Input::Commands {
-                        cmd: _,
-                        route: _,
-                        offset: _,
-                        count: _,
-                    } => Err((
-                        ErrorKind::ClientError,
-                        "req_packed_commands isn't supported with multiple nodes",
-                    )
-                        .into()),
-                }
-                .map(|res| (addr, res))
+                let result = self.get_connection_by_addr(connections, addr).and_then(
+                    |connection| match input {
+                        Input::Slice { cmd, routable: _ } => connection.req_packed_command(cmd),
+                        Input::Cmd(cmd) => connection.req_command(cmd),
+                        Input::Commands {
+                            cmd: _,
+                            route: _,
+                            offset: _,
+                            count: _,
+                        } => Err((
+                            ErrorKind::ClientError,
+                            "req_packed_commands isn't supported with multiple nodes",
+                        )
+                            .into()),
+                    },
+                );
+                (Some(addr), result)
             })
             .collect()
     }
@@ -526,7 +590,7 @@ where
         input: Input,
         slots: &'a mut SlotMap,
         connections: &'a mut HashMap<String, C>,
-    ) -> Vec<RedisResult<(&'a str, Value)>> {
+    ) -> Vec<(Option<&'a str>, RedisResult<Value>)> {
         self.execute_on_all(input, slots.addresses_for_all_nodes(), connections)
     }
 
@@ -535,7 +599,7 @@ where
         input: Input,
         slots: &'a mut SlotMap,
         connections: &'a mut HashMap<String, C>,
-    ) -> Vec<RedisResult<(&'a str, Value)>> {
+    ) -> Vec<(Option<&'a str>, RedisResult<Value>)> {
         self.execute_on_all(input, slots.addresses_for_all_primaries(), connections)
     }
 
@@ -545,7 +609,7 @@ where
         slots: &'a mut SlotMap,
         connections: &'a mut HashMap<String, C>,
         routes: &'b [(Route, Vec<usize>)],
-    ) -> Vec<RedisResult<(&'a str, Value)>>
+    ) -> Vec<(Option<&'a str>, RedisResult<Value>)>
     where
         'b: 'a,
     {
@@ -553,15 +617,23 @@ where
             .addresses_for_multi_slot(routes)
             .enumerate()
             .map(|(index, addr)| {
-                let addr = addr.ok_or(RedisError::from((
-                    ErrorKind::IoError,
-                    "Couldn't find connection",
-                )))?;
-                let connection = self.get_connection_by_addr(connections, addr)?;
-                let (_, indices) = routes.get(index).unwrap();
-                let cmd =
-                    crate::cluster_routing::command_for_multi_slot_indices(&input, indices.iter());
-                connection.req_command(&cmd).map(|res| (addr, res))
+                let Some(addr) = addr else {
+                    return (
+                        None,
+                        Err((ErrorKind::IoError, "Couldn't find connection").into()),
+                    );
+                };
+                let result =
+                    self.get_connection_by_addr(connections, addr)
+                        .and_then(|connection| {
+                            let (_, indices) = routes.get(index).unwrap();
+                            let cmd = crate::cluster_routing::command_for_multi_slot_indices(
+                                &input,
+                                indices.iter(),
+                            );
+                            connection.req_command(&cmd)
+                        });
+                (Some(addr), result)
             })
             .collect()
     }
@@ -589,7 +661,7 @@ where
 
         match response_policy {
             Some(ResponsePolicy::AllSucceeded) => {
-                for result in results {
+                for (_, result) in results {
                     result?;
                 }
 
@@ -598,9 +670,9 @@ where
             Some(ResponsePolicy::OneSucceeded) => {
                 let mut last_failure = None;
 
-                for result in results {
+                for (_, result) in results {
                     match result {
-                        Ok((_, val)) => return Ok(val),
+                        Ok(val) => return Ok(val),
                         Err(err) => last_failure = Some(err),
                     }
                 }
@@ -617,8 +689,8 @@ where
                 let mut last_failure = None;
                 let num_of_results = results.len();
                 let mut nil_counter = 0;
-                for result in results {
-                    match result.map(|(_, res)| res) {
+                for (_, result) in results {
+                    match result {
                         Ok(Value::Nil) => nil_counter += 1,
                         Ok(val) => return Ok(val),
                         Err(err) => last_failure = Some(err),
@@ -635,21 +707,21 @@ where
             Some(ResponsePolicy::Aggregate(op)) => {
                 let results = results
                     .into_iter()
-                    .map(|res| res.map(|(_, val)| val))
+                    .map(|(_, result)| result)
                     .collect::<RedisResult<Vec<_>>>()?;
                 crate::cluster_routing::aggregate(results, op)
             }
             Some(ResponsePolicy::AggregateLogical(op)) => {
                 let results = results
                     .into_iter()
-                    .map(|res| res.map(|(_, val)| val))
+                    .map(|(_, result)| result)
                     .collect::<RedisResult<Vec<_>>>()?;
                 crate::cluster_routing::logical_aggregate(results, op)
             }
             Some(ResponsePolicy::CombineArrays) => {
                 let results = results
                     .into_iter()
-                    .map(|res| res.map(|(_, val)| val))
+                    .map(|(_, result)| result)
                     .collect::<RedisResult<Vec<_>>>()?;
                 match routing {
                     MultipleNodeRoutingInfo::MultiSlot(vec) => {
@@ -663,21 +735,26 @@ where
             }
             Some(ResponsePolicy::Special) | None => {
                 // This is our assumption - if there's no coherent way to aggregate the responses, we just map each response to the sender, and pass it to the user.
-                // TODO - once Value::Error is merged, we can use join_all and report separate errors and also pass successes.
-                let results = results
-                    .into_iter()
-                    .map(|result| {
-                        result.map(|(addr, val)| (Value::BulkString(addr.as_bytes().to_vec()), val))
-                    })
-                    .collect::<RedisResult<Vec<_>>>()?;
-                Ok(Value::Map(results))
+                crate::cluster_routing::map_results_by_node(results)
             }
         }
     }
 
     #[allow(clippy::unnecessary_unwrap)]
     fn request(&self, input: Input) -> RedisResult<Output> {
+        match &input {
+            Input::Slice { cmd: _, routable } => {
+                self.cluster_params.check_command_allowed(routable)?
+            }
+            Input::Cmd(cmd) => self.cluster_params.check_command_allowed(*cmd)?,
+            Input::Commands { .. } => {}
+        }
+        let strict_routing = self.cluster_params.strict_routing;
         let route_option = match &input {
+            Input::Slice { cmd: _, routable } if strict_routing => {
+                Some(RoutingInfo::for_routable_strict(routable)?)
+            }
+            Input::Cmd(cmd) if strict_routing => Some(RoutingInfo::for_routable_strict(*cmd)?),
             Input::Slice { cmd: _, routable } => RoutingInfo::for_routable(routable),
             Input::Cmd(cmd) => RoutingInfo::for_routable(*cmd),
             Input::Commands {
@@ -766,11 +843,7 @@ where
                         }
                         crate::types::RetryMethod::Reconnect => {
                             if *self.auto_reconnect.borrow() {
-                                if let Ok(mut conn) = self.connect(&addr) {
-                                    if conn.check_connection() {
-                                        self.connections.borrow_mut().insert(addr, conn);
-                                    }
-                                }
+                                self.reconnect(addr)?;
                             }
                         }
                         crate::types::RetryMethod::NoRetry => {
@@ -881,6 +954,10 @@ impl<C: Connect + ConnectionLike> ConnectionLike for ClusterConnection<C> {
         offset: usize,
         count: usize,
     ) -> RedisResult<Vec<Value>> {
+        // An empty pipeline has no replies to wait for, nor a command to route it by.
+        if cmd.is_empty() {
+            return Ok(vec![]);
+        }
         let actual_cmd = if cmd.starts_with(MULTI) {
             &cmd[MULTI.len()..]
         } else {
@@ -959,9 +1036,16 @@ fn get_random_connection<C: ConnectionLike + Connect + Sized>(
     (addr, con)
 }
 
+/// A callback that maps the address of a node to the address that its connections connect to, as set by
+/// [`ClusterClientBuilder::transport_mapping`](crate::cluster::ClusterClientBuilder::transport_mapping).
+pub(crate) type TransportMapping =
+    std::sync::Arc<dyn Fn(&str) -> Option<ConnectionAddr> + Send + Sync>;
+
 // The node string passed to this function will always be in the format host:port as it is either:
-// - Created by calling ConnectionAddr::to_string (unix connections are not supported in cluster mode)
-// - Returned from redis via the ASK/MOVED response
+// - Created by calling ConnectionAddr::to_string (unix initial nodes are rejected in cluster mode)
+// - Returned from redis via the ASK/MOVED response or the topology
+// The node is connected through its mapped transport if there's one, which may be any address that the `Connect`
+// implementation handles, such as a Unix socket or a proxy, and otherwise through TCP or TCP-TLS.
 pub(crate) fn get_connection_info(
     node: &str,
     cluster_params: ClusterParams,
@@ -976,14 +1060,24 @@ pub(crate) fn get_connection_info(
                 .zip(u16::from_str(port).ok())
         })
         .ok_or_else(invalid_error)?;
+    let mapped_transport = cluster_params.mapped_transport(node);
+
+    #[cfg(feature = "tls-rustls")]
+    let tls_params = match cluster_params.tls_session_cache {
+        Some(session_cache) if cluster_params.tls.is_some() => {
+            let mut tls_params = cluster_params.tls_params.unwrap_or_default();
+            tls_params.session_store = Some(session_cache);
+            Some(tls_params)
+        }
+        _ => cluster_params.tls_params,
+    };
+    #[cfg(not(feature = "tls-rustls"))]
+    let tls_params = cluster_params.tls_params;
 
     Ok(ConnectionInfo {
-        addr: get_connection_addr(
-            host.to_string(),
-            port,
-            cluster_params.tls,
-            cluster_params.tls_params,
-        ),
+        addr: mapped_transport.unwrap_or_else(|| {
+            get_connection_addr(host.to_string(), port, cluster_params.tls, tls_params)
+        }),
         redis: RedisConnectionInfo {
             password: cluster_params.password,
             username: cluster_params.username,
@@ -1066,4 +1160,20 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn get_connection_info_uses_the_mapped_transport() {
+        let params = ClusterParams {
+            transport_mapping: Some(std::sync::Arc::new(|node: &str| {
+                node.strip_suffix(":6379")
+                    .map(|host| ConnectionAddr::Unix(format!("/tmp/{host}.sock").into()))
+            })),
+            ..Default::default()
+        };
+
+        let res = get_connection_info("node1:6379", params.clone()).unwrap();
+        assert_eq!(res.addr, ConnectionAddr::Unix("/tmp/node1.sock".into()));
+        let res = get_connection_info("node1:6380", params).unwrap();
+        assert_eq!(res.addr, ConnectionAddr::Tcp("node1".to_string(), 6380));
+    }
 }
@@ -0,0 +1,116 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use arcstr::ArcStr;
+
+use crate::RedisError;
+
+/// The budget of failures of each node, as set by
+/// [`ClusterClientBuilder::circuit_breaker`](crate::cluster::ClusterClientBuilder::circuit_breaker).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CircuitBreakerParams {
+    pub(crate) failure_budget: u32,
+    pub(crate) refill_interval: Duration,
+    pub(crate) cooldown: Duration,
+}
+
+/// The failure budget of a node, as a token bucket that regains a token every refill interval.
+struct NodeBudget {
+    tokens: u32,
+    last_refill: Instant,
+    open_until: Option<Instant>,
+}
+
+/// The circuit breakers of the nodes, by their addresses.
+#[derive(Default)]
+pub(crate) struct CircuitBreakers(HashMap<ArcStr, NodeBudget>);
+
+impl CircuitBreakers {
+    /// Returns whether the requests to the node should fail fast, because its circuit is open. A circuit whose
+    /// cooldown expired is closed again, with a full budget.
+    pub(crate) fn is_open(&mut self, address: &str, now: Instant) -> bool {
+        let Some(budget) = self.0.get_mut(address) else {
+            return false;
+        };
+        match budget.open_until {
+            Some(open_until) if now < open_until => true,
+            Some(_) => {
+                self.0.remove(address);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Takes a token from the node's budget, and opens its circuit if the budget is exhausted.
+    pub(crate) fn record_failure(
+        &mut self,
+        params: &CircuitBreakerParams,
+        address: &ArcStr,
+        now: Instant,
+    ) {
+        let budget = self.0.entry(address.clone()).or_insert(NodeBudget {
+            tokens: params.failure_budget,
+            last_refill: now,
+            open_until: None,
+        });
+        if budget.open_until.is_some() {
+            return;
+        }
+        if !params.refill_interval.is_zero() {
+            let refills = now.saturating_duration_since(budget.last_refill).as_nanos()
+                / params.refill_interval.as_nanos();
+            if refills > 0 {
+                budget.tokens = u32::try_from(refills)
+                    .unwrap_or(u32::MAX)
+                    .saturating_add(budget.tokens)
+                    .min(params.failure_budget);
+                budget.last_refill = now;
+            }
+        }
+        budget.tokens = budget.tokens.saturating_sub(1);
+        if budget.tokens == 0 {
+            budget.open_until = Some(now + params.cooldown);
+        }
+    }
+}
+
+/// Whether the error means that the node itself failed, rather than that it replied with an error.
+pub(crate) fn is_node_failure(err: &RedisError) -> bool {
+    err.is_io_error() || err.is_timeout() || err.is_unrecoverable_error()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARAMS: CircuitBreakerParams = CircuitBreakerParams {
+        failure_budget: 2,
+        refill_interval: Duration::from_secs(10),
+        cooldown: Duration::from_secs(30),
+    };
+
+    #[test]
+    fn test_circuit_opens_when_the_budget_is_exhausted_until_the_cooldown_expires() {
+        let mut breakers = CircuitBreakers::default();
+        let address = ArcStr::from("node1:6379");
+        let start = Instant::now();
+
+        breakers.record_failure(&PARAMS, &address, start);
+        assert!(!breakers.is_open(&address, start));
+        // A token was refilled in the meantime.
+        breakers.record_failure(&PARAMS, &address, start + Duration::from_secs(10));
+        assert!(!breakers.is_open(&address, start + Duration::from_secs(10)));
+
+        breakers.record_failure(&PARAMS, &address, start + Duration::from_secs(11));
+        assert!(breakers.is_open(&address, start + Duration::from_secs(11)));
+        assert!(!breakers.is_open("node2:6379", start + Duration::from_secs(11)));
+        assert!(breakers.is_open(&address, start + Duration::from_secs(40)));
+
+        assert!(!breakers.is_open(&address, start + Duration::from_secs(41)));
+        breakers.record_failure(&PARAMS, &address, start + Duration::from_secs(41));
+        assert!(!breakers.is_open(&address, start + Duration::from_secs(41)));
+    }
+}
@@ -0,0 +1,75 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures::future::BoxFuture;
+
+/// The source of time of the timeouts, retry waits, backoffs, periodic checks and health freshness windows of a
+/// [`ClusterConnection`](super::ClusterConnection), as set by
+/// [`ClusterClientBuilder::clock`](crate::cluster::ClusterClientBuilder::clock).
+///
+/// The connection reads the time only through its clock, so a test can replace it with a clock that it advances
+/// by itself instead of waiting for the real time to pass.
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    fn now(&self) -> Instant;
+
+    /// Returns a future that completes once `duration` passed on this clock.
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()>;
+}
+
+/// The real time, with the sleeps of the async runtime. This is the default clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        super::boxed_sleep(duration)
+    }
+}
+
+/// The time of the tokio runtime, which follows [`tokio::time::pause`] and [`tokio::time::advance`], for test
+/// suites that run with a paused clock.
+#[cfg(feature = "tokio-comp")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioClock;
+
+#[cfg(feature = "tokio-comp")]
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        tokio::time::Instant::now().into_std()
+    }
+
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// The clock of a connection, shared by all the connections of a client.
+#[derive(Clone)]
+pub(crate) struct SharedClock(Arc<dyn Clock>);
+
+impl SharedClock {
+    pub(crate) fn new(clock: impl Clock + 'static) -> Self {
+        Self(Arc::new(clock))
+    }
+
+    pub(crate) fn now(&self) -> Instant {
+        self.0.now()
+    }
+
+    pub(crate) fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        self.0.sleep(duration)
+    }
+}
+
+impl Default for SharedClock {
+    fn default() -> Self {
+        Self::new(SystemClock)
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+
+use futures::future;
+use tracing::warn;
+
+use super::{connections_container::ConnectionType, ClusterConnection, Connect};
+use crate::{
+    aio::ConnectionLike, cmd, from_owned_redis_value, ClientInfo, Cmd, ErrorKind, FromRedisValue,
+    RedisError, RedisResult, ToRedisArgs, Value,
+};
+
+// The management operations are sent on the management connections of the nodes, so that they aren't queued behind
+// the user's requests, and they're sent to each node once, without retries or redirections.
+impl<C> ClusterConnection<C>
+where
+    C: ConnectionLike + Connect + Clone + Send + Sync + Unpin + 'static,
+{
+    /// Runs `CLIENT LIST` on all the nodes of the cluster, and returns each node's clients by the node's address.
+    pub async fn client_list_all_nodes(&mut self) -> RedisResult<HashMap<String, Vec<ClientInfo>>> {
+        self.query_management_connections::<String>(cmd("CLIENT").arg("LIST"))
+            .await?
+            .into_iter()
+            .map(|(address, list)| Ok((address, ClientInfo::from_list(&list)?)))
+            .collect()
+    }
+
+    /// Runs `CLIENT KILL ID <id>` on the node with the given address, and returns whether the client was killed.
+    ///
+    /// The ids of the clients are only unique on each node, as returned by
+    /// [`client_list_all_nodes`](Self::client_list_all_nodes), so the client is only killed on the given node.
+    pub async fn client_kill_by_id(&mut self, address: &str, id: u64) -> RedisResult<bool> {
+        let killed: i64 = from_owned_redis_value(
+            self.query_management_connection(address, cmd("CLIENT").arg("KILL").arg("ID").arg(id))
+                .await?,
+        )?;
+        Ok(killed > 0)
+    }
+
+    /// Runs `CLIENT UNPAUSE` on the node with the given address, resuming its clients after a `CLIENT PAUSE`.
+    pub async fn client_unpause(&mut self, address: &str) -> RedisResult<()> {
+        self.query_management_connection(address, cmd("CLIENT").arg("UNPAUSE"))
+            .await?;
+        Ok(())
+    }
+
+    /// Runs `CLIENT UNPAUSE` on all the nodes of the cluster.
+    pub async fn client_unpause_all(&mut self) -> RedisResult<()> {
+        self.query_management_connections::<Value>(cmd("CLIENT").arg("UNPAUSE"))
+            .await?;
+        Ok(())
+    }
+
+    /// Runs `CONFIG GET <pattern>` on all the nodes of the cluster, and returns each node's matching parameters
+    /// by the node's address. The pattern may contain glob-style wildcards, for example `maxmemory*`.
+    pub async fn config_get_all(
+        &mut self,
+        pattern: &str,
+    ) -> RedisResult<HashMap<String, HashMap<String, String>>> {
+        self.query_management_connections(cmd("CONFIG").arg("GET").arg(pattern))
+            .await
+    }
+
+    /// Runs `CONFIG SET <parameter> <value>` on all the nodes of the cluster.
+    ///
+    /// The call succeeds only if the parameter was set on all the nodes. Otherwise, the nodes on which it was set are
+    /// rolled back to the parameter's previous value, and the returned error lists the nodes that failed, the nodes
+    /// that were rolled back, and the nodes that failed to roll back.
+    ///
+    /// `parameter` must name a single existing parameter, since the previous values are read with `CONFIG GET`
+    /// before setting the new value.
+    pub async fn config_set_all<V: ToRedisArgs>(
+        &mut self,
+        parameter: &str,
+        value: V,
+    ) -> RedisResult<()> {
+        let mut previous_values = Vec::new();
+        for (address, values) in self.config_get_all(parameter).await? {
+            let mut values = values.into_values();
+            match (values.next(), values.next()) {
+                (Some(previous_value), None) => previous_values.push((address, previous_value)),
+                _ => fail!((
+                    ErrorKind::ClientError,
+                    "CONFIG SET requires a single existing parameter",
+                    format!("`{parameter}` doesn't match a single parameter on node {address}")
+                )),
+            }
+        }
+
+        let mut set_cmd = cmd("CONFIG");
+        set_cmd.arg("SET").arg(parameter).arg(value);
+        let this = &*self;
+        let results = future::join_all(
+            previous_values
+                .iter()
+                .map(|(address, _)| this.query_management_connection(address, &set_cmd)),
+        )
+        .await;
+        let mut failed = Vec::new();
+        let mut succeeded = Vec::new();
+        for ((address, previous_value), result) in previous_values.into_iter().zip(results) {
+            match result {
+                Ok(_) => succeeded.push((address, previous_value)),
+                Err(err) => failed.push((address, err)),
+            }
+        }
+        let Some(kind) = failed.first().map(|(_, err)| err.kind()) else {
+            return Ok(());
+        };
+
+        let rollback_results =
+            future::join_all(succeeded.iter().map(|(address, previous_value)| {
+                let mut rollback_cmd = cmd("CONFIG");
+                rollback_cmd.arg("SET").arg(parameter).arg(previous_value);
+                async move {
+                    this.query_management_connection(address, &rollback_cmd)
+                        .await
+                }
+            }))
+            .await;
+        let mut rolled_back = Vec::new();
+        let mut rollback_failed = Vec::new();
+        for ((address, _), result) in succeeded.into_iter().zip(rollback_results) {
+            match result {
+                Ok(_) => rolled_back.push(address),
+                Err(err) => rollback_failed.push(format!("{address}: {err}")),
+            }
+        }
+        let failed: Vec<String> = failed
+            .into_iter()
+            .map(|(address, err)| format!("{address}: {err}"))
+            .collect();
+        warn!(
+            "CONFIG SET {parameter} failed on nodes {failed:?}, rolled back nodes {rolled_back:?}, failed to roll back nodes {rollback_failed:?}"
+        );
+        Err(RedisError::from((
+            kind,
+            "CONFIG SET failed on some of the nodes",
+            format!(
+                "failed: {failed:?}, rolled back: {rolled_back:?}, failed to roll back: {rollback_failed:?}"
+            ),
+        )))
+    }
+
+    // Sends `cmd` to all the nodes, and returns each node's reply by the node's address. Fails if any of the nodes
+    // fails.
+    async fn query_management_connections<T: FromRedisValue>(
+        &self,
+        cmd: &Cmd,
+    ) -> RedisResult<HashMap<String, T>> {
+        self.1.cluster_params.check_command_allowed(cmd)?;
+        let connections: Vec<_> = self
+            .1
+            .conn_lock
+            .read()
+            .await
+            .all_nodes()
+            .map(|(address, node)| {
+                (
+                    address.to_string(),
+                    node.get_connection(&ConnectionType::PreferManagement),
+                )
+            })
+            .collect();
+        if connections.is_empty() {
+            fail!((
+                ErrorKind::ClusterConnectionNotFound,
+                "No nodes are connected"
+            ));
+        }
+        future::try_join_all(
+            connections
+                .into_iter()
+                .map(|(address, connection)| async move {
+                    let value = connection.await.req_packed_command(cmd).await?;
+                    Ok((address, from_owned_redis_value(value)?))
+                }),
+        )
+        .await
+        .map(|replies| replies.into_iter().collect())
+    }
+
+    async fn query_management_connection(&self, address: &str, cmd: &Cmd) -> RedisResult<Value> {
+        self.1.cluster_params.check_command_allowed(cmd)?;
+        let connection = self
+            .1
+            .conn_lock
+            .read()
+            .await
+            .node_for_address(address)
+            .map(|node| node.get_connection(&ConnectionType::PreferManagement));
+        let Some(connection) = connection else {
+            fail!((
+                ErrorKind::ClusterConnectionNotFound,
+                "Requested connection not found",
+                address.to_string()
+            ));
+        };
+        connection.await.req_packed_command(cmd).await
+    }
+}
@@ -0,0 +1,123 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// The state of the limiter of concurrent connection attempts, as returned by
+/// [`ClusterConnection::connect_limiter_stats`](super::ClusterConnection::connect_limiter_stats).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectLimiterStats {
+    /// The maximal number of concurrent connection attempts.
+    pub permits: usize,
+    /// The number of connection attempts that are in progress.
+    pub in_flight: usize,
+    /// The number of connection attempts that are waiting for a permit.
+    pub waiting: usize,
+    /// The number of connection attempts that had to wait for a permit, since the limiter was created.
+    pub delayed_connects: u64,
+    /// The total time that the connection attempts waited for permits, since the limiter was created.
+    pub total_wait: Duration,
+}
+
+/// Limits the number of concurrent connection attempts to the nodes, so that reconnecting to many nodes at once,
+/// for example after a network blip, doesn't open hundreds of sockets at the same time.
+pub(crate) struct ConnectLimiter {
+    semaphore: Semaphore,
+    permits: usize,
+    waiting: AtomicUsize,
+    delayed_connects: AtomicU64,
+    total_wait_micros: AtomicU64,
+}
+
+impl ConnectLimiter {
+    pub(crate) fn new(permits: usize) -> Self {
+        let permits = permits.max(1);
+        Self {
+            semaphore: Semaphore::new(permits),
+            permits,
+            waiting: AtomicUsize::new(0),
+            delayed_connects: AtomicU64::new(0),
+            total_wait_micros: AtomicU64::new(0),
+        }
+    }
+
+    /// Waits for a permit to attempt a connection. The permit is returned when it's dropped.
+    pub(crate) async fn acquire(&self) -> SemaphorePermit<'_> {
+        if let Ok(permit) = self.semaphore.try_acquire() {
+            return permit;
+        }
+        let start = Instant::now();
+        let waiting = WaitingGuard::new(&self.waiting);
+        // The semaphore is never closed.
+        let permit = self.semaphore.acquire().await.unwrap();
+        drop(waiting);
+        self.delayed_connects.fetch_add(1, Ordering::Relaxed);
+        self.total_wait_micros
+            .fetch_add(start.elapsed().as_micros() as u64, Ordering::Relaxed);
+        permit
+    }
+
+    pub(crate) fn stats(&self) -> ConnectLimiterStats {
+        ConnectLimiterStats {
+            permits: self.permits,
+            in_flight: self.permits - self.semaphore.available_permits(),
+            waiting: self.waiting.load(Ordering::Relaxed),
+            delayed_connects: self.delayed_connects.load(Ordering::Relaxed),
+            total_wait: Duration::from_micros(self.total_wait_micros.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Counts a connection attempt as waiting for a permit until it's dropped, so that an attempt that's cancelled while
+/// waiting isn't counted anymore.
+struct WaitingGuard<'a>(&'a AtomicUsize);
+
+impl<'a> WaitingGuard<'a> {
+    fn new(waiting: &'a AtomicUsize) -> Self {
+        waiting.fetch_add(1, Ordering::Relaxed);
+        Self(waiting)
+    }
+}
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_connection_attempts_wait_for_a_permit() {
+        let limiter = ConnectLimiter::new(1);
+        let permit = limiter.acquire().await;
+        assert_eq!(limiter.stats().in_flight, 1);
+
+        let waiting = limiter.acquire();
+        futures::pin_mut!(waiting);
+        assert!(futures::poll!(waiting.as_mut()).is_pending());
+        assert_eq!(limiter.stats().waiting, 1);
+
+        drop(permit);
+        let _permit = waiting.await;
+        let stats = limiter.stats();
+        assert_eq!(stats.waiting, 0);
+        assert_eq!(stats.in_flight, 1);
+        assert_eq!(stats.delayed_connects, 1);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_attempts_stop_waiting() {
+        let limiter = ConnectLimiter::new(1);
+        let _permit = limiter.acquire().await;
+
+        let mut waiting = Box::pin(limiter.acquire());
+        assert!(futures::poll!(waiting.as_mut()).is_pending());
+        assert_eq!(limiter.stats().waiting, 1);
+
+        drop(waiting);
+        assert_eq!(limiter.stats().waiting, 0);
+    }
+}
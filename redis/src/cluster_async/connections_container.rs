@@ -1,5 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use arcstr::ArcStr;
 use rand::seq::IteratorRandom;
@@ -8,11 +11,117 @@ use crate::cluster_routing::{Route, SlotAddr};
 use crate::cluster_slotmap::{ReadFromReplicaStrategy, SlotMap, SlotMapValue};
 use crate::cluster_topology::TopologyHash;
 
+use super::NodeMetadata;
+
+/// The index of the next user connection of a node's pool, shared by the clones of the node.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PoolIndex(Arc<AtomicUsize>);
+
+impl PartialEq for PoolIndex {
+    fn eq(&self, _other: &Self) -> bool {
+        // The position of the round robin isn't part of the node's identity.
+        true
+    }
+}
+
+impl Eq for PoolIndex {}
+
+/// The moving average of the measured latencies of a node, in microseconds, shared by the clones of the node.
+#[derive(Clone, Debug)]
+pub(crate) struct NodeLatency(Arc<AtomicU64>);
+
+impl NodeLatency {
+    const UNKNOWN: u64 = u64::MAX;
+    /// The weight of the previous average against a new measurement, so that a single slow ping doesn't move the
+    /// reads away from a node.
+    const AVERAGE_WEIGHT: u64 = 3;
+
+    /// Returns the average latency, or `None` if it wasn't measured, or the last measurement failed.
+    pub(crate) fn get(&self) -> Option<Duration> {
+        match self.0.load(Ordering::Relaxed) {
+            Self::UNKNOWN => None,
+            micros => Some(Duration::from_micros(micros)),
+        }
+    }
+
+    /// Adds a measurement to the average. A failed measurement, `None`, makes the latency unknown until the next
+    /// successful measurement.
+    pub(crate) fn record(&self, latency: Option<Duration>) {
+        let Some(latency) = latency else {
+            self.0.store(Self::UNKNOWN, Ordering::Relaxed);
+            return;
+        };
+        let micros = u64::try_from(latency.as_micros()).unwrap_or(Self::UNKNOWN - 1);
+        let _ = self
+            .0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |average| {
+                Some(match average {
+                    Self::UNKNOWN => micros,
+                    average => {
+                        let sum = u128::from(average) * u128::from(Self::AVERAGE_WEIGHT)
+                            + u128::from(micros);
+                        (sum / u128::from(Self::AVERAGE_WEIGHT + 1)) as u64
+                    }
+                })
+            });
+    }
+}
+
+impl Default for NodeLatency {
+    fn default() -> Self {
+        Self(Arc::new(AtomicU64::new(Self::UNKNOWN)))
+    }
+}
+
+impl PartialEq for NodeLatency {
+    fn eq(&self, _other: &Self) -> bool {
+        // The latency changes over time, it isn't part of the node's identity.
+        true
+    }
+}
+
+impl Eq for NodeLatency {}
+
+/// Whether a node is paused, shared by the clones of the node. A node is paused when its health probe times out over
+/// a connection that is still open, as with `CLIENT PAUSE`, until a probe succeeds again.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct NodePause(Arc<AtomicBool>);
+
+impl NodePause {
+    pub(crate) fn is_paused(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Sets whether the node is paused, and returns whether it was paused before.
+    pub(crate) fn set(&self, paused: bool) -> bool {
+        self.0.swap(paused, Ordering::Relaxed)
+    }
+}
+
+impl PartialEq for NodePause {
+    fn eq(&self, _other: &Self) -> bool {
+        // The pause is a passing state of the server, it isn't part of the node's identity.
+        true
+    }
+}
+
+impl Eq for NodePause {}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct ClusterNode<Connection> {
     pub user_connection: Connection,
+    /// The user connections that are opened in addition to `user_connection`, when the connection pool size is
+    /// larger than 1.
+    pub pooled_user_connections: Vec<Connection>,
     pub management_connection: Option<Connection>,
     pub ip: Option<IpAddr>,
+    /// The user metadata that was attached to the node when it was discovered.
+    pub metadata: Arc<NodeMetadata>,
+    pub(crate) pool_index: PoolIndex,
+    /// The latency of the node, which is measured by the periodic topology checks.
+    pub(crate) latency: NodeLatency,
+    /// Whether the node is paused, as detected by the periodic topology checks.
+    pub(crate) paused: NodePause,
 }
 
 impl<Connection> ClusterNode<Connection>
@@ -26,18 +135,41 @@ where
     ) -> Self {
         Self {
             user_connection,
+            pooled_user_connections: Vec::new(),
             management_connection,
             ip,
+            metadata: Default::default(),
+            pool_index: Default::default(),
+            latency: Default::default(),
+            paused: Default::default(),
+        }
+    }
+
+    /// Returns the next user connection of the node's pool, in a round-robin manner.
+    pub(crate) fn next_user_connection(&self) -> Connection {
+        if self.pooled_user_connections.is_empty() {
+            return self.user_connection.clone();
+        }
+        let index = self.pool_index.0.fetch_add(1, Ordering::Relaxed)
+            % (self.pooled_user_connections.len() + 1);
+        match index {
+            0 => self.user_connection.clone(),
+            index => self.pooled_user_connections[index - 1].clone(),
         }
     }
 
+    /// Returns all the user connections of the node's pool.
+    pub(crate) fn all_user_connections(&self) -> impl Iterator<Item = &Connection> + '_ {
+        std::iter::once(&self.user_connection).chain(self.pooled_user_connections.iter())
+    }
+
     pub(crate) fn get_connection(&self, conn_type: &ConnectionType) -> Connection {
         match conn_type {
-            ConnectionType::User => self.user_connection.clone(),
+            ConnectionType::User => self.next_user_connection(),
             ConnectionType::PreferManagement => self
                 .management_connection
                 .clone()
-                .unwrap_or_else(|| self.user_connection.clone()),
+                .unwrap_or_else(|| self.next_user_connection()),
         }
     }
 }
@@ -66,8 +198,11 @@ impl<Connection> std::fmt::Display for ConnectionsMap<Connection> {
 pub(crate) struct ConnectionsContainer<Connection> {
     connection_map: HashMap<ArcStr, ClusterNode<Connection>>,
     pub(crate) slot_map: SlotMap,
+    // The slot map of the last refresh, if slots were updated by `MOVED` redirections since.
+    refreshed_slot_map: Option<SlotMap>,
     read_from_replica_strategy: ReadFromReplicaStrategy,
     topology_hash: TopologyHash,
+    slots_refreshed_at: Option<SystemTime>,
 }
 
 impl<Connection> Default for ConnectionsContainer<Connection> {
@@ -75,8 +210,10 @@ impl<Connection> Default for ConnectionsContainer<Connection> {
         Self {
             connection_map: Default::default(),
             slot_map: Default::default(),
+            refreshed_slot_map: None,
             read_from_replica_strategy: ReadFromReplicaStrategy::AlwaysFromPrimary,
             topology_hash: 0,
+            slots_refreshed_at: None,
         }
     }
 }
@@ -93,14 +230,36 @@ where
         read_from_replica_strategy: ReadFromReplicaStrategy,
         topology_hash: TopologyHash,
     ) -> Self {
+        // The containers without slots hold the connections to the initial nodes, before the slots are refreshed.
+        let slots_refreshed_at = (!slot_map.slots.is_empty()).then(SystemTime::now);
         Self {
             connection_map: connection_map.0,
             slot_map,
+            refreshed_slot_map: None,
             read_from_replica_strategy,
             topology_hash,
+            slots_refreshed_at,
         }
     }
 
+    /// Returns the slot map of the last refresh, without the slots that were updated by `MOVED` redirections since.
+    pub(crate) fn refreshed_slot_map(&self) -> &SlotMap {
+        self.refreshed_slot_map.as_ref().unwrap_or(&self.slot_map)
+    }
+
+    /// Points `slot` to `primary` until the next refresh, as found by a `MOVED` redirection.
+    pub(crate) fn update_slot_primary(&mut self, slot: u16, primary: &str) {
+        if self.refreshed_slot_map.is_none() {
+            self.refreshed_slot_map = Some(self.slot_map.clone());
+        }
+        self.slot_map.update_slot_primary(slot, primary);
+    }
+
+    /// Returns when the slot map was last refreshed, or `None` if it wasn't refreshed yet.
+    pub(crate) fn slots_refreshed_at(&self) -> Option<SystemTime> {
+        self.slots_refreshed_at
+    }
+
     /// Returns true if the address represents a known primary node.
     pub(crate) fn is_primary(&self, address: &ArcStr) -> bool {
         self.connection_for_address(address).is_some()
@@ -122,12 +281,12 @@ where
         loop {
             check_count += 1;
 
-            // Looped through all replicas, no connected replica was found.
+            // Looped through all replicas, no connected replica that isn't paused was found.
             if check_count > addrs.replicas.len() {
                 return self.connection_for_address(addrs.primary.as_str());
             }
             let index = (initial_index + check_count) % addrs.replicas.len();
-            if let Some(connection) = self.connection_for_address(addrs.replicas[index].as_str()) {
+            if let Some(connection) = self.unpaused_connection_for_address(&addrs.replicas[index]) {
                 let _ = slot_map_value.latest_used_replica.compare_exchange_weak(
                     initial_index,
                     index,
@@ -155,11 +314,47 @@ where
                 ReadFromReplicaStrategy::RoundRobin => {
                     self.round_robin_read_from_replica(slot_map_value)
                 }
+                ReadFromReplicaStrategy::LowestLatency => self
+                    .lowest_latency_replica(slot_map_value)
+                    .or_else(|| self.round_robin_read_from_replica(slot_map_value)),
             },
             SlotAddr::ReplicaRequired => self.round_robin_read_from_replica(slot_map_value),
+            SlotAddr::Nearest => self.lowest_latency_connection(slot_map_value),
         }
     }
 
+    /// Returns the connected node of the slot with the lowest latency, skipping the paused nodes. The nodes whose
+    /// latency is unknown are only chosen if no latency is known, in which case the primary is preferred.
+    fn lowest_latency_connection(
+        &self,
+        slot_map_value: &SlotMapValue,
+    ) -> Option<ConnectionAndAddress<Connection>> {
+        self.lowest_latency_node(slot_map_value.addrs.into_iter())
+    }
+
+    /// Returns the connected replica of the slot with the lowest known latency, or `None` if no replica's latency is
+    /// known.
+    fn lowest_latency_replica(
+        &self,
+        slot_map_value: &SlotMapValue,
+    ) -> Option<ConnectionAndAddress<Connection>> {
+        let (address, connection) =
+            self.lowest_latency_node(slot_map_value.addrs.replicas.iter())?;
+        self.connection_map[&address].latency.get()?;
+        Some((address, connection))
+    }
+
+    fn lowest_latency_node<'a>(
+        &self,
+        addresses: impl Iterator<Item = &'a String>,
+    ) -> Option<ConnectionAndAddress<Connection>> {
+        addresses
+            .filter_map(|address| self.connection_map.get_key_value(address.as_str()))
+            .filter(|(_, node)| !node.paused.is_paused())
+            .min_by_key(|(_, node)| node.latency.get().unwrap_or(Duration::MAX))
+            .map(|(address, node)| (address.clone(), node.next_user_connection()))
+    }
+
     pub(crate) fn connection_for_route(
         &self,
         route: &Route,
@@ -178,7 +373,7 @@ where
     ) -> impl Iterator<Item = ConnectionAndAddress<Connection>> + '_ {
         self.connection_map
             .iter()
-            .map(move |(address, node)| (address.clone(), node.user_connection.clone()))
+            .map(move |(address, node)| (address.clone(), node.next_user_connection()))
     }
 
     pub(crate) fn all_primary_connections(
@@ -190,6 +385,20 @@ where
             .flat_map(|addr| self.connection_for_address(addr))
     }
 
+    pub(crate) fn all_nodes(
+        &self,
+    ) -> impl Iterator<Item = (&ArcStr, &ClusterNode<Connection>)> + '_ {
+        self.connection_map.iter()
+    }
+
+    pub(crate) fn all_node_metadata(
+        &self,
+    ) -> impl Iterator<Item = (&ArcStr, &Arc<NodeMetadata>)> + '_ {
+        self.connection_map
+            .iter()
+            .map(|(address, node)| (address, &node.metadata))
+    }
+
     pub(crate) fn node_for_address(&self, address: &str) -> Option<ClusterNode<Connection>> {
         self.connection_map.get(address).cloned()
     }
@@ -200,7 +409,17 @@ where
     ) -> Option<ConnectionAndAddress<Connection>> {
         self.connection_map
             .get_key_value(address)
-            .map(|(address, conn)| (address.clone(), conn.user_connection.clone()))
+            .map(|(address, node)| (address.clone(), node.next_user_connection()))
+    }
+
+    fn unpaused_connection_for_address(
+        &self,
+        address: &str,
+    ) -> Option<ConnectionAndAddress<Connection>> {
+        self.connection_map
+            .get_key_value(address)
+            .filter(|(_, node)| !node.paused.is_paused())
+            .map(|(address, node)| (address.clone(), node.next_user_connection()))
     }
 
     pub(crate) fn random_connections(
@@ -240,6 +459,24 @@ where
         self.topology_hash
     }
 
+    /// Replaces the slot ranges that are served by the given shard, along with the topology hash. Returns false,
+    /// without changing anything, if the rest of the slot ranges differ from `slot_map`, or if slots were updated
+    /// by redirections since the last refresh.
+    pub(crate) fn replace_shard(
+        &mut self,
+        shard_addresses: &HashSet<String>,
+        slot_map: SlotMap,
+        topology_hash: TopologyHash,
+    ) -> bool {
+        if self.refreshed_slot_map.is_some()
+            || !self.slot_map.replace_shard(shard_addresses, slot_map)
+        {
+            return false;
+        }
+        self.topology_hash = topology_hash;
+        true
+    }
+
     /// Returns true if the connections container contains no connections.
     pub(crate) fn is_empty(&self) -> bool {
         self.connection_map.is_empty()
@@ -248,8 +485,6 @@ where
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashSet;
-
     use crate::cluster_routing::Slot;
 
     use super::*;
@@ -258,11 +493,7 @@ mod tests {
         Connection: Clone,
     {
         pub(crate) fn new_only_with_user_conn(user_connection: Connection) -> Self {
-            Self {
-                user_connection,
-                management_connection: None,
-                ip: None,
-            }
+            Self::new(user_connection, None, None)
         }
     }
     fn remove_nodes(container: &mut ConnectionsContainer<usize>, addresss: &[&str]) {
@@ -357,9 +588,11 @@ mod tests {
 
         ConnectionsContainer {
             slot_map,
+            refreshed_slot_map: None,
             connection_map,
             read_from_replica_strategy: stragey,
             topology_hash: 0,
+            slots_refreshed_at: None,
         }
     }
 
@@ -501,6 +734,141 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn get_replica_connection_skips_paused_replicas() {
+        let container = create_container();
+        let pause = |address: &str, paused: bool| {
+            container.connection_map[address].paused.set(paused);
+        };
+        let replica = |slot_addr| {
+            container
+                .connection_for_route(&Route::new(2001, slot_addr))
+                .unwrap()
+                .1
+        };
+
+        pause("replica3-1", true);
+        for _ in 0..4 {
+            assert_eq!(32, replica(SlotAddr::ReplicaOptional));
+        }
+        pause("primary3", true);
+        assert_eq!(32, replica(SlotAddr::Nearest));
+
+        // Without nodes that aren't paused, the reads go to the primary, which still receives the writes when it's
+        // paused.
+        pause("replica3-2", true);
+        assert_eq!(3, replica(SlotAddr::ReplicaRequired));
+        assert_eq!(3, replica(SlotAddr::Nearest));
+        assert_eq!(3, replica(SlotAddr::Master));
+
+        pause("replica3-1", false);
+        assert_eq!(31, replica(SlotAddr::ReplicaOptional));
+    }
+
+    #[test]
+    fn get_lowest_latency_connection_for_nearest_route() {
+        let container =
+            create_container_with_strategy(ReadFromReplicaStrategy::AlwaysFromPrimary, false);
+        let set_latency = |address: &str, latency: Option<Duration>| {
+            container.connection_map[address].latency.record(latency)
+        };
+        let nearest = || {
+            container
+                .connection_for_route(&Route::new(2001, SlotAddr::Nearest))
+                .unwrap()
+                .1
+        };
+
+        // Without known latencies, the primary is chosen.
+        assert_eq!(3, nearest());
+
+        set_latency("primary3", Some(Duration::from_millis(5)));
+        set_latency("replica3-1", Some(Duration::from_millis(2)));
+        set_latency("replica3-2", Some(Duration::from_millis(3)));
+        assert_eq!(31, nearest());
+
+        // A node whose last measurement failed is only chosen if no latency is known.
+        set_latency("replica3-1", None);
+        assert_eq!(32, nearest());
+    }
+
+    #[test]
+    fn get_nearest_connection_keeps_latency_of_cloned_nodes() {
+        let container = create_container();
+        let node = container.node_for_address("replica2-1").unwrap();
+        node.latency.record(Some(Duration::from_micros(1)));
+        container.connection_map["primary2"]
+            .latency
+            .record(Some(Duration::from_millis(1)));
+
+        assert_eq!(
+            21,
+            container
+                .connection_for_route(&Route::new(1002, SlotAddr::Nearest))
+                .unwrap()
+                .1
+        );
+    }
+
+    #[test]
+    fn get_lowest_latency_replica_for_replica_route_with_lowest_latency_strategy() {
+        let container =
+            create_container_with_strategy(ReadFromReplicaStrategy::LowestLatency, false);
+        let record_latency = |address: &str, latency: Option<Duration>| {
+            container.connection_map[address].latency.record(latency)
+        };
+        let replica = || {
+            container
+                .connection_for_route(&Route::new(2001, SlotAddr::ReplicaOptional))
+                .unwrap()
+                .1
+        };
+
+        // Without known latencies, the replicas are used in a round robin manner.
+        assert!(one_of(
+            container.connection_for_route(&Route::new(2001, SlotAddr::ReplicaOptional)),
+            &[31, 32],
+        ));
+
+        record_latency("primary3", Some(Duration::from_millis(1)));
+        record_latency("replica3-1", Some(Duration::from_millis(3)));
+        record_latency("replica3-2", Some(Duration::from_millis(2)));
+        assert_eq!(32, replica());
+
+        // A single slow measurement doesn't outweigh the average.
+        record_latency("replica3-2", Some(Duration::from_millis(5)));
+        assert_eq!(32, replica());
+        record_latency("replica3-2", Some(Duration::from_millis(5)));
+        assert_eq!(31, replica());
+
+        record_latency("replica3-1", None);
+        assert_eq!(32, replica());
+
+        assert_eq!(
+            3,
+            container
+                .connection_for_route(&Route::new(2001, SlotAddr::Master))
+                .unwrap()
+                .1
+        );
+    }
+
+    #[test]
+    fn node_latency_is_a_moving_average_of_the_measurements() {
+        let latency = NodeLatency::default();
+        assert_eq!(None, latency.get());
+
+        latency.record(Some(Duration::from_micros(400)));
+        assert_eq!(Some(Duration::from_micros(400)), latency.get());
+        latency.record(Some(Duration::from_micros(800)));
+        assert_eq!(Some(Duration::from_micros(500)), latency.get());
+
+        latency.record(None);
+        assert_eq!(None, latency.get());
+        latency.record(Some(Duration::from_micros(100)));
+        assert_eq!(Some(Duration::from_micros(100)), latency.get());
+    }
+
     #[test]
     fn get_primary_connection_for_replica_route_if_all_replicas_were_removed() {
         let mut container = create_container();
@@ -1,6 +1,6 @@
 use std::net::{IpAddr, SocketAddr};
 
-use super::{connections_container::ClusterNode, Connect};
+use super::{connections_container::ClusterNode, Connect, HealthCheckProbe, SharedClock};
 use crate::{
     aio::{get_socket_addrs, ConnectionLike, Runtime},
     cluster::get_connection_info,
@@ -11,6 +11,7 @@ use crate::{
 
 use futures::prelude::*;
 use futures_util::{future::BoxFuture, join};
+use std::sync::Arc;
 use tokio::sync::mpsc;
 use tracing::warn;
 
@@ -42,17 +43,32 @@ where
 /// If no socket addresses are discovered for the node's host address, or if it's a non-DNS address, it returns false.
 /// In case the node's host address resolves to socket addresses and none of them match the current connection's IP,
 /// a DNS change is detected, so the current connection isn't valid anymore and a new connection should be made.
-async fn has_dns_changed(addr: &str, curr_ip: &IpAddr) -> bool {
+async fn has_dns_changed(addr: &str, curr_ip: &IpAddr, params: &ClusterParams) -> bool {
     let (host, port) = match get_host_and_port_from_addr(addr) {
         Some((host, port)) => (host, port),
         None => return false,
     };
-    let mut updated_addresses = match get_socket_addrs(host, port).await {
+    let updated_addresses = match resolve_socket_addrs(host, port, params).await {
         Ok(socket_addrs) => socket_addrs,
         Err(_) => return false,
     };
 
-    !updated_addresses.any(|socket_addr| socket_addr.ip() == *curr_ip)
+    !updated_addresses
+        .iter()
+        .any(|socket_addr| socket_addr.ip() == *curr_ip)
+}
+
+/// Resolves the host and port into socket addresses.
+/// If the client was configured with [`SharedResources`](super::SharedResources), the shared DNS cache and resolver are used.
+pub(crate) async fn resolve_socket_addrs(
+    host: &str,
+    port: u16,
+    params: &ClusterParams,
+) -> RedisResult<Vec<SocketAddr>> {
+    match &params.shared_resources {
+        Some(shared_resources) => shared_resources.resolve(host, port).await,
+        None => Ok(get_socket_addrs(host, port).await?.collect()),
+    }
 }
 
 fn failed_management_connection<C>(
@@ -172,7 +188,7 @@ where
             } else {
                 // Use only the connection with the latest IP address
                 warn_mismatch_ip(addr, user_ip, management_ip);
-                if has_dns_changed(addr, &user_ip.unwrap()).await {
+                if has_dns_changed(addr, &user_ip.unwrap(), &params).await {
                     // The user_ip is incorrect. Use the created `management_conn` for the user connection
                     user_conn = management_conn;
                     user_ip = management_ip;
@@ -255,10 +271,21 @@ where
                 return failed_management_connection(addr, final_user_conn, user_ip, err);
             }
 
+            // The pool is kept only when the user connection is, a new one is opened along with the new user connection.
+            let pooled_user_connections = if user_ip == prev_node.ip {
+                prev_node.pooled_user_connections
+            } else {
+                Vec::new()
+            };
             ConnectAndCheckResult::Success(ClusterNode {
                 user_connection: final_user_conn,
+                pooled_user_connections,
                 ip: user_ip,
                 management_connection: Some(to_future(mngm_conn.0)),
+                metadata: prev_node.metadata,
+                pool_index: prev_node.pool_index,
+                latency: prev_node.latency,
+                paused: prev_node.paused,
             })
         }
     }
@@ -287,6 +314,16 @@ impl<C> ConnectAndCheckResult<C> {
         }
     }
 
+    fn map_node(self, f: impl FnOnce(AsyncClusterNode<C>) -> AsyncClusterNode<C>) -> Self {
+        match self {
+            ConnectAndCheckResult::Success(node) => ConnectAndCheckResult::Success(f(node)),
+            ConnectAndCheckResult::ManagementConnectionFailed { node, err } => {
+                ConnectAndCheckResult::ManagementConnectionFailed { node: f(node), err }
+            }
+            ConnectAndCheckResult::Failed(err) => ConnectAndCheckResult::Failed(err),
+        }
+    }
+
     pub fn get_error(self) -> Option<RedisError> {
         match self {
             ConnectAndCheckResult::Success(_) => None,
@@ -326,6 +363,89 @@ pub async fn connect_and_check<C>(
     node: Option<AsyncClusterNode<C>>,
     push_sender: Option<mpsc::UnboundedSender<PushInfo>>,
 ) -> ConnectAndCheckResult<C>
+where
+    C: ConnectionLike + Connect + Send + Sync + 'static + Clone,
+{
+    // A reconnected node keeps its metadata, and the metadata of a new node is provided once it's connected.
+    let metadata = node.as_ref().map(|node| node.metadata.clone());
+    let node_metadata_provider = params.node_metadata_provider.clone();
+    let result = connect_and_check_node(
+        addr,
+        params.clone(),
+        socket_addr,
+        conn_type,
+        node,
+        push_sender.clone(),
+    )
+    .await;
+    let pooled_user_connections = match &result {
+        ConnectAndCheckResult::Success(node)
+        | ConnectAndCheckResult::ManagementConnectionFailed { node, .. } => {
+            let missing = params
+                .connection_pool_size
+                .saturating_sub(1)
+                .saturating_sub(node.pooled_user_connections.len());
+            create_pooled_user_connections(addr, &params, socket_addr, push_sender, missing).await
+        }
+        ConnectAndCheckResult::Failed(_) => Vec::new(),
+    };
+    result.map_node(|mut node| {
+        node.pooled_user_connections.extend(pooled_user_connections);
+        node.metadata = match (metadata, node_metadata_provider) {
+            (Some(metadata), _) => metadata,
+            (None, Some(provider)) => Arc::new(provider(addr, node.ip)),
+            (None, None) => Default::default(),
+        };
+        node
+    })
+}
+
+/// Opens the user connections of the node's pool that are opened in addition to its main user connection. A
+/// connection that fails is left out, and is retried by the next health check of the node.
+async fn create_pooled_user_connections<C>(
+    addr: &str,
+    params: &ClusterParams,
+    socket_addr: Option<SocketAddr>,
+    push_sender: Option<mpsc::UnboundedSender<PushInfo>>,
+    amount: usize,
+) -> Vec<ConnectionFuture<C>>
+where
+    C: ConnectionLike + Connect + Send + Sync + 'static + Clone,
+{
+    let mut params = params.clone();
+    // The subscriptions are restored only on the main user connection, so that their messages aren't duplicated.
+    params.pubsub_subscriptions = None;
+    future::join_all((0..amount).map(|_| {
+        create_and_setup_user_connection::<C>(
+            addr,
+            params.clone(),
+            socket_addr,
+            push_sender.clone(),
+        )
+    }))
+    .await
+    .into_iter()
+    .filter_map(|result| match result {
+        Ok((conn, _ip)) => Some(to_future(conn)),
+        Err(err) => {
+            warn!(
+                "Failed to create a pooled user connection for node `{:?}`. Error: `{:?}`",
+                addr, err
+            );
+            None
+        }
+    })
+    .collect()
+}
+
+async fn connect_and_check_node<C>(
+    addr: &str,
+    params: ClusterParams,
+    socket_addr: Option<SocketAddr>,
+    conn_type: RefreshConnectionType,
+    node: Option<AsyncClusterNode<C>>,
+    push_sender: Option<mpsc::UnboundedSender<PushInfo>>,
+) -> ConnectAndCheckResult<C>
 where
     C: ConnectionLike + Connect + Send + Sync + 'static + Clone,
 {
@@ -399,6 +519,21 @@ where
     Ok((conn, ip))
 }
 
+/// Opens a user connection to `addr` that isn't part of the node's connections, for a request that would hold up the
+/// requests that share the node's connections. The connection receives no push notifications, and it's closed once
+/// it's dropped.
+pub(crate) async fn create_ephemeral_connection<C>(
+    addr: &str,
+    params: ClusterParams,
+) -> RedisResult<C>
+where
+    C: ConnectionLike + Connect + Send + 'static,
+{
+    create_and_setup_user_connection(addr, params, None, None)
+        .await
+        .map(|(conn, _ip)| conn)
+}
+
 async fn create_and_setup_management_connection<C>(
     node: &str,
     params: ClusterParams,
@@ -420,11 +555,14 @@ where
     let read_from_replicas = params.read_from_replicas
         != crate::cluster_slotmap::ReadFromReplicaStrategy::AlwaysFromPrimary;
     let connection_timeout = params.connection_timeout;
-    check_connection(conn, connection_timeout).await?;
+    check_connection(conn, connection_timeout, &params.health_check_probe).await?;
     if read_from_replicas {
         // If READONLY is sent to primary nodes, it will have no effect
         crate::cmd("READONLY").query_async(conn).await?;
     }
+    if let Some(cache_config) = &params.cache_config {
+        cache_config.tracking_command().query_async(conn).await?;
+    }
     Ok(())
 }
 
@@ -454,19 +592,66 @@ where
 {
     let connection_timeout = params.connection_timeout;
     let response_timeout = params.response_timeout;
+    let flush_policy = params.flush_policy;
+    let max_reply_size = params.max_reply_size;
     // ignore pubsub subscriptions and push notifications for management connections
     if is_management {
         params.pubsub_subscriptions = None;
     }
+    let shared_resources = params.shared_resources.clone();
+    // Nodes that are mapped to a custom transport are connected to through it, rather than through their address.
+    let is_mapped = params.mapped_transport(node).is_some();
+    let socket_addr = socket_addr.filter(|_| !is_mapped);
+    // When the DNS cache is shared, resolve the node's address through it instead of letting the connection resolve it again.
+    let shared_dns_entry = match (&shared_resources, socket_addr) {
+        (Some(shared_resources), None) if !is_mapped => {
+            get_host_and_port_from_addr(node).map(|(host, port)| (shared_resources, host, port))
+        }
+        _ => None,
+    };
+    let socket_addrs = match shared_dns_entry {
+        Some((shared_resources, host, port)) => {
+            shared_resources.resolve(host, port).await.map_or_else(
+                |_| vec![None],
+                |addresses| addresses.into_iter().map(Some).collect(),
+            )
+        }
+        None => vec![socket_addr],
+    };
+    let connect_limiter = params.connect_limiter.clone();
     let info = get_connection_info(node, params)?;
-    C::connect(
-        info,
-        response_timeout,
-        connection_timeout,
-        socket_addr,
-        if !is_management { push_sender } else { None },
-    )
-    .await
+    let _permit = match &connect_limiter {
+        Some(connect_limiter) => Some(connect_limiter.acquire().await),
+        None => None,
+    };
+    let push_sender = if !is_management { push_sender } else { None };
+    // The resolved addresses are tried in turn, so that an unreachable address doesn't fail the connection while
+    // another address of the node is reachable.
+    let mut socket_addrs = socket_addrs.into_iter().peekable();
+    let result = loop {
+        let result = C::connect(
+            info.clone(),
+            response_timeout,
+            connection_timeout,
+            socket_addrs.next().flatten(),
+            push_sender.clone(),
+        )
+        .await;
+        if result.is_ok() || socket_addrs.peek().is_none() {
+            break result;
+        }
+    };
+    if let (Err(_), Some((shared_resources, host, port))) = (&result, shared_dns_entry) {
+        // The cached addresses might be stale, so the next attempt should query the resolver
+        shared_resources.invalidate_dns_entry(host, port);
+    }
+    result.map(|(mut conn, ip)| {
+        conn.set_flush_policy(flush_policy);
+        if let Some(max_reply_size) = max_reply_size {
+            conn.set_max_reply_size(max_reply_size);
+        }
+        (conn, ip)
+    })
 }
 
 /// The function returns None if the checked connection/s are healthy. Otherwise, it returns the type of the unhealthy connection/s.
@@ -479,16 +664,19 @@ pub async fn check_node_connections<C>(
     address: &str,
 ) -> Option<RefreshConnectionType>
 where
-    C: ConnectionLike + Send + 'static + Clone,
+    C: ConnectionLike + Connect + Send + 'static + Clone,
 {
     let timeout = params.connection_timeout;
+    let freshness = params.health_check_freshness;
+    let probe = &params.health_check_probe;
+    let clock = &params.clock;
     let (check_mgmt_connection, check_user_connection) = match conn_type {
         RefreshConnectionType::OnlyUserConnection => (false, true),
         RefreshConnectionType::OnlyManagementConnection => (true, false),
         RefreshConnectionType::AllConnections => (true, true),
     };
     let check = |conn, timeout, conn_type| async move {
-        match check_connection(&mut conn.await, timeout).await {
+        match check_connection_health(&mut conn.await, timeout, freshness, probe, clock).await {
             Ok(_) => false,
             Err(err) => {
                 warn!(
@@ -516,8 +704,17 @@ where
             if !check_user_connection {
                 return false;
             }
-            let conn = node.user_connection.clone();
-            check(conn, timeout, "user").await
+            if node.pooled_user_connections.len() + 1 < params.connection_pool_size {
+                warn!("The user connection pool for node {} isn't full", address);
+                return true;
+            }
+            future::join_all(
+                node.all_user_connections()
+                    .map(|conn| check(conn.clone(), timeout, "user")),
+            )
+            .await
+            .into_iter()
+            .any(|failed| failed)
         },
     );
 
@@ -529,14 +726,38 @@ where
     }
 }
 
-async fn check_connection<C>(conn: &mut C, timeout: std::time::Duration) -> RedisResult<()>
+pub(crate) async fn check_connection<C>(
+    conn: &mut C,
+    timeout: std::time::Duration,
+    probe: &HealthCheckProbe,
+) -> RedisResult<()>
 where
     C: ConnectionLike + Send + 'static,
 {
     Runtime::locate()
-        .timeout(timeout, crate::cmd("PING").query_async::<_, String>(conn))
-        .await??;
-    Ok(())
+        .timeout(timeout, probe.check(conn))
+        .await?
+}
+
+/// Checks the health of a connection like [`check_connection`], unless the connection received a successful
+/// response within `freshness`, in which case its traffic already shows that it's healthy.
+pub(crate) async fn check_connection_health<C>(
+    conn: &mut C,
+    timeout: std::time::Duration,
+    freshness: Option<std::time::Duration>,
+    probe: &HealthCheckProbe,
+    clock: &SharedClock,
+) -> RedisResult<()>
+where
+    C: ConnectionLike + Connect + Send + 'static,
+{
+    let last_response = freshness.zip(conn.last_successful_response());
+    if let Some((freshness, last_response)) = last_response {
+        if clock.now().saturating_duration_since(last_response) <= freshness {
+            return Ok(());
+        }
+    }
+    check_connection(conn, timeout, probe).await
 }
 
 /// Splits a string address into host and port. If the passed address cannot be parsed, None is returned.
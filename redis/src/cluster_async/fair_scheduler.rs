@@ -0,0 +1,127 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use arcstr::ArcStr;
+
+/// Orders the requests of the tenants that share a connection by weighted round robin, as set by
+/// [`ClusterClientBuilder::tenant_weight`](crate::cluster::ClusterClientBuilder::tenant_weight).
+///
+/// Each tenant with queued requests gets a turn in which it may dispatch up to its weight of requests, so a tenant
+/// with a long queue delays the others by at most its weight. Untagged requests are scheduled as one more tenant.
+/// Tenants without a configured weight have a weight of 1.
+pub(crate) struct FairScheduler<T> {
+    weights: Arc<HashMap<String, u32>>,
+    queues: HashMap<Option<ArcStr>, VecDeque<T>>,
+    // The tenants with queued requests, in the order of their turns. The first one's turn is the current one.
+    turns: VecDeque<Option<ArcStr>>,
+    dispatched_in_turn: u32,
+    len: usize,
+}
+
+impl<T> FairScheduler<T> {
+    pub(crate) fn new(weights: Arc<HashMap<String, u32>>) -> Self {
+        Self {
+            weights,
+            queues: HashMap::new(),
+            turns: VecDeque::new(),
+            dispatched_in_turn: 0,
+            len: 0,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn push(&mut self, tenant: Option<ArcStr>, item: T) {
+        let queue = self.queues.entry(tenant.clone()).or_default();
+        if queue.is_empty() {
+            self.turns.push_back(tenant);
+        }
+        queue.push_back(item);
+        self.len += 1;
+    }
+
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        let tenant = self.turns.front()?.clone();
+        let queue = self.queues.get_mut(&tenant)?;
+        let item = queue.pop_front();
+        self.len -= 1;
+        self.dispatched_in_turn += 1;
+        if queue.is_empty() {
+            self.queues.remove(&tenant);
+            self.turns.pop_front();
+            self.dispatched_in_turn = 0;
+        } else if self.dispatched_in_turn >= self.weight(&tenant) {
+            self.turns.rotate_left(1);
+            self.dispatched_in_turn = 0;
+        }
+        item
+    }
+
+    fn weight(&self, tenant: &Option<ArcStr>) -> u32 {
+        tenant
+            .as_ref()
+            .and_then(|tenant| self.weights.get(tenant.as_str()))
+            .copied()
+            .unwrap_or(1)
+            .max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tenant(name: &str) -> Option<ArcStr> {
+        Some(ArcStr::from(name))
+    }
+
+    #[test]
+    fn test_tenants_take_turns() {
+        let mut scheduler = FairScheduler::new(Default::default());
+        for i in 0..3 {
+            scheduler.push(tenant("noisy"), format!("noisy-{i}"));
+        }
+        scheduler.push(tenant("quiet"), "quiet-0".to_string());
+        scheduler.push(None, "untagged-0".to_string());
+
+        let order: Vec<_> = std::iter::from_fn(|| scheduler.pop()).collect();
+        assert_eq!(
+            order,
+            vec!["noisy-0", "quiet-0", "untagged-0", "noisy-1", "noisy-2"]
+        );
+        assert_eq!(scheduler.len(), 0);
+    }
+
+    #[test]
+    fn test_tenants_dispatch_up_to_their_weight_in_a_turn() {
+        let weights = HashMap::from([("heavy".to_string(), 3)]);
+        let mut scheduler = FairScheduler::new(Arc::new(weights));
+        for i in 0..4 {
+            scheduler.push(tenant("heavy"), format!("heavy-{i}"));
+            scheduler.push(tenant("light"), format!("light-{i}"));
+        }
+
+        let order: Vec<_> = std::iter::from_fn(|| scheduler.pop()).collect();
+        assert_eq!(
+            order,
+            vec![
+                "heavy-0", "heavy-1", "heavy-2", "light-0", "heavy-3", "light-1", "light-2",
+                "light-3"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_a_tenant_that_empties_its_queue_rejoins_at_the_back() {
+        let mut scheduler = FairScheduler::new(Default::default());
+        scheduler.push(tenant("a"), 1);
+        scheduler.push(tenant("b"), 2);
+        assert_eq!(scheduler.pop(), Some(1));
+        scheduler.push(tenant("a"), 3);
+        assert_eq!(scheduler.pop(), Some(2));
+        assert_eq!(scheduler.pop(), Some(3));
+        assert_eq!(scheduler.pop(), None);
+    }
+}
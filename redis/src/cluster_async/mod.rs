@@ -5,7 +5,7 @@
 //! the sync cluster module, certain commands do not route identically, due most notably to
 //! a current lack of support for routing commands to multiple nodes.
 //!
-//! Also note that pubsub functionality is not currently provided by this module.
+//! Pubsub subscriptions are managed at runtime by [`ClusterPubSub`], which requires RESP3.
 //!
 //! # Example
 //! ```rust,no_run
@@ -22,43 +22,108 @@
 //! }
 //! ```
 
+mod circuit_breaker;
+mod clock;
+mod cluster_management;
+mod connect_limiter;
 mod connections_container;
 mod connections_logic;
+mod fair_scheduler;
+mod node_identity;
+mod node_overrides;
+mod pending_requests;
+mod pinned_route;
+mod pipeline_stream;
+mod pubsub;
+mod reconnect_backoff;
+pub mod replication_group;
+mod request_size;
+mod scan;
+mod shared_resources;
+mod slot_migrations;
+mod slot_stats;
+mod topology;
+mod topology_check_interval;
+mod topology_check_status;
+#[cfg(feature = "traffic-recorder")]
+mod traffic_recorder;
+pub(crate) use circuit_breaker::CircuitBreakerParams;
+pub(crate) use clock::SharedClock;
+#[cfg(feature = "tokio-comp")]
+pub use clock::TokioClock;
+pub use clock::{Clock, SystemClock};
+pub(crate) use connect_limiter::ConnectLimiter;
+pub use connect_limiter::ConnectLimiterStats;
+pub(crate) use node_overrides::NodeOverrides;
+pub use node_overrides::{NodeOverride, AVAILABILITY_ZONE_METADATA_KEY, WEIGHT_METADATA_KEY};
+pub use pending_requests::PendingRequestsStats;
+pub use pinned_route::PinnedRoute;
+pub use pipeline_stream::ClusterPipelineStream;
+pub use pubsub::ClusterPubSub;
+pub(crate) use reconnect_backoff::ReconnectBackoffParams;
+pub use request_size::RequestSize;
+pub use scan::ClusterScanStream;
+pub use shared_resources::{
+    DnsResolver, SharedResources, SharedResourcesBuilder, DEFAULT_DNS_CACHE_TTL,
+};
+pub use slot_migrations::SlotMigration;
+pub use slot_stats::SlotStats;
+pub use topology::{
+    ClusterTopology, SlotRangeMove, TopologyChangePlan, TopologyEvent, TopologyNode, TopologySlot,
+};
+pub(crate) use topology_check_interval::AdaptiveTopologyChecks;
+pub use topology_check_status::TopologyCheckStats;
+#[cfg(feature = "traffic-recorder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "traffic-recorder")))]
+pub use traffic_recorder::{
+    read_traffic_log, RecordedArg, RecordedRouting, TrafficRecord, TrafficRecorder, TrafficReplayer,
+};
 /// Exposed only for testing.
 pub mod testing {
     pub use super::connections_logic::*;
 }
 use std::{
-    collections::{HashMap, HashSet},
-    fmt, io, mem,
+    any::Any,
+    collections::{HashMap, HashSet, VecDeque},
+    fmt, io,
     net::{IpAddr, SocketAddr},
+    panic::{self, AssertUnwindSafe},
     pin::Pin,
     sync::{
-        atomic::{self, AtomicUsize, Ordering},
-        Arc, Mutex,
+        atomic::{self, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
     },
     task::{self, Poll},
-    time::SystemTime,
+    time::Instant,
 };
 
+#[cfg(feature = "acl")]
+use crate::acl::{AclInfo, AclRules};
+#[cfg(feature = "script")]
+use crate::Script;
 use crate::{
     cluster_slotmap::SlotMap,
     cluster_topology::SLOT_SIZE,
     cmd,
-    commands::cluster_scan::{cluster_scan, ClusterScanArgs, ObjectType, ScanStateRC},
-    FromRedisValue, InfoDict,
+    commands::cluster_scan::{
+        cluster_scan, cluster_scan_all_shards, ClusterScanArgs, ClusterScanCursor,
+        ClusterScanOptions, ObjectType, ScanStateRC,
+    },
+    from_owned_redis_value, from_redis_value, FromRedisValue, InfoDict, MemoryStats,
 };
 
 use crate::{
-    aio::{get_socket_addrs, ConnectionLike, MultiplexedConnection, Runtime},
+    aio::{ConnectionLike, MultiplexedConnection, Runtime},
     cluster::slot_cmd,
     cluster_async::connections_logic::{
-        get_host_and_port_from_addr, get_or_create_conn, ConnectionFuture, RefreshConnectionType,
+        check_connection, check_connection_health, create_ephemeral_connection,
+        get_host_and_port_from_addr, get_or_create_conn, resolve_socket_addrs, ConnectionFuture,
+        RefreshConnectionType,
     },
     cluster_client::{ClusterParams, RetryParams},
     cluster_routing::{
-        self, MultipleNodeRoutingInfo, Redirect, ResponsePolicy, Route, SingleNodeRoutingInfo,
-        SlotAddr,
+        self, MultipleNodeRoutingInfo, ReadPreference, Redirect, ResponsePolicy, Routable, Route,
+        SingleNodeRoutingInfo, SlotAddr,
     },
     cluster_topology::{
         calculate_topology, get_slot, SlotRefreshState, DEFAULT_NUMBER_OF_REFRESH_SLOTS_RETRIES,
@@ -66,6 +131,7 @@ use crate::{
     },
     connection::{PubSubSubscriptionInfo, PubSubSubscriptionKind},
     push_manager::PushInfo,
+    types::PushKind,
     Cmd, ConnectionInfo, ErrorKind, IntoConnectionInfo, RedisError, RedisFuture, RedisResult,
     Value,
 };
@@ -85,27 +151,40 @@ use backoff_tokio::future::retry;
 #[cfg(feature = "tokio-comp")]
 use backoff_tokio::{Error as BackoffError, ExponentialBackoff};
 
-use dispose::{Disposable, Dispose};
 use futures::{future::BoxFuture, prelude::*, ready};
 use pin_project_lite::pin_project;
 use std::sync::atomic::AtomicBool;
 use tokio::sync::{
-    mpsc,
+    broadcast, mpsc,
     oneshot::{self, Receiver},
-    RwLock,
+    Mutex as AsyncMutex, RwLock,
 };
 use tracing::{debug, info, trace, warn};
 
 use self::{
+    circuit_breaker::CircuitBreakers,
     connections_container::{ConnectionAndAddress, ConnectionType, ConnectionsMap},
     connections_logic::connect_and_check,
+    fair_scheduler::FairScheduler,
+    node_identity::{NodeChange, NodeIdentities},
+    pending_requests::PendingRequests,
+    reconnect_backoff::ReconnectBackoffs,
+    request_size::RequestSizeCounter,
+    slot_migrations::SlotMigrations,
+    slot_stats::SlotCounters,
+    topology_check_interval::TopologyCheckInterval,
+    topology_check_status::TopologyCheckStatus,
 };
 
 /// This represents an async Redis Cluster connection. It stores the
 /// underlying connections maintained for each node in the cluster, as well
 /// as common parameters for connecting to nodes and executing commands.
 #[derive(Clone)]
-pub struct ClusterConnection<C = MultiplexedConnection>(mpsc::Sender<Message<C>>);
+pub struct ClusterConnection<C = MultiplexedConnection>(
+    mpsc::Sender<Message<C>>,
+    Core<C>,
+    Option<ArcStr>,
+);
 
 impl<C> ClusterConnection<C>
 where
@@ -119,20 +198,540 @@ where
         ClusterConnInner::new(initial_nodes, cluster_params, push_sender)
             .await
             .map(|inner| {
-                let (tx, mut rx) = mpsc::channel::<Message<_>>(100);
-                let stream = async move {
-                    let _ = stream::poll_fn(move |cx| rx.poll_recv(cx))
-                        .map(Ok)
-                        .forward(inner)
-                        .await;
+                let core = inner.inner.clone();
+                let shutdown_flag = inner.shutdown_flag.clone();
+                let (tx, rx) = mpsc::channel::<Message<_>>(100);
+                let stream = Driver {
+                    core: core.clone(),
+                    shutdown_flag,
+                    future: ClusterConnInner::supervise(inner, rx).boxed(),
                 };
                 #[cfg(feature = "tokio-comp")]
                 tokio::spawn(stream);
                 #[cfg(all(not(feature = "tokio-comp"), feature = "async-std-comp"))]
                 AsyncStd::spawn(stream);
 
-                ClusterConnection(tx)
+                ClusterConnection(tx, core, None)
+            })
+    }
+
+    /// Runs `MEMORY DOCTOR` on all the nodes of the cluster, and returns each node's report by the node's address.
+    pub async fn memory_doctor_all(&mut self) -> RedisResult<HashMap<String, String>> {
+        self.query_all_nodes(cmd("MEMORY").arg("DOCTOR")).await
+    }
+
+    /// Runs `MEMORY STATS` on all the nodes of the cluster, and returns each node's stats by the node's address.
+    pub async fn memory_stats_all(&mut self) -> RedisResult<HashMap<String, MemoryStats>> {
+        self.query_all_nodes(cmd("MEMORY").arg("STATS")).await
+    }
+
+    /// Runs `ACL SETUSER <username> <rules>` on all the nodes of the cluster, and returns each node's result by the
+    /// node's address.
+    ///
+    /// ACL changes aren't propagated between the nodes of a cluster, so they must be applied to every node. Failures
+    /// on some of the nodes don't fail the call, and can be retried on the reported nodes.
+    #[cfg(feature = "acl")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "acl")))]
+    pub async fn acl_setuser_all(
+        &mut self,
+        username: &str,
+        rules: &AclRules,
+    ) -> RedisResult<HashMap<String, RedisResult<()>>> {
+        self.query_each_node(cmd("ACL").arg("SETUSER").arg(username).arg(rules))
+            .await
+    }
+
+    /// Runs `ACL DELUSER <usernames>` on all the nodes of the cluster, and returns each node's result, the number of
+    /// users that were deleted, by the node's address.
+    #[cfg(feature = "acl")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "acl")))]
+    pub async fn acl_deluser_all(
+        &mut self,
+        usernames: &[&str],
+    ) -> RedisResult<HashMap<String, RedisResult<usize>>> {
+        self.query_each_node(cmd("ACL").arg("DELUSER").arg(usernames))
+            .await
+    }
+
+    /// Runs `ACL GETUSER <username>` on all the nodes of the cluster, and returns each node's rules for the user by
+    /// the node's address, or `None` for nodes on which the user doesn't exist.
+    #[cfg(feature = "acl")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "acl")))]
+    pub async fn acl_getuser_all(
+        &mut self,
+        username: &str,
+    ) -> RedisResult<HashMap<String, Option<AclInfo>>> {
+        self.query_all_nodes(cmd("ACL").arg("GETUSER").arg(username))
+            .await
+    }
+
+    /// Runs `ACL LIST` on all the nodes of the cluster, and returns each node's users by the node's address.
+    #[cfg(feature = "acl")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "acl")))]
+    pub async fn acl_list_all(&mut self) -> RedisResult<HashMap<String, Vec<String>>> {
+        self.query_all_nodes(cmd("ACL").arg("LIST")).await
+    }
+
+    /// Runs `SCRIPT EXISTS <hashes>` on all the nodes of the cluster, and returns by each node's address whether
+    /// each of the hashes exists on the node, in the order of `hashes`.
+    ///
+    /// Unlike `SCRIPT EXISTS` with the default routing, which aggregates the replies of the primaries, this includes
+    /// the replicas, since scripts may be invoked on replicas with `EVALSHA_RO`.
+    #[cfg(feature = "script")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "script")))]
+    pub async fn script_exists_all(
+        &mut self,
+        hashes: &[&str],
+    ) -> RedisResult<HashMap<String, Vec<bool>>> {
+        self.query_all_nodes(cmd("SCRIPT").arg("EXISTS").arg(hashes))
+            .await
+    }
+
+    /// Loads `script` on the nodes of the cluster that don't have it, and returns the addresses of the nodes on
+    /// which it was loaded.
+    ///
+    /// This can be used to repair the script cache of the cluster after nodes were replaced or restarted, so that
+    /// `EVALSHA` succeeds on every node.
+    #[cfg(feature = "script")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "script")))]
+    pub async fn ensure_script_loaded(&mut self, script: &Script) -> RedisResult<Vec<String>> {
+        let missing: Vec<String> = self
+            .script_exists_all(&[script.get_hash()])
+            .await?
+            .into_iter()
+            .filter(|(_, exists)| exists.first() != Some(&true))
+            .map(|(address, _)| address)
+            .collect();
+        let load_cmd = script.load_cmd();
+        let results = future::join_all(missing.iter().map(|address| {
+            let mut connection = self.clone();
+            let load_cmd = &load_cmd;
+            async move { connection.route_command_to_address(load_cmd, address).await }
+        }))
+        .await;
+        for result in results {
+            result?;
+        }
+        Ok(missing)
+    }
+
+    async fn route_command_to_address(&mut self, cmd: &Cmd, address: &str) -> RedisResult<Value> {
+        let (host, port) = get_host_and_port_from_addr(address).ok_or_else(|| {
+            RedisError::from((
+                ErrorKind::ClientError,
+                "Invalid node address",
+                address.to_string(),
+            ))
+        })?;
+        self.route_command(
+            cmd,
+            cluster_routing::RoutingInfo::SingleNode(SingleNodeRoutingInfo::ByAddress {
+                host: host.to_string(),
+                port,
+            }),
+        )
+        .await
+    }
+
+    // Unlike `query_all_nodes`, a failure on one of the nodes doesn't fail the call, and is reported in the node's result.
+    #[cfg(feature = "acl")]
+    async fn query_each_node<T: FromRedisValue>(
+        &mut self,
+        cmd: &Cmd,
+    ) -> RedisResult<HashMap<String, RedisResult<T>>> {
+        let addresses: Vec<String> = self
+            .1
+            .conn_lock
+            .read()
+            .await
+            .all_node_connections()
+            .map(|(address, _)| address.to_string())
+            .collect();
+        if addresses.is_empty() {
+            fail!((
+                ErrorKind::ClusterConnectionNotFound,
+                "No nodes are connected"
+            ));
+        }
+        let results = future::join_all(addresses.iter().map(|address| {
+            let mut connection = self.clone();
+            async move {
+                connection
+                    .route_command_to_address(cmd, address)
+                    .await
+                    .and_then(|value| from_redis_value(&value))
+            }
+        }))
+        .await;
+        Ok(addresses.into_iter().zip(results).collect())
+    }
+
+    async fn query_all_nodes<T: FromRedisValue>(
+        &mut self,
+        cmd: &Cmd,
+    ) -> RedisResult<HashMap<String, T>> {
+        let value = self
+            .route_command(
+                cmd,
+                cluster_routing::RoutingInfo::MultiNode((
+                    MultipleNodeRoutingInfo::AllNodes,
+                    Some(ResponsePolicy::Special),
+                )),
+            )
+            .await?;
+        from_redis_value(&value)
+    }
+
+    /// Runs `WAIT <num_replicas> <timeout>`, and returns the number of replicas that acknowledged the writes that
+    /// were sent before it. With a `route`, `WAIT` is sent to the primary that owns the route's slot. Otherwise it's
+    /// sent to all the primaries, and the lowest number of acknowledgements is returned.
+    ///
+    /// A timeout of zero waits until `num_replicas` replicas acknowledged the writes. `WAIT` counts the writes that
+    /// were sent on the same connection to the node, so with a
+    /// [`connection_pool_size`](crate::cluster::ClusterClientBuilder::connection_pool_size) above one, the writes
+    /// might not be counted. The response timeout still applies to `WAIT`, and should be longer than `timeout`.
+    pub async fn wait(
+        &mut self,
+        num_replicas: usize,
+        timeout: Duration,
+        route: Option<Route>,
+    ) -> RedisResult<usize> {
+        let mut wait_cmd = cmd("WAIT");
+        wait_cmd
+            .arg(num_replicas)
+            .arg(u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX));
+        let routing = match route {
+            Some(route) => cluster_routing::RoutingInfo::SingleNode(
+                SingleNodeRoutingInfo::SpecificNode(Route::new(route.slot(), SlotAddr::Master)),
+            ),
+            None => cluster_routing::RoutingInfo::MultiNode((
+                MultipleNodeRoutingInfo::AllMasters,
+                Some(ResponsePolicy::Aggregate(cluster_routing::AggregateOp::Min)),
+            )),
+        };
+        let value = self.route_command(&wait_cmd, routing).await?;
+        from_owned_redis_value(value)
+    }
+
+    /// Returns the ids of the cluster's nodes, as reported by `CLUSTER MYID`, by the nodes' addresses.
+    ///
+    /// The ids are only tracked if enabled with
+    /// [`ClusterClientBuilder::node_id_cache_ttl`](crate::cluster::ClusterClientBuilder::node_id_cache_ttl),
+    /// otherwise the returned map is empty.
+    pub async fn node_ids(&self) -> HashMap<String, String> {
+        match &self.1.node_identities {
+            Some(node_identities) => node_identities
+                .read()
+                .await
+                .ids_by_address()
+                .map(|(address, id)| (address.to_string(), id.to_string()))
+                .collect(),
+            None => HashMap::new(),
+        }
+    }
+
+    /// Returns a snapshot of the cluster's topology: the slot ranges, the addresses of their primaries and replicas,
+    /// the ids of the nodes if they're tracked, and when the slots were last refreshed.
+    ///
+    /// This allows exposing the cluster's map without sending `CLUSTER SLOTS` to the nodes.
+    pub async fn get_topology(&self) -> ClusterTopology {
+        let ids_by_address = self.node_ids().await;
+        let conn_lock = self.1.conn_lock.read().await;
+        ClusterTopology::new(
+            &conn_lock.slot_map,
+            conn_lock.slots_refreshed_at(),
+            &ids_by_address,
+        )
+    }
+
+    /// Fetches the slots from the nodes like a refresh does, and returns the changes that applying them would make,
+    /// without applying them. This allows tooling to preview the impact of a refresh, such as the slots that would
+    /// move and the connections that would be opened and closed.
+    pub async fn plan_slots_refresh(&self) -> RedisResult<TopologyChangePlan> {
+        let num_of_nodes_to_query = self.1.conn_lock.read().await.len().min(MAX_REQUESTED_NODES);
+        let (mut new_slots, _) =
+            calculate_topology_from_random_nodes(&self.1, num_of_nodes_to_query, 0)
+                .await
+                .0?;
+        self.1
+            .cluster_params
+            .node_overrides
+            .apply_roles(&mut new_slots);
+        let read_guard = self.1.conn_lock.read().await;
+        Ok(TopologyChangePlan::new(
+            read_guard.refreshed_slot_map(),
+            &new_slots,
+            read_guard.all_nodes().map(|(address, _)| address.as_str()),
+        ))
+    }
+
+    /// Returns a receiver of the changes of the cluster's topology: the nodes that are added and removed, and the
+    /// slots that move to a new primary, such as after a failover. The changes are sent whenever a refresh of all
+    /// the slots or of some shards installs a new slot map, so applications and proxies can react to them without
+    /// polling.
+    ///
+    /// Each receiver only gets the changes that are sent after it's created. A receiver that falls more than
+    /// [`TOPOLOGY_EVENTS_CAPACITY`] changes behind misses the oldest ones, and is notified with
+    /// [`RecvError::Lagged`](broadcast::error::RecvError::Lagged).
+    pub fn topology_updates(&self) -> broadcast::Receiver<TopologyEvent> {
+        self.1.topology_events.subscribe()
+    }
+
+    /// Returns a handle to the same connection whose requests are tagged with `tenant`, for connections that are
+    /// shared by several tenants.
+    ///
+    /// The driver task takes turns between the tenants with queued requests, in proportion to their weights as set by
+    /// [`ClusterClientBuilder::tenant_weight`](crate::cluster::ClusterClientBuilder::tenant_weight), so that a tenant
+    /// with many queued requests can't starve the others. The requests that a tagged request fans out to, and its
+    /// retries, keep its tag.
+    pub fn with_tenant(&self, tenant: &str) -> ClusterConnection<C> {
+        ClusterConnection(self.0.clone(), self.1.clone(), Some(ArcStr::from(tenant)))
+    }
+
+    /// Returns the metadata of the cluster's nodes, as returned by the callback that is set by
+    /// [`ClusterClientBuilder::node_metadata_provider`](crate::cluster::ClusterClientBuilder::node_metadata_provider)
+    /// and merged with the availability zones and weights of the [`NodeOverride`]s, by the nodes' addresses.
+    ///
+    /// This allows custom placement logic, for example sending a command with
+    /// [`route_command`](Self::route_command) to a node by its address after choosing it by its rack.
+    pub async fn node_metadata(&self) -> HashMap<String, Arc<NodeMetadata>> {
+        self.1
+            .conn_lock
+            .read()
+            .await
+            .all_node_metadata()
+            .map(|(address, metadata)| {
+                let node_overrides = &self.1.cluster_params.node_overrides;
+                (
+                    address.to_string(),
+                    node_overrides.merge_metadata(address, metadata),
+                )
             })
+            .collect()
+    }
+
+    /// Returns the addresses of the nodes that the periodic topology checks detected as paused, e.g. by
+    /// `CLIENT PAUSE`. The reads from replicas avoid these nodes until they respond again. See
+    /// [`ClusterClientBuilder::unpause_paused_nodes`](crate::cluster::ClusterClientBuilder::unpause_paused_nodes).
+    pub async fn paused_nodes(&self) -> Vec<String> {
+        self.1
+            .conn_lock
+            .read()
+            .await
+            .all_nodes()
+            .filter(|(_, node)| node.paused.is_paused())
+            .map(|(address, _)| address.to_string())
+            .collect()
+    }
+
+    /// Sets what an external source knows about the node at `address`, replacing its previous override, for all the
+    /// connections of the client. The role is applied by the next refresh of the slots, and the availability zone and
+    /// the weight immediately. See
+    /// [`ClusterClientBuilder::node_override`](crate::cluster::ClusterClientBuilder::node_override).
+    pub fn set_node_override(&self, address: &str, node_override: NodeOverride) {
+        self.1
+            .cluster_params
+            .node_overrides
+            .set(address.to_string(), node_override);
+    }
+
+    /// Removes the override of the node at `address`, so that the next refresh of the slots uses the role that the
+    /// cluster reports.
+    pub fn remove_node_override(&self, address: &str) {
+        self.1.cluster_params.node_overrides.remove(address);
+    }
+
+    /// Returns the slots that were observed being migrated between nodes, by their slot numbers.
+    ///
+    /// Migrations are observed by `ASK` redirections, and by [`refresh_slot_migrations`](Self::refresh_slot_migrations).
+    /// A migration is forgotten once a `MOVED` redirection or a slot refresh shows that the slot completed its move.
+    /// This allows applications to throttle writes to migrating slots, or to defer heavy operations until the
+    /// migration completes.
+    pub fn slot_migrations(&self) -> HashMap<u16, SlotMigration> {
+        self.1.slot_migrations.lock().unwrap().all()
+    }
+
+    /// Returns the migration of the slot that the given key belongs to, if one was observed.
+    /// See [`slot_migrations`](Self::slot_migrations).
+    pub fn slot_migration_for_key(&self, key: &[u8]) -> Option<SlotMigration> {
+        self.1
+            .slot_migrations
+            .lock()
+            .unwrap()
+            .get(get_slot(key))
+            .cloned()
+    }
+
+    /// Queries `CLUSTER NODES` from all the primaries, and replaces the observed slot migrations with the slots
+    /// that the primaries report as migrating or importing. Returns the updated migrations.
+    pub async fn refresh_slot_migrations(&mut self) -> RedisResult<HashMap<u16, SlotMigration>> {
+        let value = self
+            .route_command(
+                cmd("CLUSTER").arg("NODES"),
+                cluster_routing::RoutingInfo::MultiNode((
+                    MultipleNodeRoutingInfo::AllMasters,
+                    Some(ResponsePolicy::Special),
+                )),
+            )
+            .await?;
+        let replies: HashMap<String, String> = from_redis_value(&value)?;
+        let mut slot_migrations = self.1.slot_migrations.lock().unwrap();
+        slot_migrations.replace_from_cluster_nodes(
+            replies
+                .iter()
+                .map(|(address, reply)| (address.as_str(), reply.as_str())),
+        );
+        Ok(slot_migrations.all())
+    }
+
+    /// Pins a route to the node with the given address, for a sequence of commands that must be sent to the same
+    /// node, such as a diagnostic session. See [`PinnedRoute`] for how it differs from this connection.
+    ///
+    /// Fails if there's no connection to a node with the given address.
+    pub async fn pin_to_address(&self, address: &str) -> RedisResult<PinnedRoute<C>> {
+        let connection = self
+            .1
+            .conn_lock
+            .read()
+            .await
+            .connection_for_address(address);
+        match connection {
+            Some((address, connection)) => {
+                Ok(PinnedRoute::new(address.to_string(), connection.await))
+            }
+            None => Err((
+                ErrorKind::ClusterConnectionNotFound,
+                "Requested connection not found",
+                address.to_string(),
+            )
+                .into()),
+        }
+    }
+
+    /// Pins a route to the node that currently serves the given route, such as the primary of a slot. See
+    /// [`pin_to_address`](Self::pin_to_address).
+    pub async fn pin_to_route(&self, route: &Route) -> RedisResult<PinnedRoute<C>> {
+        let connection = self.1.conn_lock.read().await.connection_for_route(route);
+        match connection {
+            Some((address, connection)) => {
+                Ok(PinnedRoute::new(address.to_string(), connection.await))
+            }
+            None => Err((
+                ErrorKind::ClusterConnectionNotFound,
+                "No connection found for route",
+                format!("{route:?}"),
+            )
+                .into()),
+        }
+    }
+
+    /// Runs `f` with a connection to a single node, for a short critical section of dependent commands that must be
+    /// sent to the same node, such as `DUMP` and `RESTORE`, a write followed by `WAIT`, or the coordination of a
+    /// `MIGRATE`. The node is selected by its address, by a route to its slot, or at random.
+    ///
+    /// The connection is a [`PinnedRoute`], so the commands aren't retried and redirections aren't followed. It's
+    /// released once the future returned by `f` completes, unless `f` keeps a clone of it. Fails without calling
+    /// `f` if there's no connection to the selected node.
+    pub async fn execute_on_node<T, F, Fut>(
+        &self,
+        node: SingleNodeRoutingInfo,
+        f: F,
+    ) -> RedisResult<T>
+    where
+        F: FnOnce(PinnedRoute<C>) -> Fut,
+        Fut: Future<Output = RedisResult<T>>,
+    {
+        let pinned_route = match node {
+            SingleNodeRoutingInfo::SpecificNode(route) => self.pin_to_route(&route).await?,
+            SingleNodeRoutingInfo::ByAddress { host, port } => {
+                self.pin_to_address(&format!("{host}:{port}")).await?
+            }
+            SingleNodeRoutingInfo::Random => {
+                let connection = self
+                    .1
+                    .conn_lock
+                    .read()
+                    .await
+                    .random_connections(1, ConnectionType::User)
+                    .next();
+                match connection {
+                    Some((address, connection)) => {
+                        PinnedRoute::new(address.to_string(), connection.await)
+                    }
+                    None => {
+                        return Err((
+                            ErrorKind::ClusterConnectionNotFound,
+                            "No connection found for a random node",
+                        )
+                            .into())
+                    }
+                }
+            }
+        };
+        f(pinned_route).await
+    }
+
+    /// Returns a snapshot of the pubsub subscriptions tracked by this connection, and of the number of
+    /// pubsub messages delivered to the push sender.
+    ///
+    /// This can be used to verify that the subscriptions were restored on the right nodes after a failover
+    /// or a topology change.
+    pub async fn pubsub_state(&self) -> PubSubState {
+        let subscriptions_by_address = self
+            .1
+            .subscriptions_by_address
+            .read()
+            .await
+            .iter()
+            .map(|(address, subscriptions)| (address.to_string(), subscriptions.clone()))
+            .collect();
+        let unassigned_subscriptions = self.1.unassigned_subscriptions.read().await.clone();
+        PubSubState {
+            subscriptions_by_address,
+            unassigned_subscriptions,
+            delivered_messages: self.1.pubsub_stats.delivered.load(Ordering::Relaxed),
+            dropped_messages: self.1.pubsub_stats.dropped.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns the numbers of requests that were sent to each slot since the previous sample, and starts a new
+    /// sampling window, or `None` if the requests aren't counted. The requests are counted when they're enabled by
+    /// [`ClusterClientBuilder::slot_stats`](crate::cluster::ClusterClientBuilder::slot_stats).
+    ///
+    /// The counts are shared by all the clones of the connection, so sampling from one clone resets them for all.
+    pub fn sample_slot_stats(&self) -> Option<SlotStats> {
+        self.1.slot_counters.as_ref().map(SlotCounters::sample)
+    }
+
+    /// Returns the state of the limiter of concurrent connection attempts, or `None` if the connection attempts
+    /// aren't limited. The limit is set by
+    /// [`ClusterClientBuilder::max_concurrent_connects`](crate::cluster::ClusterClientBuilder::max_concurrent_connects).
+    pub fn connect_limiter_stats(&self) -> Option<ConnectLimiterStats> {
+        self.1
+            .cluster_params
+            .connect_limiter
+            .as_ref()
+            .map(|limiter| limiter.stats())
+    }
+
+    /// Returns the state of the queue of the requests that wait to be sent, which grows while the nodes they're
+    /// routed to are unreachable. See [`ClusterClientBuilder::max_pending_requests`](crate::cluster::ClusterClientBuilder::max_pending_requests).
+    pub fn pending_requests_stats(&self) -> PendingRequestsStats {
+        self.1.pending_requests.stats()
+    }
+
+    /// Returns the outcomes of the [periodic topology checks](crate::cluster::ClusterClientBuilder::periodic_topology_checks):
+    /// when a check last succeeded, and how many checks failed since, with the error of the last one.
+    pub fn topology_check_stats(&self) -> TopologyCheckStats {
+        self.1.topology_check_status.lock().unwrap().stats()
+    }
+
+    /// Converts the connection into a [`ClusterPubSub`], which subscribes to channels and patterns at runtime.
+    ///
+    /// Fails if the connection doesn't use RESP3, or if it was created without a push sender to deliver the
+    /// messages to.
+    pub fn into_pubsub(self) -> RedisResult<ClusterPubSub<C>> {
+        ClusterPubSub::new(self)
     }
 
     // Special handling for `SCAN` command, using cluster_scan
@@ -200,35 +799,73 @@ where
         self.route_cluster_scan(cluster_scan_args).await
     }
 
+    /// Scans the keys of all the shards of the cluster, sending the `SCAN` of every shard concurrently.
+    ///
+    /// Unlike [`ClusterConnection::cluster_scan`], which scans the nodes one after the other, each round of the
+    /// returned stream sends the next `SCAN` to all the shards that weren't fully scanned. The scan of each shard
+    /// tracks the epoch of the scanned node, so it follows the shard's slots when they move during the scan.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use futures::StreamExt;
+    /// use redis::cluster::ClusterClient;
+    /// use redis::{from_redis_value, ClusterScanOptions};
+    ///
+    /// async fn scan_all_users() -> Vec<String> {
+    ///     let nodes = vec!["redis://127.0.0.1/"];
+    ///     let client = ClusterClient::new(nodes).unwrap();
+    ///     let connection = client.get_async_connection(None).await.unwrap();
+    ///     let mut stream = connection.scan(ClusterScanOptions::new().match_pattern("user:*"));
+    ///     let mut keys = vec![];
+    ///     while let Some(scan_keys) = stream.next().await {
+    ///         for key in scan_keys.unwrap() {
+    ///             keys.push(from_redis_value(&key).unwrap());
+    ///         }
+    ///     }
+    ///     keys
+    /// }
+    /// ```
+    pub fn scan(&self, options: ClusterScanOptions) -> ClusterScanStream<C> {
+        self.scan_from(ClusterScanCursor::new(), options)
+    }
+
+    /// Resumes a scan of all the shards of the cluster from a cursor returned by [`ClusterScanStream::cursor`].
+    pub fn scan_from(
+        &self,
+        cursor: ClusterScanCursor,
+        options: ClusterScanOptions,
+    ) -> ClusterScanStream<C> {
+        ClusterScanStream::new(self.clone(), cursor, options)
+    }
+
+    pub(crate) async fn route_cluster_scan_all_shards(
+        &mut self,
+        cursor: ClusterScanCursor,
+        options: ClusterScanOptions,
+    ) -> RedisResult<(ClusterScanCursor, Vec<Value>)> {
+        self.send_request(CmdArg::ClusterScanAllShards { cursor, options }, None, None)
+            .await
+            .map(|response| match response {
+                Response::ClusterScanAllShardsResult(cursor, keys) => (cursor, keys),
+                Response::ClusterScanResult(_, _) => unreachable!(),
+                Response::Single(_) => unreachable!(),
+                Response::PerNode(_) => unreachable!(),
+                Response::Multiple(_) => unreachable!(),
+            })
+    }
+
     /// Route cluster scan to be handled by internal cluster_scan command
     async fn route_cluster_scan(
         &mut self,
         cluster_scan_args: ClusterScanArgs,
     ) -> RedisResult<(ScanStateRC, Vec<Value>)> {
-        let (sender, receiver) = oneshot::channel();
-        self.0
-            .send(Message {
-                cmd: CmdArg::ClusterScan { cluster_scan_args },
-                sender,
-            })
-            .await
-            .map_err(|_| {
-                RedisError::from(io::Error::new(
-                    io::ErrorKind::BrokenPipe,
-                    "redis_cluster: Unable to send command",
-                ))
-            })?;
-        receiver
+        self.send_request(CmdArg::ClusterScan { cluster_scan_args }, None, None)
             .await
-            .unwrap_or_else(|_| {
-                Err(RedisError::from(io::Error::new(
-                    io::ErrorKind::BrokenPipe,
-                    "redis_cluster: Unable to receive command",
-                )))
-            })
             .map(|response| match response {
                 Response::ClusterScanResult(new_scan_state_ref, key) => (new_scan_state_ref, key),
+                Response::ClusterScanAllShardsResult(_, _) => unreachable!(),
                 Response::Single(_) => unreachable!(),
+                Response::PerNode(_) => unreachable!(),
                 Response::Multiple(_) => unreachable!(),
             })
     }
@@ -239,39 +876,241 @@ where
         cmd: &Cmd,
         routing: cluster_routing::RoutingInfo,
     ) -> RedisResult<Value> {
-        trace!("route_command");
-        let (sender, receiver) = oneshot::channel();
-        self.0
-            .send(Message {
-                cmd: CmdArg::Cmd {
-                    cmd: Arc::new(cmd.clone()),
-                    routing: routing.into(),
-                },
-                sender,
-            })
+        self.route_command_with_options(cmd, routing, RouteOptions::default())
             .await
-            .map_err(|_| {
-                RedisError::from(io::Error::new(
-                    io::ErrorKind::BrokenPipe,
-                    "redis_cluster: Unable to send command",
-                ))
-            })?;
-        receiver
+    }
+
+    /// Sends a command to the given `routing`, like [`Self::route_command`], with the given `options`.
+    ///
+    /// A request that times out is removed from the connection's in-flight requests and isn't retried, but the
+    /// server might have executed it anyway.
+    pub async fn route_command_with_options(
+        &mut self,
+        cmd: &Cmd,
+        routing: cluster_routing::RoutingInfo,
+        options: RouteOptions,
+    ) -> RedisResult<Value> {
+        self.send_command(Arc::new(cmd.clone()), routing, options, None)
+            .await
+    }
+
+    /// Sends a command to the given `routing` like [`Self::route_command`], taking ownership of the command so that
+    /// it isn't copied. The retries, the redirects and the requests to each node of a command that is sent to
+    /// multiple nodes share it, and it's encoded from a reference when it's written to a node.
+    ///
+    /// A command that is sent repeatedly can be kept in an [`Arc`] and passed by cloning the [`Arc`].
+    pub async fn route_command_owned(
+        &mut self,
+        cmd: impl Into<Arc<Cmd>>,
+        routing: cluster_routing::RoutingInfo,
+    ) -> RedisResult<Value> {
+        self.send_command(cmd.into(), routing, RouteOptions::default(), None)
+            .await
+    }
+
+    /// Sends a command like [`Self::route_command_with_options`], and returns with its result the number of bytes
+    /// that it wrote to and read from the nodes, so that the network usage can be attributed to call sites and
+    /// payload budgets enforced. See [`RequestSize`] for what is counted.
+    pub async fn route_command_with_size(
+        &mut self,
+        cmd: &Cmd,
+        routing: cluster_routing::RoutingInfo,
+        options: RouteOptions,
+    ) -> (RedisResult<Value>, RequestSize) {
+        let size = Arc::new(RequestSizeCounter::default());
+        let result = self
+            .send_command(Arc::new(cmd.clone()), routing, options, Some(size.clone()))
+            .await;
+        (result, size.get())
+    }
+
+    async fn send_command(
+        &mut self,
+        cmd: Arc<Cmd>,
+        routing: cluster_routing::RoutingInfo,
+        options: RouteOptions,
+        size: Option<Arc<RequestSizeCounter>>,
+    ) -> RedisResult<Value> {
+        trace!("route_command");
+        self.1.cluster_params.check_command_allowed(cmd.as_ref())?;
+        let routing = match (routing, options.reducer) {
+            (cluster_routing::RoutingInfo::MultiNode((routing, _)), Some(reducer)) => {
+                InternalRoutingInfo::MultiNode((routing, MultiNodeResponsePolicy::Reduce(reducer)))
+            }
+            (routing, _) => routing.into(),
+        };
+        self.send_request(CmdArg::Cmd { cmd, routing }, options.request_timeout, size)
             .await
-            .unwrap_or_else(|_| {
-                Err(RedisError::from(io::Error::new(
-                    io::ErrorKind::BrokenPipe,
-                    "redis_cluster: Unable to receive command",
-                )))
-            })
             .map(|response| match response {
                 Response::Single(value) => value,
+                Response::PerNode(_) => unreachable!(),
                 Response::Multiple(_) => unreachable!(),
                 Response::ClusterScanResult(_, _) => unreachable!(),
+                Response::ClusterScanAllShardsResult(_, _) => unreachable!(),
+            })
+    }
+
+    /// Sends `cmd` to the nodes that it's routed to by default, and combines the responses of the nodes by
+    /// `response_policy` instead of the command's own response policy. For example, a command that's sent to all
+    /// the primaries can return the first successful response with [`ResponsePolicy::OneSucceeded`], or the
+    /// response of each node by its address with `None`, like [`ResponsePolicy::Special`].
+    ///
+    /// The policy has no effect on commands that are routed to a single node.
+    pub async fn route_command_with_policy(
+        &mut self,
+        cmd: &Cmd,
+        response_policy: Option<ResponsePolicy>,
+    ) -> RedisResult<Value> {
+        let routing = match self.routing_for_command(cmd)? {
+            cluster_routing::RoutingInfo::MultiNode((routing, _)) => {
+                cluster_routing::RoutingInfo::MultiNode((routing, response_policy))
+            }
+            routing => routing,
+        };
+        self.route_command(cmd, routing).await
+    }
+
+    fn routing_for_command(&self, cmd: &Cmd) -> RedisResult<cluster_routing::RoutingInfo> {
+        if self.1.cluster_params.strict_routing {
+            cluster_routing::RoutingInfo::for_routable_strict(cmd)
+        } else {
+            Ok(cluster_routing::RoutingInfo::for_routable(cmd).unwrap_or(
+                cluster_routing::RoutingInfo::SingleNode(SingleNodeRoutingInfo::Random),
+            ))
+        }
+    }
+
+    /// Sends `cmd` to the nodes of `routing`, and returns the response of each node by its address, instead of
+    /// combining the responses by the command's response policy like [`Self::route_command`] does, so that the
+    /// errors of some of the nodes don't hide the responses of the others.
+    ///
+    /// [`MultipleNodeRoutingInfo::MultiSlot`] isn't supported, since several of its sub-commands may be sent to the
+    /// same node.
+    pub async fn route_command_to_nodes(
+        &mut self,
+        cmd: &Cmd,
+        routing: MultipleNodeRoutingInfo,
+    ) -> RedisResult<MultiNodeResponse> {
+        trace!("route_command_to_nodes");
+        if let MultipleNodeRoutingInfo::MultiSlot(_) = routing {
+            return Err(RedisError::from((
+                ErrorKind::ClientError,
+                "Per-node responses aren't supported for multi-slot routing",
+            )));
+        }
+        self.1.cluster_params.check_command_allowed(cmd)?;
+        self.send_request(
+            CmdArg::Cmd {
+                cmd: Arc::new(cmd.clone()),
+                routing: InternalRoutingInfo::MultiNode((
+                    routing,
+                    MultiNodeResponsePolicy::PerNode,
+                )),
+            },
+            None,
+            None,
+        )
+        .await
+        .map(|response| match response {
+            Response::PerNode(responses) => responses,
+            Response::Single(_) => unreachable!(),
+            Response::Multiple(_) => unreachable!(),
+            Response::ClusterScanResult(_, _) => unreachable!(),
+            Response::ClusterScanAllShardsResult(_, _) => unreachable!(),
+        })
+    }
+
+    /// Sends a read-only command to the node of its slot that `read_preference` chooses, instead of the node chosen
+    /// by the connection's read from replicas setting. Commands that must reach the primaries are routed as usual.
+    pub async fn route_command_with_read_preference(
+        &mut self,
+        cmd: &Cmd,
+        routing: cluster_routing::RoutingInfo,
+        read_preference: ReadPreference,
+    ) -> RedisResult<Value> {
+        self.route_command(cmd, routing.with_read_preference(read_preference))
+            .await
+    }
+
+    /// Sends a request to the driver task, and waits for its response. The request fails with
+    /// [`ErrorKind::Timeout`] if it doesn't complete within `request_timeout`, or within the client's
+    /// request timeout if it's `None`.
+    async fn send_request(
+        &mut self,
+        cmd: CmdArg<C>,
+        request_timeout: Option<Duration>,
+        size: Option<Arc<RequestSizeCounter>>,
+    ) -> RedisResult<Response> {
+        let request_timeout = request_timeout.or(self.1.cluster_params.request_timeout);
+        let (sender, receiver) = oneshot::channel();
+        self.0
+            .send(Message {
+                cmd,
+                sender,
+                tenant: self.2.clone(),
+                request_timeout,
+                size,
             })
+            .await
+            .map_err(|_| self.channel_error("redis_cluster: Unable to send command"))?;
+        // The driver task doesn't poll the requests while it recovers the connections, so the deadline is also
+        // enforced here. Dropping the receiver keeps the driver task from sending or retrying the request.
+        let response = match request_timeout {
+            Some(timeout) => {
+                match future::select(receiver, self.1.cluster_params.clock.sleep(timeout)).await {
+                    future::Either::Left((response, _)) => response,
+                    future::Either::Right(_) => return Err(request_timeout_error()),
+                }
+            }
+            None => receiver.await,
+        };
+        response
+            .unwrap_or_else(|_| Err(self.channel_error("redis_cluster: Unable to receive command")))
+    }
+
+    // The channel to the driver task only fails if the task stopped, or if it dropped the request.
+    fn channel_error(&self, message: &'static str) -> RedisError {
+        match self.1.driver_stop_reason.lock().unwrap().clone() {
+            Some(reason) => RedisError::from((
+                ErrorKind::DriverStopped,
+                "The cluster connection's driver task has stopped",
+                reason,
+            )),
+            None => RedisError::from(io::Error::new(io::ErrorKind::BrokenPipe, message)),
+        }
+    }
+
+    /// Executes the commands of `pipeline` atomically, in a `MULTI`/`EXEC` transaction, whether the pipeline is in
+    /// atomic mode or not.
+    ///
+    /// All the keys of the commands must hash to the same slot, otherwise a [`CrossSlot`](ErrorKind::CrossSlot)
+    /// error is returned without sending anything. The transaction is sent to the node that owns the slot, or to
+    /// `route` if it's given. If a command is redirected with `MOVED` or `ASK` while it's queued, the server aborts
+    /// the transaction on `EXEC`, so the whole transaction is retried on the node it's redirected to.
+    ///
+    /// Like for [`Pipeline::query_async`](crate::Pipeline::query_async), the results of the ignored commands are
+    /// skipped, and a transaction that was aborted because a `WATCH`ed key changed returns `nil`.
+    pub async fn exec_transaction<T: FromRedisValue>(
+        &mut self,
+        pipeline: &crate::Pipeline,
+        route: Option<SingleNodeRoutingInfo>,
+    ) -> RedisResult<T> {
+        let count = pipeline.cmd_iter().count();
+        if count == 0 {
+            return from_owned_redis_value(Value::Array(vec![]));
+        }
+        let mut transaction = pipeline.clone();
+        transaction.atomic();
+        let slot_route = route_for_pipeline(&transaction)?;
+        let route = route.unwrap_or_else(|| slot_route.into());
+        let values = self
+            .route_pipeline(&transaction, count + 1, 1, route)
+            .await?;
+        from_owned_redis_value(transaction.make_transaction_results(values)?)
     }
 
     /// Send commands in `pipeline` to the given `route`. If `route` is [None], it will be computed from `pipeline`.
+    /// An empty pipeline returns an empty result without sending anything.
     pub async fn route_pipeline<'a>(
         &'a mut self,
         pipeline: &'a crate::Pipeline,
@@ -279,48 +1118,398 @@ where
         count: usize,
         route: SingleNodeRoutingInfo,
     ) -> RedisResult<Vec<Value>> {
-        let (sender, receiver) = oneshot::channel();
-        self.0
-            .send(Message {
-                cmd: CmdArg::Pipeline {
-                    pipeline: Arc::new(pipeline.clone()),
-                    offset,
-                    count,
-                    route: route.into(),
-                },
-                sender,
-            })
-            .await
-            .map_err(|_| RedisError::from(io::Error::from(io::ErrorKind::BrokenPipe)))?;
+        if pipeline.is_empty() {
+            return Ok(vec![]);
+        }
+        for cmd in pipeline.cmd_iter() {
+            self.1.cluster_params.check_command_allowed(cmd)?;
+        }
+        self.send_request(
+            CmdArg::Pipeline {
+                pipeline: Arc::new(pipeline.clone()),
+                offset,
+                count,
+                route: route.into(),
+            },
+            None,
+            None,
+        )
+        .await
+        .map(|response| match response {
+            Response::Multiple(values) => values,
+            Response::Single(_) => unreachable!(),
+            Response::PerNode(_) => unreachable!(),
+            Response::ClusterScanResult(_, _) => unreachable!(),
+            Response::ClusterScanAllShardsResult(_, _) => unreachable!(),
+        })
+    }
 
-        receiver
-            .await
-            .unwrap_or_else(|_| Err(RedisError::from(io::Error::from(io::ErrorKind::BrokenPipe))))
-            .map(|response| match response {
-                Response::Multiple(values) => values,
-                Response::Single(_) => unreachable!(),
-                Response::ClusterScanResult(_, _) => unreachable!(),
-            })
+    /// Sends the commands in `pipeline` to the given `route` in the chunks of `chunking`, and returns a stream of
+    /// their replies. Unlike [`ClusterConnection::route_pipeline`], which buffers the replies of the whole pipeline
+    /// before returning them, the caller can process the replies of a chunk before the next chunk is sent, so the
+    /// memory of a very large pipeline stays bounded. See [`ClusterPipelineStream`].
+    ///
+    /// Atomic pipelines aren't split, since `MULTI`/`EXEC` can't span several chunks.
+    pub fn route_pipeline_streaming(
+        &self,
+        pipeline: &crate::Pipeline,
+        offset: usize,
+        count: usize,
+        route: SingleNodeRoutingInfo,
+        chunking: crate::PipelineChunking,
+    ) -> ClusterPipelineStream<C> {
+        ClusterPipelineStream::new(self.clone(), pipeline, offset, count, route, chunking)
+    }
+
+    /// Sends a non-atomic pipeline whose commands are in several slots as a sub-pipeline per slot, and reassembles
+    /// the replies in the order of the commands. The keyless commands are sent with the sub-pipeline of the first
+    /// slot.
+    ///
+    /// The sub-pipelines are sent concurrently, so the ones that are routed to the same node are written together on
+    /// its connection, and each of them is retried on its own, e.g. on `MOVED` and `ASK` redirections.
+    async fn route_cross_slot_pipeline(
+        &mut self,
+        pipeline: &crate::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        let commands: Vec<&Cmd> = pipeline.cmd_iter().skip(offset).take(count).collect();
+        let slots: Vec<Option<u16>> = commands
+            .iter()
+            .map(|cmd| route_for_command(cmd).map(|route| route.slot()))
+            .collect();
+        let first_slot = slots.iter().flatten().next().copied();
+
+        // The sub-pipelines with the indices of their commands in the pipeline.
+        let mut sub_pipelines: Vec<(crate::Pipeline, Vec<usize>)> = Vec::new();
+        let mut sub_pipeline_by_slot: HashMap<Option<u16>, usize> = HashMap::new();
+        for (idx, (cmd, slot)) in commands.into_iter().zip(slots).enumerate() {
+            let sub_pipeline = *sub_pipeline_by_slot
+                .entry(slot.or(first_slot))
+                .or_insert_with(|| {
+                    sub_pipelines.push((crate::Pipeline::new(), Vec::new()));
+                    sub_pipelines.len() - 1
+                });
+            let (sub_pipeline, indices) = &mut sub_pipelines[sub_pipeline];
+            sub_pipeline.add_command(cmd.clone());
+            indices.push(idx);
+        }
+
+        let results =
+            futures::future::join_all(sub_pipelines.iter().map(|(sub_pipeline, indices)| {
+                let mut connection = self.clone();
+                async move {
+                    let route = route_for_pipeline(sub_pipeline)?;
+                    connection
+                        .route_pipeline(sub_pipeline, 0, indices.len(), route.into())
+                        .await
+                }
+            }))
+            .await;
+
+        let mut values = vec![Value::Nil; count];
+        for ((_, indices), result) in sub_pipelines.iter().zip(results) {
+            for (idx, value) in indices.iter().zip(result?) {
+                values[*idx] = value;
+            }
+        }
+        Ok(values)
+    }
+}
+
+/// The task that drives the requests of a [`ClusterConnection`], which records the reason for which it stopped,
+/// so that requests that can't be sent anymore fail with an actionable error.
+struct Driver<C> {
+    core: Core<C>,
+    // Signals the connection's periodic tasks to stop once the driver task stops.
+    shutdown_flag: Arc<AtomicBool>,
+    future: BoxFuture<'static, ()>,
+}
+
+impl<C> Future for Driver<C> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<()> {
+        match panic::catch_unwind(AssertUnwindSafe(|| self.future.as_mut().poll(cx))) {
+            Ok(Poll::Ready(())) => {
+                self.core
+                    .record_driver_stop("the connection was closed".to_string());
+                Poll::Ready(())
+            }
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(panic) => {
+                self.core.record_driver_stop(format!(
+                    "the driver task panicked: {}",
+                    panic_message(&*panic)
+                ));
+                panic::resume_unwind(panic)
+            }
+        }
     }
 }
 
+impl<C> Drop for Driver<C> {
+    fn drop(&mut self) {
+        // Runs before the future is dropped, so the reason is recorded before the pending requests fail.
+        self.core.record_driver_stop(
+            "the driver task was cancelled, for example because its runtime was shut down"
+                .to_string(),
+        );
+        self.shutdown_flag.store(true, Ordering::Relaxed);
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_default()
+}
+
 type ConnectionMap<C> = connections_container::ConnectionsMap<ConnectionFuture<C>>;
+
+/// The number of times the driver task may be restarted within [`DRIVER_RESTARTS_WINDOW`] before giving up.
+const MAX_DRIVER_RESTARTS: usize = 5;
+const DRIVER_RESTARTS_WINDOW: Duration = Duration::from_secs(60);
+
+/// The maximal number of nodes that are queried for the slots by a refresh of all the slots.
+const MAX_REQUESTED_NODES: usize = 50;
+/// The number of pending requests that are started, and of in-flight requests that are completed, in a single
+/// poll of the driver task before it yields to the runtime.
+const POLL_COMPLETE_BUDGET: usize = 1024;
 type ConnectionsContainer<C> =
     self::connections_container::ConnectionsContainer<ConnectionFuture<C>>;
 
 pub(crate) struct InnerCore<C> {
     pub(crate) conn_lock: RwLock<ConnectionsContainer<C>>,
+    // Serializes the refreshes of the slots and of the connections. They connect to the nodes without holding
+    // `conn_lock`, so that the requests aren't blocked meanwhile, and only take it to swap their changes in.
+    refresh_lock: AsyncMutex<()>,
     cluster_params: ClusterParams,
-    pending_requests: Mutex<Vec<PendingRequest<C>>>,
+    // Requests are pushed both by the driver task and by the request futures, and only the driver task pops them.
+    // Requests pushed by the same producer are popped in the order they were pushed.
+    pending_requests: PendingRequests<PendingRequest<C>>,
+    // The pending requests that were taken from the queue, waiting for their tenant's turn to start. Only the driver
+    // task uses them, but they're kept here so that they survive the restarts of the driver task.
+    scheduler: Mutex<FairScheduler<PendingRequest<C>>>,
     slot_refresh_state: SlotRefreshState,
     initial_nodes: Vec<ConnectionInfo>,
     push_sender: Option<mpsc::UnboundedSender<PushInfo>>,
     subscriptions_by_address: RwLock<HashMap<ArcStr, PubSubSubscriptionInfo>>,
     unassigned_subscriptions: RwLock<PubSubSubscriptionInfo>,
+    pubsub_stats: Arc<PubSubMessageStats>,
+    node_identities: Option<RwLock<NodeIdentities>>,
+    slot_migrations: Mutex<SlotMigrations>,
+    circuit_breakers: Mutex<CircuitBreakers>,
+    reconnect_backoffs: Mutex<ReconnectBackoffs>,
+    // The nodes that are being reconnected by the driver task or by the periodic checks, with the connections that
+    // are reconnected, so that the same node isn't reconnected by both at once.
+    reconnecting_nodes: Mutex<HashMap<ArcStr, RefreshConnectionType>>,
+    // The redirections and connection errors since the last periodic topology check, which adapt its interval.
+    topology_errors: AtomicUsize,
+    topology_check_status: Mutex<TopologyCheckStatus>,
+    driver_stop_reason: Mutex<Option<String>>,
+    topology_events: broadcast::Sender<TopologyEvent>,
+    slot_counters: Option<SlotCounters>,
 }
 
 pub(crate) type Core<C> = Arc<InnerCore<C>>;
 
+/// The number of topology changes that are kept for the receivers of
+/// [`ClusterConnection::topology_updates`] that didn't receive them yet.
+pub const TOPOLOGY_EVENTS_CAPACITY: usize = 256;
+
+/// How long creating a [`ClusterConnection`] waits for the initial slot refresh, which queries the topology of the
+/// cluster, as set by [`ClusterClientBuilder::initial_slots_refresh`](crate::cluster::ClusterClientBuilder::initial_slots_refresh).
+///
+/// Until the initial refresh completes, the slots aren't mapped to any node, so commands that are routed by their
+/// keys wait for a slot refresh before they're sent, while randomly routed commands are sent to the initial nodes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InitialSlotsRefresh {
+    /// Wait until the initial refresh completes, and fail the connection if it fails. This is the default.
+    #[default]
+    Wait,
+    /// Wait for the initial refresh at most the given duration, and complete it in the background if it takes longer.
+    WaitAtMost(Duration),
+    /// Return right after connecting to the initial nodes, and refresh the slots in the background.
+    Background,
+}
+
+/// The command that the health checks send to probe a connection, and the validation of its response, as set by
+/// [`ClusterClientBuilder::health_check_probe`](crate::cluster::ClusterClientBuilder::health_check_probe).
+///
+/// The default probe sends `PING` and accepts any string response. A probe that fails, either with an error, a
+/// timeout or a response that isn't valid, marks the connection as unhealthy.
+#[derive(Clone)]
+pub struct HealthCheckProbe {
+    cmd: Cmd,
+    validate: Arc<dyn Fn(&Value) -> bool + Send + Sync>,
+}
+
+impl HealthCheckProbe {
+    /// A probe that sends `cmd`, and considers the connection healthy if `validate` returns `true` for the response.
+    pub fn new(cmd: Cmd, validate: impl Fn(&Value) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            cmd,
+            validate: Arc::new(validate),
+        }
+    }
+
+    /// A probe that sends `ECHO message`, and expects the message in the response.
+    pub fn echo(message: impl Into<String>) -> Self {
+        let message = message.into();
+        let mut echo = crate::cmd("ECHO");
+        echo.arg(&message);
+        Self::new(
+            echo,
+            move |value| matches!(String::from_redis_value(value), Ok(response) if response == message),
+        )
+    }
+
+    pub(crate) async fn check<C>(&self, conn: &mut C) -> RedisResult<()>
+    where
+        C: ConnectionLike,
+    {
+        let value: Value = self.cmd.query_async(conn).await?;
+        if (self.validate)(&value) {
+            Ok(())
+        } else {
+            Err(RedisError::from((
+                ErrorKind::ResponseError,
+                "Unexpected response to the health check probe",
+                format!("{value:?}"),
+            )))
+        }
+    }
+}
+
+impl Default for HealthCheckProbe {
+    fn default() -> Self {
+        Self::new(crate::cmd("PING"), |value| {
+            String::from_redis_value(value).is_ok()
+        })
+    }
+}
+
+impl fmt::Debug for HealthCheckProbe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HealthCheckProbe")
+            .field("cmd", &self.cmd)
+            .finish_non_exhaustive()
+    }
+}
+
+/// How many of the initial nodes must be connected to for a [`ClusterConnection`] to be created, as set by
+/// [`ClusterClientBuilder::min_initial_connections`](crate::cluster::ClusterClientBuilder::min_initial_connections).
+///
+/// The initial nodes are counted after their host names were resolved, so a host name that resolves to several
+/// addresses counts as several nodes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MinInitialConnections {
+    /// At least the given number of initial nodes, or all of them if there are fewer.
+    Count(usize),
+    /// At least the given fraction of the initial nodes, rounded up. Must be in `(0, 1]`.
+    Fraction(f64),
+}
+
+impl Default for MinInitialConnections {
+    /// At least one initial node.
+    fn default() -> Self {
+        MinInitialConnections::Count(1)
+    }
+}
+
+impl MinInitialConnections {
+    fn required(&self, initial_nodes: usize) -> usize {
+        let required = match *self {
+            MinInitialConnections::Count(count) => count,
+            MinInitialConnections::Fraction(fraction) => {
+                (fraction * initial_nodes as f64).ceil() as usize
+            }
+        };
+        required.clamp(1, initial_nodes.max(1))
+    }
+}
+
+/// A snapshot of the pubsub state of a [`ClusterConnection`], as returned by [`ClusterConnection::pubsub_state`].
+#[derive(Debug, Clone)]
+pub struct PubSubState {
+    /// The subscriptions that are currently held by each node, by the node's address.
+    pub subscriptions_by_address: HashMap<String, PubSubSubscriptionInfo>,
+    /// The subscriptions that aren't held by any node yet, for example because their node is unreachable.
+    pub unassigned_subscriptions: PubSubSubscriptionInfo,
+    /// The number of pubsub messages that were delivered to the push sender.
+    pub delivered_messages: u64,
+    /// The number of pubsub messages that were dropped because the push receiver was closed.
+    pub dropped_messages: u64,
+}
+
+#[derive(Default)]
+struct PubSubMessageStats {
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// A callback that is called with the address of a node whose pubsub connection was found dead by the health checks.
+pub(crate) type PubSubDeadConnectionCallback = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// User metadata that is attached to a node, such as its rack, availability zone or weight, as returned by the
+/// callback that is set by
+/// [`ClusterClientBuilder::node_metadata_provider`](crate::cluster::ClusterClientBuilder::node_metadata_provider).
+pub type NodeMetadata = HashMap<String, String>;
+
+/// A callback that is called with the address and the IP of a newly discovered node, and returns its metadata.
+pub(crate) type NodeMetadataProvider =
+    Arc<dyn Fn(&str, Option<IpAddr>) -> NodeMetadata + Send + Sync>;
+
+impl<C> InnerCore<C> {
+    // Returns the metadata of the node at `address` merged with its override, or empty metadata if the node isn't
+    // connected.
+    fn node_metadata(&self, connections: &ConnectionsContainer<C>, address: &str) -> NodeMetadata {
+        connections
+            .node_for_address(address)
+            .map(|node| {
+                let metadata = self
+                    .cluster_params
+                    .node_overrides
+                    .merge_metadata(address, &node.metadata);
+                NodeMetadata::clone(&metadata)
+            })
+            .unwrap_or_default()
+    }
+
+    fn send_topology_events(&self, events: Vec<TopologyEvent>) {
+        for event in events {
+            // Fails only if there are no receivers.
+            let _ = self.topology_events.send(event);
+        }
+    }
+
+    // A panic of the driver task while the lock is held leaves the scheduler in a consistent state, since requests
+    // are only pushed or popped, so the poisoning is ignored rather than losing the scheduled requests.
+    fn scheduler(&self) -> MutexGuard<'_, FairScheduler<PendingRequest<C>>> {
+        self.scheduler
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Only the first reason is kept, since it's the one that stopped the driver task. The requests that the driver
+    // task didn't start are dropped, since the core outlives the task while connection handles hold it, so that they
+    // fail with the reason instead of waiting forever.
+    fn record_driver_stop(&self, reason: String) {
+        {
+            let mut driver_stop_reason = self.driver_stop_reason.lock().unwrap();
+            if driver_stop_reason.is_none() {
+                *driver_stop_reason = Some(reason);
+            }
+        }
+        while self.pending_requests.pop().is_some() {}
+        let mut scheduler = self.scheduler();
+        while scheduler.pop().is_some() {}
+    }
+}
+
 impl<C> InnerCore<C>
 where
     C: ConnectionLike + Connect + Clone + Send + Sync + 'static,
@@ -385,6 +1574,26 @@ where
             .slot_map
             .get_slots_of_node(node_address)
     }
+
+    pub(crate) async fn get_primary_addresses(&self) -> Vec<String> {
+        self.conn_lock
+            .read()
+            .await
+            .slot_map
+            .addresses_for_all_primaries()
+            .into_iter()
+            .map(|address| address.to_string())
+            .collect()
+    }
+
+    pub(crate) fn get_migrating_slots(&self) -> HashSet<u16> {
+        self.slot_migrations
+            .lock()
+            .unwrap()
+            .all()
+            .into_keys()
+            .collect()
+    }
 }
 
 pub(crate) struct ClusterConnInner<C> {
@@ -397,16 +1606,21 @@ pub(crate) struct ClusterConnInner<C> {
     shutdown_flag: Arc<AtomicBool>,
 }
 
-impl<C> Dispose for ClusterConnInner<C> {
-    fn dispose(self) {
-        self.shutdown_flag.store(true, Ordering::Relaxed);
-    }
-}
-
 #[derive(Clone)]
 pub(crate) enum InternalRoutingInfo<C> {
     SingleNode(InternalSingleNodeRouting<C>),
-    MultiNode((MultipleNodeRoutingInfo, Option<ResponsePolicy>)),
+    MultiNode((MultipleNodeRoutingInfo, MultiNodeResponsePolicy)),
+}
+
+/// How the responses of the nodes to a command that was sent to multiple nodes are returned.
+#[derive(Clone)]
+pub(crate) enum MultiNodeResponsePolicy {
+    /// Combined into one response by the response policy.
+    Aggregate(Option<ResponsePolicy>),
+    /// Combined into one response by a reducer of the caller, once all the nodes responded.
+    Reduce(ResponseReducer),
+    /// Returned separately, by the addresses of the nodes.
+    PerNode,
 }
 
 #[derive(PartialEq, Clone, Debug)]
@@ -426,8 +1640,11 @@ impl<C> From<cluster_routing::RoutingInfo> for InternalRoutingInfo<C> {
             cluster_routing::RoutingInfo::SingleNode(route) => {
                 InternalRoutingInfo::SingleNode(route.into())
             }
-            cluster_routing::RoutingInfo::MultiNode(routes) => {
-                InternalRoutingInfo::MultiNode(routes)
+            cluster_routing::RoutingInfo::MultiNode((routing, response_policy)) => {
+                InternalRoutingInfo::MultiNode((
+                    routing,
+                    MultiNodeResponsePolicy::Aggregate(response_policy),
+                ))
             }
         }
     }
@@ -454,6 +1671,57 @@ pub(crate) enum InternalSingleNodeRouting<C> {
     },
 }
 
+impl<C> InternalSingleNodeRouting<C> {
+    // Whether the request was redirected by an ASK error, and must be preceded by ASKING.
+    fn is_ask_redirect(&self) -> bool {
+        matches!(
+            self,
+            InternalSingleNodeRouting::Redirect {
+                redirect: Redirect::Ask(_),
+                ..
+            }
+        )
+    }
+}
+
+#[cfg(feature = "traffic-recorder")]
+impl<C> InternalRoutingInfo<C> {
+    fn recorded(&self) -> traffic_recorder::RecordedRouting {
+        use traffic_recorder::RecordedRouting;
+        match self {
+            InternalRoutingInfo::SingleNode(routing) => routing.recorded(),
+            InternalRoutingInfo::MultiNode((MultipleNodeRoutingInfo::AllNodes, _)) => {
+                RecordedRouting::AllNodes
+            }
+            InternalRoutingInfo::MultiNode((MultipleNodeRoutingInfo::AllMasters, _)) => {
+                RecordedRouting::AllPrimaries
+            }
+            InternalRoutingInfo::MultiNode((MultipleNodeRoutingInfo::MultiSlot(_), _)) => {
+                RecordedRouting::MultiSlot
+            }
+        }
+    }
+}
+
+#[cfg(feature = "traffic-recorder")]
+impl<C> InternalSingleNodeRouting<C> {
+    fn recorded(&self) -> traffic_recorder::RecordedRouting {
+        use traffic_recorder::RecordedRouting;
+        match self {
+            InternalSingleNodeRouting::Random => RecordedRouting::Random,
+            InternalSingleNodeRouting::SpecificNode(route) => RecordedRouting::Slot {
+                slot: route.slot(),
+                slot_addr: route.slot_addr(),
+            },
+            InternalSingleNodeRouting::ByAddress(_)
+            | InternalSingleNodeRouting::Connection { .. } => RecordedRouting::Address,
+            InternalSingleNodeRouting::Redirect { redirect, .. } => RecordedRouting::Redirect {
+                ask: matches!(redirect, Redirect::Ask(_)),
+            },
+        }
+    }
+}
+
 impl<C> Default for InternalSingleNodeRouting<C> {
     fn default() -> Self {
         Self::Random
@@ -490,23 +1758,50 @@ enum CmdArg<C> {
         // struct containing the arguments for the cluster scan command - scan state cursor, match pattern, count and object type.
         cluster_scan_args: ClusterScanArgs,
     },
+    ClusterScanAllShards {
+        cursor: ClusterScanCursor,
+        options: ClusterScanOptions,
+    },
 }
 
-fn route_for_pipeline(pipeline: &crate::Pipeline) -> RedisResult<Option<Route>> {
-    fn route_for_command(cmd: &Cmd) -> Option<Route> {
-        match cluster_routing::RoutingInfo::for_routable(cmd) {
-            Some(cluster_routing::RoutingInfo::SingleNode(SingleNodeRoutingInfo::Random)) => None,
-            Some(cluster_routing::RoutingInfo::SingleNode(
-                SingleNodeRoutingInfo::SpecificNode(route),
-            )) => Some(route),
-            Some(cluster_routing::RoutingInfo::MultiNode(_)) => None,
-            Some(cluster_routing::RoutingInfo::SingleNode(SingleNodeRoutingInfo::ByAddress {
+impl<C> CmdArg<C> {
+    /// Returns the slots that the request is routed by.
+    fn routed_slots(&self) -> Vec<u16> {
+        match self {
+            CmdArg::Cmd {
+                routing:
+                    InternalRoutingInfo::SingleNode(InternalSingleNodeRouting::SpecificNode(route)),
+                ..
+            }
+            | CmdArg::Pipeline {
+                route: InternalSingleNodeRouting::SpecificNode(route),
                 ..
-            })) => None,
-            None => None,
+            } => vec![route.slot()],
+            CmdArg::Cmd {
+                routing:
+                    InternalRoutingInfo::MultiNode((MultipleNodeRoutingInfo::MultiSlot(routes), _)),
+                ..
+            } => routes.iter().map(|(route, _)| route.slot()).collect(),
+            _ => Vec::new(),
         }
     }
+}
+
+fn route_for_command(cmd: &Cmd) -> Option<Route> {
+    match cluster_routing::RoutingInfo::for_routable(cmd) {
+        Some(cluster_routing::RoutingInfo::SingleNode(SingleNodeRoutingInfo::Random)) => None,
+        Some(cluster_routing::RoutingInfo::SingleNode(SingleNodeRoutingInfo::SpecificNode(
+            route,
+        ))) => Some(route),
+        Some(cluster_routing::RoutingInfo::MultiNode(_)) => None,
+        Some(cluster_routing::RoutingInfo::SingleNode(SingleNodeRoutingInfo::ByAddress {
+            ..
+        })) => None,
+        None => None,
+    }
+}
 
+fn route_for_pipeline(pipeline: &crate::Pipeline) -> RedisResult<Option<Route>> {
     // Find first specific slot and send to it. There's no need to check If later commands
     // should be routed to a different slot, since the server will return an error indicating this.
     pipeline.cmd_iter().map(route_for_command).try_fold(
@@ -535,9 +1830,78 @@ fn boxed_sleep(duration: Duration) -> BoxFuture<'static, ()> {
     return Box::pin(async_std::task::sleep(duration));
 }
 
+fn pending_requests_full_error() -> RedisError {
+    RedisError::from((ErrorKind::TryAgain, "The queue of pending requests is full"))
+}
+
+fn request_timeout_error() -> RedisError {
+    RedisError::from((
+        ErrorKind::Timeout,
+        "The request didn't complete within its request timeout",
+    ))
+}
+
+/// The address of a node, as `host:port`.
+pub type NodeAddress = String;
+
+/// The responses of the nodes to a command that was sent to multiple nodes, by the nodes' addresses, as returned by
+/// [`ClusterConnection::route_command_to_nodes`].
+pub type MultiNodeResponse = HashMap<NodeAddress, RedisResult<Value>>;
+
+/// Combines the responses of the nodes to a command that was sent to multiple nodes into one response, as set by
+/// [`RouteOptions::reducer`].
+pub type ResponseReducer = Arc<dyn Fn(Vec<Value>) -> RedisResult<Value> + Send + Sync>;
+
+/// The options of a single request, as passed to [`ClusterConnection::route_command_with_options`].
+#[derive(Clone, Default)]
+pub struct RouteOptions {
+    request_timeout: Option<Duration>,
+    reducer: Option<ResponseReducer>,
+}
+
+impl fmt::Debug for RouteOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RouteOptions")
+            .field("request_timeout", &self.request_timeout)
+            .field("reducer", &self.reducer.as_ref().map(|_| "<reducer>"))
+            .finish()
+    }
+}
+
+impl RouteOptions {
+    /// Creates options that don't change the defaults of the client.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fails the request with [`ErrorKind::Timeout`] if it doesn't complete within `timeout`, including its retries,
+    /// redirects and the waits for reconnects, instead of the client's
+    /// [`request_timeout`](crate::cluster::ClusterClientBuilder::request_timeout).
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Combines the responses of the nodes to a command that is sent to multiple nodes with `reducer`, instead of
+    /// the response policy of the routing. The responses are passed to `reducer` once all the nodes responded, and
+    /// the request fails with the first error of a node, like [`ResponsePolicy::Aggregate`]. The responses of a
+    /// command that is split by [`MultipleNodeRoutingInfo::MultiSlot`] are in the order of its sub-commands.
+    ///
+    /// The reducer has no effect on commands that are routed to a single node.
+    pub fn reducer(
+        mut self,
+        reducer: impl Fn(Vec<Value>) -> RedisResult<Value> + Send + Sync + 'static,
+    ) -> Self {
+        self.reducer = Some(Arc::new(reducer));
+        self
+    }
+}
+
 pub(crate) enum Response {
     Single(Value),
+    PerNode(MultiNodeResponse),
     ClusterScanResult(ScanStateRC, Vec<Value>),
+    ClusterScanAllShardsResult(ClusterScanCursor, Vec<Value>),
     Multiple(Vec<Value>),
 }
 
@@ -557,6 +1921,11 @@ impl From<ArcStr> for OperationTarget {
 struct Message<C> {
     cmd: CmdArg<C>,
     sender: oneshot::Sender<RedisResult<Response>>,
+    tenant: Option<ArcStr>,
+    // The time in which the request must complete, including its retries.
+    request_timeout: Option<Duration>,
+    // Counts the bytes of the request's attempts, when the caller asked for them.
+    size: Option<Arc<RequestSizeCounter>>,
 }
 
 enum RecoverFuture {
@@ -585,6 +1954,10 @@ impl fmt::Debug for ConnectionState {
 #[derive(Clone)]
 struct RequestInfo<C> {
     cmd: CmdArg<C>,
+    tenant: Option<ArcStr>,
+    // The request fails with a timeout error when it didn't complete by this time.
+    deadline: Option<Instant>,
+    size: Option<Arc<RequestSizeCounter>>,
 }
 
 impl<C> RequestInfo<C> {
@@ -612,7 +1985,7 @@ impl<C> RequestInfo<C> {
                     *route = redirect;
                 }
                 // cluster_scan is sent as a normal command internally so we will not reach that point.
-                CmdArg::ClusterScan { .. } => {
+                CmdArg::ClusterScan { .. } | CmdArg::ClusterScanAllShards { .. } => {
                     unreachable!()
                 }
             }
@@ -646,7 +2019,7 @@ impl<C> RequestInfo<C> {
                 fix_route(route);
             }
             // cluster_scan is sent as a normal command internally so we will not reach that point.
-            CmdArg::ClusterScan { .. } => {
+            CmdArg::ClusterScan { .. } | CmdArg::ClusterScanAllShards { .. } => {
                 unreachable!()
             }
         }
@@ -680,6 +2053,9 @@ pin_project! {
         request: Option<PendingRequest<C>>,
         #[pin]
         future: RequestState<BoxFuture<'static, OperationResult>>,
+        // Completes at the deadline of the request.
+        timeout: Option<BoxFuture<'static, ()>>,
+        clock: SharedClock,
     }
 }
 
@@ -717,6 +2093,13 @@ impl<C> Future for Request<C> {
         if this.request.is_none() {
             return Poll::Ready(Next::Done);
         }
+        if let Some(timeout) = this.timeout.as_mut() {
+            if timeout.as_mut().poll(cx).is_ready() {
+                // Dropping the request drops its future, so it's neither sent again nor retried.
+                self.respond(Err(request_timeout_error()));
+                return Next::Done.into();
+            }
+        }
         let future = match this.future.as_mut().project() {
             RequestStateProj::Future { future } => future,
             RequestStateProj::Sleep { sleep } => {
@@ -728,7 +2111,21 @@ impl<C> Future for Request<C> {
             }
             _ => panic!("Request future must be Some"),
         };
-        match ready!(future.poll(cx)) {
+        // A panic while handling a single request fails that request, instead of the whole driver task.
+        let result = match panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(result) => ready!(result),
+            Err(panic) => {
+                let message = panic_message(&*panic);
+                warn!("A request panicked: {message}");
+                self.respond(Err(RedisError::from((
+                    ErrorKind::ClientError,
+                    "The request panicked",
+                    message,
+                ))));
+                return Next::Done.into();
+            }
+        };
+        match result {
             Ok(item) => {
                 self.respond(Ok(item));
                 Next::Done.into()
@@ -763,6 +2160,11 @@ impl<C> Future for Request<C> {
                     self.respond(Err(err));
                     return next;
                 }
+                // A request that none waits for anymore isn't retried.
+                if request.sender.is_closed() {
+                    this.request.take();
+                    return Next::Done.into();
+                }
                 request.retry = request.retry.saturating_add(1);
 
                 if err.kind() == ErrorKind::ClusterConnectionNotFound {
@@ -821,7 +2223,7 @@ impl<C> Future for Request<C> {
                         let sleep_duration = this.retry_params.wait_time_for_retry(request.retry);
                         // Sleep and retry.
                         this.future.set(RequestState::Sleep {
-                            sleep: boxed_sleep(sleep_duration),
+                            sleep: this.clock.sleep(sleep_duration),
                         });
                         self.poll(cx)
                     }
@@ -858,6 +2260,25 @@ impl<C> Future for Request<C> {
 }
 
 impl<C> Request<C> {
+    fn new(
+        params: &ClusterParams,
+        request: PendingRequest<C>,
+        future: RequestState<BoxFuture<'static, OperationResult>>,
+    ) -> Self {
+        let clock = params.clock.clone();
+        let timeout = request
+            .info
+            .deadline
+            .map(|deadline| clock.sleep(deadline.saturating_duration_since(clock.now())));
+        Self {
+            retry_params: params.retry_params.clone(),
+            request: Some(request),
+            future,
+            timeout,
+            clock,
+        }
+    }
+
     fn respond(self: Pin<&mut Self>, msg: RedisResult<Response>) {
         // If `send` errors the receiver has dropped and thus does not care about the message
         let _ = self
@@ -884,12 +2305,20 @@ where
         initial_nodes: &[ConnectionInfo],
         cluster_params: ClusterParams,
         push_sender: Option<mpsc::UnboundedSender<PushInfo>>,
-    ) -> RedisResult<Disposable<Self>> {
-        let connections =
-            Self::create_initial_connections(initial_nodes, &cluster_params, push_sender.clone())
-                .await?;
+    ) -> RedisResult<Self> {
+        let pubsub_stats = Arc::new(PubSubMessageStats::default());
+        let push_sender =
+            push_sender.map(|sender| Self::count_pubsub_messages(sender, pubsub_stats.clone()));
+        let connections = Self::create_initial_connections(
+            initial_nodes,
+            &cluster_params,
+            push_sender.clone(),
+            cluster_params.min_initial_connections,
+        )
+        .await?;
 
         let topology_checks_interval = cluster_params.topology_checks_interval;
+        let pubsub_health_check_interval = cluster_params.pubsub_health_check_interval;
         let slots_refresh_rate_limiter = cluster_params.slots_refresh_rate_limit;
         let inner = Arc::new(InnerCore {
             conn_lock: RwLock::new(ConnectionsContainer::new(
@@ -898,8 +2327,10 @@ where
                 cluster_params.read_from_replicas,
                 0,
             )),
+            refresh_lock: AsyncMutex::new(()),
             cluster_params: cluster_params.clone(),
-            pending_requests: Mutex::new(Vec::new()),
+            pending_requests: PendingRequests::new(cluster_params.max_pending_requests),
+            scheduler: Mutex::new(FairScheduler::new(cluster_params.tenant_weights.clone())),
             slot_refresh_state: SlotRefreshState::new(slots_refresh_rate_limiter),
             initial_nodes: initial_nodes.to_vec(),
             push_sender: push_sender.clone(),
@@ -911,6 +2342,19 @@ where
                 },
             ),
             subscriptions_by_address: RwLock::new(Default::default()),
+            pubsub_stats,
+            node_identities: cluster_params
+                .node_id_cache_ttl
+                .map(|ttl| RwLock::new(NodeIdentities::new(ttl))),
+            slot_migrations: Mutex::new(Default::default()),
+            circuit_breakers: Mutex::new(Default::default()),
+            reconnect_backoffs: Mutex::new(Default::default()),
+            reconnecting_nodes: Mutex::new(Default::default()),
+            topology_errors: AtomicUsize::new(0),
+            topology_check_status: Mutex::new(Default::default()),
+            driver_stop_reason: Mutex::new(None),
+            topology_events: broadcast::channel(TOPOLOGY_EVENTS_CAPACITY).0,
+            slot_counters: cluster_params.slot_stats.then(SlotCounters::new),
         });
         let shutdown_flag = Arc::new(AtomicBool::new(false));
         let connection = ClusterConnInner {
@@ -920,14 +2364,41 @@ where
             state: ConnectionState::PollComplete,
             shutdown_flag: shutdown_flag.clone(),
         };
-        Self::refresh_slots_and_subscriptions_with_retries(
+        let initial_refresh = Self::refresh_slots_and_subscriptions_with_retries(
             connection.inner.clone(),
             &RefreshPolicy::NotThrottable,
-        )
-        .await?;
+        );
+        match cluster_params.initial_slots_refresh {
+            InitialSlotsRefresh::Wait => initial_refresh.await?,
+            InitialSlotsRefresh::WaitAtMost(timeout) => {
+                match future::select(initial_refresh.boxed(), cluster_params.clock.sleep(timeout))
+                    .await
+                {
+                    future::Either::Left((result, _)) => result?,
+                    future::Either::Right((_, initial_refresh)) => {
+                        Self::complete_initial_refresh_in_background(initial_refresh)
+                    }
+                }
+            }
+            InitialSlotsRefresh::Background => {
+                Self::complete_initial_refresh_in_background(initial_refresh.boxed())
+            }
+        }
 
         if let Some(duration) = topology_checks_interval {
             let periodic_task = ClusterConnInner::periodic_topology_check(
+                connection.inner.clone(),
+                duration,
+                shutdown_flag.clone(),
+            );
+            #[cfg(feature = "tokio-comp")]
+            tokio::spawn(periodic_task);
+            #[cfg(all(not(feature = "tokio-comp"), feature = "async-std-comp"))]
+            AsyncStd::spawn(periodic_task);
+        }
+
+        if let Some(duration) = pubsub_health_check_interval {
+            let periodic_task = ClusterConnInner::periodic_pubsub_health_check(
                 connection.inner.clone(),
                 duration,
                 shutdown_flag,
@@ -938,16 +2409,124 @@ where
             AsyncStd::spawn(periodic_task);
         }
 
-        Ok(Disposable::new(connection))
+        Ok(connection)
+    }
+
+    fn complete_initial_refresh_in_background(
+        initial_refresh: BoxFuture<'static, RedisResult<()>>,
+    ) {
+        let initial_refresh = async move {
+            if let Err(err) = initial_refresh.await {
+                warn!("The initial slot refresh failed: {err}");
+            }
+        };
+        #[cfg(feature = "tokio-comp")]
+        tokio::spawn(initial_refresh);
+        #[cfg(all(not(feature = "tokio-comp"), feature = "async-std-comp"))]
+        AsyncStd::spawn(initial_refresh);
+    }
+
+    /// Drives the requests that are received from `receiver` until all the senders are dropped.
+    ///
+    /// If the driver panics, the requests it was handling fail with a retryable `IoError` that contains the panic,
+    /// and a new driver is started from the same core, which keeps the connections and the queued requests. If the
+    /// driver keeps panicking, the task stops, and the following requests fail with a `DriverStopped` error that
+    /// contains the last panic.
+    async fn supervise(mut connection: Self, mut receiver: mpsc::Receiver<Message<C>>)
+    where
+        C: Unpin,
+    {
+        let mut restarts: VecDeque<Instant> = VecDeque::new();
+        loop {
+            let driver = stream::poll_fn(|cx| receiver.poll_recv(cx))
+                .map(Ok)
+                .forward(&mut connection);
+            let Err(panic) = AssertUnwindSafe(driver).catch_unwind().await else {
+                return;
+            };
+            let message = panic_message(&*panic);
+
+            let now = Instant::now();
+            restarts.retain(|restart| now.duration_since(*restart) < DRIVER_RESTARTS_WINDOW);
+            if restarts.len() >= MAX_DRIVER_RESTARTS {
+                connection.inner.record_driver_stop(format!(
+                    "the driver task panicked more than {MAX_DRIVER_RESTARTS} times within {DRIVER_RESTARTS_WINDOW:?}, the last panic: {message}"
+                ));
+                return;
+            }
+            restarts.push_back(now);
+            warn!(
+                "The cluster connection's driver task panicked, restarting it. Panic: `{message}`"
+            );
+
+            // The requests of the panicked driver fail, instead of being dropped, so that their callers can tell
+            // that they may be retried.
+            for mut request in std::mem::take(&mut connection.in_flight_requests) {
+                if let Some(request) = request.as_mut().project().request.take() {
+                    let _ = request.sender.send(Err(RedisError::from((
+                        ErrorKind::IoError,
+                        "The cluster connection's driver task panicked and was restarted",
+                        message.clone(),
+                    ))));
+                }
+            }
+            connection.refresh_error = None;
+            connection.state = ConnectionState::PollComplete;
+        }
+    }
+
+    /// Returns a sender that forwards the push messages of the nodes' connections to `push_sender`,
+    /// while counting the delivered and dropped pubsub messages.
+    fn count_pubsub_messages(
+        push_sender: mpsc::UnboundedSender<PushInfo>,
+        pubsub_stats: Arc<PubSubMessageStats>,
+    ) -> mpsc::UnboundedSender<PushInfo> {
+        let (sender, mut receiver) = mpsc::unbounded_channel::<PushInfo>();
+        let forward = async move {
+            while let Some(push_info) = receiver.recv().await {
+                let is_message = matches!(
+                    push_info.kind,
+                    PushKind::Message | PushKind::PMessage | PushKind::SMessage
+                );
+                let delivered = push_sender.send(push_info).is_ok();
+                if is_message {
+                    let counter = if delivered {
+                        &pubsub_stats.delivered
+                    } else {
+                        &pubsub_stats.dropped
+                    };
+                    counter.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+        };
+        #[cfg(feature = "tokio-comp")]
+        tokio::spawn(forward);
+        #[cfg(all(not(feature = "tokio-comp"), feature = "async-std-comp"))]
+        AsyncStd::spawn(forward);
+        sender
     }
 
     /// Go through each of the initial nodes and attempt to retrieve all IP entries from them.
     /// If there's a DNS endpoint that directs to several IP addresses, add all addresses to the initial nodes list.
     /// Returns a vector of tuples, each containing a node's address (including the hostname) and its corresponding SocketAddr if retrieved.
+    /// Returns an `InvalidClientConfig` error if any of the initial nodes is a Unix socket address.
     pub(crate) async fn try_to_expand_initial_nodes(
         initial_nodes: &[ConnectionInfo],
-    ) -> Vec<(String, Option<SocketAddr>)> {
-        stream::iter(initial_nodes)
+        params: &ClusterParams,
+    ) -> RedisResult<Vec<(String, Option<SocketAddr>)>> {
+        // The rest of the cluster logic identifies nodes by their host:port, as announced by the cluster topology,
+        // so a Unix socket initial node could never be matched against the discovered nodes.
+        if let Some(info) = initial_nodes
+            .iter()
+            .find(|info| matches!(info.addr, crate::ConnectionAddr::Unix(_)))
+        {
+            return Err(RedisError::from((
+                ErrorKind::InvalidClientConfig,
+                "Unix socket initial nodes are not supported in cluster mode",
+                info.addr.to_string(),
+            )));
+        }
+        let expanded_nodes = stream::iter(initial_nodes)
             .fold(
                 Vec::with_capacity(initial_nodes.len()),
                 |mut acc, info| async {
@@ -960,12 +2539,14 @@ where
                             tls_params: _,
                         } => (host, port),
                         crate::ConnectionAddr::Unix(_) => {
-                            // We don't support multiple addresses for a Unix address. Store the initial node address and continue
-                            acc.push((info.addr.to_string(), None));
-                            return acc;
+                            unreachable!("Unix addresses are rejected above")
                         }
                     };
-                    match get_socket_addrs(host, *port).await {
+                    if params.mapped_transport(&info.addr.to_string()).is_some() {
+                        acc.push((info.addr.to_string(), None));
+                        return acc;
+                    }
+                    match resolve_socket_addrs(host, *port, params).await {
                         Ok(socket_addrs) => {
                             for addr in socket_addrs {
                                 acc.push((info.addr.to_string(), Some(addr)));
@@ -979,16 +2560,18 @@ where
                     acc
                 },
             )
-            .await
+            .await;
+        Ok(expanded_nodes)
     }
 
     async fn create_initial_connections(
         initial_nodes: &[ConnectionInfo],
         params: &ClusterParams,
         push_sender: Option<mpsc::UnboundedSender<PushInfo>>,
+        min_connections: MinInitialConnections,
     ) -> RedisResult<ConnectionMap<C>> {
         let initial_nodes: Vec<(String, Option<SocketAddr>)> =
-            Self::try_to_expand_initial_nodes(initial_nodes).await;
+            Self::try_to_expand_initial_nodes(initial_nodes, params).await?;
         let connections = stream::iter(initial_nodes.iter().cloned())
             .map(|(node_addr, socket_addr)| {
                 let mut params: ClusterParams = params.clone();
@@ -1012,31 +2595,40 @@ where
                     } else {
                         node_addr
                     };
-                    result.map(|node| (node_address, node))
+                    match result {
+                        Ok(node) => Ok((node_address, node)),
+                        Err(err) => Err((node_address, err)),
+                    }
                 }
             })
             .buffer_unordered(initial_nodes.len())
             .fold(
                 (
                     ConnectionsMap(HashMap::with_capacity(initial_nodes.len())),
-                    None,
+                    Vec::new(),
                 ),
-                |mut connections: (ConnectionMap<C>, Option<String>), addr_conn_res| async move {
+                |mut connections: (ConnectionMap<C>, Vec<String>), addr_conn_res| async move {
                     match addr_conn_res {
                         Ok((addr, node)) => {
                             connections.0 .0.insert(addr.into(), node);
-                            (connections.0, None)
                         }
-                        Err(e) => (connections.0, Some(e.to_string())),
+                        Err((addr, e)) => connections.1.push(format!("{addr}: {e}")),
                     }
+                    connections
                 },
             )
             .await;
-        if connections.0 .0.is_empty() {
+        let required = min_connections.required(initial_nodes.len());
+        if connections.0 .0.len() < required {
             return Err(RedisError::from((
                 ErrorKind::IoError,
                 "Failed to create initial connections",
-                connections.1.unwrap_or("".to_string()),
+                format!(
+                    "connected to {} of {} initial nodes, while at least {required} are required. Errors: {}",
+                    connections.0 .0.len(),
+                    initial_nodes.len(),
+                    connections.1.join(", ")
+                ),
             )));
         }
         info!("Connected to initial nodes:\n{}", connections.0);
@@ -1046,10 +2638,12 @@ where
     fn reconnect_to_initial_nodes(&mut self) -> impl Future<Output = ()> {
         let inner = self.inner.clone();
         async move {
+            // Any initial node is enough to rediscover the topology, so the startup policy doesn't apply here.
             let connection_map = match Self::create_initial_connections(
                 &inner.initial_nodes,
                 &inner.cluster_params,
                 None,
+                MinInitialConnections::default(),
             )
             .await
             {
@@ -1078,22 +2672,52 @@ where
         }
     }
 
+    // Reconnects to the nodes that aren't already being reconnected by another task with the same connections, and
+    // returns the addresses of the nodes that were reconnected.
+    async fn reconnect_nodes(
+        inner: Arc<InnerCore<C>>,
+        addresses: Vec<ArcStr>,
+        conn_type: RefreshConnectionType,
+    ) -> Vec<ArcStr> {
+        let (addresses, _claim) =
+            ReconnectClaim::new(&inner.reconnecting_nodes, addresses, conn_type);
+        if !addresses.is_empty() {
+            Self::refresh_connections(inner.clone(), addresses.clone(), conn_type).await;
+        }
+        addresses
+    }
+
     async fn refresh_connections(
         inner: Arc<InnerCore<C>>,
         addresses: Vec<ArcStr>,
         conn_type: RefreshConnectionType,
     ) {
         info!("Started refreshing connections to {:?}", addresses);
-        let mut connections_container = inner.conn_lock.write().await;
+        let _refresh_guard = inner.refresh_lock.lock().await;
+        let previous_nodes: Vec<_> = {
+            let read_guard = inner.conn_lock.read().await;
+            addresses
+                .into_iter()
+                .map(|address| {
+                    let node = read_guard.node_for_address(&address);
+                    (address, node)
+                })
+                .collect()
+        };
         let cluster_params = &inner.cluster_params;
         let subscriptions_by_address = &inner.subscriptions_by_address;
         let push_sender = &inner.push_sender;
+        let core = &inner;
 
-        stream::iter(addresses.into_iter())
+        let refreshed_nodes = stream::iter(previous_nodes)
             .fold(
-                &mut *connections_container,
-                |connections_container, address| async move {
-                    let node_option = connections_container.remove_node(&address);
+                Vec::new(),
+                |mut refreshed_nodes, (address, node_option)| async move {
+                    if let Err(err) = Self::check_reconnect_backoff(core, &address) {
+                        debug!("Skipping the reconnect to node {address}, which is backing off after `{err}`");
+                        return refreshed_nodes;
+                    }
+                    let was_connected = node_option.is_some();
 
                     // override subscriptions for this connection
                     let mut cluster_params = cluster_params.clone();
@@ -1108,22 +2732,39 @@ where
                         push_sender.clone(),
                     )
                     .await;
-                    match node {
-                        Ok(node) => {
-                            connections_container
-                                .replace_or_add_connection_for_address(address, node);
-                        }
+                    Self::observe_reconnect(core, &address, node.as_ref().map(|_| ()));
+                    let node = match node {
+                        Ok(node) => Some(node),
                         Err(err) => {
                             warn!(
                                 "Failed to refresh connection for node {}. Error: `{:?}`",
                                 address, err
                             );
+                            None
                         }
-                    }
-                    connections_container
+                    };
+                    refreshed_nodes.push((address, was_connected, node));
+                    refreshed_nodes
                 },
             )
             .await;
+
+        let mut write_guard = inner.conn_lock.write().await;
+        for (address, was_connected, node) in refreshed_nodes {
+            // A node that was removed from the topology while it was reconnecting isn't added back.
+            if was_connected && write_guard.node_for_address(&address).is_none() {
+                continue;
+            }
+            match node {
+                Some(node) => {
+                    write_guard.replace_or_add_connection_for_address(address, node);
+                }
+                None => {
+                    write_guard.remove_node(&address);
+                }
+            }
+        }
+        drop(write_guard);
         info!("refresh connections completed");
     }
 
@@ -1134,8 +2775,10 @@ where
     ) -> RedisResult<Value> {
         let extract_result = |response| match response {
             Response::Single(value) => value,
+            Response::PerNode(_) => unreachable!(),
             Response::Multiple(_) => unreachable!(),
             Response::ClusterScanResult(_, _) => unreachable!(),
+            Response::ClusterScanAllShardsResult(_, _) => unreachable!(),
         };
 
         let convert_result = |res: Result<RedisResult<Response>, _>| {
@@ -1147,7 +2790,6 @@ where
             convert_result(receiver.await)
         };
 
-        // TODO - once Value::Error will be merged, these will need to be updated to handle this new value.
         match response_policy {
             Some(ResponsePolicy::AllSucceeded) => {
                 future::try_join_all(receivers.into_iter().map(get_receiver))
@@ -1220,14 +2862,12 @@ where
             }
             Some(ResponsePolicy::Special) | None => {
                 // This is our assumption - if there's no coherent way to aggregate the responses, we just map each response to the sender, and pass it to the user.
-                // TODO - once Value::Error is merged, we can use join_all and report separate errors and also pass successes.
-                future::try_join_all(receivers.into_iter().map(|(addr, receiver)| async move {
-                    let result = convert_result(receiver.await)?;
-                    // The unwrap here is possible, because if `addr` is None, an error should have been sent on the receiver.
-                    Ok((Value::BulkString(addr.unwrap().as_bytes().to_vec()), result))
-                }))
-                .await
-                .map(Value::Map)
+                let results =
+                    future::join_all(receivers.into_iter().map(|(addr, receiver)| async move {
+                        (addr, convert_result(receiver.await))
+                    }))
+                    .await;
+                crate::cluster_routing::map_results_by_node(results)
             }
         }
     }
@@ -1238,32 +2878,24 @@ where
         policy: &RefreshPolicy,
     ) -> RedisResult<()> {
         let SlotRefreshState {
-            in_progress,
             last_run,
             rate_limiter,
+            ..
         } = &inner.slot_refresh_state;
         // Ensure only a single slot refresh operation occurs at a time
-        if in_progress
-            .compare_exchange(false, true, Ordering::Relaxed, Ordering::Relaxed)
-            .is_err()
-        {
+        let Some(refresh_guard) = inner.slot_refresh_state.try_start() else {
             return Ok(());
-        }
+        };
         let mut skip_slots_refresh = false;
         if *policy == RefreshPolicy::Throttable {
             // Check if the current slot refresh is triggered before the wait duration has passed
             let last_run_rlock = last_run.read().await;
             if let Some(last_run_time) = *last_run_rlock {
-                let passed_time = SystemTime::now()
-                    .duration_since(last_run_time)
-                    .unwrap_or_else(|err| {
-                        warn!(
-                            "Failed to get the duration since the last slot refresh, received error: {:?}",
-                            err
-                        );
-                        // Setting the passed time to 0 will force the current refresh to continue and reset the stored last_run timestamp with the current one
-                        Duration::from_secs(0)
-                    });
+                let passed_time = inner
+                    .cluster_params
+                    .clock
+                    .now()
+                    .saturating_duration_since(last_run_time);
                 let wait_duration = rate_limiter.wait_duration();
                 if passed_time <= wait_duration {
                     debug!("Skipping slot refresh as the wait duration hasn't yet passed. Passed time = {:?}, 
@@ -1288,7 +2920,7 @@ where
             })
             .await;
         }
-        in_progress.store(false, Ordering::Relaxed);
+        drop(refresh_guard);
 
         Self::refresh_pubsub_subscriptions(inner).await;
 
@@ -1299,11 +2931,49 @@ where
         inner: Arc<InnerCore<C>>,
         policy: &RefreshPolicy,
     ) -> bool {
-        let topology_changed = Self::check_for_topology_diff(inner.clone()).await;
-        if topology_changed {
-            let _ = Self::refresh_slots_and_subscriptions_with_retries(inner.clone(), policy).await;
+        Self::check_topology_and_refresh(inner, policy).await.0
+    }
+
+    // Returns whether the topology changed, and whether the check succeeded, which it doesn't if none of the queried
+    // nodes returned their topology, or if the slots of a changed topology couldn't be refreshed.
+    async fn check_topology_and_refresh(
+        inner: Arc<InnerCore<C>>,
+        policy: &RefreshPolicy,
+    ) -> (bool, RedisResult<()>) {
+        match Self::check_for_topology_diff(inner.clone()).await {
+            Ok(true) => (
+                true,
+                Self::refresh_slots_and_subscriptions_with_retries(inner, policy).await,
+            ),
+            Ok(false) => (false, Ok(())),
+            Err(err) => (false, Err(err)),
+        }
+    }
+
+    fn record_topology_check(inner: &InnerCore<C>, result: RedisResult<()>) {
+        let mut status = inner.topology_check_status.lock().unwrap();
+        let err = match result {
+            Ok(()) => {
+                status.record_success();
+                return;
+            }
+            Err(err) => err,
+        };
+        warn!("Periodic topology check failed: `{err}`");
+        let failing_for = status.record_failure(
+            err.clone(),
+            inner.cluster_params.clock.now(),
+            inner.cluster_params.topology_check_failure_threshold,
+        );
+        let consecutive_failures = status.stats().consecutive_failures;
+        drop(status);
+        if let Some(failing_for) = failing_for {
+            inner.send_topology_events(vec![TopologyEvent::TopologyChecksFailing {
+                failing_for,
+                consecutive_failures,
+                last_error: err.to_string(),
+            }]);
         }
-        topology_changed
     }
 
     async fn periodic_topology_check(
@@ -1311,14 +2981,20 @@ where
         interval_duration: Duration,
         shutdown_flag: Arc<AtomicBool>,
     ) {
+        let mut interval = TopologyCheckInterval::new(
+            interval_duration,
+            inner.cluster_params.adaptive_topology_checks,
+        );
         loop {
             if shutdown_flag.load(Ordering::Relaxed) {
                 return;
             }
-            let _ = boxed_sleep(interval_duration).await;
-            let topology_changed =
-                Self::check_topology_and_refresh_if_diff(inner.clone(), &RefreshPolicy::Throttable)
-                    .await;
+            let interval_duration = interval.current();
+            let _ = inner.cluster_params.clock.sleep(interval_duration).await;
+            interval.update(inner.topology_errors.swap(0, Ordering::Relaxed));
+            let (topology_changed, result) =
+                Self::check_topology_and_refresh(inner.clone(), &RefreshPolicy::Throttable).await;
+            Self::record_topology_check(&inner, result);
             if !topology_changed {
                 // This serves as a safety measure for validating pubsub subsctiptions state in case it has drifted
                 // while topology stayed the same.
@@ -1326,7 +3002,135 @@ where
                 // might leave a node unconnected indefinitely in case topology is stable and no request are attempted to this node.
                 Self::refresh_pubsub_subscriptions(inner.clone()).await;
             }
+            Self::update_node_latencies(inner.clone(), interval_duration).await;
+        }
+    }
+
+    /// Pings all the nodes, preferably over their management connections, and adds the round-trip times to the
+    /// nodes' average latencies, for the requests that are routed with [`ReadPreference::Nearest`] and for the
+    /// lowest latency read from replica strategy. A node that doesn't respond within the timeout has an unknown
+    /// latency until its next successful ping, and is considered paused if its connection didn't fail.
+    async fn update_node_latencies(inner: Arc<InnerCore<C>>, timeout: Duration) {
+        let nodes: Vec<_> = inner
+            .conn_lock
+            .read()
+            .await
+            .all_nodes()
+            .map(|(address, node)| {
+                (
+                    address.clone(),
+                    node.get_connection(&ConnectionType::PreferManagement),
+                    node.latency.clone(),
+                    node.paused.clone(),
+                )
+            })
+            .collect();
+        let probe = &inner.cluster_params.health_check_probe;
+        let unpause = inner.cluster_params.unpause_paused_nodes;
+        future::join_all(
+            nodes
+                .into_iter()
+                .map(|(address, conn, latency, paused)| async move {
+                    let mut conn = conn.await;
+                    let start = Instant::now();
+                    let result = check_connection(&mut conn, timeout, probe).await;
+                    latency.record(result.as_ref().ok().map(|()| start.elapsed()));
+                    let is_paused = matches!(&result, Err(err) if err.is_timeout());
+                    match (paused.set(is_paused), is_paused) {
+                        (false, true) => {
+                            warn!("Node {address} didn't respond within {timeout:?}, considering it paused");
+                            if unpause {
+                                Self::unpause_node(&mut conn, &address).await;
+                            }
+                        }
+                        (true, false) => info!("Node {address} resumed"),
+                        _ => {}
+                    }
+                }),
+        )
+        .await;
+    }
+
+    async fn unpause_node(conn: &mut C, address: &str) {
+        match conn.req_packed_command(cmd("CLIENT").arg("UNPAUSE")).await {
+            Ok(_) => info!("Sent CLIENT UNPAUSE to node {address}"),
+            Err(err) => warn!("Failed to send CLIENT UNPAUSE to node {address}: `{err}`"),
+        }
+    }
+
+    async fn periodic_pubsub_health_check(
+        inner: Arc<InnerCore<C>>,
+        interval_duration: Duration,
+        shutdown_flag: Arc<AtomicBool>,
+    ) {
+        loop {
+            if shutdown_flag.load(Ordering::Relaxed) {
+                return;
+            }
+            let _ = inner.cluster_params.clock.sleep(interval_duration).await;
+            Self::check_pubsub_connections(inner.clone(), interval_duration).await;
+        }
+    }
+
+    /// Pings the connections of the nodes that hold pubsub subscriptions, and reconnects the nodes whose connections
+    /// don't respond within the given timeout, which resubscribes them.
+    async fn check_pubsub_connections(inner: Arc<InnerCore<C>>, timeout: Duration) {
+        if inner.cluster_params.protocol != crate::types::ProtocolVersion::RESP3 {
+            return;
+        }
+
+        let addresses: Vec<ArcStr> = inner
+            .subscriptions_by_address
+            .read()
+            .await
+            .keys()
+            .cloned()
+            .collect();
+        if addresses.is_empty() {
+            return;
+        }
+        let connections: Vec<_> = {
+            let conns_read_guard = inner.conn_lock.read().await;
+            addresses
+                .into_iter()
+                .filter_map(|address| conns_read_guard.connection_for_address(&address))
+                .collect()
+        };
+
+        let freshness = inner.cluster_params.health_check_freshness;
+        let probe = &inner.cluster_params.health_check_probe;
+        let clock = &inner.cluster_params.clock;
+        let dead_addresses: Vec<ArcStr> =
+            future::join_all(connections.into_iter().map(|(address, conn)| async move {
+                let mut conn = conn.await;
+                match check_connection_health(&mut conn, timeout, freshness, probe, clock).await {
+                    Ok(()) => None,
+                    Err(err) => {
+                        warn!("PubSub health check failed for node {address}. Error: `{err}`");
+                        Some(address)
+                    }
+                }
+            }))
+            .await
+            .into_iter()
+            .flatten()
+            .collect();
+        if dead_addresses.is_empty() {
+            return;
+        }
+
+        if let Some(callback) = &inner.cluster_params.pubsub_dead_connection_callback {
+            for address in dead_addresses.iter() {
+                callback(address);
+            }
         }
+        let mut conns_write_guard = inner.conn_lock.write().await;
+        // have to remove or otherwise the refresh_connection wont trigger node recreation
+        for address in dead_addresses.iter() {
+            conns_write_guard.remove_node(address);
+        }
+        drop(conns_write_guard);
+        Self::reconnect_nodes(inner, dead_addresses, RefreshConnectionType::AllConnections).await;
     }
 
     async fn refresh_pubsub_subscriptions(inner: Arc<InnerCore<C>>) {
@@ -1424,10 +3228,9 @@ where
 
     /// Queries log2n nodes (where n represents the number of cluster nodes) to determine whether their
     /// topology view differs from the one currently stored in the connection manager.
-    /// Returns true if change was detected, otherwise false.
-    async fn check_for_topology_diff(inner: Arc<InnerCore<C>>) -> bool {
-        let read_guard = inner.conn_lock.read().await;
-        let num_of_nodes: usize = read_guard.len();
+    /// Returns true if change was detected, otherwise false, or the error if no node returned its topology.
+    async fn check_for_topology_diff(inner: Arc<InnerCore<C>>) -> RedisResult<bool> {
+        let num_of_nodes: usize = inner.conn_lock.read().await.len();
         // TODO: Starting from Rust V1.67, integers has logarithms support.
         // When we no longer need to support Rust versions < 1.67, remove fast_math and transition to the ilog2 function.
         let num_of_nodes_to_query =
@@ -1435,26 +3238,164 @@ where
         let (res, failed_connections) = calculate_topology_from_random_nodes(
             &inner,
             num_of_nodes_to_query,
-            &read_guard,
             DEFAULT_NUMBER_OF_REFRESH_SLOTS_RETRIES,
         )
         .await;
 
         if let Ok((_, found_topology_hash)) = res {
-            if read_guard.get_current_topology_hash() != found_topology_hash {
-                return true;
+            if inner.conn_lock.read().await.get_current_topology_hash() != found_topology_hash {
+                return Ok(true);
             }
         }
-        drop(read_guard);
 
-        Self::refresh_connections(
+        Self::reconnect_nodes(
             inner,
             failed_connections,
             RefreshConnectionType::OnlyManagementConnection,
         )
         .await;
 
-        false
+        res.map(|_| false)
+    }
+
+    /// Re-queries only the primaries and replicas of the shards that the given addresses belong to, and updates just
+    /// the slot ranges and connections of those shards. A full slots refresh is used only if the topology changed
+    /// beyond these shards.
+    async fn refresh_shards(inner: Arc<InnerCore<C>>, addresses: Vec<ArcStr>) {
+        let read_guard = inner.conn_lock.read().await;
+        let shard_addresses: HashSet<String> = addresses
+            .iter()
+            .flat_map(|address| read_guard.slot_map.shard_addresses(address))
+            .map(String::from)
+            .collect();
+        let requested_nodes: Vec<_> = shard_addresses
+            .iter()
+            .filter_map(|address| {
+                let node = read_guard.node_for_address(address)?;
+                Some((
+                    ArcStr::from(address.as_str()),
+                    node.get_connection(&ConnectionType::PreferManagement),
+                ))
+            })
+            .collect();
+        let current_topology_hash = read_guard.get_current_topology_hash();
+        drop(read_guard);
+        if requested_nodes.is_empty() {
+            return;
+        }
+
+        let num_of_nodes_to_query = requested_nodes.len();
+        let (mut new_slots, topology_hash) = match calculate_topology_from_nodes(
+            &inner,
+            requested_nodes.into_iter(),
+            num_of_nodes_to_query,
+            DEFAULT_NUMBER_OF_REFRESH_SLOTS_RETRIES,
+        )
+        .await
+        .0
+        {
+            Ok(result) => result,
+            Err(err) => {
+                warn!("Failed to refresh the shards of {addresses:?}: `{err}`");
+                return;
+            }
+        };
+        if topology_hash == current_topology_hash {
+            return;
+        }
+        let role_conflicts = inner
+            .cluster_params
+            .node_overrides
+            .apply_roles(&mut new_slots);
+
+        // Connect to the nodes that joined the shards before replacing them.
+        let refresh_guard = inner.refresh_lock.lock().await;
+        let read_guard = inner.conn_lock.read().await;
+        let joined_addresses: Vec<ArcStr> = new_slots
+            .slots
+            .values()
+            .filter(|slot_value| {
+                slot_value
+                    .addrs
+                    .into_iter()
+                    .any(|addr| shard_addresses.contains(addr))
+            })
+            .flat_map(|slot_value| slot_value.addrs.into_iter())
+            .filter(|addr| read_guard.node_for_address(addr).is_none())
+            .map(|addr| ArcStr::from(addr.as_str()))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        drop(read_guard);
+        let mut joined_nodes = Vec::with_capacity(joined_addresses.len());
+        for address in joined_addresses {
+            if let Err(err) = Self::check_reconnect_backoff(&inner, &address) {
+                debug!(
+                    "Skipping the connection to node {address}, which is backing off after `{err}`"
+                );
+                continue;
+            }
+            let mut cluster_params = inner.cluster_params.clone();
+            let subs_guard = inner.subscriptions_by_address.read().await;
+            cluster_params.pubsub_subscriptions = subs_guard.get(&address).cloned();
+            drop(subs_guard);
+            match get_or_create_conn(
+                &address,
+                None,
+                &cluster_params,
+                RefreshConnectionType::AllConnections,
+                inner.push_sender.clone(),
+            )
+            .await
+            {
+                Ok(node) => {
+                    Self::observe_reconnect(&inner, &address, Ok(()));
+                    joined_nodes.push((address, node));
+                }
+                Err(err) => {
+                    Self::observe_reconnect(&inner, &address, Err(&err));
+                    warn!("Failed to connect to {address}: `{err:?}`");
+                }
+            }
+        }
+
+        let mut write_guard = inner.conn_lock.write().await;
+        let old_slots = write_guard.slot_map.clone();
+        if !write_guard.replace_shard(&shard_addresses, new_slots, topology_hash) {
+            drop(write_guard);
+            drop(refresh_guard);
+            info!("The topology changed beyond the shards of {addresses:?}, refreshing all slots");
+            if let Err(err) = Self::refresh_slots_and_subscriptions_with_retries(
+                inner,
+                &RefreshPolicy::Throttable,
+            )
+            .await
+            {
+                warn!("Failed to refresh slots: `{err}`");
+            }
+            return;
+        }
+        for (address, node) in joined_nodes {
+            write_guard.replace_or_add_connection_for_address(address, node);
+        }
+        let plan = TopologyChangePlan::new(
+            &old_slots,
+            &write_guard.slot_map,
+            shard_addresses.iter().map(String::as_str),
+        );
+        for address in &plan.connections_to_close {
+            write_guard.remove_node(&ArcStr::from(address.as_str()));
+        }
+        info!(
+            "Refreshed the shards of {addresses:?}:\n{}",
+            write_guard.slot_map
+        );
+        let events = plan.events(|address| inner.node_metadata(&write_guard, address));
+        drop(write_guard);
+        drop(refresh_guard);
+        inner.send_topology_events(events.into_iter().chain(role_conflicts).collect());
+
+        Self::refresh_pubsub_subscriptions(inner).await;
     }
 
     async fn refresh_slots(
@@ -1462,7 +3403,7 @@ where
         curr_retry: usize,
     ) -> Result<(), BackoffError<RedisError>> {
         // Update the slot refresh last run timestamp
-        let now = SystemTime::now();
+        let now = inner.cluster_params.clock.now();
         let mut last_run_wlock = inner.slot_refresh_state.last_run.write().await;
         *last_run_wlock = Some(now);
         drop(last_run_wlock);
@@ -1487,30 +3428,39 @@ where
 
     // Query a node to discover slot-> master mappings
     async fn refresh_slots_inner(inner: Arc<InnerCore<C>>, curr_retry: usize) -> RedisResult<()> {
-        let read_guard = inner.conn_lock.read().await;
-        let num_of_nodes = read_guard.len();
-        const MAX_REQUESTED_NODES: usize = 50;
+        // The topology is queried and the nodes are connected without holding `conn_lock`, which is only written to
+        // swap the new slot map in, so that the requests aren't blocked while the nodes respond.
+        let _refresh_guard = inner.refresh_lock.lock().await;
+        let num_of_nodes = inner.conn_lock.read().await.len();
         let num_of_nodes_to_query = std::cmp::min(num_of_nodes, MAX_REQUESTED_NODES);
-        let (new_slots, topology_hash) = calculate_topology_from_random_nodes(
-            &inner,
-            num_of_nodes_to_query,
-            &read_guard,
-            curr_retry,
-        )
-        .await
-        .0?;
-        let connections = &*read_guard;
+        let (mut new_slots, topology_hash) =
+            calculate_topology_from_random_nodes(&inner, num_of_nodes_to_query, curr_retry)
+                .await
+                .0?;
+        let role_conflicts = inner
+            .cluster_params
+            .node_overrides
+            .apply_roles(&mut new_slots);
         // Create a new connection vector of the found nodes
         let mut nodes = new_slots.values().flatten().collect::<Vec<_>>();
         nodes.sort_unstable();
         nodes.dedup();
         let nodes_len = nodes.len();
-        let addresses_and_connections_iter = stream::iter(nodes)
+        let existing_nodes: Vec<_> = {
+            let read_guard = inner.conn_lock.read().await;
+            nodes
+                .into_iter()
+                .map(|addr| (addr, read_guard.node_for_address(addr.as_str())))
+                .collect()
+        };
+        let cluster_params = &inner.cluster_params;
+        let conn_lock = &inner.conn_lock;
+        let addresses_and_connections_iter = stream::iter(existing_nodes)
             .fold(
                 Vec::with_capacity(nodes_len),
-                |mut addrs_and_conns, addr| async move {
-                    if let Some(node) = connections.node_for_address(addr.as_str()) {
-                        addrs_and_conns.push((addr, Some(node)));
+                |mut addrs_and_conns, (addr, node)| async move {
+                    if node.is_some() {
+                        addrs_and_conns.push((addr, node));
                         return addrs_and_conns;
                     }
                     // If it's a DNS endpoint, it could have been stored in the existing connections vector using the resolved IP address instead of the DNS endpoint's name.
@@ -1522,14 +3472,15 @@ where
                             return addrs_and_conns;
                         }
                     };
-                    let conn = get_socket_addrs(host, port)
-                        .await
-                        .ok()
-                        .map(|mut socket_addresses| {
+                    let conn = match resolve_socket_addrs(host, port, cluster_params).await {
+                        Ok(socket_addresses) => {
+                            let read_guard = conn_lock.read().await;
                             socket_addresses
-                                .find_map(|addr| connections.node_for_address(&addr.to_string()))
-                        })
-                        .unwrap_or(None);
+                                .into_iter()
+                                .find_map(|addr| read_guard.node_for_address(&addr.to_string()))
+                        }
+                        Err(_) => None,
+                    };
                     addrs_and_conns.push((addr, conn));
                     addrs_and_conns
                 },
@@ -1539,6 +3490,17 @@ where
             .fold(
                 ConnectionsMap(HashMap::with_capacity(nodes_len)),
                 |mut connections, (addr, node)| async {
+                    // A node that is backing off keeps its current connection, if it has one, without being checked.
+                    if let Err(err) = Self::check_reconnect_backoff(&inner, addr) {
+                        debug!(
+                            "Skipping the reconnect to node {}, which is backing off after `{err}`",
+                            addr.as_str()
+                        );
+                        if let Some(node) = node {
+                            connections.0.insert(addr.into(), node);
+                        }
+                        return connections;
+                    }
                     let mut cluster_params = inner.cluster_params.clone();
                     let subs_guard = inner.subscriptions_by_address.read().await;
                     cluster_params.pubsub_subscriptions =
@@ -1552,6 +3514,7 @@ where
                         inner.push_sender.clone(),
                     )
                     .await;
+                    Self::observe_reconnect(&inner, addr, node.as_ref().map(|_| ()));
                     if let Ok(node) = node {
                         connections.0.insert(addr.into(), node);
                     }
@@ -1560,24 +3523,148 @@ where
             )
             .await;
 
-        drop(read_guard);
         info!("refresh_slots found nodes:\n{new_connections}");
         // Replace the current slot map and connection vector with the new ones
+        inner
+            .slot_migrations
+            .lock()
+            .unwrap()
+            .retain_unfinished(&new_slots);
         let mut write_guard = inner.conn_lock.write().await;
+        let plan = TopologyChangePlan::new(
+            write_guard.refreshed_slot_map(),
+            &new_slots,
+            write_guard.all_nodes().map(|(address, _)| address.as_str()),
+        );
+        debug!(
+            "Closing the connections to {:?}, connected to {:?}",
+            plan.connections_to_close, plan.connections_to_create
+        );
         *write_guard = ConnectionsContainer::new(
             new_slots,
             new_connections,
             inner.cluster_params.read_from_replicas,
             topology_hash,
         );
+        let events = plan.events(|address| inner.node_metadata(&write_guard, address));
+        drop(write_guard);
+        inner.send_topology_events(events.into_iter().chain(role_conflicts).collect());
+        // The ids are queried in the background, so that the new slot map is used without waiting for them.
+        if inner.node_identities.is_some() {
+            let refresh_node_identities = Self::refresh_node_identities(inner.clone());
+            #[cfg(feature = "tokio-comp")]
+            tokio::spawn(refresh_node_identities);
+            #[cfg(all(not(feature = "tokio-comp"), feature = "async-std-comp"))]
+            AsyncStd::spawn(refresh_node_identities);
+        }
         Ok(())
     }
 
+    /// Queries the ids of the nodes whose ids are unknown or stale, and replaces the connections to the addresses
+    /// whose nodes were replaced by different nodes.
+    async fn refresh_node_identities(inner: Arc<InnerCore<C>>) {
+        let Some(node_identities) = &inner.node_identities else {
+            return;
+        };
+        let (current_addresses, connections): (HashSet<ArcStr>, Vec<_>) = {
+            let read_guard = inner.conn_lock.read().await;
+            let current_addresses: HashSet<ArcStr> = read_guard
+                .all_node_connections()
+                .map(|(address, _)| address)
+                .collect();
+            let connections = node_identities
+                .read()
+                .await
+                .addresses_to_query(current_addresses.iter())
+                .into_iter()
+                .filter_map(|address| {
+                    let connection = read_guard
+                        .node_for_address(&address)?
+                        .get_connection(&ConnectionType::PreferManagement);
+                    Some((address, connection))
+                })
+                .collect();
+            (current_addresses, connections)
+        };
+        let observed = future::join_all(connections.into_iter().map(
+            |(address, connection)| async move {
+                let mut connection = connection.await;
+                let id = cmd("CLUSTER")
+                    .arg("MYID")
+                    .query_async::<_, String>(&mut connection)
+                    .await;
+                (address, id)
+            },
+        ))
+        .await
+        .into_iter()
+        .filter_map(|(address, id)| match id {
+            Ok(id) => Some((address, id)),
+            Err(err) => {
+                debug!("Failed to get the node id of {address}. Error: `{err}`");
+                None
+            }
+        })
+        .collect();
+
+        let changes = node_identities
+            .write()
+            .await
+            .update(&current_addresses, observed);
+        let mut replaced = Vec::new();
+        for change in changes {
+            match change {
+                NodeChange::Added { address, id } => {
+                    info!("Node {id} joined the topology at {address}")
+                }
+                NodeChange::Moved { id, from, to } => info!("Node {id} moved from {from} to {to}"),
+                NodeChange::Removed { address, id } => {
+                    info!("Node {id} at {address} left the topology")
+                }
+                NodeChange::Replaced {
+                    address,
+                    previous_id,
+                    id,
+                } => {
+                    info!("Node {previous_id} at {address} was replaced by node {id}");
+                    replaced.push((address, previous_id, id));
+                }
+            }
+        }
+        if replaced.is_empty() {
+            return;
+        }
+
+        // The connections to a replaced node are dropped, so that new ones are set up for the node at the address.
+        let mut write_guard = inner.conn_lock.write().await;
+        for (address, _, _) in &replaced {
+            write_guard.remove_node(address);
+        }
+        drop(write_guard);
+        let addresses = replaced
+            .iter()
+            .map(|(address, _, _)| address.clone())
+            .collect();
+        inner.send_topology_events(
+            replaced
+                .into_iter()
+                .map(|(address, previous_id, id)| TopologyEvent::NodeReplaced {
+                    address: address.to_string(),
+                    previous_id,
+                    id,
+                })
+                .collect(),
+        );
+        Self::refresh_connections(inner, addresses, RefreshConnectionType::AllConnections).await;
+    }
+
     async fn execute_on_multiple_nodes<'a>(
         cmd: &'a Arc<Cmd>,
         routing: &'a MultipleNodeRoutingInfo,
         core: Core<C>,
-        response_policy: Option<ResponsePolicy>,
+        response_policy: MultiNodeResponsePolicy,
+        tenant: Option<ArcStr>,
+        size: Option<Arc<RequestSizeCounter>>,
     ) -> OperationResult {
         trace!("execute_on_multiple_nodes");
         let connections_container = core.conn_lock.read().await;
@@ -1600,6 +3687,8 @@ where
             iterator: impl Iterator<
                 Item = Option<(Arc<Cmd>, ConnectionAndAddress<ConnectionFuture<C>>)>,
             >,
+            tenant: Option<ArcStr>,
+            size: Option<Arc<RequestSizeCounter>>,
         ) -> (
             Vec<(Option<ArcStr>, Receiver<Result<Response, RedisError>>)>,
             Vec<Option<PendingRequest<C>>>,
@@ -1624,6 +3713,10 @@ where
                                         }
                                         .into(),
                                     },
+                                    tenant: tenant.clone(),
+                                    // The sub-requests are canceled with the request that they're part of.
+                                    deadline: None,
+                                    size: size.clone(),
                                 },
                             }),
                         )
@@ -1644,14 +3737,18 @@ where
                 connections_container
                     .all_node_connections()
                     .map(|tuple| Some((cmd.clone(), tuple))),
+                tenant,
+                size,
             ),
             MultipleNodeRoutingInfo::AllMasters => into_channels(
                 connections_container
                     .all_primary_connections()
                     .map(|tuple| Some((cmd.clone(), tuple))),
+                tenant,
+                size,
             ),
-            MultipleNodeRoutingInfo::MultiSlot(slots) => {
-                into_channels(slots.iter().map(|(route, indices)| {
+            MultipleNodeRoutingInfo::MultiSlot(slots) => into_channels(
+                slots.iter().map(|(route, indices)| {
                     connections_container
                         .connection_for_route(route)
                         .map(|tuple| {
@@ -1661,26 +3758,121 @@ where
                             );
                             (Arc::new(new_cmd), tuple)
                         })
+                }),
+                tenant,
+                size,
+            ),
+        };
+
+        drop(connections_container);
+        for request in requests.into_iter().flatten() {
+            core.pending_requests.push(request);
+        }
+
+        match response_policy {
+            MultiNodeResponsePolicy::Aggregate(response_policy) => {
+                Self::aggregate_results(receivers, routing, response_policy)
+                    .await
+                    .map(Response::Single)
+                    .map_err(|err| (OperationTarget::FanOut, err))
+            }
+            MultiNodeResponsePolicy::Reduce(reducer) => {
+                future::try_join_all(receivers.into_iter().map(|(_, receiver)| async move {
+                    match receiver.await {
+                        Ok(Ok(Response::Single(value))) => Ok(value),
+                        Ok(Ok(_)) => unreachable!(),
+                        Ok(Err(err)) => Err(err),
+                        Err(_) => Err(RedisError::from((
+                            ErrorKind::ResponseError,
+                            "request wasn't handled due to internal failure",
+                        ))),
+                    }
                 }))
+                .await
+                .and_then(|results| reducer(results))
+                .map(Response::Single)
+                .map_err(|err| (OperationTarget::FanOut, err))
             }
-        };
+            MultiNodeResponsePolicy::PerNode => {
+                // Without multi-slot routing, every request has a connection, and so an address.
+                let responses =
+                    future::join_all(receivers.into_iter().map(|(address, receiver)| async move {
+                        let response = receiver
+                            .await
+                            .unwrap_or_else(|_| {
+                                Err(RedisError::from((
+                                    ErrorKind::ResponseError,
+                                    "request wasn't handled due to internal failure",
+                                )))
+                            })
+                            .map(|response| match response {
+                                Response::Single(value) => value,
+                                Response::PerNode(_) => unreachable!(),
+                                Response::Multiple(_) => unreachable!(),
+                                Response::ClusterScanResult(_, _) => unreachable!(),
+                                Response::ClusterScanAllShardsResult(_, _) => unreachable!(),
+                            });
+                        (address.unwrap_or_default().to_string(), response)
+                    }))
+                    .await;
+                Ok(Response::PerNode(responses.into_iter().collect()))
+            }
+        }
+    }
 
-        drop(connections_container);
-        core.pending_requests
-            .lock()
-            .unwrap()
-            .extend(requests.into_iter().flatten());
+    pub(crate) async fn try_cmd_request(
+        cmd: Arc<Cmd>,
+        routing: InternalRoutingInfo<C>,
+        core: Core<C>,
+        tenant: Option<ArcStr>,
+        size: Option<Arc<RequestSizeCounter>>,
+    ) -> OperationResult {
+        #[cfg(feature = "traffic-recorder")]
+        if let Some(recorder) = core.cluster_params.traffic_recorder.clone() {
+            return Self::try_recorded_cmd_request(recorder, cmd, routing, core, tenant, size)
+                .await;
+        }
+        Self::send_cmd_request(cmd, routing, core, tenant, size, &mut None).await
+    }
 
-        Self::aggregate_results(receivers, routing, response_policy)
-            .await
-            .map(Response::Single)
-            .map_err(|err| (OperationTarget::FanOut, err))
+    /// Sends the command like [`Self::try_cmd_request`], and records it with `recorder`.
+    #[cfg(feature = "traffic-recorder")]
+    async fn try_recorded_cmd_request(
+        recorder: traffic_recorder::TrafficRecorder,
+        cmd: Arc<Cmd>,
+        routing: InternalRoutingInfo<C>,
+        core: Core<C>,
+        tenant: Option<ArcStr>,
+        size: Option<Arc<RequestSizeCounter>>,
+    ) -> OperationResult {
+        let started_at = std::time::SystemTime::now();
+        let start = Instant::now();
+        let args = traffic_recorder::TrafficRecord::redact_args(&cmd);
+        let recorded_routing = routing.recorded();
+        let mut node = None;
+        let result = Self::send_cmd_request(cmd, routing, core, tenant, size, &mut node).await;
+        recorder.record(traffic_recorder::TrafficRecord {
+            started_at,
+            elapsed: start.elapsed(),
+            args,
+            routing: recorded_routing,
+            node: node.map(|node| node.to_string()),
+            outcome: match &result {
+                Ok(_) => Ok(()),
+                Err((_, err)) => Err(err.kind()),
+            },
+        });
+        result
     }
 
-    pub(crate) async fn try_cmd_request(
+    // Sets `node` to the address of the node that the command is sent to, if it's sent to a single node.
+    async fn send_cmd_request(
         cmd: Arc<Cmd>,
         routing: InternalRoutingInfo<C>,
         core: Core<C>,
+        tenant: Option<ArcStr>,
+        size: Option<Arc<RequestSizeCounter>>,
+        node: &mut Option<ArcStr>,
     ) -> OperationResult {
         let routing = match routing {
             // commands that are sent to multiple nodes are handled here.
@@ -1690,6 +3882,8 @@ where
                     &multi_node_routing,
                     core,
                     response_policy,
+                    tenant,
+                    size,
                 )
                 .await;
             }
@@ -1697,49 +3891,276 @@ where
             InternalRoutingInfo::SingleNode(routing) => routing,
         };
         trace!("route request to single node");
+        let asking = routing.is_ask_redirect();
 
         // if we reached this point, we're sending the command only to single node, and we need to find the
         // right connection to the node.
-        let (address, mut conn) = Self::get_connection(routing, core)
+        let (address, mut conn) = Self::get_connection(routing, core.clone())
             .await
             .map_err(|err| (OperationTarget::NotFound, err))?;
-        conn.req_packed_command(&cmd)
-            .await
-            .map(Response::Single)
-            .map_err(|err| (address.into(), err))
+        *node = Some(address.clone());
+        Self::check_circuit(&core, &address)?;
+        if let Some(timeout) = cluster_routing::blocking_timeout(cmd.as_ref()) {
+            // Blocking commands are sent on a connection of their own, so that they don't hold up the requests that
+            // are multiplexed on the node's connections.
+            conn = Self::blocking_connection(&core, &address, timeout)
+                .await
+                .map_err(|err| (address.clone().into(), err))?;
+        } else if core.cluster_params.connection_pool_size > 1 && cmd.is_subscription() {
+            // The subscriptions of a node are kept on its main user connection, so that they're unsubscribed from,
+            // and restored after a reconnect, on the connection that they were subscribed on.
+            let node = core.conn_lock.read().await.node_for_address(&address);
+            if let Some(node) = node {
+                conn = node.user_connection.await;
+            }
+        }
+        let result = if asking {
+            // ASKING only applies to the next command of the connection, so it's sent in the same packet as the
+            // command, which also saves a round trip.
+            let mut pipeline = crate::Pipeline::with_capacity(2);
+            pipeline
+                .add_command(crate::cmd::cmd("ASKING"))
+                .add_command(cmd.as_ref().clone());
+            conn.req_packed_commands(&pipeline, 1, 1)
+                .await
+                .and_then(|mut values| {
+                    values.pop().ok_or_else(|| {
+                        RedisError::from((
+                            ErrorKind::ResponseError,
+                            "No response to the command after ASKING",
+                        ))
+                    })
+                })
+        } else {
+            conn.req_packed_command(&cmd).await
+        };
+        if let Some(size) = &size {
+            size.record(&cmd, &result, core.cluster_params.protocol);
+        }
+        let value = result.map_err(|err| (address.clone().into(), err))?;
+        Self::track_shard_subscriptions(&core, &address, &cmd).await;
+        Ok(Response::Single(value))
+    }
+
+    /// Opens a short-lived connection to `address` for a blocking command that blocks for up to `timeout`. The
+    /// response timeout of the connection is extended by `timeout`, so that the command isn't failed while it waits.
+    async fn blocking_connection(
+        core: &Core<C>,
+        address: &str,
+        timeout: Duration,
+    ) -> RedisResult<C> {
+        let mut params = core.cluster_params.clone();
+        params.response_timeout = params.response_timeout.saturating_add(timeout);
+        params.pubsub_subscriptions = None;
+        create_ephemeral_connection::<C>(address, params).await
+    }
+
+    /// Tracks the shard channels that were subscribed or unsubscribed by a `SSUBSCRIBE` or `SUNSUBSCRIBE` that was
+    /// sent to the given address, so that the subscriptions follow the slots' owners after slot refreshes.
+    async fn track_shard_subscriptions(core: &Core<C>, address: &ArcStr, cmd: &Cmd) {
+        if core.cluster_params.protocol != crate::types::ProtocolVersion::RESP3 {
+            return;
+        }
+        let subscribe = match cmd.command().as_deref() {
+            Some(b"SSUBSCRIBE") => true,
+            Some(b"SUNSUBSCRIBE") => false,
+            _ => return,
+        };
+        let channels = (1..).map_while(|idx| cmd.arg_idx(idx));
+        let mut subscriptions_by_address = core.subscriptions_by_address.write().await;
+        if subscribe {
+            subscriptions_by_address
+                .entry(address.clone())
+                .or_default()
+                .entry(PubSubSubscriptionKind::Sharded)
+                .or_default()
+                .extend(channels.map(<[u8]>::to_vec));
+        } else if let Some(sharded) = subscriptions_by_address
+            .get_mut(address)
+            .and_then(|subscriptions| subscriptions.get_mut(&PubSubSubscriptionKind::Sharded))
+        {
+            for channel in channels {
+                sharded.remove(channel);
+            }
+        }
     }
 
     async fn try_pipeline_request(
         pipeline: Arc<crate::Pipeline>,
         offset: usize,
         count: usize,
-        conn: impl Future<Output = RedisResult<(ArcStr, C)>>,
+        route: InternalSingleNodeRouting<C>,
+        core: Core<C>,
     ) -> OperationResult {
         trace!("try_pipeline_request");
-        let (address, mut conn) = conn.await.map_err(|err| (OperationTarget::NotFound, err))?;
-        conn.req_packed_commands(&pipeline, offset, count)
+        let asking = route.is_ask_redirect();
+        let (address, mut conn) = Self::get_connection(route, core.clone())
             .await
+            .map_err(|err| (OperationTarget::NotFound, err))?;
+        Self::check_circuit(&core, &address)?;
+        let result = if asking {
+            // A redirected pipeline isn't split in chunks, so that it's sent in the same packet as ASKING.
+            conn.req_packed_commands(&pipeline.with_asking(), offset + 1, count)
+                .await
+        } else {
+            match core
+                .cluster_params
+                .pipeline_chunking
+                .split(&pipeline, offset, count)
+            {
+                Some(chunks) => crate::pipeline::send_chunks(&mut conn, chunks).await,
+                None => conn.req_packed_commands(&pipeline, offset, count).await,
+            }
+        };
+        result
             .map(Response::Multiple)
             .map_err(|err| (OperationTarget::Node { address }, err))
     }
 
     async fn try_request(info: RequestInfo<C>, core: Core<C>) -> OperationResult {
+        let result = Self::try_request_inner(info, core.clone()).await;
+        if let Err((OperationTarget::Node { address }, err)) = &result {
+            Self::observe_slot_migration(&core, address, err);
+            Self::observe_node_failure(&core, address, err);
+            Self::observe_topology_error(&core, err);
+            Self::apply_moved_redirect(&core, err).await;
+        }
+        result
+    }
+
+    // Points the slot of a `MOVED` redirection to its new primary right away, so that the next requests to the slot
+    // aren't sent to the old primary while the slots are refreshed. The new primary is connected first, since the
+    // requests to slots without a connection are sent to random nodes.
+    async fn apply_moved_redirect(core: &Core<C>, err: &RedisError) {
+        if err.kind() != ErrorKind::Moved {
+            return;
+        }
+        let Some((address, slot)) = err.redirect_node() else {
+            return;
+        };
+        let connected = core
+            .conn_lock
+            .read()
+            .await
+            .connection_for_address(address)
+            .is_some();
+        if !connected {
+            let routing = InternalSingleNodeRouting::Redirect {
+                redirect: Redirect::Moved(address.to_string()),
+                previous_routing: Box::new(InternalSingleNodeRouting::Random),
+            };
+            if let Err(err) = Self::get_connection(routing, core.clone()).await {
+                debug!("Failed to connect to {address} after a MOVED redirection: {err}");
+                return;
+            }
+        }
+        core.conn_lock
+            .write()
+            .await
+            .update_slot_primary(slot, address);
+    }
+
+    // Counts the redirections and connection errors, which hint at topology changes, for the adaptive interval of
+    // the periodic topology checks.
+    fn observe_topology_error(core: &Core<C>, err: &RedisError) {
+        if matches!(err.kind(), ErrorKind::Moved | ErrorKind::Ask)
+            || err.is_io_error()
+            || err.kind() == ErrorKind::ClusterConnectionNotFound
+        {
+            core.topology_errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Uses the failure budget of the node if the error means that the node failed.
+    fn observe_node_failure(core: &Core<C>, address: &ArcStr, err: &RedisError) {
+        let Some(params) = &core.cluster_params.retry_params.circuit_breaker else {
+            return;
+        };
+        if circuit_breaker::is_node_failure(err) {
+            core.circuit_breakers.lock().unwrap().record_failure(
+                params,
+                address,
+                core.cluster_params.clock.now(),
+            );
+        }
+    }
+
+    /// Fails with the error of the last attempt to connect to the node, if its reconnect backoff didn't expire yet.
+    fn check_reconnect_backoff(core: &Core<C>, address: &str) -> RedisResult<()> {
+        if core.cluster_params.retry_params.reconnect_backoff.is_none() {
+            return Ok(());
+        }
+        core.reconnect_backoffs
+            .lock()
+            .unwrap()
+            .check(address, core.cluster_params.clock.now())
+    }
+
+    /// Backs off the reconnects to the node after a failed attempt, and resets its backoff after a successful one.
+    fn observe_reconnect(core: &Core<C>, address: &str, result: Result<(), &RedisError>) {
+        let Some(params) = &core.cluster_params.retry_params.reconnect_backoff else {
+            return;
+        };
+        let mut reconnect_backoffs = core.reconnect_backoffs.lock().unwrap();
+        match result {
+            Ok(()) => reconnect_backoffs.record_success(address),
+            Err(err) => reconnect_backoffs.record_failure(
+                params,
+                &ArcStr::from(address),
+                err.clone(),
+                core.cluster_params.clock.now(),
+            ),
+        }
+    }
+
+    /// Fails the request fast if the circuit breaker of the node is open.
+    fn check_circuit(
+        core: &Core<C>,
+        address: &ArcStr,
+    ) -> Result<(), (OperationTarget, RedisError)> {
+        if core.cluster_params.retry_params.circuit_breaker.is_none() {
+            return Ok(());
+        }
+        if core
+            .circuit_breakers
+            .lock()
+            .unwrap()
+            .is_open(address, core.cluster_params.clock.now())
+        {
+            return Err((
+                address.clone().into(),
+                RedisError::from((
+                    ErrorKind::CircuitOpen,
+                    "The circuit breaker of the node is open",
+                    address.to_string(),
+                )),
+            ));
+        }
+        Ok(())
+    }
+
+    fn observe_slot_migration(core: &Core<C>, address: &str, err: &RedisError) {
+        let Some((target, slot)) = err.redirect_node() else {
+            return;
+        };
+        let mut slot_migrations = core.slot_migrations.lock().unwrap();
+        match err.kind() {
+            ErrorKind::Ask => slot_migrations.observe_ask(slot, address, target),
+            _ => slot_migrations.observe_moved(slot),
+        }
+    }
+
+    async fn try_request_inner(info: RequestInfo<C>, core: Core<C>) -> OperationResult {
         match info.cmd {
-            CmdArg::Cmd { cmd, routing } => Self::try_cmd_request(cmd, routing, core).await,
+            CmdArg::Cmd { cmd, routing } => {
+                Self::try_cmd_request(cmd, routing, core, info.tenant, info.size).await
+            }
             CmdArg::Pipeline {
                 pipeline,
                 offset,
                 count,
                 route,
-            } => {
-                Self::try_pipeline_request(
-                    pipeline,
-                    offset,
-                    count,
-                    Self::get_connection(route, core),
-                )
-                .await
-            }
+            } => Self::try_pipeline_request(pipeline, offset, count, route, core).await,
             CmdArg::ClusterScan {
                 cluster_scan_args, ..
             } => {
@@ -1754,6 +4175,14 @@ where
                     Err(err) => Err((OperationTarget::FanOut, err)),
                 }
             }
+            CmdArg::ClusterScanAllShards { cursor, options } => {
+                match cluster_scan_all_shards(core, cursor, options).await {
+                    Ok((cursor, values)) => {
+                        Ok(Response::ClusterScanAllShardsResult(cursor, values))
+                    }
+                    Err(err) => Err((OperationTarget::FanOut, err)),
+                }
+            }
         }
     }
 
@@ -1762,8 +4191,6 @@ where
         core: Core<C>,
     ) -> RedisResult<(ArcStr, C)> {
         let read_guard = core.conn_lock.read().await;
-        let mut asking = false;
-
         let conn_check = match routing {
             InternalSingleNodeRouting::Redirect {
                 redirect: Redirect::Moved(moved_addr),
@@ -1777,13 +4204,10 @@ where
             InternalSingleNodeRouting::Redirect {
                 redirect: Redirect::Ask(ask_addr),
                 ..
-            } => {
-                asking = true;
-                read_guard.connection_for_address(ask_addr.as_str()).map_or(
-                    ConnectionCheck::OnlyAddress(ask_addr),
-                    ConnectionCheck::Found,
-                )
-            }
+            } => read_guard.connection_for_address(ask_addr.as_str()).map_or(
+                ConnectionCheck::OnlyAddress(ask_addr),
+                ConnectionCheck::Found,
+            ),
             // This means that a request routed to a route without a matching connection will be sent to a random node, hopefully to be redirected afterwards.
             InternalSingleNodeRouting::SpecificNode(route) => {
                 read_guard.connection_for_route(&route).map_or_else(
@@ -1796,26 +4220,28 @@ where
             }
             InternalSingleNodeRouting::Random => ConnectionCheck::RandomConnection,
             InternalSingleNodeRouting::Connection { address, conn } => {
-                return Ok((address, conn.await));
+                ConnectionCheck::Found((address, conn))
             }
             InternalSingleNodeRouting::ByAddress(address) => {
-                if let Some((address, conn)) = read_guard.connection_for_address(&address) {
-                    return Ok((address, conn.await));
-                } else {
-                    return Err((
-                        ErrorKind::ClusterConnectionNotFound,
-                        "Requested connection not found",
-                        address,
-                    )
-                        .into());
+                match read_guard.connection_for_address(&address) {
+                    Some(connection) => ConnectionCheck::Found(connection),
+                    None => {
+                        return Err((
+                            ErrorKind::ClusterConnectionNotFound,
+                            "Requested connection not found",
+                            address,
+                        )
+                            .into())
+                    }
                 }
             }
         };
         drop(read_guard);
 
-        let (address, mut conn) = match conn_check {
+        let (address, conn) = match conn_check {
             ConnectionCheck::Found((address, connection)) => (address, connection.await),
             ConnectionCheck::OnlyAddress(addr) => {
+                Self::check_reconnect_backoff(&core, &addr)?;
                 let mut this_conn_params = core.cluster_params.clone();
                 let subs_guard = core.subscriptions_by_address.read().await;
                 this_conn_params.pubsub_subscriptions = subs_guard.get(addr.as_str()).cloned();
@@ -1832,6 +4258,7 @@ where
                 .get_node()
                 {
                     Ok(node) => {
+                        Self::observe_reconnect(&core, &addr, Ok(()));
                         let connection_clone = node.user_connection.clone().await;
                         let mut connections = core.conn_lock.write().await;
                         let address = connections.replace_or_add_connection_for_address(addr, node);
@@ -1839,26 +4266,26 @@ where
                         (address, connection_clone)
                     }
                     Err(err) => {
+                        Self::observe_reconnect(&core, &addr, Err(&err));
                         return Err(err);
                     }
                 }
             }
             ConnectionCheck::RandomConnection => {
-                let read_guard = core.conn_lock.read().await;
-                let (random_address, random_conn_future) = read_guard
+                let random_connection = core
+                    .conn_lock
+                    .read()
+                    .await
                     .random_connections(1, ConnectionType::User)
-                    .next()
-                    .ok_or(RedisError::from((
+                    .next();
+                let (random_address, random_conn_future) =
+                    random_connection.ok_or(RedisError::from((
                         ErrorKind::ClusterConnectionNotFound,
                         "No random connection found",
                     )))?;
                 return Ok((random_address, random_conn_future.await));
             }
         };
-
-        if asking {
-            let _ = conn.req_packed_command(&crate::cmd::cmd("ASKING")).await;
-        }
         Ok((address, conn))
     }
 
@@ -1907,7 +4334,7 @@ where
         } else {
             // If the connection is primary, just sleep and retry
             let sleep_duration = core.cluster_params.retry_params.wait_time_for_retry(retry);
-            boxed_sleep(sleep_duration).await;
+            core.cluster_params.clock.sleep(sleep_duration).await;
         }
 
         Self::try_request(info, core).await
@@ -1915,30 +4342,50 @@ where
 
     fn poll_complete(&mut self, cx: &mut task::Context<'_>) -> Poll<PollFlushAction> {
         let mut poll_flush_action = PollFlushAction::None;
-
-        let mut pending_requests_guard = self.inner.pending_requests.lock().unwrap();
-        if !pending_requests_guard.is_empty() {
-            let mut pending_requests = mem::take(&mut *pending_requests_guard);
-            for request in pending_requests.drain(..) {
-                // Drop the request if none is waiting for a response to free up resources for
-                // requests callers care about (load shedding). It will be ambiguous whether the
-                // request actually goes through regardless.
-                if request.sender.is_closed() {
-                    continue;
-                }
-
-                let future = Self::try_request(request.info.clone(), self.inner.clone()).boxed();
-                self.in_flight_requests.push(Box::pin(Request {
-                    retry_params: self.inner.cluster_params.retry_params.clone(),
-                    request: Some(request),
-                    future: RequestState::Future { future },
-                }));
+        // Set when the budget ran out before all the work that's ready was done, in which case the driver task
+        // wakes itself up to continue after the other tasks of the runtime had a chance to run.
+        let mut budget_exhausted = false;
+
+        // Only the requests that were queued when the poll started are taken, so that requests that are pushed
+        // concurrently can't keep the driver task in this loop. They're started in the order of the scheduler, so
+        // that a tenant with many queued requests can't starve the others.
+        let core = self.inner.clone();
+        let mut scheduler = core.scheduler();
+        for _ in 0..core.pending_requests.len() {
+            let Some(request) = core.pending_requests.pop() else {
+                break;
+            };
+            scheduler.push(request.info.tenant.clone(), request);
+        }
+        if scheduler.len() > POLL_COMPLETE_BUDGET {
+            budget_exhausted = true;
+        }
+        for _ in 0..scheduler.len().min(POLL_COMPLETE_BUDGET) {
+            let Some(request) = scheduler.pop() else {
+                break;
+            };
+            // Drop the request if none is waiting for a response to free up resources for
+            // requests callers care about (load shedding). It will be ambiguous whether the
+            // request actually goes through regardless.
+            if request.sender.is_closed() {
+                continue;
             }
-            *pending_requests_guard = pending_requests;
+
+            let future = Self::try_request(request.info.clone(), self.inner.clone()).boxed();
+            self.in_flight_requests.push(Box::pin(Request::new(
+                &self.inner.cluster_params,
+                request,
+                RequestState::Future { future },
+            )));
         }
-        drop(pending_requests_guard);
+        core.pending_requests.set_scheduled(scheduler.len());
+        drop(scheduler);
 
-        loop {
+        for completed_requests in 0.. {
+            if completed_requests == POLL_COMPLETE_BUDGET {
+                budget_exhausted = true;
+                break;
+            }
             let result = match Pin::new(&mut self.in_flight_requests).poll_next(cx) {
                 Poll::Ready(Some(result)) => result,
                 Poll::Ready(None) | Poll::Pending => break,
@@ -1947,13 +4394,13 @@ where
                 Next::Done => {}
                 Next::Retry { request } => {
                     let future = Self::try_request(request.info.clone(), self.inner.clone());
-                    self.in_flight_requests.push(Box::pin(Request {
-                        retry_params: self.inner.cluster_params.retry_params.clone(),
-                        request: Some(request),
-                        future: RequestState::Future {
+                    self.in_flight_requests.push(Box::pin(Request::new(
+                        &self.inner.cluster_params,
+                        request,
+                        RequestState::Future {
                             future: Box::pin(future),
                         },
-                    }));
+                    )));
                 }
                 Next::RetryBusyLoadingError { request, address } => {
                     // TODO - do we also want to try and reconnect to replica if it is loading?
@@ -1963,13 +4410,13 @@ where
                         address,
                         request.retry,
                     );
-                    self.in_flight_requests.push(Box::pin(Request {
-                        retry_params: self.inner.cluster_params.retry_params.clone(),
-                        request: Some(request),
-                        future: RequestState::Future {
+                    self.in_flight_requests.push(Box::pin(Request::new(
+                        &self.inner.cluster_params,
+                        request,
+                        RequestState::Future {
                             future: Box::pin(future),
                         },
-                    }));
+                    )));
                 }
                 Next::RefreshSlots {
                     request,
@@ -1982,7 +4429,7 @@ where
                             Pin<Box<dyn Future<Output = OperationResult> + Send>>,
                         > = match sleep_duration {
                             Some(sleep_duration) => RequestState::Sleep {
-                                sleep: boxed_sleep(sleep_duration),
+                                sleep: self.inner.cluster_params.clock.sleep(sleep_duration),
                             },
                             None => RequestState::Future {
                                 future: Box::pin(Self::try_request(
@@ -1991,11 +4438,11 @@ where
                                 )),
                             },
                         };
-                        self.in_flight_requests.push(Box::pin(Request {
-                            retry_params: self.inner.cluster_params.retry_params.clone(),
-                            request: Some(request),
+                        self.in_flight_requests.push(Box::pin(Request::new(
+                            &self.inner.cluster_params,
+                            request,
                             future,
-                        }));
+                        )));
                     }
                 }
                 Next::Reconnect {
@@ -2004,21 +4451,27 @@ where
                     poll_flush_action =
                         poll_flush_action.change_state(PollFlushAction::Reconnect(vec![target]));
                     if let Some(request) = request {
-                        self.inner.pending_requests.lock().unwrap().push(request);
+                        self.inner.pending_requests.push(request);
                     }
                 }
                 Next::ReconnectToInitialNodes { request } => {
                     poll_flush_action = poll_flush_action
                         .change_state(PollFlushAction::ReconnectFromInitialConnections);
                     if let Some(request) = request {
-                        self.inner.pending_requests.lock().unwrap().push(request);
+                        self.inner.pending_requests.push(request);
                     }
                 }
             }
         }
 
+        if budget_exhausted {
+            cx.waker().wake_by_ref();
+        }
+
         if matches!(poll_flush_action, PollFlushAction::None) {
-            if self.in_flight_requests.is_empty() {
+            if budget_exhausted {
+                Poll::Pending
+            } else if self.in_flight_requests.is_empty() {
                 Poll::Ready(poll_flush_action)
             } else {
                 Poll::Pending
@@ -2037,7 +4490,12 @@ where
                 (*request)
                     .as_mut()
                     .respond(Err(self.refresh_error.take().unwrap()));
-            } else if let Some(request) = self.inner.pending_requests.lock().unwrap().pop() {
+            } else if let Some(request) = self
+                .inner
+                .scheduler()
+                .pop()
+                .or_else(|| self.inner.pending_requests.pop())
+            {
                 let _ = request.sender.send(Err(self.refresh_error.take().unwrap()));
             }
         }
@@ -2066,14 +4524,78 @@ impl PollFlushAction {
             }
 
             (PollFlushAction::Reconnect(mut addrs), PollFlushAction::Reconnect(new_addrs)) => {
-                addrs.extend(new_addrs);
+                for addr in new_addrs {
+                    if !addrs.contains(&addr) {
+                        addrs.push(addr);
+                    }
+                }
                 Self::Reconnect(addrs)
             }
         }
     }
 }
 
-impl<C> Sink<Message<C>> for Disposable<ClusterConnInner<C>>
+// The nodes that a reconnect claimed in `InnerCore::reconnecting_nodes`, which are released once it completes or
+// is dropped.
+struct ReconnectClaim<'a> {
+    reconnecting_nodes: &'a Mutex<HashMap<ArcStr, RefreshConnectionType>>,
+    addresses: Vec<ArcStr>,
+}
+
+impl<'a> ReconnectClaim<'a> {
+    // Returns the addresses to reconnect, without the nodes whose connections of `conn_type` are already being
+    // reconnected, and the claim of the addresses that no other task is reconnecting.
+    fn new(
+        reconnecting_nodes: &'a Mutex<HashMap<ArcStr, RefreshConnectionType>>,
+        addresses: Vec<ArcStr>,
+        conn_type: RefreshConnectionType,
+    ) -> (Vec<ArcStr>, Self) {
+        let mut claimed = Vec::new();
+        let mut to_reconnect = Vec::new();
+        let mut reconnecting = reconnecting_nodes.lock().unwrap();
+        for address in addresses {
+            if to_reconnect.contains(&address) {
+                continue;
+            }
+            match reconnecting.get(&address) {
+                None => {
+                    reconnecting.insert(address.clone(), conn_type);
+                    claimed.push(address.clone());
+                }
+                Some(&in_progress)
+                    if in_progress == conn_type
+                        || in_progress == RefreshConnectionType::AllConnections =>
+                {
+                    debug!(
+                        "Skipping the reconnect to node {address}, which is already reconnecting"
+                    );
+                    continue;
+                }
+                Some(_) => {}
+            }
+            to_reconnect.push(address);
+        }
+        drop(reconnecting);
+        (
+            to_reconnect,
+            Self {
+                reconnecting_nodes,
+                addresses: claimed,
+            },
+        )
+    }
+}
+
+impl Drop for ReconnectClaim<'_> {
+    fn drop(&mut self) {
+        let mut reconnecting = self.reconnecting_nodes.lock().unwrap();
+        for address in &self.addresses {
+            reconnecting.remove(address);
+        }
+    }
+}
+
+impl<C> Sink<Message<C>> for ClusterConnInner<C>
 where
     C: ConnectionLike + Connect + Clone + Send + Sync + Unpin + 'static,
 {
@@ -2084,19 +4606,40 @@ where
     }
 
     fn start_send(self: Pin<&mut Self>, msg: Message<C>) -> Result<(), Self::Error> {
-        let Message { cmd, sender } = msg;
-
-        let info = RequestInfo { cmd };
+        let Message {
+            cmd,
+            sender,
+            tenant,
+            request_timeout,
+            size,
+        } = msg;
+
+        if let Some(slot_counters) = &self.inner.slot_counters {
+            for slot in cmd.routed_slots() {
+                slot_counters.record(slot);
+            }
+        }
+        let deadline = request_timeout
+            .and_then(|timeout| self.inner.cluster_params.clock.now().checked_add(timeout));
+        let info = RequestInfo {
+            cmd,
+            tenant,
+            deadline,
+            size,
+        };
 
-        self.inner
+        let request = PendingRequest {
+            retry: 0,
+            sender,
+            info,
+        };
+        if let Err(request) = self
+            .inner
             .pending_requests
-            .lock()
-            .unwrap()
-            .push(PendingRequest {
-                retry: 0,
-                sender,
-                info,
-            });
+            .try_push_new(request, self.inner.scheduler().len())
+        {
+            let _ = request.sender.send(Err(pending_requests_full_error()));
+        }
         Ok(())
     }
 
@@ -2132,13 +4675,20 @@ where
                     )));
                 }
                 PollFlushAction::Reconnect(addresses) => {
-                    self.state = ConnectionState::Recover(RecoverFuture::Reconnect(Box::pin(
-                        ClusterConnInner::refresh_connections(
-                            self.inner.clone(),
-                            addresses,
-                            RefreshConnectionType::OnlyUserConnection,
-                        ),
-                    )));
+                    let inner = self.inner.clone();
+                    self.state =
+                        ConnectionState::Recover(RecoverFuture::Reconnect(Box::pin(async move {
+                            let addresses = ClusterConnInner::reconnect_nodes(
+                                inner.clone(),
+                                addresses,
+                                RefreshConnectionType::OnlyUserConnection,
+                            )
+                            .await;
+                            // The failure might be due to a failover, which only changes the node's shard.
+                            if !addresses.is_empty() {
+                                ClusterConnInner::refresh_shards(inner, addresses).await;
+                            }
+                        })));
                 }
                 PollFlushAction::ReconnectFromInitialConnections => {
                     self.state = ConnectionState::Recover(RecoverFuture::Reconnect(Box::pin(
@@ -2169,10 +4719,40 @@ where
     }
 }
 
-async fn calculate_topology_from_random_nodes<'a, C>(
+async fn calculate_topology_from_random_nodes<C>(
+    inner: &Core<C>,
+    num_of_nodes_to_query: usize,
+    curr_retry: usize,
+) -> (
+    RedisResult<(
+        crate::cluster_slotmap::SlotMap,
+        crate::cluster_topology::TopologyHash,
+    )>,
+    Vec<ArcStr>,
+)
+where
+    C: ConnectionLike + Connect + Clone + Send + Sync + 'static,
+{
+    // The connections are collected first, so that `conn_lock` isn't held while waiting for the nodes.
+    let requested_nodes: Vec<_> = inner
+        .conn_lock
+        .read()
+        .await
+        .random_connections(num_of_nodes_to_query, ConnectionType::PreferManagement)
+        .collect();
+    calculate_topology_from_nodes(
+        inner,
+        requested_nodes.into_iter(),
+        num_of_nodes_to_query,
+        curr_retry,
+    )
+    .await
+}
+
+async fn calculate_topology_from_nodes<C>(
     inner: &Core<C>,
+    requested_nodes: impl Iterator<Item = (ArcStr, ConnectionFuture<C>)>,
     num_of_nodes_to_query: usize,
-    read_guard: &tokio::sync::RwLockReadGuard<'a, ConnectionsContainer<C>>,
     curr_retry: usize,
 ) -> (
     RedisResult<(
@@ -2184,12 +4764,16 @@ async fn calculate_topology_from_random_nodes<'a, C>(
 where
     C: ConnectionLike + Connect + Clone + Send + Sync + 'static,
 {
-    let requested_nodes =
-        read_guard.random_connections(num_of_nodes_to_query, ConnectionType::PreferManagement);
+    let topology_command = inner.cluster_params.topology_command;
     let topology_join_results =
         futures::future::join_all(requested_nodes.map(|(addr, conn)| async move {
             let mut conn: C = conn.await;
-            let res = conn.req_packed_command(&slot_cmd()).await;
+            let res = match conn.req_packed_command(&topology_command.cmd()).await {
+                Err(err) if topology_command.should_fall_back(&err) => {
+                    conn.req_packed_command(&slot_cmd()).await
+                }
+                res => res,
+            };
             (addr, res)
         }))
         .await;
@@ -2212,6 +4796,7 @@ where
             inner.cluster_params.tls,
             num_of_nodes_to_query,
             inner.cluster_params.read_from_replicas,
+            inner.cluster_params.address_resolver.as_ref(),
         ),
         failed_addresses,
     )
@@ -2222,9 +4807,13 @@ where
     C: ConnectionLike + Send + Clone + Unpin + Sync + Connect + 'static,
 {
     fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
-        let routing = cluster_routing::RoutingInfo::for_routable(cmd).unwrap_or(
-            cluster_routing::RoutingInfo::SingleNode(SingleNodeRoutingInfo::Random),
-        );
+        if let Err(err) = self.1.cluster_params.check_command_allowed(cmd) {
+            return future::err(err).boxed();
+        }
+        let routing = match self.routing_for_command(cmd) {
+            Ok(routing) => routing,
+            Err(err) => return future::err(err).boxed(),
+        };
         self.route_command(cmd, routing).boxed()
     }
 
@@ -2235,7 +4824,26 @@ where
         count: usize,
     ) -> RedisFuture<'a, Vec<Value>> {
         async move {
-            let route = route_for_pipeline(pipeline)?;
+            for cmd in pipeline.cmd_iter() {
+                self.1.cluster_params.check_command_allowed(cmd)?;
+            }
+            if self.1.cluster_params.strict_routing {
+                for cmd in pipeline.cmd_iter() {
+                    cluster_routing::RoutingInfo::for_routable_strict(cmd)?;
+                }
+            }
+            let route = match route_for_pipeline(pipeline) {
+                Err(err)
+                    if err.kind() == ErrorKind::CrossSlot
+                        && !pipeline.is_atomic()
+                        && self.1.cluster_params.split_cross_slot_pipelines =>
+                {
+                    return self
+                        .route_cross_slot_pipeline(pipeline, offset, count)
+                        .await;
+                }
+                route => route?,
+            };
             self.route_pipeline(pipeline, offset, count, route.into())
                 .await
         }
@@ -2261,6 +4869,23 @@ pub trait Connect: Sized {
     ) -> RedisFuture<'a, (Self, Option<IpAddr>)>
     where
         T: IntoConnectionInfo + Send + 'a;
+
+    /// Applies the [`FlushPolicy`](crate::aio::FlushPolicy) of the cluster to a connection that was connected. The
+    /// connections that don't buffer their writes can ignore it, which is the default.
+    fn set_flush_policy(&mut self, _policy: crate::aio::FlushPolicy) {}
+
+    /// Applies the [`max_reply_size`](crate::cluster::ClusterClientBuilder::max_reply_size) of the cluster to a
+    /// connection that was connected. The connections that can't limit their replies ignore it, which is the
+    /// default.
+    fn set_max_reply_size(&mut self, _max_bytes: usize) {}
+
+    /// Returns when the connection last received a successful response. Within the
+    /// [`health_check_freshness`](crate::cluster::ClusterClientBuilder::health_check_freshness) of the cluster, the
+    /// health checks consider the connection healthy without sending a `PING`. The connections that don't track
+    /// their responses return `None`, which is the default, so they're always pinged.
+    fn last_successful_response(&self) -> Option<Instant> {
+        None
+    }
 }
 
 impl Connect for MultiplexedConnection {
@@ -2305,6 +4930,18 @@ impl Connect for MultiplexedConnection {
         }
         .boxed()
     }
+
+    fn set_flush_policy(&mut self, policy: crate::aio::FlushPolicy) {
+        MultiplexedConnection::set_flush_policy(self, policy)
+    }
+
+    fn set_max_reply_size(&mut self, max_bytes: usize) {
+        MultiplexedConnection::set_max_reply_size(self, max_bytes)
+    }
+
+    fn last_successful_response(&self) -> Option<Instant> {
+        MultiplexedConnection::last_successful_response(self)
+    }
 }
 
 #[cfg(test)]
@@ -2390,3 +5027,66 @@ mod pipeline_routing_tests {
         );
     }
 }
+
+#[cfg(test)]
+mod reconnect_tests {
+    use super::{PollFlushAction, ReconnectClaim, RefreshConnectionType};
+    use arcstr::ArcStr;
+    use std::{collections::HashMap, sync::Mutex};
+
+    fn addresses(addresses: &[&str]) -> Vec<ArcStr> {
+        addresses
+            .iter()
+            .map(|address| ArcStr::from(*address))
+            .collect()
+    }
+
+    #[test]
+    fn test_reconnect_actions_are_deduplicated() {
+        let action = PollFlushAction::Reconnect(addresses(&["node1:6379"]))
+            .change_state(PollFlushAction::Reconnect(addresses(&[
+                "node2:6379",
+                "node1:6379",
+            ])))
+            .change_state(PollFlushAction::Reconnect(addresses(&["node2:6379"])));
+        let PollFlushAction::Reconnect(targets) = action else {
+            panic!("expected a reconnect");
+        };
+        assert_eq!(targets, addresses(&["node1:6379", "node2:6379"]));
+    }
+
+    #[test]
+    fn test_reconnect_claim_skips_the_nodes_that_are_already_reconnecting() {
+        let reconnecting_nodes = Mutex::new(HashMap::new());
+        let (targets, claim) = ReconnectClaim::new(
+            &reconnecting_nodes,
+            addresses(&["node1:6379", "node1:6379"]),
+            RefreshConnectionType::AllConnections,
+        );
+        assert_eq!(targets, addresses(&["node1:6379"]));
+
+        // Every connection of node1 is already reconnecting, while only the management connection of node2 is.
+        let (targets, _management_claim) = ReconnectClaim::new(
+            &reconnecting_nodes,
+            addresses(&["node1:6379", "node2:6379"]),
+            RefreshConnectionType::OnlyManagementConnection,
+        );
+        assert_eq!(targets, addresses(&["node2:6379"]));
+        let (targets, user_claim) = ReconnectClaim::new(
+            &reconnecting_nodes,
+            addresses(&["node1:6379", "node2:6379"]),
+            RefreshConnectionType::OnlyUserConnection,
+        );
+        assert_eq!(targets, addresses(&["node2:6379"]));
+        drop(user_claim);
+
+        // The nodes are released by the claims that reconnect them.
+        drop(claim);
+        let (targets, _claim) = ReconnectClaim::new(
+            &reconnecting_nodes,
+            addresses(&["node1:6379", "node2:6379"]),
+            RefreshConnectionType::OnlyManagementConnection,
+        );
+        assert_eq!(targets, addresses(&["node1:6379"]));
+    }
+}
@@ -0,0 +1,255 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use arcstr::ArcStr;
+
+/// A change of a node between two topology views, as identified by the node's `CLUSTER MYID`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum NodeChange {
+    /// A node that wasn't known before joined the topology.
+    Added { address: ArcStr, id: String },
+    /// A known node is now reachable under a different address.
+    Moved {
+        id: String,
+        from: ArcStr,
+        to: ArcStr,
+    },
+    /// A known node left the topology.
+    Removed { address: ArcStr, id: String },
+    /// The node at a known address was replaced by a node with a different id.
+    Replaced {
+        address: ArcStr,
+        previous_id: String,
+        id: String,
+    },
+}
+
+struct NodeIdentity {
+    id: String,
+    captured_at: Instant,
+}
+
+/// A time-based cache of the node ids of the cluster's addresses, which maps ids to addresses and back.
+pub(crate) struct NodeIdentities {
+    ttl: Duration,
+    by_address: HashMap<ArcStr, NodeIdentity>,
+    by_id: HashMap<String, ArcStr>,
+}
+
+impl NodeIdentities {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            by_address: HashMap::new(),
+            by_id: HashMap::new(),
+        }
+    }
+
+    /// Returns the given addresses whose node ids are unknown or older than the TTL.
+    pub(crate) fn addresses_to_query<'a>(
+        &self,
+        addresses: impl Iterator<Item = &'a ArcStr>,
+    ) -> Vec<ArcStr> {
+        addresses
+            .filter(|address| {
+                self.by_address
+                    .get(*address)
+                    .map_or(true, |identity| identity.captured_at.elapsed() >= self.ttl)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the node ids by address.
+    pub(crate) fn ids_by_address(&self) -> impl Iterator<Item = (&ArcStr, &str)> + '_ {
+        self.by_address
+            .iter()
+            .map(|(address, identity)| (address, identity.id.as_str()))
+    }
+
+    /// Records the ids that were observed at the given addresses, forgets the addresses that aren't part of the
+    /// current topology, and returns how the nodes changed.
+    pub(crate) fn update(
+        &mut self,
+        current_addresses: &HashSet<ArcStr>,
+        observed: Vec<(ArcStr, String)>,
+    ) -> Vec<NodeChange> {
+        let mut removed: HashMap<ArcStr, String> = HashMap::new();
+        self.by_address.retain(|address, identity| {
+            let is_current = current_addresses.contains(address);
+            if !is_current {
+                removed.insert(address.clone(), identity.id.clone());
+            }
+            is_current
+        });
+
+        let mut changes = Vec::new();
+        let now = Instant::now();
+        for (address, id) in observed {
+            let previous_id = self
+                .by_address
+                .get(&address)
+                .map(|identity| &identity.id)
+                .filter(|previous_id| **previous_id != id)
+                .cloned();
+            match self.by_id.get(&id) {
+                Some(previous_address) if *previous_address != address => {
+                    removed.remove(previous_address);
+                    self.by_address.remove(previous_address);
+                    changes.push(NodeChange::Moved {
+                        id: id.clone(),
+                        from: previous_address.clone(),
+                        to: address.clone(),
+                    });
+                }
+                Some(_) => {}
+                // A new node at a known address is reported as a replacement, rather than as an addition.
+                None if previous_id.is_some() => {}
+                None => changes.push(NodeChange::Added {
+                    address: address.clone(),
+                    id: id.clone(),
+                }),
+            }
+            if let Some(previous_id) = previous_id {
+                changes.push(NodeChange::Replaced {
+                    address: address.clone(),
+                    previous_id,
+                    id: id.clone(),
+                });
+            }
+            // The address might have been reused by a different node, whose id shouldn't point to it anymore.
+            if let Some(previous) = self.by_address.insert(
+                address.clone(),
+                NodeIdentity {
+                    id: id.clone(),
+                    captured_at: now,
+                },
+            ) {
+                if previous.id != id && self.by_id.get(&previous.id) == Some(&address) {
+                    self.by_id.remove(&previous.id);
+                }
+            }
+            self.by_id.insert(id, address);
+        }
+
+        for (address, id) in removed {
+            if self.by_id.get(&id) == Some(&address) {
+                self.by_id.remove(&id);
+            }
+            changes.push(NodeChange::Removed { address, id });
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addresses(addresses: &[&str]) -> HashSet<ArcStr> {
+        addresses
+            .iter()
+            .map(|address| ArcStr::from(*address))
+            .collect()
+    }
+
+    fn observed(nodes: &[(&str, &str)]) -> Vec<(ArcStr, String)> {
+        nodes
+            .iter()
+            .map(|(address, id)| (ArcStr::from(*address), id.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_update_distinguishes_moved_nodes_from_new_nodes() {
+        let mut identities = NodeIdentities::new(Duration::from_secs(60));
+        let changes = identities.update(
+            &addresses(&["a:6379", "b:6379"]),
+            observed(&[("a:6379", "id-a"), ("b:6379", "id-b")]),
+        );
+        assert_eq!(changes.len(), 2);
+        assert!(identities
+            .addresses_to_query(addresses(&["a:6379", "c:6379"]).iter())
+            .contains(&ArcStr::from("c:6379")));
+
+        let changes = identities.update(
+            &addresses(&["a:6379", "c:6379", "d:6379"]),
+            observed(&[("c:6379", "id-b"), ("d:6379", "id-d")]),
+        );
+        assert_eq!(
+            changes,
+            vec![
+                NodeChange::Moved {
+                    id: "id-b".to_string(),
+                    from: ArcStr::from("b:6379"),
+                    to: ArcStr::from("c:6379"),
+                },
+                NodeChange::Added {
+                    address: ArcStr::from("d:6379"),
+                    id: "id-d".to_string(),
+                },
+            ]
+        );
+
+        let changes = identities.update(&addresses(&["c:6379", "d:6379"]), vec![]);
+        assert_eq!(
+            changes,
+            vec![NodeChange::Removed {
+                address: ArcStr::from("a:6379"),
+                id: "id-a".to_string(),
+            }]
+        );
+        let mut ids: Vec<_> = identities
+            .ids_by_address()
+            .map(|(address, id)| (address.to_string(), id.to_string()))
+            .collect();
+        ids.sort();
+        assert_eq!(
+            ids,
+            vec![
+                ("c:6379".to_string(), "id-b".to_string()),
+                ("d:6379".to_string(), "id-d".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_update_reports_replaced_nodes() {
+        let mut identities = NodeIdentities::new(Duration::from_secs(60));
+        identities.update(
+            &addresses(&["a:6379", "b:6379"]),
+            observed(&[("a:6379", "id-a"), ("b:6379", "id-b")]),
+        );
+
+        let changes = identities.update(
+            &addresses(&["a:6379", "b:6379"]),
+            observed(&[("a:6379", "id-c"), ("b:6379", "id-b")]),
+        );
+        assert_eq!(
+            changes,
+            vec![NodeChange::Replaced {
+                address: ArcStr::from("a:6379"),
+                previous_id: "id-a".to_string(),
+                id: "id-c".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_addresses_to_query_respects_ttl() {
+        let mut identities = NodeIdentities::new(Duration::ZERO);
+        identities.update(&addresses(&["a:6379"]), observed(&[("a:6379", "id-a")]));
+        assert_eq!(
+            identities.addresses_to_query(addresses(&["a:6379"]).iter()),
+            vec![ArcStr::from("a:6379")]
+        );
+
+        let mut identities = NodeIdentities::new(Duration::from_secs(60));
+        identities.update(&addresses(&["a:6379"]), observed(&[("a:6379", "id-a")]));
+        assert!(identities
+            .addresses_to_query(addresses(&["a:6379"]).iter())
+            .is_empty());
+    }
+}
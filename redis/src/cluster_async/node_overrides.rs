@@ -0,0 +1,241 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::{cluster_slotmap::SlotMap, info::ReplicationRole};
+
+use super::{NodeMetadata, TopologyEvent};
+
+/// The key of the availability zone of a [`NodeOverride`] in the metadata of its node.
+pub const AVAILABILITY_ZONE_METADATA_KEY: &str = "availability_zone";
+/// The key of the weight of a [`NodeOverride`] in the metadata of its node.
+pub const WEIGHT_METADATA_KEY: &str = "weight";
+
+/// What an external source, such as the service discovery of the platform, knows about a node before the cluster
+/// reports it, as set by [`ClusterClientBuilder::node_override`](crate::cluster::ClusterClientBuilder::node_override)
+/// and [`ClusterConnection::set_node_override`](super::ClusterConnection::set_node_override).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeOverride {
+    role: Option<ReplicationRole>,
+    availability_zone: Option<String>,
+    weight: Option<u32>,
+}
+
+impl NodeOverride {
+    /// Creates an override that doesn't change anything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overrides the role of the node in the slots that are fetched from the cluster. A replica that is overridden
+    /// as a primary takes the place of its shard's primary, and a primary that is overridden as a replica gives its
+    /// place to the first replica of its shard that isn't overridden as a replica, if there's one.
+    pub fn role(mut self, role: ReplicationRole) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    /// Sets the availability zone of the node, as the [`AVAILABILITY_ZONE_METADATA_KEY`] of its metadata.
+    pub fn availability_zone(mut self, availability_zone: impl Into<String>) -> Self {
+        self.availability_zone = Some(availability_zone.into());
+        self
+    }
+
+    /// Sets the weight of the node, as the [`WEIGHT_METADATA_KEY`] of its metadata.
+    pub fn weight(mut self, weight: u32) -> Self {
+        self.weight = Some(weight);
+        self
+    }
+}
+
+/// The overrides of the nodes of a client, by their addresses.
+#[derive(Default)]
+pub(crate) struct NodeOverrides(Mutex<HashMap<String, NodeOverride>>);
+
+impl NodeOverrides {
+    pub(crate) fn new(overrides: HashMap<String, NodeOverride>) -> Self {
+        Self(Mutex::new(overrides))
+    }
+
+    pub(crate) fn set(&self, address: String, node_override: NodeOverride) {
+        self.0.lock().unwrap().insert(address, node_override);
+    }
+
+    pub(crate) fn remove(&self, address: &str) {
+        self.0.lock().unwrap().remove(address);
+    }
+
+    /// Applies the overridden roles to the slots that were fetched from the cluster, and returns a conflict event
+    /// for each node whose role was changed.
+    pub(crate) fn apply_roles(&self, slot_map: &mut SlotMap) -> Vec<TopologyEvent> {
+        let overrides = self.0.lock().unwrap();
+        let role = |address: &str| overrides.get(address).and_then(|o| o.role.clone());
+        let mut conflicts = Vec::new();
+        for slot_value in slot_map.slots.values_mut() {
+            let addrs = &mut slot_value.addrs;
+            let promoted = addrs
+                .replicas
+                .iter()
+                .position(|replica| role(replica) == Some(ReplicationRole::Primary))
+                .or_else(|| {
+                    if role(&addrs.primary) != Some(ReplicationRole::Replica) {
+                        return None;
+                    }
+                    addrs
+                        .replicas
+                        .iter()
+                        .position(|replica| role(replica) != Some(ReplicationRole::Replica))
+                });
+            let Some(index) = promoted else {
+                continue;
+            };
+            std::mem::swap(&mut addrs.primary, &mut addrs.replicas[index]);
+            let (primary, demoted) = (&addrs.primary, &addrs.replicas[index]);
+            if role(primary) == Some(ReplicationRole::Primary) {
+                conflicts.push((primary.clone(), ReplicationRole::Primary));
+            }
+            if role(demoted) == Some(ReplicationRole::Replica) {
+                conflicts.push((demoted.clone(), ReplicationRole::Replica));
+            }
+        }
+        // The shards that span several slot ranges report their conflicts once.
+        conflicts.sort_by(|(a, _), (b, _)| a.cmp(b));
+        conflicts.dedup();
+        conflicts
+            .into_iter()
+            .map(|(address, overridden)| role_conflict(&address, overridden))
+            .collect()
+    }
+
+    /// Returns the metadata of the node with its overridden availability zone and weight.
+    pub(crate) fn merge_metadata(
+        &self,
+        address: &str,
+        metadata: &Arc<NodeMetadata>,
+    ) -> Arc<NodeMetadata> {
+        let overrides = self.0.lock().unwrap();
+        let Some(node_override) = overrides
+            .get(address)
+            .filter(|o| o.availability_zone.is_some() || o.weight.is_some())
+        else {
+            return metadata.clone();
+        };
+        let mut metadata = NodeMetadata::clone(metadata);
+        if let Some(availability_zone) = &node_override.availability_zone {
+            metadata.insert(
+                AVAILABILITY_ZONE_METADATA_KEY.to_string(),
+                availability_zone.clone(),
+            );
+        }
+        if let Some(weight) = node_override.weight {
+            metadata.insert(WEIGHT_METADATA_KEY.to_string(), weight.to_string());
+        }
+        Arc::new(metadata)
+    }
+}
+
+fn role_conflict(address: &str, overridden: ReplicationRole) -> TopologyEvent {
+    let reported = match overridden {
+        ReplicationRole::Primary => ReplicationRole::Replica,
+        ReplicationRole::Replica => ReplicationRole::Primary,
+    };
+    TopologyEvent::NodeRoleConflict {
+        address: address.to_string(),
+        reported,
+        overridden,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cluster_routing::Slot;
+    use crate::cluster_slotmap::ReadFromReplicaStrategy;
+
+    fn slot_map() -> SlotMap {
+        SlotMap::new(
+            vec![
+                Slot::new(0, 100, "a:1".to_string(), vec!["b:1".to_string()]),
+                Slot::new(
+                    101,
+                    200,
+                    "c:1".to_string(),
+                    vec!["d:1".to_string(), "e:1".to_string()],
+                ),
+                Slot::new(201, 300, "a:1".to_string(), vec!["b:1".to_string()]),
+            ],
+            ReadFromReplicaStrategy::AlwaysFromPrimary,
+        )
+    }
+
+    fn primaries(slot_map: &SlotMap) -> Vec<&str> {
+        slot_map
+            .values()
+            .map(|addrs| addrs.primary.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_overridden_roles_replace_the_reported_primaries() {
+        let overrides = NodeOverrides::default();
+        overrides.set(
+            "b:1".to_string(),
+            NodeOverride::new().role(ReplicationRole::Primary),
+        );
+        overrides.set(
+            "c:1".to_string(),
+            NodeOverride::new().role(ReplicationRole::Replica),
+        );
+        overrides.set(
+            "d:1".to_string(),
+            NodeOverride::new().role(ReplicationRole::Replica),
+        );
+        let mut slots = slot_map();
+
+        let events = overrides.apply_roles(&mut slots);
+        assert_eq!(primaries(&slots), vec!["b:1", "e:1", "b:1"]);
+        assert_eq!(
+            events,
+            vec![
+                role_conflict("b:1", ReplicationRole::Primary),
+                role_conflict("c:1", ReplicationRole::Replica),
+            ]
+        );
+
+        // Roles that agree with the cluster aren't conflicts.
+        overrides.remove("b:1");
+        overrides.set(
+            "a:1".to_string(),
+            NodeOverride::new().role(ReplicationRole::Primary),
+        );
+        let mut slots = slot_map();
+        overrides.apply_roles(&mut slots);
+        assert_eq!(primaries(&slots), vec!["a:1", "e:1", "a:1"]);
+    }
+
+    #[test]
+    fn test_availability_zone_and_weight_are_merged_into_the_metadata() {
+        let overrides = NodeOverrides::default();
+        let metadata = Arc::new(NodeMetadata::from([
+            ("rack".to_string(), "r1".to_string()),
+            (
+                AVAILABILITY_ZONE_METADATA_KEY.to_string(),
+                "az1".to_string(),
+            ),
+        ]));
+        assert!(Arc::ptr_eq(
+            &overrides.merge_metadata("a:1", &metadata),
+            &metadata
+        ));
+
+        overrides.set(
+            "a:1".to_string(),
+            NodeOverride::new().availability_zone("az2").weight(3),
+        );
+        let merged = overrides.merge_metadata("a:1", &metadata);
+        assert_eq!(merged["rack"], "r1");
+        assert_eq!(merged[AVAILABILITY_ZONE_METADATA_KEY], "az2");
+        assert_eq!(merged[WEIGHT_METADATA_KEY], "3");
+    }
+}
@@ -0,0 +1,114 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+use crossbeam_queue::SegQueue;
+
+/// The state of the queue of the requests that wait to be sent, as returned by
+/// [`ClusterConnection::pending_requests_stats`](super::ClusterConnection::pending_requests_stats).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PendingRequestsStats {
+    /// The maximal number of queued requests, or `None` if the queue is unbounded.
+    pub max_pending_requests: Option<usize>,
+    /// The number of requests that wait to be sent.
+    pub depth: usize,
+    /// The largest number of requests that waited to be sent at the same time, since the connection was created.
+    pub peak_depth: usize,
+    /// The number of requests that were rejected because the queue was full, since the connection was created.
+    pub rejected: u64,
+}
+
+/// The requests that wait to be sent by the driver task, before it moves them to its scheduler.
+///
+/// Only new requests are bounded. Retries and the sub-requests of requests that were already accepted are always
+/// queued, so that accepting a request means that it runs to completion.
+pub(crate) struct PendingRequests<T> {
+    queue: SegQueue<T>,
+    max_pending_requests: Option<usize>,
+    // The number of requests in the scheduler of the driver task, as of its last poll.
+    scheduled: AtomicUsize,
+    peak_depth: AtomicUsize,
+    rejected: AtomicU64,
+}
+
+impl<T> PendingRequests<T> {
+    pub(crate) fn new(max_pending_requests: Option<usize>) -> Self {
+        Self {
+            queue: SegQueue::new(),
+            max_pending_requests,
+            scheduled: AtomicUsize::new(0),
+            peak_depth: AtomicUsize::new(0),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn push(&self, request: T) {
+        self.queue.push(request);
+        self.peak_depth.fetch_max(self.depth(), Ordering::Relaxed);
+    }
+
+    /// Queues a new request, or returns it if the queue is full. `scheduled` is the number of requests in the
+    /// scheduler of the driver task, which count towards the bound.
+    pub(crate) fn try_push_new(&self, request: T, scheduled: usize) -> Result<(), T> {
+        if self
+            .max_pending_requests
+            .map_or(false, |max| self.queue.len() + scheduled >= max)
+        {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(request);
+        }
+        self.queue.push(request);
+        self.peak_depth
+            .fetch_max(self.queue.len() + scheduled, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub(crate) fn pop(&self) -> Option<T> {
+        self.queue.pop()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Records the number of requests in the scheduler of the driver task, for the stats.
+    pub(crate) fn set_scheduled(&self, scheduled: usize) {
+        self.scheduled.store(scheduled, Ordering::Relaxed);
+    }
+
+    fn depth(&self) -> usize {
+        self.queue.len() + self.scheduled.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn stats(&self) -> PendingRequestsStats {
+        PendingRequestsStats {
+            max_pending_requests: self.max_pending_requests,
+            depth: self.depth(),
+            peak_depth: self.peak_depth.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_new_requests_are_bounded() {
+        let requests = PendingRequests::new(Some(2));
+        assert!(requests.try_push_new(1, 0).is_ok());
+        assert_eq!(requests.try_push_new(2, 1), Err(2));
+        assert!(requests.try_push_new(2, 0).is_ok());
+        assert_eq!(requests.try_push_new(3, 0), Err(3));
+
+        // Retries are queued above the bound.
+        requests.push(4);
+        let stats = requests.stats();
+        assert_eq!(stats.depth, 3);
+        assert_eq!(stats.peak_depth, 3);
+        assert_eq!(stats.rejected, 2);
+
+        while requests.pop().is_some() {}
+        assert_eq!(requests.stats().depth, 0);
+        assert!(requests.try_push_new(5, 0).is_ok());
+    }
+}
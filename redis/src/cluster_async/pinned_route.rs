@@ -0,0 +1,53 @@
+use crate::{
+    aio::{ConnectionLike, MultiplexedConnection},
+    Cmd, RedisFuture, Value,
+};
+
+/// A connection that sends all its commands to a single node of the cluster, as returned by
+/// [`ClusterConnection::pin_to_address`](super::ClusterConnection::pin_to_address) and
+/// [`ClusterConnection::pin_to_route`](super::ClusterConnection::pin_to_route).
+///
+/// Unlike the cluster connection, the commands aren't retried, and redirections such as `MOVED` are returned as
+/// errors instead of being followed, so that a sequence of commands is guaranteed to stay on the same node. Once the
+/// node's connection fails, all the following commands fail, and a new route should be pinned.
+#[derive(Clone)]
+pub struct PinnedRoute<C = MultiplexedConnection> {
+    address: String,
+    connection: C,
+}
+
+impl<C> PinnedRoute<C> {
+    pub(crate) fn new(address: String, connection: C) -> Self {
+        Self {
+            address,
+            connection,
+        }
+    }
+
+    /// Returns the address of the node that the commands are sent to.
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+}
+
+impl<C> ConnectionLike for PinnedRoute<C>
+where
+    C: ConnectionLike,
+{
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        self.connection.req_packed_command(cmd)
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        pipeline: &'a crate::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        self.connection.req_packed_commands(pipeline, offset, count)
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+use futures::{future::BoxFuture, ready, Stream};
+
+use super::{ClusterConnection, Connect};
+use crate::{
+    aio::{ConnectionLike, MultiplexedConnection},
+    cluster_routing::SingleNodeRoutingInfo,
+    Pipeline, PipelineChunking, RedisResult, Value,
+};
+
+type ChunkFuture = BoxFuture<'static, RedisResult<Vec<Value>>>;
+
+// A chunk of a pipeline, with the offset and the count of its replies.
+struct Chunk {
+    pipeline: Pipeline,
+    offset: usize,
+    count: usize,
+}
+
+/// A stream of the replies of the commands of a pipeline, as returned by
+/// [`ClusterConnection::route_pipeline_streaming`](super::ClusterConnection::route_pipeline_streaming).
+///
+/// The pipeline is sent in chunks, and a chunk is only sent once the replies of the previous one were consumed, so
+/// at most the replies of one chunk are held in memory. The stream yields the replies that
+/// [`ClusterConnection::route_pipeline`](super::ClusterConnection::route_pipeline) would return, one at a time. When a
+/// chunk fails, each of its replies is the chunk's error, and the following chunks are still sent, unless the error
+/// is an IO error or an unrecoverable error, after which the stream ends.
+pub struct ClusterPipelineStream<C = MultiplexedConnection> {
+    connection: ClusterConnection<C>,
+    route: SingleNodeRoutingInfo,
+    chunks: VecDeque<Chunk>,
+    replies: VecDeque<RedisResult<Value>>,
+    in_flight: Option<(ChunkFuture, usize)>,
+}
+
+impl<C> ClusterPipelineStream<C>
+where
+    C: ConnectionLike + Connect + Clone + Send + Sync + Unpin + 'static,
+{
+    pub(crate) fn new(
+        connection: ClusterConnection<C>,
+        pipeline: &Pipeline,
+        offset: usize,
+        count: usize,
+        route: SingleNodeRoutingInfo,
+        chunking: PipelineChunking,
+    ) -> Self {
+        let chunks = match chunking.split(pipeline, offset, count) {
+            Some(chunks) => chunks
+                .into_iter()
+                .map(|pipeline| {
+                    let count = pipeline.cmd_iter().count();
+                    Chunk {
+                        pipeline,
+                        offset: 0,
+                        count,
+                    }
+                })
+                .collect(),
+            None if pipeline.is_empty() => VecDeque::new(),
+            None => VecDeque::from([Chunk {
+                pipeline: pipeline.clone(),
+                offset,
+                count,
+            }]),
+        };
+        Self {
+            connection,
+            route,
+            chunks,
+            replies: VecDeque::new(),
+            in_flight: None,
+        }
+    }
+}
+
+impl<C> Stream for ClusterPipelineStream<C>
+where
+    C: ConnectionLike + Connect + Clone + Send + Sync + Unpin + 'static,
+{
+    type Item = RedisResult<Value>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(reply) = this.replies.pop_front() {
+                return Poll::Ready(Some(reply));
+            }
+            if this.in_flight.is_none() {
+                let Some(chunk) = this.chunks.pop_front() else {
+                    return Poll::Ready(None);
+                };
+                let mut connection = this.connection.clone();
+                let route = this.route.clone();
+                let count = chunk.count;
+                let future = Box::pin(async move {
+                    connection
+                        .route_pipeline(&chunk.pipeline, chunk.offset, chunk.count, route)
+                        .await
+                });
+                this.in_flight = Some((future, count));
+            }
+
+            let (future, count) = this.in_flight.as_mut().unwrap();
+            let result = ready!(future.as_mut().poll(cx));
+            let count = *count;
+            this.in_flight = None;
+            match result {
+                Ok(values) => this.replies.extend(values.into_iter().map(Ok)),
+                Err(err) => {
+                    // The connection can't deliver the following chunks either.
+                    if err.is_io_error() || err.is_unrecoverable_error() {
+                        this.chunks.clear();
+                    }
+                    this.replies
+                        .extend(std::iter::repeat_with(|| Err(err.clone())).take(count));
+                }
+            }
+        }
+    }
+}
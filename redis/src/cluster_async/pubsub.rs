@@ -0,0 +1,239 @@
+use std::collections::HashSet;
+
+use arcstr::ArcStr;
+
+use super::{ClusterConnection, Connect};
+use crate::{
+    aio::{ConnectionLike, MultiplexedConnection},
+    cluster_routing::{Route, SlotAddr},
+    cluster_topology::get_slot,
+    cmd, ErrorKind, ProtocolVersion, PubSubChannelOrPattern, PubSubSubscriptionInfo,
+    PubSubSubscriptionKind, RedisError, RedisResult,
+};
+
+/// A cluster connection that subscribes to pubsub channels and patterns at runtime, as returned by
+/// [`ClusterConnection::into_pubsub`].
+///
+/// Each subscription is sent to the primary that owns the slot of its channel or pattern, which is required for
+/// sharded subscriptions, and is as good as any other node for the global ones. The subscriptions are tracked per
+/// node, and are restored transparently when a node reconnects, and moved to the new owner of their slot after a
+/// slot migration or a `MOVED` redirection is discovered by a slot refresh.
+///
+/// The messages are delivered to the push sender that was given when the connection was created. Since the
+/// subscriptions share the nodes' connections with the other commands, RESP3 is required.
+#[derive(Clone)]
+pub struct ClusterPubSub<C = MultiplexedConnection> {
+    connection: ClusterConnection<C>,
+}
+
+impl<C> ClusterPubSub<C>
+where
+    C: ConnectionLike + Connect + Clone + Send + Sync + Unpin + 'static,
+{
+    pub(crate) fn new(connection: ClusterConnection<C>) -> RedisResult<Self> {
+        let inner = &connection.1;
+        if inner.cluster_params.protocol != ProtocolVersion::RESP3 {
+            return Err(RedisError::from((
+                ErrorKind::InvalidClientConfig,
+                "Cluster pubsub requires RESP3",
+            )));
+        }
+        if inner.push_sender.is_none() {
+            return Err(RedisError::from((
+                ErrorKind::InvalidClientConfig,
+                "Cluster pubsub requires a push sender to deliver the messages to",
+            )));
+        }
+        Ok(Self { connection })
+    }
+
+    /// Subscribes to a channel, whose messages are published with `PUBLISH`.
+    pub async fn subscribe(
+        &mut self,
+        channel: impl Into<PubSubChannelOrPattern>,
+    ) -> RedisResult<()> {
+        self.add_subscription(PubSubSubscriptionKind::Exact, channel.into())
+            .await
+    }
+
+    /// Subscribes to the channels that match a glob pattern, whose messages are published with `PUBLISH`.
+    pub async fn psubscribe(
+        &mut self,
+        pattern: impl Into<PubSubChannelOrPattern>,
+    ) -> RedisResult<()> {
+        self.add_subscription(PubSubSubscriptionKind::Pattern, pattern.into())
+            .await
+    }
+
+    /// Subscribes to a shard channel, whose messages are published with `SPUBLISH`.
+    pub async fn ssubscribe(
+        &mut self,
+        channel: impl Into<PubSubChannelOrPattern>,
+    ) -> RedisResult<()> {
+        self.add_subscription(PubSubSubscriptionKind::Sharded, channel.into())
+            .await
+    }
+
+    /// Unsubscribes from a channel that was subscribed with [`subscribe`](Self::subscribe).
+    pub async fn unsubscribe(
+        &mut self,
+        channel: impl Into<PubSubChannelOrPattern>,
+    ) -> RedisResult<()> {
+        self.remove_subscription(PubSubSubscriptionKind::Exact, channel.into())
+            .await
+    }
+
+    /// Unsubscribes from a pattern that was subscribed with [`psubscribe`](Self::psubscribe).
+    pub async fn punsubscribe(
+        &mut self,
+        pattern: impl Into<PubSubChannelOrPattern>,
+    ) -> RedisResult<()> {
+        self.remove_subscription(PubSubSubscriptionKind::Pattern, pattern.into())
+            .await
+    }
+
+    /// Unsubscribes from a shard channel that was subscribed with [`ssubscribe`](Self::ssubscribe).
+    pub async fn sunsubscribe(
+        &mut self,
+        channel: impl Into<PubSubChannelOrPattern>,
+    ) -> RedisResult<()> {
+        self.remove_subscription(PubSubSubscriptionKind::Sharded, channel.into())
+            .await
+    }
+
+    /// Returns all the subscriptions, regardless of the nodes that they're assigned to.
+    pub async fn subscriptions(&self) -> PubSubSubscriptionInfo {
+        let state = self.connection.pubsub_state().await;
+        let mut subscriptions = state.unassigned_subscriptions;
+        for (kind, channels_patterns) in state
+            .subscriptions_by_address
+            .into_values()
+            .flat_map(|subscriptions| subscriptions.into_iter())
+        {
+            subscriptions
+                .entry(kind)
+                .or_insert_with(HashSet::new)
+                .extend(channels_patterns);
+        }
+        subscriptions
+    }
+
+    /// Returns the underlying cluster connection, which keeps restoring the subscriptions.
+    pub fn connection(&mut self) -> &mut ClusterConnection<C> {
+        &mut self.connection
+    }
+
+    async fn add_subscription(
+        &mut self,
+        kind: PubSubSubscriptionKind,
+        channel_pattern: PubSubChannelOrPattern,
+    ) -> RedisResult<()> {
+        let inner = &self.connection.1;
+        let route = Route::new(get_slot(&channel_pattern), SlotAddr::Master);
+        let address = inner
+            .conn_lock
+            .read()
+            .await
+            .connection_for_route(&route)
+            .map(|(address, _)| address);
+        let Some(address) = address else {
+            // The subscription is assigned to a node once the slot is covered by a slot refresh.
+            inner
+                .unassigned_subscriptions
+                .write()
+                .await
+                .entry(kind)
+                .or_insert_with(HashSet::new)
+                .insert(channel_pattern);
+            return Ok(());
+        };
+
+        // The subscription is tracked before it's sent, so that a concurrent reconnect of the node restores it.
+        inner
+            .subscriptions_by_address
+            .write()
+            .await
+            .entry(address.clone())
+            .or_default()
+            .entry(kind)
+            .or_insert_with(HashSet::new)
+            .insert(channel_pattern.clone());
+        let result = self
+            .connection
+            .route_command_to_address(
+                cmd(subscription_command(kind, true)).arg(&channel_pattern),
+                &address,
+            )
+            .await;
+        if result.is_err() {
+            self.untrack(&address, kind, &channel_pattern).await;
+        }
+        result.map(|_| ())
+    }
+
+    async fn remove_subscription(
+        &mut self,
+        kind: PubSubSubscriptionKind,
+        channel_pattern: PubSubChannelOrPattern,
+    ) -> RedisResult<()> {
+        let inner = &self.connection.1;
+        if let Some(channels_patterns) = inner.unassigned_subscriptions.write().await.get_mut(&kind)
+        {
+            channels_patterns.remove(&channel_pattern);
+        }
+        let addresses: Vec<ArcStr> = inner
+            .subscriptions_by_address
+            .read()
+            .await
+            .iter()
+            .filter(|(_, subscriptions)| {
+                subscriptions.get(&kind).map_or(false, |channels_patterns| {
+                    channels_patterns.contains(&channel_pattern)
+                })
+            })
+            .map(|(address, _)| address.clone())
+            .collect();
+        for address in addresses {
+            self.untrack(&address, kind, &channel_pattern).await;
+            self.connection
+                .route_command_to_address(
+                    cmd(subscription_command(kind, false)).arg(&channel_pattern),
+                    &address,
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn untrack(
+        &self,
+        address: &ArcStr,
+        kind: PubSubSubscriptionKind,
+        channel_pattern: &PubSubChannelOrPattern,
+    ) {
+        let mut subscriptions_by_address = self.connection.1.subscriptions_by_address.write().await;
+        let Some(subscriptions) = subscriptions_by_address.get_mut(address) else {
+            return;
+        };
+        if let Some(channels_patterns) = subscriptions.get_mut(&kind) {
+            channels_patterns.remove(channel_pattern);
+            if channels_patterns.is_empty() {
+                subscriptions.remove(&kind);
+            }
+        }
+        if subscriptions.is_empty() {
+            subscriptions_by_address.remove(address);
+        }
+    }
+}
+
+fn subscription_command(kind: PubSubSubscriptionKind, subscribe: bool) -> &'static str {
+    match (kind, subscribe) {
+        (PubSubSubscriptionKind::Exact, true) => "SUBSCRIBE",
+        (PubSubSubscriptionKind::Exact, false) => "UNSUBSCRIBE",
+        (PubSubSubscriptionKind::Pattern, true) => "PSUBSCRIBE",
+        (PubSubSubscriptionKind::Pattern, false) => "PUNSUBSCRIBE",
+        (PubSubSubscriptionKind::Sharded, true) => "SSUBSCRIBE",
+        (PubSubSubscriptionKind::Sharded, false) => "SUNSUBSCRIBE",
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use arcstr::ArcStr;
+use rand::Rng;
+
+use crate::RedisError;
+
+/// The backoff between the reconnect attempts to each node, as set by
+/// [`ClusterClientBuilder::reconnect_backoff`](crate::cluster::ClusterClientBuilder::reconnect_backoff).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ReconnectBackoffParams {
+    pub(crate) base: Duration,
+    pub(crate) max: Duration,
+}
+
+impl ReconnectBackoffParams {
+    /// Returns the wait after `failures` consecutive failed attempts, drawn uniformly between zero and the
+    /// exponential backoff, which is capped by the maximal wait.
+    fn wait(&self, failures: u32) -> Duration {
+        let ceiling = self
+            .base
+            .checked_mul(1 << failures.saturating_sub(1).min(31))
+            .unwrap_or(self.max)
+            .min(self.max);
+        if ceiling.is_zero() {
+            return ceiling;
+        }
+        rand::thread_rng().gen_range(Duration::ZERO..=ceiling)
+    }
+}
+
+struct NodeBackoff {
+    failures: u32,
+    next_attempt: Instant,
+    last_error: RedisError,
+}
+
+/// The reconnect backoff of the nodes whose last connection attempt failed, by their addresses.
+#[derive(Default)]
+pub(crate) struct ReconnectBackoffs(HashMap<ArcStr, NodeBackoff>);
+
+impl ReconnectBackoffs {
+    /// Returns the error of the last attempt to connect to the node, if the node should not be reconnected to before
+    /// its backoff expires.
+    pub(crate) fn check(&self, address: &str, now: Instant) -> Result<(), RedisError> {
+        match self.0.get(address) {
+            Some(backoff) if now < backoff.next_attempt => Err(backoff.last_error.clone()),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn record_failure(
+        &mut self,
+        params: &ReconnectBackoffParams,
+        address: &ArcStr,
+        err: RedisError,
+        now: Instant,
+    ) {
+        let backoff = self.0.entry(address.clone()).or_insert(NodeBackoff {
+            failures: 0,
+            next_attempt: now,
+            last_error: err.clone(),
+        });
+        backoff.failures = backoff.failures.saturating_add(1);
+        backoff.next_attempt = now + params.wait(backoff.failures);
+        backoff.last_error = err;
+    }
+
+    pub(crate) fn record_success(&mut self, address: &str) {
+        self.0.remove(address);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorKind;
+
+    const PARAMS: ReconnectBackoffParams = ReconnectBackoffParams {
+        base: Duration::from_millis(100),
+        max: Duration::from_secs(1),
+    };
+
+    #[test]
+    fn test_wait_is_jittered_below_the_capped_exponential_backoff() {
+        for failures in 1..40 {
+            let ceiling = Duration::from_millis(100 * (1 << (failures - 1).min(20)))
+                .min(Duration::from_secs(1));
+            assert!(PARAMS.wait(failures) <= ceiling, "{failures}");
+        }
+    }
+
+    #[test]
+    fn test_node_isnt_reconnected_to_until_its_backoff_expires() {
+        let mut backoffs = ReconnectBackoffs::default();
+        let address = ArcStr::from("node1:6379");
+        let start = Instant::now();
+        let err = RedisError::from((ErrorKind::IoError, "connection refused"));
+
+        backoffs.record_failure(&PARAMS, &address, err, start);
+        let next_attempt = backoffs.0[&address].next_attempt;
+        if next_attempt > start {
+            let err = backoffs.check(&address, start).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::IoError);
+        }
+        assert!(backoffs.check("node2:6379", start).is_ok());
+        let after_max_wait = start + PARAMS.max + Duration::from_millis(1);
+        assert!(backoffs.check(&address, after_max_wait).is_ok());
+
+        backoffs.record_success(&address);
+        assert!(backoffs.check(&address, start).is_ok());
+    }
+}
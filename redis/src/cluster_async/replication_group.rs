@@ -0,0 +1,626 @@
+//! This module provides support for replication groups with cluster mode disabled.
+//!
+//! Managed services such as ElastiCache expose such a group through two DNS endpoints: a primary endpoint that
+//! always points to the current primary, and a reader endpoint that balances over the replicas. A
+//! [`ReplicationGroupConnection`] routes write commands to the primary endpoint and read-only commands to the
+//! reader endpoint, using the same retry parameters as the cluster client, but without any slot handling.
+//!
+//! After a failover, the primary endpoint's DNS record is updated to point to the promoted replica. Connections
+//! that still point to the demoted primary are detected either by `READONLY` errors or by the periodic health
+//! checks, and are reconnected, which resolves the endpoint's address again.
+//!
+//! # Example
+//! ```rust,no_run
+//! use redis::cluster_async::replication_group::ReplicationGroupClient;
+//! use redis::AsyncCommands;
+//! use std::time::Duration;
+//!
+//! async fn fetch_an_integer() -> String {
+//!     let client = ReplicationGroupClient::builder("redis://primary.example.com/")
+//!         .reader_endpoint("redis://reader.example.com/")
+//!         .health_check_interval(Duration::from_secs(5))
+//!         .build()
+//!         .unwrap();
+//!     let mut connection = client.get_async_connection().await.unwrap();
+//!     let _: () = connection.set("test", "test_data").await.unwrap();
+//!     let rv: String = connection.get("test").await.unwrap();
+//!     return rv;
+//! }
+//! ```
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::{Duration, Instant},
+};
+
+use arcstr::ArcStr;
+use futures::{future::BoxFuture, FutureExt};
+use tracing::{info, warn};
+
+#[cfg(all(not(feature = "tokio-comp"), feature = "async-std-comp"))]
+use crate::aio::{async_std::AsyncStd, RedisRuntime};
+use crate::{
+    aio::{ConnectionLike, MultiplexedConnection, Runtime},
+    cluster_client::RetryParams,
+    cluster_routing::is_readonly,
+    cmd,
+    info::{Info, ReplicationRole},
+    types::RetryMethod,
+    Cmd, ConnectionInfo, ErrorKind, IntoConnectionInfo, Pipeline, RedisFuture, RedisResult, Value,
+};
+
+use super::{
+    boxed_sleep,
+    connections_logic::check_connection,
+    reconnect_backoff::{ReconnectBackoffParams, ReconnectBackoffs},
+    Connect, HealthCheckProbe,
+};
+
+// The reconnect backoff of the reader endpoint, unless another one is set with
+// `ReplicationGroupClientBuilder::reconnect_backoff`.
+const DEFAULT_READER_RECONNECT_BACKOFF: ReconnectBackoffParams = ReconnectBackoffParams {
+    base: Duration::from_millis(100),
+    max: Duration::from_secs(5),
+};
+
+#[derive(Clone)]
+struct ReplicationGroupParams {
+    retry_params: RetryParams,
+    connection_timeout: Duration,
+    response_timeout: Duration,
+    health_check_interval: Option<Duration>,
+}
+
+impl ReplicationGroupParams {
+    fn reader_backoff(&self) -> ReconnectBackoffParams {
+        self.retry_params
+            .reconnect_backoff
+            .unwrap_or(DEFAULT_READER_RECONNECT_BACKOFF)
+    }
+}
+
+/// Used to configure and build a [`ReplicationGroupClient`].
+pub struct ReplicationGroupClientBuilder {
+    primary: RedisResult<ConnectionInfo>,
+    reader: Option<RedisResult<ConnectionInfo>>,
+    retry_params: RetryParams,
+    connection_timeout: Option<Duration>,
+    response_timeout: Option<Duration>,
+    health_check_interval: Option<Duration>,
+}
+
+impl ReplicationGroupClientBuilder {
+    /// Creates a new `ReplicationGroupClientBuilder` with the provided primary endpoint.
+    ///
+    /// This is the same as `ReplicationGroupClient::builder(primary)`.
+    pub fn new<T: IntoConnectionInfo>(primary: T) -> ReplicationGroupClientBuilder {
+        ReplicationGroupClientBuilder {
+            primary: primary.into_connection_info(),
+            reader: None,
+            retry_params: RetryParams::default(),
+            connection_timeout: None,
+            response_timeout: None,
+            health_check_interval: None,
+        }
+    }
+
+    /// Sets the reader endpoint, to which read-only commands are routed.
+    ///
+    /// If not set, or while the reader endpoint isn't connected, all commands are routed to the primary endpoint.
+    /// Requests never wait for the reader endpoint to connect: it's reconnected in the background, with the
+    /// [reconnect backoff](Self::reconnect_backoff) between the failed attempts.
+    pub fn reader_endpoint<T: IntoConnectionInfo>(
+        mut self,
+        reader: T,
+    ) -> ReplicationGroupClientBuilder {
+        self.reader = Some(reader.into_connection_info());
+        self
+    }
+
+    /// Sets the backoff between the attempts to reconnect to the reader endpoint, like
+    /// [`ClusterClientBuilder::reconnect_backoff`](crate::cluster::ClusterClientBuilder::reconnect_backoff).
+    ///
+    /// Defaults to a base of 100 milliseconds and a maximum of 5 seconds.
+    pub fn reconnect_backoff(
+        mut self,
+        base: Duration,
+        max: Duration,
+    ) -> ReplicationGroupClientBuilder {
+        self.retry_params.reconnect_backoff = Some(ReconnectBackoffParams { base, max });
+        self
+    }
+
+    /// Sets number of retries for the new ReplicationGroupClient.
+    pub fn retries(mut self, retries: u32) -> ReplicationGroupClientBuilder {
+        self.retry_params.number_of_retries = retries;
+        self
+    }
+
+    /// Enables timing out on slow connection time.
+    ///
+    /// If enabled, the client will only wait the given time on each connection attempt to each endpoint.
+    pub fn connection_timeout(
+        mut self,
+        connection_timeout: Duration,
+    ) -> ReplicationGroupClientBuilder {
+        self.connection_timeout = Some(connection_timeout);
+        self
+    }
+
+    /// Enables timing out on slow responses.
+    ///
+    /// If enabled, the client will only wait the given time to each response from each endpoint.
+    pub fn response_timeout(mut self, response_timeout: Duration) -> ReplicationGroupClientBuilder {
+        self.response_timeout = Some(response_timeout);
+        self
+    }
+
+    /// Enables periodic health checks of the endpoints' connections.
+    ///
+    /// If enabled, the reader connection is checked with `PING`, and the primary connection is checked with
+    /// `INFO REPLICATION` in order to detect that it still points to the primary. Unhealthy connections are
+    /// reconnected, which resolves the endpoint's address again.
+    pub fn health_check_interval(mut self, interval: Duration) -> ReplicationGroupClientBuilder {
+        self.health_check_interval = Some(interval);
+        self
+    }
+
+    /// Creates a new [`ReplicationGroupClient`] from the parameters.
+    ///
+    /// This does not create connections to the endpoints.
+    ///
+    /// # Errors
+    ///
+    /// Upon failure to parse the endpoints, or if an endpoint is a Unix socket, an error is returned.
+    pub fn build(self) -> RedisResult<ReplicationGroupClient> {
+        let primary = self.primary?;
+        let reader = self.reader.transpose()?;
+        for info in std::iter::once(&primary).chain(reader.iter()) {
+            if let crate::ConnectionAddr::Unix(_) = info.addr {
+                return Err((
+                    ErrorKind::InvalidClientConfig,
+                    "Replication group endpoints must be TCP addresses",
+                    info.addr.to_string(),
+                )
+                    .into());
+            }
+        }
+
+        Ok(ReplicationGroupClient {
+            primary,
+            reader,
+            params: ReplicationGroupParams {
+                retry_params: self.retry_params,
+                connection_timeout: self.connection_timeout.unwrap_or(Duration::MAX),
+                response_timeout: self.response_timeout.unwrap_or(Duration::MAX),
+                health_check_interval: self.health_check_interval,
+            },
+        })
+    }
+}
+
+/// A client for a replication group with cluster mode disabled, made of a primary endpoint and an optional
+/// reader endpoint.
+#[derive(Clone)]
+pub struct ReplicationGroupClient {
+    primary: ConnectionInfo,
+    reader: Option<ConnectionInfo>,
+    params: ReplicationGroupParams,
+}
+
+impl ReplicationGroupClient {
+    /// Creates a [`ReplicationGroupClientBuilder`] with the provided primary endpoint.
+    pub fn builder<T: IntoConnectionInfo>(primary: T) -> ReplicationGroupClientBuilder {
+        ReplicationGroupClientBuilder::new(primary)
+    }
+
+    /// Creates connections to the endpoints and returns a [`ReplicationGroupConnection`].
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the connection to the primary endpoint fails.
+    /// A failure to connect to the reader endpoint isn't an error, since reads can be served by the primary.
+    pub async fn get_async_connection(&self) -> RedisResult<ReplicationGroupConnection> {
+        ReplicationGroupConnection::new(self).await
+    }
+
+    #[doc(hidden)]
+    pub async fn get_async_generic_connection<C>(
+        &self,
+    ) -> RedisResult<ReplicationGroupConnection<C>>
+    where
+        C: ConnectionLike + Connect + Clone + Send + Sync + 'static,
+    {
+        ReplicationGroupConnection::new(self).await
+    }
+}
+
+struct EndpointConnection<C> {
+    /// Increased on each successful reconnect, so that concurrent failures of the same connection trigger a single reconnect.
+    generation: u64,
+    /// None if the connection was found broken, or if the last connection attempt failed.
+    connection: Option<C>,
+}
+
+struct Endpoint<C> {
+    info: ConnectionInfo,
+    address: ArcStr,
+    is_primary: bool,
+    connection: Mutex<EndpointConnection<C>>,
+    reconnect_lock: tokio::sync::Mutex<()>,
+}
+
+impl<C> Endpoint<C>
+where
+    C: ConnectionLike + Connect + Clone + Send + Sync + 'static,
+{
+    fn new(info: ConnectionInfo, is_primary: bool, connection: Option<C>) -> Self {
+        Self {
+            address: info.addr.to_string().into(),
+            info,
+            is_primary,
+            connection: Mutex::new(EndpointConnection {
+                generation: 0,
+                connection,
+            }),
+            reconnect_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        if self.is_primary {
+            "primary"
+        } else {
+            "reader"
+        }
+    }
+
+    async fn connect(info: &ConnectionInfo, params: &ReplicationGroupParams) -> RedisResult<C> {
+        let (mut connection, _ip) = C::connect(
+            info.clone(),
+            params.response_timeout,
+            params.connection_timeout,
+            None,
+            None,
+        )
+        .await?;
+        check_connection(
+            &mut connection,
+            params.connection_timeout,
+            &HealthCheckProbe::default(),
+        )
+        .await?;
+        Ok(connection)
+    }
+
+    fn current(&self) -> (u64, Option<C>) {
+        let guard = self.connection.lock().unwrap();
+        (guard.generation, guard.connection.clone())
+    }
+
+    /// Marks the connection of the given generation as broken, so that the next request reconnects.
+    fn invalidate(&self, generation: u64) {
+        let mut guard = self.connection.lock().unwrap();
+        if guard.generation == generation {
+            guard.connection = None;
+        }
+    }
+
+    async fn get_connection(&self, params: &ReplicationGroupParams) -> RedisResult<(u64, C)> {
+        match self.current() {
+            (generation, Some(connection)) => Ok((generation, connection)),
+            (generation, None) => self.reconnect(params, generation).await,
+        }
+    }
+
+    async fn reconnect(
+        &self,
+        params: &ReplicationGroupParams,
+        failed_generation: u64,
+    ) -> RedisResult<(u64, C)> {
+        let _guard = self.reconnect_lock.lock().await;
+        if let (generation, Some(connection)) = self.current() {
+            if generation != failed_generation {
+                // Another task already reconnected while we waited for the lock
+                return Ok((generation, connection));
+            }
+        }
+
+        match Self::connect(&self.info, params).await {
+            Ok(connection) => {
+                let mut guard = self.connection.lock().unwrap();
+                guard.generation += 1;
+                guard.connection = Some(connection.clone());
+                info!("Reconnected to the {} endpoint", self.name());
+                Ok((guard.generation, connection))
+            }
+            Err(err) => {
+                self.invalidate(failed_generation);
+                warn!(
+                    "Failed to reconnect to the {} endpoint. Error: `{err}`",
+                    self.name()
+                );
+                Err(err)
+            }
+        }
+    }
+
+    async fn is_healthy(&self, connection: &mut C, params: &ReplicationGroupParams) -> bool {
+        if !self.is_primary {
+            return check_connection(
+                connection,
+                params.connection_timeout,
+                &HealthCheckProbe::default(),
+            )
+            .await
+            .is_ok();
+        }
+        // A healthy connection to the primary endpoint might still point to a demoted primary after a failover
+        match Runtime::locate()
+            .timeout(
+                params.connection_timeout,
+                cmd("INFO")
+                    .arg("REPLICATION")
+                    .query_async::<_, Info>(connection),
+            )
+            .await
+        {
+            Ok(Ok(info)) => info.replication.role == Some(ReplicationRole::Primary),
+            _ => false,
+        }
+    }
+
+    async fn check_health(&self, params: &ReplicationGroupParams) {
+        let (generation, connection) = self.current();
+        if let Some(mut connection) = connection {
+            if self.is_healthy(&mut connection, params).await {
+                return;
+            }
+            warn!(
+                "The {} endpoint's connection is unhealthy, reconnecting",
+                self.name()
+            );
+        }
+        let _ = self.reconnect(params, generation).await;
+    }
+}
+
+struct Inner<C> {
+    primary: Endpoint<C>,
+    reader: Option<Endpoint<C>>,
+    params: ReplicationGroupParams,
+    reader_backoff: Mutex<ReconnectBackoffs>,
+    // Whether the reader endpoint is being reconnected in the background.
+    reconnecting_reader: AtomicBool,
+}
+
+impl<C> Inner<C>
+where
+    C: ConnectionLike + Connect + Clone + Send + Sync + 'static,
+{
+    async fn execute<'a, T>(
+        self: &'a Arc<Self>,
+        read_only: bool,
+        mut request: impl FnMut(C) -> BoxFuture<'a, RedisResult<T>>,
+    ) -> RedisResult<T> {
+        let retry_params = &self.params.retry_params;
+        let mut use_reader = read_only && self.reader.is_some();
+        let mut retry = 0;
+        loop {
+            let endpoint = match (use_reader, &self.reader) {
+                (true, Some(reader)) => reader,
+                _ => &self.primary,
+            };
+            let connection = if use_reader {
+                match endpoint.current() {
+                    (generation, Some(connection)) => Ok((generation, connection)),
+                    (generation, None) => {
+                        // Reads are served by the primary while the reader endpoint is reconnected
+                        self.reconnect_reader(generation);
+                        use_reader = false;
+                        continue;
+                    }
+                }
+            } else {
+                endpoint.get_connection(&self.params).await
+            };
+            let (generation, connection) = match connection {
+                Ok(connection) => connection,
+                Err(err) => {
+                    if retry >= retry_params.number_of_retries {
+                        return Err(err);
+                    }
+                    retry += 1;
+                    boxed_sleep(retry_params.wait_time_for_retry(retry)).await;
+                    continue;
+                }
+            };
+
+            let err = match request(connection).await {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+            if retry >= retry_params.number_of_retries {
+                return Err(err);
+            }
+            retry += 1;
+            if err.kind() == ErrorKind::ReadOnly && endpoint.is_primary {
+                // After a failover, the connection might still point to the demoted primary
+                endpoint.invalidate(generation);
+                continue;
+            }
+            match err.retry_method() {
+                RetryMethod::Reconnect | RetryMethod::RetryImmediately => {
+                    endpoint.invalidate(generation);
+                }
+                RetryMethod::WaitAndRetry => {
+                    boxed_sleep(retry_params.wait_time_for_retry(retry)).await;
+                }
+                RetryMethod::WaitAndRetryOnPrimaryRedirectOnReplica => {
+                    if use_reader {
+                        use_reader = false;
+                    } else {
+                        boxed_sleep(retry_params.wait_time_for_retry(retry)).await;
+                    }
+                }
+                RetryMethod::NoRetry | RetryMethod::MovedRedirect | RetryMethod::AskRedirect => {
+                    return Err(err);
+                }
+            }
+        }
+    }
+
+    /// Reconnects the reader endpoint in the background, unless it's already being reconnected or its reconnect
+    /// backoff didn't expire yet, so that neither the requests nor the health checks wait for an unreachable reader.
+    fn reconnect_reader(self: &Arc<Self>, failed_generation: u64) {
+        let Some(reader) = &self.reader else {
+            return;
+        };
+        if self
+            .reader_backoff
+            .lock()
+            .unwrap()
+            .check(&reader.address, Instant::now())
+            .is_err()
+            || self.reconnecting_reader.swap(true, Ordering::AcqRel)
+        {
+            return;
+        }
+        let inner = self.clone();
+        let reconnect = async move {
+            let Some(reader) = &inner.reader else {
+                return;
+            };
+            let result = reader.reconnect(&inner.params, failed_generation).await;
+            let mut reader_backoff = inner.reader_backoff.lock().unwrap();
+            match result {
+                Ok(_) => reader_backoff.record_success(&reader.address),
+                Err(err) => reader_backoff.record_failure(
+                    &inner.params.reader_backoff(),
+                    &reader.address,
+                    err,
+                    Instant::now(),
+                ),
+            }
+            inner.reconnecting_reader.store(false, Ordering::Release);
+        };
+        #[cfg(feature = "tokio-comp")]
+        tokio::spawn(reconnect);
+        #[cfg(all(not(feature = "tokio-comp"), feature = "async-std-comp"))]
+        AsyncStd::spawn(reconnect);
+    }
+
+    async fn periodic_health_check(inner: Weak<Self>, interval: Duration) {
+        loop {
+            boxed_sleep(interval).await;
+            let Some(inner) = inner.upgrade() else {
+                return;
+            };
+            inner.primary.check_health(&inner.params).await;
+            if let Some(reader) = &inner.reader {
+                let (generation, connection) = reader.current();
+                if let Some(mut connection) = connection {
+                    if reader.is_healthy(&mut connection, &inner.params).await {
+                        continue;
+                    }
+                    warn!("The reader endpoint's connection is unhealthy, reconnecting");
+                    reader.invalidate(generation);
+                }
+                inner.reconnect_reader(generation);
+            }
+        }
+    }
+}
+
+/// A connection to a replication group with cluster mode disabled.
+///
+/// Write commands are sent to the primary endpoint, and read-only commands are sent to the reader endpoint.
+/// Cloning the connection is cheap, and all clones share the same underlying connections.
+#[derive(Clone)]
+pub struct ReplicationGroupConnection<C = MultiplexedConnection> {
+    inner: Arc<Inner<C>>,
+}
+
+impl<C> ReplicationGroupConnection<C>
+where
+    C: ConnectionLike + Connect + Clone + Send + Sync + 'static,
+{
+    async fn new(client: &ReplicationGroupClient) -> RedisResult<Self> {
+        let params = client.params.clone();
+        let primary_connection = Endpoint::<C>::connect(&client.primary, &params).await?;
+        let mut reader_backoff = ReconnectBackoffs::default();
+        let reader = match &client.reader {
+            Some(info) => {
+                let connection = Endpoint::<C>::connect(info, &params).await;
+                let reader = Endpoint::new(info.clone(), false, connection.as_ref().ok().cloned());
+                if let Err(err) = connection {
+                    warn!("Failed to connect to the reader endpoint. Error: `{err}`");
+                    reader_backoff.record_failure(
+                        &params.reader_backoff(),
+                        &reader.address,
+                        err,
+                        Instant::now(),
+                    );
+                }
+                Some(reader)
+            }
+            None => None,
+        };
+
+        let inner = Arc::new(Inner {
+            primary: Endpoint::new(client.primary.clone(), true, Some(primary_connection)),
+            reader,
+            params,
+            reader_backoff: Mutex::new(reader_backoff),
+            reconnecting_reader: AtomicBool::new(false),
+        });
+        if let Some(interval) = inner.params.health_check_interval {
+            let health_check = Inner::periodic_health_check(Arc::downgrade(&inner), interval);
+            #[cfg(feature = "tokio-comp")]
+            tokio::spawn(health_check);
+            #[cfg(all(not(feature = "tokio-comp"), feature = "async-std-comp"))]
+            AsyncStd::spawn(health_check);
+        }
+        Ok(Self { inner })
+    }
+}
+
+impl<C> ConnectionLike for ReplicationGroupConnection<C>
+where
+    C: ConnectionLike + Connect + Clone + Send + Sync + 'static,
+{
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        let read_only = is_readonly(cmd);
+        self.inner
+            .execute(read_only, move |mut connection| {
+                async move { connection.req_packed_command(cmd).await }.boxed()
+            })
+            .boxed()
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        pipeline: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        let read_only = pipeline.cmd_iter().all(is_readonly);
+        self.inner
+            .execute(read_only, move |mut connection| {
+                async move {
+                    connection
+                        .req_packed_commands(pipeline, offset, count)
+                        .await
+                }
+                .boxed()
+            })
+            .boxed()
+    }
+
+    fn get_db(&self) -> i64 {
+        self.inner.primary.info.redis.db
+    }
+}
@@ -0,0 +1,169 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{cmd::cmd_len, types::ProtocolVersion, Cmd, RedisResult, Value};
+
+/// The number of bytes that a request wrote to and read from the nodes, as returned by
+/// [`ClusterConnection::route_command_with_size`](super::ClusterConnection::route_command_with_size).
+///
+/// Every attempt is counted, including the retries, the redirects and the requests to each node of a command that
+/// is sent to multiple nodes. The bytes that are read are the size of the replies in RESP, as computed from the
+/// parsed replies, so they can differ from the bytes on the wire by the formatting of the doubles. The errors that
+/// aren't replies of a node, such as IO errors, read nothing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RequestSize {
+    /// The number of bytes of the packed commands that were sent.
+    pub bytes_written: u64,
+    /// The number of bytes of the replies that were received.
+    pub bytes_read: u64,
+}
+
+/// The sizes of the attempts of a request, shared by its retries and its sub-requests.
+#[derive(Default)]
+pub(crate) struct RequestSizeCounter {
+    bytes_written: AtomicU64,
+    bytes_read: AtomicU64,
+}
+
+impl RequestSizeCounter {
+    pub(crate) fn record(&self, cmd: &Cmd, reply: &RedisResult<Value>, protocol: ProtocolVersion) {
+        self.bytes_written
+            .fetch_add(cmd_len(cmd) as u64, Ordering::Relaxed);
+        let reply_len = match reply {
+            Ok(value) => value_len(value, protocol),
+            Err(err) => err.code().map_or(0, |code| error_len(code, err.detail())),
+        };
+        self.bytes_read
+            .fetch_add(reply_len as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn get(&self) -> RequestSize {
+        RequestSize {
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// The length of a line that starts with a type byte and ends with CRLF.
+fn line_len(content_len: usize) -> usize {
+    1 + content_len + 2
+}
+
+// The length of the textual representation of a number or a name.
+fn text_len(value: impl ToString) -> usize {
+    value.to_string().len()
+}
+
+fn error_len(code: &str, detail: Option<&str>) -> usize {
+    line_len(code.len() + detail.map_or(0, |detail| 1 + detail.len()))
+}
+
+fn aggregate_len<'a>(
+    len: usize,
+    values: impl Iterator<Item = &'a Value>,
+    protocol: ProtocolVersion,
+) -> usize {
+    line_len(text_len(len))
+        + values
+            .map(|value| value_len(value, protocol))
+            .sum::<usize>()
+}
+
+fn pairs_len(pairs: &[(Value, Value)], protocol: ProtocolVersion) -> usize {
+    aggregate_len(
+        pairs.len(),
+        pairs.iter().flat_map(|(key, value)| [key, value]),
+        protocol,
+    )
+}
+
+/// Returns the length of the RESP encoding of the value.
+fn value_len(value: &Value, protocol: ProtocolVersion) -> usize {
+    match value {
+        Value::Nil if protocol == ProtocolVersion::RESP2 => line_len(2),
+        Value::Nil => line_len(0),
+        Value::Int(value) => line_len(text_len(value)),
+        Value::BulkString(bytes) => line_len(text_len(bytes.len())) + bytes.len() + 2,
+        Value::Array(values) | Value::Set(values) => {
+            aggregate_len(values.len(), values.iter(), protocol)
+        }
+        Value::SimpleString(string) => line_len(string.len()),
+        Value::Okay => line_len(2),
+        Value::Map(pairs) => pairs_len(pairs, protocol),
+        Value::Attribute { data, attributes } => {
+            pairs_len(attributes, protocol) + value_len(data, protocol)
+        }
+        Value::Double(value) => line_len(text_len(value)),
+        Value::Boolean(_) => line_len(1),
+        Value::VerbatimString { format, text } => {
+            let len = text_len(format) + 1 + text.len();
+            line_len(text_len(len)) + len + 2
+        }
+        Value::BigNumber(value) => line_len(text_len(value)),
+        // The kind is the first element of the push, as a bulk string.
+        Value::Push { kind, data } => {
+            let kind = text_len(kind);
+            line_len(text_len(data.len() + 1))
+                + line_len(text_len(kind))
+                + kind
+                + 2
+                + data
+                    .iter()
+                    .map(|value| value_len(value, protocol))
+                    .sum::<usize>()
+        }
+        Value::Error(err) => err.code().map_or(0, |code| error_len(code, err.detail())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_redis_value;
+
+    #[test]
+    fn test_value_len_is_the_length_of_the_reply() {
+        for (reply, protocol) in [
+            (&b"$-1\r\n"[..], ProtocolVersion::RESP2),
+            (b"_\r\n", ProtocolVersion::RESP3),
+            (b":-1234\r\n", ProtocolVersion::RESP2),
+            (b"$5\r\nhello\r\n", ProtocolVersion::RESP2),
+            (b"+OK\r\n", ProtocolVersion::RESP2),
+            (b"+PONG\r\n", ProtocolVersion::RESP2),
+            (
+                b"*12\r\n:1\r\n:2\r\n:3\r\n:4\r\n:5\r\n:6\r\n:7\r\n:8\r\n:9\r\n:10\r\n$0\r\n\r\n_\r\n",
+                ProtocolVersion::RESP3,
+            ),
+            (b"%1\r\n+key\r\n~2\r\n#t\r\n#f\r\n", ProtocolVersion::RESP3),
+            (b",1.5\r\n", ProtocolVersion::RESP3),
+            (b"=15\r\ntxt:Some string\r\n", ProtocolVersion::RESP3),
+            (b"(3492890328409238509324850943850943825024385\r\n", ProtocolVersion::RESP3),
+        ] {
+            let value = parse_redis_value(reply).unwrap();
+            assert_eq!(
+                value_len(&value, protocol),
+                reply.len(),
+                "{}",
+                String::from_utf8_lossy(reply)
+            );
+        }
+    }
+
+    #[test]
+    fn test_counter_adds_up_the_attempts() {
+        let counter = RequestSizeCounter::default();
+        let mut cmd = crate::cmd("GET");
+        cmd.arg("foo");
+        counter.record(&cmd, &Ok(Value::Nil), ProtocolVersion::RESP2);
+        let moved = parse_redis_value(b"-MOVED 12182 node:6380\r\n").unwrap_err();
+        counter.record(&cmd, &Err(moved), ProtocolVersion::RESP2);
+
+        assert_eq!(
+            counter.get(),
+            RequestSize {
+                bytes_written: 2 * b"*2\r\n$3\r\nGET\r\n$3\r\nfoo\r\n".len() as u64,
+                bytes_read: (b"$-1\r\n".len() + b"-MOVED 12182 node:6380\r\n".len()) as u64,
+            }
+        );
+    }
+}
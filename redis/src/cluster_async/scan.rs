@@ -0,0 +1,81 @@
+use std::pin::Pin;
+use std::task::{self, Poll};
+
+use futures::{future::BoxFuture, ready, Stream};
+
+use super::{ClusterConnection, Connect};
+use crate::{
+    aio::{ConnectionLike, MultiplexedConnection},
+    commands::cluster_scan::{ClusterScanCursor, ClusterScanOptions},
+    RedisResult, Value,
+};
+
+type ScanFuture = BoxFuture<'static, RedisResult<(ClusterScanCursor, Vec<Value>)>>;
+
+/// A stream of the keys of all the shards of the cluster, as returned by
+/// [`ClusterConnection::scan`](super::ClusterConnection::scan) and
+/// [`ClusterConnection::scan_from`](super::ClusterConnection::scan_from).
+///
+/// Each item holds the keys that the shards returned for one concurrent round of `SCAN` calls. After an error, the
+/// stream can be polled again to retry the round, or the scan can be resumed later from [`ClusterScanStream::cursor`].
+/// As with `SCAN`, a key may be returned more than once.
+pub struct ClusterScanStream<C = MultiplexedConnection> {
+    connection: ClusterConnection<C>,
+    cursor: ClusterScanCursor,
+    options: ClusterScanOptions,
+    in_flight: Option<ScanFuture>,
+}
+
+impl<C> ClusterScanStream<C>
+where
+    C: ConnectionLike + Connect + Clone + Send + Sync + Unpin + 'static,
+{
+    pub(crate) fn new(
+        connection: ClusterConnection<C>,
+        cursor: ClusterScanCursor,
+        options: ClusterScanOptions,
+    ) -> Self {
+        Self {
+            connection,
+            cursor,
+            options,
+            in_flight: None,
+        }
+    }
+
+    /// Returns the cursor of the keys that weren't returned yet.
+    pub fn cursor(&self) -> &ClusterScanCursor {
+        &self.cursor
+    }
+}
+
+impl<C> Stream for ClusterScanStream<C>
+where
+    C: ConnectionLike + Connect + Clone + Send + Sync + Unpin + 'static,
+{
+    type Item = RedisResult<Vec<Value>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.in_flight.is_none() {
+            if this.cursor.is_finished() {
+                return Poll::Ready(None);
+            }
+            let mut connection = this.connection.clone();
+            let cursor = this.cursor.clone();
+            let options = this.options.clone();
+            this.in_flight = Some(Box::pin(async move {
+                connection
+                    .route_cluster_scan_all_shards(cursor, options)
+                    .await
+            }));
+        }
+
+        let result = ready!(this.in_flight.as_mut().unwrap().as_mut().poll(cx));
+        this.in_flight = None;
+        Poll::Ready(Some(result.map(|(cursor, keys)| {
+            this.cursor = cursor;
+            keys
+        })))
+    }
+}
@@ -0,0 +1,268 @@
+//! Resources that can be shared between several cluster clients in the same process.
+//!
+//! Processes that create many [`ClusterClient`](crate::cluster::ClusterClient) instances against the same
+//! cluster (for example, one client per tenant) repeat the same DNS lookups and TLS handshakes for every client.
+//! A [`SharedResources`] handle can be passed to each client's builder in order to share the DNS cache, the DNS
+//! resolver and the TLS session cache between them, while the connections and the cluster state of each client
+//! remain isolated.
+//!
+//! # Example
+//! ```rust,no_run
+//! use redis::cluster::ClusterClient;
+//! use redis::cluster_async::SharedResources;
+//! use std::time::Duration;
+//!
+//! let resources = SharedResources::builder()
+//!     .dns_cache_ttl(Duration::from_secs(30))
+//!     .build();
+//! let first_client = ClusterClient::builder(vec!["redis://127.0.0.1:6379/"])
+//!     .shared_resources(resources.clone())
+//!     .build()
+//!     .unwrap();
+//! let second_client = ClusterClient::builder(vec!["redis://127.0.0.1:6379/"])
+//!     .shared_resources(resources)
+//!     .build()
+//!     .unwrap();
+//! ```
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures::FutureExt;
+
+use crate::{aio::get_socket_addrs, ErrorKind, RedisError, RedisFuture, RedisResult};
+
+/// The default time for which resolved addresses are kept in the shared DNS cache.
+pub const DEFAULT_DNS_CACHE_TTL: Duration = Duration::from_secs(10);
+
+#[cfg(feature = "tls-rustls")]
+use crate::tls::{TlsResumptionStats, TlsSessionCache, DEFAULT_TLS_SESSION_CACHE_SIZE};
+
+/// A resolver used to translate a node's host into socket addresses.
+///
+/// Implement this trait in order to plug a custom resolver into [`SharedResources`].
+pub trait DnsResolver: Send + Sync {
+    /// Resolves the given host and port into a list of socket addresses.
+    fn resolve<'a>(&'a self, host: &'a str, port: u16) -> RedisFuture<'a, Vec<SocketAddr>>;
+}
+
+/// Resolves addresses using the runtime's default resolver.
+struct SystemResolver;
+
+impl DnsResolver for SystemResolver {
+    fn resolve<'a>(&'a self, host: &'a str, port: u16) -> RedisFuture<'a, Vec<SocketAddr>> {
+        async move { Ok(get_socket_addrs(host, port).await?.collect()) }.boxed()
+    }
+}
+
+struct DnsCacheEntry {
+    resolved_at: Instant,
+    addresses: Vec<SocketAddr>,
+}
+
+struct SharedResourcesInner {
+    resolver: Arc<dyn DnsResolver>,
+    dns_cache_ttl: Duration,
+    dns_cache: Mutex<HashMap<(String, u16), DnsCacheEntry>>,
+    #[cfg(feature = "tls-rustls")]
+    tls_session_cache: Arc<TlsSessionCache>,
+}
+
+/// A handle to resources that can be shared between several cluster clients.
+///
+/// Cloning the handle is cheap, and all clones refer to the same resources.
+#[derive(Clone)]
+pub struct SharedResources {
+    inner: Arc<SharedResourcesInner>,
+}
+
+impl Default for SharedResources {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SharedResources {
+    /// Creates shared resources with the default configuration.
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    /// Creates a [`SharedResourcesBuilder`] for configuring the shared resources.
+    pub fn builder() -> SharedResourcesBuilder {
+        SharedResourcesBuilder::default()
+    }
+
+    /// Removes all entries from the shared DNS cache.
+    pub fn clear_dns_cache(&self) {
+        self.inner.dns_cache.lock().unwrap().clear();
+    }
+
+    /// Resolves the host and port, returning cached addresses if they are still fresh.
+    pub(crate) async fn resolve(&self, host: &str, port: u16) -> RedisResult<Vec<SocketAddr>> {
+        let key = (host.to_string(), port);
+        if let Some(entry) = self.inner.dns_cache.lock().unwrap().get(&key) {
+            if entry.resolved_at.elapsed() < self.inner.dns_cache_ttl {
+                return Ok(entry.addresses.clone());
+            }
+        }
+
+        let addresses = self.inner.resolver.resolve(host, port).await?;
+        if addresses.is_empty() {
+            return Err(RedisError::from((
+                ErrorKind::InvalidClientConfig,
+                "No address found for host",
+            )));
+        }
+        if !self.inner.dns_cache_ttl.is_zero() {
+            self.inner.dns_cache.lock().unwrap().insert(
+                key,
+                DnsCacheEntry {
+                    resolved_at: Instant::now(),
+                    addresses: addresses.clone(),
+                },
+            );
+        }
+        Ok(addresses)
+    }
+
+    /// Removes the cached addresses of the host and port, so that the next lookup will query the resolver.
+    pub(crate) fn invalidate_dns_entry(&self, host: &str, port: u16) {
+        self.inner
+            .dns_cache
+            .lock()
+            .unwrap()
+            .remove(&(host.to_string(), port));
+    }
+
+    /// Returns the TLS session resumption statistics of all clients sharing these resources.
+    #[cfg(feature = "tls-rustls")]
+    pub fn tls_resumption_stats(&self) -> TlsResumptionStats {
+        self.inner.tls_session_cache.stats()
+    }
+
+    #[cfg(feature = "tls-rustls")]
+    pub(crate) fn tls_session_cache(&self) -> Arc<TlsSessionCache> {
+        self.inner.tls_session_cache.clone()
+    }
+}
+
+/// Used to configure and build [`SharedResources`].
+pub struct SharedResourcesBuilder {
+    resolver: Arc<dyn DnsResolver>,
+    dns_cache_ttl: Duration,
+    #[cfg(feature = "tls-rustls")]
+    tls_session_cache_size: usize,
+}
+
+impl Default for SharedResourcesBuilder {
+    fn default() -> Self {
+        Self {
+            resolver: Arc::new(SystemResolver),
+            dns_cache_ttl: DEFAULT_DNS_CACHE_TTL,
+            #[cfg(feature = "tls-rustls")]
+            tls_session_cache_size: DEFAULT_TLS_SESSION_CACHE_SIZE,
+        }
+    }
+}
+
+impl SharedResourcesBuilder {
+    /// Sets the resolver used to translate nodes' hosts into socket addresses.
+    pub fn dns_resolver(mut self, resolver: impl DnsResolver + 'static) -> SharedResourcesBuilder {
+        self.resolver = Arc::new(resolver);
+        self
+    }
+
+    /// Sets the time for which resolved addresses are cached. A zero duration disables the DNS cache.
+    pub fn dns_cache_ttl(mut self, dns_cache_ttl: Duration) -> SharedResourcesBuilder {
+        self.dns_cache_ttl = dns_cache_ttl;
+        self
+    }
+
+    /// Sets the maximal number of TLS sessions kept in the shared TLS session cache.
+    #[cfg(feature = "tls-rustls")]
+    pub fn tls_session_cache_size(mut self, size: usize) -> SharedResourcesBuilder {
+        self.tls_session_cache_size = size;
+        self
+    }
+
+    /// Creates the [`SharedResources`] from the builder's parameters.
+    pub fn build(self) -> SharedResources {
+        SharedResources {
+            inner: Arc::new(SharedResourcesInner {
+                resolver: self.resolver,
+                dns_cache_ttl: self.dns_cache_ttl,
+                dns_cache: Mutex::new(HashMap::new()),
+                #[cfg(feature = "tls-rustls")]
+                tls_session_cache: Arc::new(TlsSessionCache::new(self.tls_session_cache_size)),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingResolver {
+        calls: Arc<AtomicUsize>,
+    }
+
+    impl DnsResolver for CountingResolver {
+        fn resolve<'a>(&'a self, _host: &'a str, port: u16) -> RedisFuture<'a, Vec<SocketAddr>> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            async move { Ok(vec![SocketAddr::from(([127, 0, 0, 1], port))]) }.boxed()
+        }
+    }
+
+    fn resources_with_counter(ttl: Duration) -> (SharedResources, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let resources = SharedResources::builder()
+            .dns_resolver(CountingResolver {
+                calls: calls.clone(),
+            })
+            .dns_cache_ttl(ttl)
+            .build();
+        (resources, calls)
+    }
+
+    #[test]
+    fn test_dns_cache_is_shared_between_clones() {
+        let (resources, calls) = resources_with_counter(Duration::from_secs(60));
+        let other = resources.clone();
+
+        let first = block_on(resources.resolve("node", 6379)).unwrap();
+        let second = block_on(other.resolve("node", 6379)).unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_dns_cache_invalidation() {
+        let (resources, calls) = resources_with_counter(Duration::from_secs(60));
+
+        block_on(resources.resolve("node", 6379)).unwrap();
+        resources.invalidate_dns_entry("node", 6379);
+        block_on(resources.resolve("node", 6379)).unwrap();
+        block_on(resources.resolve("node", 6380)).unwrap();
+
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_zero_ttl_disables_dns_cache() {
+        let (resources, calls) = resources_with_counter(Duration::ZERO);
+
+        block_on(resources.resolve("node", 6379)).unwrap();
+        block_on(resources.resolve("node", 6379)).unwrap();
+
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,187 @@
+use std::{collections::HashMap, time::Instant};
+
+use crate::cluster_routing::SlotAddr;
+use crate::cluster_slotmap::SlotMap;
+
+/// A slot that was observed being migrated between nodes, either by an `ASK` redirection or by the
+/// `[slot->-node]` and `[slot-<-node]` entries of `CLUSTER NODES`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotMigration {
+    /// The address of the node that the slot is migrated from, if it's known.
+    pub source: Option<String>,
+    /// The address of the node that the slot is migrated to, if it's known.
+    pub target: Option<String>,
+    /// When the migration was last observed.
+    pub observed_at: Instant,
+}
+
+/// The slots that are being migrated between nodes, by their slot numbers.
+#[derive(Default)]
+pub(crate) struct SlotMigrations(HashMap<u16, SlotMigration>);
+
+impl SlotMigrations {
+    pub(crate) fn get(&self, slot: u16) -> Option<&SlotMigration> {
+        self.0.get(&slot)
+    }
+
+    pub(crate) fn all(&self) -> HashMap<u16, SlotMigration> {
+        self.0.clone()
+    }
+
+    /// Records an `ASK` redirection of the slot from `source` to `target`.
+    pub(crate) fn observe_ask(&mut self, slot: u16, source: &str, target: &str) {
+        self.0.insert(
+            slot,
+            SlotMigration {
+                source: Some(source.to_string()),
+                target: Some(target.to_string()),
+                observed_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Records that the slot was moved to its new owner, which completes its migration.
+    pub(crate) fn observe_moved(&mut self, slot: u16) {
+        self.0.remove(&slot);
+    }
+
+    /// Forgets the migrations whose target already serves the slot in the given slot map.
+    pub(crate) fn retain_unfinished(&mut self, slot_map: &SlotMap) {
+        self.0.retain(|slot, migration| {
+            let owner = slot_map.get_node_address_for_slot(*slot, SlotAddr::Master);
+            owner.is_none() || owner != migration.target
+        });
+    }
+
+    /// Replaces the tracked migrations with the ones that are reported by the `CLUSTER NODES` replies of the given
+    /// addresses.
+    pub(crate) fn replace_from_cluster_nodes<'a>(
+        &mut self,
+        replies: impl Iterator<Item = (&'a str, &'a str)>,
+    ) {
+        let now = Instant::now();
+        let mut migrations: HashMap<u16, SlotMigration> = HashMap::new();
+        for (address, reply) in replies {
+            for (slot, source, target) in parse_cluster_nodes_migrations(address, reply) {
+                let migration = migrations.entry(slot).or_insert(SlotMigration {
+                    source: None,
+                    target: None,
+                    observed_at: now,
+                });
+                migration.source = migration.source.take().or(source);
+                migration.target = migration.target.take().or(target);
+            }
+        }
+        self.0 = migrations;
+    }
+}
+
+/// Parses the migrating and importing slots of the node that replied with the given `CLUSTER NODES` output, as
+/// `(slot, source, target)`. The replying node is referred to by `address`, and the other nodes by the addresses in
+/// the reply.
+fn parse_cluster_nodes_migrations(
+    address: &str,
+    reply: &str,
+) -> Vec<(u16, Option<String>, Option<String>)> {
+    let lines: Vec<Vec<&str>> = reply
+        .lines()
+        .map(|line| line.split_ascii_whitespace().collect())
+        .filter(|fields: &Vec<&str>| fields.len() >= 8)
+        .collect();
+    // The address field looks like `ip:port@cport[,hostname]`.
+    let address_of = |id: &str| {
+        lines
+            .iter()
+            .find(|fields| fields[0] == id)
+            .and_then(|fields| fields[1].split('@').next())
+            .filter(|address| !address.starts_with(':'))
+            .map(str::to_string)
+    };
+
+    let Some(myself) = lines
+        .iter()
+        .find(|fields| fields[2].split(',').any(|flag| flag == "myself"))
+    else {
+        return Vec::new();
+    };
+    myself[8..]
+        .iter()
+        .filter_map(|entry| {
+            let entry = entry.strip_prefix('[')?.strip_suffix(']')?;
+            if let Some((slot, id)) = entry.split_once("->-") {
+                Some((
+                    slot.parse().ok()?,
+                    Some(address.to_string()),
+                    address_of(id),
+                ))
+            } else if let Some((slot, id)) = entry.split_once("-<-") {
+                Some((
+                    slot.parse().ok()?,
+                    address_of(id),
+                    Some(address.to_string()),
+                ))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cluster_routing::Slot;
+    use crate::cluster_slotmap::ReadFromReplicaStrategy;
+
+    const SOURCE_NODES: &str = "\
+07c37dfeb235213a872192d90877d0cd55635b91 127.0.0.1:30004@31004 slave e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca 0 1426238317239 4 connected
+e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca 127.0.0.1:30001@31001 myself,master - 0 0 1 connected 0-5460 [5460->-67ed2db8d677e59ec4a4cefb06858cf2a1a89fa1]
+67ed2db8d677e59ec4a4cefb06858cf2a1a89fa1 127.0.0.1:30002@31002 master - 0 1426238316232 2 connected 5461-10922
+";
+
+    const TARGET_NODES: &str = "\
+e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca 127.0.0.1:30001@31001 master - 0 1426238316232 1 connected 0-5460
+67ed2db8d677e59ec4a4cefb06858cf2a1a89fa1 127.0.0.1:30002@31002 myself,master - 0 0 2 connected 5461-10922 [5460-<-e7d1eecce10fd6bb5eb35b9f99a514335d9ba9ca] [100-<-unknown]
+";
+
+    #[test]
+    fn test_replace_from_cluster_nodes_merges_both_sides_of_a_migration() {
+        let mut migrations = SlotMigrations::default();
+        migrations.observe_ask(42, "127.0.0.1:30001", "127.0.0.1:30002");
+        migrations.replace_from_cluster_nodes(
+            [
+                ("127.0.0.1:30001", SOURCE_NODES),
+                ("127.0.0.1:30002", TARGET_NODES),
+            ]
+            .into_iter(),
+        );
+
+        assert!(migrations.get(42).is_none());
+        let migration = migrations.get(5460).unwrap();
+        assert_eq!(migration.source.as_deref(), Some("127.0.0.1:30001"));
+        assert_eq!(migration.target.as_deref(), Some("127.0.0.1:30002"));
+        let migration = migrations.get(100).unwrap();
+        assert_eq!(migration.source, None);
+        assert_eq!(migration.target.as_deref(), Some("127.0.0.1:30002"));
+    }
+
+    #[test]
+    fn test_migrations_complete_when_the_target_owns_the_slot() {
+        let mut migrations = SlotMigrations::default();
+        migrations.observe_ask(1, "node1:6379", "node2:6379");
+        migrations.observe_ask(2, "node1:6379", "node2:6379");
+        migrations.observe_ask(3, "node1:6379", "node2:6379");
+        migrations.observe_moved(3);
+
+        let slot_map = SlotMap::new(
+            vec![
+                Slot::new(0, 1, "node1:6379".to_owned(), vec![]),
+                Slot::new(2, 16383, "node2:6379".to_owned(), vec![]),
+            ],
+            ReadFromReplicaStrategy::AlwaysFromPrimary,
+        );
+        migrations.retain_unfinished(&slot_map);
+
+        assert_eq!(migrations.all().keys().collect::<Vec<_>>(), vec![&1]);
+    }
+}
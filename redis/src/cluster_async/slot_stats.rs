@@ -0,0 +1,77 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::cluster_topology::SLOT_SIZE;
+
+/// The numbers of requests that were sent to each slot in a sampling window, as returned by
+/// [`ClusterConnection::sample_slot_stats`](super::ClusterConnection::sample_slot_stats).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SlotStats {
+    /// The slots that were requested in the window with their numbers of requests, from the most requested slot
+    /// to the least requested one.
+    pub requests_by_slot: Vec<(u16, u64)>,
+}
+
+impl SlotStats {
+    /// Returns the `amount` most requested slots.
+    pub fn hottest(&self, amount: usize) -> &[(u16, u64)] {
+        &self.requests_by_slot[..amount.min(self.requests_by_slot.len())]
+    }
+
+    /// Returns the total number of requests in the window.
+    pub fn total_requests(&self) -> u64 {
+        self.requests_by_slot.iter().map(|(_, count)| count).sum()
+    }
+}
+
+/// A counter of the requests to each slot, which is reset whenever it's sampled.
+pub(crate) struct SlotCounters(Box<[AtomicU64]>);
+
+impl SlotCounters {
+    pub(crate) fn new() -> Self {
+        Self((0..SLOT_SIZE).map(|_| AtomicU64::new(0)).collect())
+    }
+
+    pub(crate) fn record(&self, slot: u16) {
+        if let Some(counter) = self.0.get(slot as usize) {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the requests since the previous sample, and starts a new window.
+    pub(crate) fn sample(&self) -> SlotStats {
+        let mut requests_by_slot: Vec<(u16, u64)> = self
+            .0
+            .iter()
+            .enumerate()
+            .filter_map(|(slot, counter)| match counter.swap(0, Ordering::Relaxed) {
+                0 => None,
+                count => Some((slot as u16, count)),
+            })
+            .collect();
+        requests_by_slot.sort_by(|(slot_a, count_a), (slot_b, count_b)| {
+            count_b.cmp(count_a).then(slot_a.cmp(slot_b))
+        });
+        SlotStats { requests_by_slot }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_orders_slots_by_requests_and_resets_the_counters() {
+        let counters = SlotCounters::new();
+        for slot in [5, 16383, 5, 12182, 5, 16383] {
+            counters.record(slot);
+        }
+
+        let stats = counters.sample();
+        assert_eq!(stats.requests_by_slot, vec![(5, 3), (16383, 2), (12182, 1)]);
+        assert_eq!(stats.hottest(1), &[(5, 3)]);
+        assert_eq!(stats.hottest(10).len(), 3);
+        assert_eq!(stats.total_requests(), 6);
+
+        assert_eq!(counters.sample(), SlotStats::default());
+    }
+}
@@ -0,0 +1,469 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, SystemTime},
+};
+
+use super::NodeMetadata;
+use crate::{cluster_slotmap::SlotMap, info::ReplicationRole};
+
+/// A snapshot of the cluster's topology, as returned by
+/// [`ClusterConnection::get_topology`](super::ClusterConnection::get_topology).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ClusterTopology {
+    /// The slot ranges, ordered by their first slot.
+    pub slots: Vec<TopologySlot>,
+    /// When the slots were last refreshed, or `None` if they weren't refreshed yet.
+    pub refreshed_at: Option<SystemTime>,
+}
+
+/// A range of slots, and the nodes that serve it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TopologySlot {
+    /// The first slot of the range.
+    pub start: u16,
+    /// The last slot of the range, inclusive.
+    pub end: u16,
+    /// The primary of the range.
+    pub primary: TopologyNode,
+    /// The replicas of the range.
+    pub replicas: Vec<TopologyNode>,
+}
+
+/// A node of the cluster's topology.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TopologyNode {
+    /// The address of the node, as `host:port`.
+    pub address: String,
+    /// The id of the node, if the ids are tracked with
+    /// [`ClusterClientBuilder::node_id_cache_ttl`](crate::cluster::ClusterClientBuilder::node_id_cache_ttl) and
+    /// the node's id is known.
+    pub id: Option<String>,
+}
+
+impl ClusterTopology {
+    pub(crate) fn new(
+        slot_map: &SlotMap,
+        refreshed_at: Option<SystemTime>,
+        ids_by_address: &HashMap<String, String>,
+    ) -> Self {
+        let node = |address: &String| TopologyNode {
+            address: address.clone(),
+            id: ids_by_address.get(address).cloned(),
+        };
+        let slots = slot_map
+            .slots
+            .iter()
+            .map(|(end, value)| TopologySlot {
+                start: value.start,
+                end: *end,
+                primary: node(&value.addrs.primary),
+                replicas: value.addrs.replicas.iter().map(node).collect(),
+            })
+            .collect();
+        Self {
+            slots,
+            refreshed_at,
+        }
+    }
+}
+
+/// A change of the cluster's topology, as sent to the receivers that are returned by
+/// [`ClusterConnection::topology_updates`](super::ClusterConnection::topology_updates).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TopologyEvent {
+    /// A node joined the topology.
+    NodeAdded {
+        /// The address of the node.
+        address: String,
+        /// The metadata of the node, as returned by the callback that is set by
+        /// [`ClusterClientBuilder::node_metadata_provider`](crate::cluster::ClusterClientBuilder::node_metadata_provider)
+        /// and merged with the availability zone and weight of its [`NodeOverride`](super::NodeOverride). It's
+        /// empty if no connection to the node could be made.
+        metadata: NodeMetadata,
+    },
+    /// A node left the topology.
+    NodeRemoved {
+        /// The address of the node.
+        address: String,
+    },
+    /// The node at an address was replaced by a node with a different `CLUSTER MYID`, for example after it was
+    /// restarted without its data, or its address was reused. The connections to the address are dropped and
+    /// recreated. It's only sent if the nodes' ids are tracked with
+    /// [`ClusterClientBuilder::node_id_cache_ttl`](crate::cluster::ClusterClientBuilder::node_id_cache_ttl).
+    NodeReplaced {
+        /// The address of the node.
+        address: String,
+        /// The id of the previous node.
+        previous_id: String,
+        /// The id of the new node.
+        id: String,
+    },
+    /// A range of slots moved to a new primary, for example after a failover or a resharding.
+    SlotsMoved {
+        /// The first slot of the range.
+        start: u16,
+        /// The last slot of the range, inclusive.
+        end: u16,
+        /// The address of the previous primary, or `None` if the slots weren't served before.
+        from: Option<String>,
+        /// The address of the new primary.
+        to: String,
+    },
+    /// The role of a node that was reported by the cluster was replaced by its
+    /// [`NodeOverride`](super::NodeOverride). It's sent by every refresh that applies the override.
+    NodeRoleConflict {
+        /// The address of the node.
+        address: String,
+        /// The role that the cluster reported.
+        reported: ReplicationRole,
+        /// The role of the override.
+        overridden: ReplicationRole,
+    },
+    /// The periodic topology checks have been failing for longer than the threshold that is set by
+    /// [`ClusterClientBuilder::topology_check_failure_threshold`](crate::cluster::ClusterClientBuilder::topology_check_failure_threshold).
+    /// It's sent once until a check succeeds again.
+    TopologyChecksFailing {
+        /// How long the checks have been failing.
+        failing_for: Duration,
+        /// The number of checks that failed in a row.
+        consecutive_failures: u32,
+        /// The error of the last check.
+        last_error: String,
+    },
+}
+
+/// A range of slots that moves to a new primary, as planned by a [`TopologyChangePlan`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SlotRangeMove {
+    /// The first slot of the range.
+    pub start: u16,
+    /// The last slot of the range, inclusive.
+    pub end: u16,
+    /// The address of the current primary, or `None` if the slots aren't served.
+    pub from: Option<String>,
+    /// The address of the new primary.
+    pub to: String,
+}
+
+/// The changes that applying a new topology would make, without applying them, as returned by
+/// [`ClusterTopology::plan_change`] and [`ClusterConnection::plan_slots_refresh`](super::ClusterConnection::plan_slots_refresh).
+///
+/// The addresses are sorted, and the moved ranges are ordered by their first slot.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TopologyChangePlan {
+    /// The ranges of slots whose primary changes.
+    pub slots_moved: Vec<SlotRangeMove>,
+    /// The nodes that join the topology.
+    pub nodes_added: Vec<String>,
+    /// The nodes that leave the topology.
+    pub nodes_removed: Vec<String>,
+    /// The nodes that would be connected to, because they're in the new topology without a connection.
+    pub connections_to_create: Vec<String>,
+    /// The nodes whose connections would be closed, because they aren't in the new topology.
+    pub connections_to_close: Vec<String>,
+}
+
+impl TopologyChangePlan {
+    /// Plans the change from the slot map that is replaced to the new one, given the addresses of the nodes that
+    /// have connections.
+    pub(crate) fn new<'a>(
+        old: &SlotMap,
+        new: &SlotMap,
+        connected: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        Self::between(
+            SlotRanges::from(old),
+            SlotRanges::from(new),
+            connected.into_iter().collect(),
+        )
+    }
+
+    fn between(old: SlotRanges, new: SlotRanges, connected: HashSet<&str>) -> Self {
+        let sorted = |addresses: HashSet<&&str>| {
+            let mut addresses: Vec<_> = addresses.into_iter().map(|a| a.to_string()).collect();
+            addresses.sort_unstable();
+            addresses
+        };
+        Self {
+            slots_moved: slots_moved(&old.ranges, &new.ranges),
+            nodes_added: sorted(new.nodes.difference(&old.nodes).collect()),
+            nodes_removed: sorted(old.nodes.difference(&new.nodes).collect()),
+            connections_to_create: sorted(new.nodes.difference(&connected).collect()),
+            connections_to_close: sorted(connected.difference(&new.nodes).collect()),
+        }
+    }
+
+    /// Returns whether applying the new topology changes nothing.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns the events of the change: the added nodes, with their metadata as returned by `node_metadata`, the
+    /// removed nodes, and the slot ranges whose primary changed, in this order.
+    pub(crate) fn events(
+        &self,
+        node_metadata: impl Fn(&str) -> NodeMetadata,
+    ) -> Vec<TopologyEvent> {
+        let added = self
+            .nodes_added
+            .iter()
+            .map(|address| TopologyEvent::NodeAdded {
+                address: address.clone(),
+                metadata: node_metadata(address),
+            });
+        let removed = self
+            .nodes_removed
+            .iter()
+            .map(|address| TopologyEvent::NodeRemoved {
+                address: address.clone(),
+            });
+        let moved = self
+            .slots_moved
+            .iter()
+            .map(|moved| TopologyEvent::SlotsMoved {
+                start: moved.start,
+                end: moved.end,
+                from: moved.from.clone(),
+                to: moved.to.clone(),
+            });
+        added.chain(removed).chain(moved).collect()
+    }
+}
+
+impl ClusterTopology {
+    /// Returns the changes that replacing this topology with `new` would make, assuming that every node of this
+    /// topology has a connection. This allows previewing a refresh, for example with a topology that was fetched
+    /// by a tool, before applying it.
+    pub fn plan_change(&self, new: &ClusterTopology) -> TopologyChangePlan {
+        let old = SlotRanges::from(self);
+        let connected = old.nodes.clone();
+        TopologyChangePlan::between(old, SlotRanges::from(new), connected)
+    }
+}
+
+/// The slot ranges of a topology with their primaries, ordered by their first slot, and the addresses of all of its
+/// nodes.
+struct SlotRanges<'a> {
+    ranges: Vec<(u16, u16, &'a str)>,
+    nodes: HashSet<&'a str>,
+}
+
+impl<'a> From<&'a SlotMap> for SlotRanges<'a> {
+    fn from(slot_map: &'a SlotMap) -> Self {
+        Self {
+            ranges: slot_map
+                .slots
+                .iter()
+                .map(|(end, value)| (value.start, *end, value.addrs.primary.as_str()))
+                .collect(),
+            nodes: slot_map.addresses_for_all_nodes(),
+        }
+    }
+}
+
+impl<'a> From<&'a ClusterTopology> for SlotRanges<'a> {
+    fn from(topology: &'a ClusterTopology) -> Self {
+        Self {
+            ranges: topology
+                .slots
+                .iter()
+                .map(|slot| (slot.start, slot.end, slot.primary.address.as_str()))
+                .collect(),
+            nodes: topology
+                .slots
+                .iter()
+                .flat_map(|slot| std::iter::once(&slot.primary).chain(&slot.replicas))
+                .map(|node| node.address.as_str())
+                .collect(),
+        }
+    }
+}
+
+fn slots_moved(old: &[(u16, u16, &str)], new: &[(u16, u16, &str)]) -> Vec<SlotRangeMove> {
+    let mut moves = Vec::new();
+    for &(start, end, to) in new {
+        // The first slot of the new range that wasn't compared with the old ranges yet.
+        let mut next = Some(start);
+        let first_overlap = old.partition_point(|(_, old_end, _)| *old_end < start);
+        for &(old_start, old_end, from) in &old[first_overlap..] {
+            let Some(current) = next else {
+                break;
+            };
+            if old_start > end {
+                break;
+            }
+            let overlap_start = old_start.max(current);
+            if overlap_start > current {
+                moves.push(slot_range_move(current, overlap_start - 1, None, to));
+            }
+            let overlap_end = old_end.min(end);
+            if from != to {
+                moves.push(slot_range_move(overlap_start, overlap_end, Some(from), to));
+            }
+            next = overlap_end.checked_add(1).filter(|next| *next <= end);
+        }
+        if let Some(current) = next {
+            moves.push(slot_range_move(current, end, None, to));
+        }
+    }
+    moves
+}
+
+fn slot_range_move(start: u16, end: u16, from: Option<&str>, to: &str) -> SlotRangeMove {
+    SlotRangeMove {
+        start,
+        end,
+        from: from.map(str::to_string),
+        to: to.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cluster_routing::Slot;
+    use crate::cluster_slotmap::ReadFromReplicaStrategy;
+
+    fn slot_map(slots: &[(u16, u16, &str, &[&str])]) -> SlotMap {
+        SlotMap::new(
+            slots
+                .iter()
+                .map(|(start, end, primary, replicas)| {
+                    Slot::new(
+                        *start,
+                        *end,
+                        primary.to_string(),
+                        replicas.iter().map(|replica| replica.to_string()).collect(),
+                    )
+                })
+                .collect(),
+            ReadFromReplicaStrategy::AlwaysFromPrimary,
+        )
+    }
+
+    fn topology_events(old: &SlotMap, new: &SlotMap) -> Vec<TopologyEvent> {
+        TopologyChangePlan::new(old, new, old.addresses_for_all_nodes())
+            .events(|_| NodeMetadata::new())
+    }
+
+    fn moved(start: u16, end: u16, from: Option<&str>, to: &str) -> TopologyEvent {
+        TopologyEvent::SlotsMoved {
+            start,
+            end,
+            from: from.map(str::to_string),
+            to: to.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_no_events_for_the_same_topology() {
+        let slots = slot_map(&[(0, 8191, "a:1", &["b:1"]), (8192, 16383, "c:1", &[])]);
+        let same = slot_map(&[(0, 8191, "a:1", &["b:1"]), (8192, 16383, "c:1", &[])]);
+
+        assert_eq!(topology_events(&slots, &same), vec![]);
+    }
+
+    #[test]
+    fn test_failover_moves_the_slots_of_the_shard() {
+        let old = slot_map(&[(0, 8191, "a:1", &["b:1"]), (8192, 16383, "c:1", &[])]);
+        let new = slot_map(&[(0, 8191, "b:1", &["a:1"]), (8192, 16383, "c:1", &[])]);
+
+        assert_eq!(
+            topology_events(&old, &new),
+            vec![moved(0, 8191, Some("a:1"), "b:1")]
+        );
+    }
+
+    #[test]
+    fn test_resharding_to_a_new_node() {
+        let old = slot_map(&[(0, 8191, "a:1", &[]), (8192, 16383, "c:1", &["d:1"])]);
+        let new = slot_map(&[
+            (0, 4095, "a:1", &[]),
+            (4096, 12287, "e:1", &[]),
+            (12288, 16383, "c:1", &[]),
+        ]);
+
+        assert_eq!(
+            topology_events(&old, &new),
+            vec![
+                TopologyEvent::NodeAdded {
+                    address: "e:1".to_string(),
+                    metadata: NodeMetadata::new(),
+                },
+                TopologyEvent::NodeRemoved {
+                    address: "d:1".to_string()
+                },
+                moved(4096, 8191, Some("a:1"), "e:1"),
+                moved(8192, 12287, Some("c:1"), "e:1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_slots_that_were_not_served_move_from_no_node() {
+        let old = slot_map(&[(100, 200, "a:1", &[])]);
+        let new = slot_map(&[(0, 300, "b:1", &[])]);
+
+        assert_eq!(
+            topology_events(&old, &new),
+            vec![
+                TopologyEvent::NodeAdded {
+                    address: "b:1".to_string(),
+                    metadata: NodeMetadata::new(),
+                },
+                TopologyEvent::NodeRemoved {
+                    address: "a:1".to_string()
+                },
+                moved(0, 99, None, "b:1"),
+                moved(100, 200, Some("a:1"), "b:1"),
+                moved(201, 300, None, "b:1"),
+            ]
+        );
+        assert_eq!(
+            topology_events(&SlotMap::default(), &new),
+            vec![
+                TopologyEvent::NodeAdded {
+                    address: "b:1".to_string(),
+                    metadata: NodeMetadata::new(),
+                },
+                moved(0, 300, None, "b:1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_closes_the_connections_of_the_nodes_that_left() {
+        let old = slot_map(&[(0, 8191, "a:1", &["b:1"]), (8192, 16383, "c:1", &[])]);
+        let new = slot_map(&[(0, 8191, "a:1", &[]), (8192, 16383, "c:1", &["d:1"])]);
+
+        let plan = TopologyChangePlan::new(&old, &new, ["a:1", "b:1", "e:1"]);
+        assert_eq!(plan.slots_moved, vec![]);
+        assert_eq!(plan.nodes_added, vec!["d:1"]);
+        assert_eq!(plan.nodes_removed, vec!["b:1"]);
+        assert_eq!(plan.connections_to_create, vec!["c:1", "d:1"]);
+        assert_eq!(plan.connections_to_close, vec!["b:1", "e:1"]);
+    }
+
+    #[test]
+    fn test_plan_change_between_topologies() {
+        let ids = HashMap::new();
+        let old = slot_map(&[(0, 8191, "a:1", &["b:1"]), (8192, 16383, "c:1", &[])]);
+        let new = slot_map(&[(0, 8191, "b:1", &["a:1"]), (8192, 16383, "c:1", &[])]);
+        let old = ClusterTopology::new(&old, None, &ids);
+        let new = ClusterTopology::new(&new, None, &ids);
+
+        assert!(old.plan_change(&old).is_empty());
+        let plan = old.plan_change(&new);
+        assert_eq!(
+            plan.slots_moved,
+            vec![slot_range_move(0, 8191, Some("a:1"), "b:1")]
+        );
+        assert!(plan.nodes_added.is_empty() && plan.connections_to_close.is_empty());
+    }
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+/// The bounds of the interval of the periodic topology checks, as set by
+/// [`ClusterClientBuilder::adaptive_topology_checks`](crate::cluster::ClusterClientBuilder::adaptive_topology_checks).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct AdaptiveTopologyChecks {
+    pub(crate) min: Duration,
+    pub(crate) max: Duration,
+}
+
+/// The interval between the periodic topology checks. With adaptive checks, it's halved down to the minimal interval
+/// after every period in which requests were redirected or failed to connect, and doubled up to the maximal interval
+/// after every stable period.
+#[derive(Debug)]
+pub(crate) struct TopologyCheckInterval {
+    current: Duration,
+    adaptive: Option<AdaptiveTopologyChecks>,
+}
+
+impl TopologyCheckInterval {
+    pub(crate) fn new(interval: Duration, adaptive: Option<AdaptiveTopologyChecks>) -> Self {
+        let current = match adaptive {
+            Some(AdaptiveTopologyChecks { min, max }) => interval.clamp(min, max),
+            None => interval,
+        };
+        Self { current, adaptive }
+    }
+
+    pub(crate) fn current(&self) -> Duration {
+        self.current
+    }
+
+    /// Adapts the interval to the number of redirections and connection errors of the period that just ended.
+    pub(crate) fn update(&mut self, errors: usize) {
+        let Some(AdaptiveTopologyChecks { min, max }) = self.adaptive else {
+            return;
+        };
+        self.current = if errors > 0 {
+            (self.current / 2).max(min)
+        } else {
+            self.current.saturating_mul(2).min(max)
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interval_shortens_on_errors_and_backs_off_when_stable() {
+        let mut interval = TopologyCheckInterval::new(
+            Duration::from_secs(60),
+            Some(AdaptiveTopologyChecks {
+                min: Duration::from_secs(1),
+                max: Duration::from_secs(30),
+            }),
+        );
+        assert_eq!(interval.current(), Duration::from_secs(30));
+
+        interval.update(3);
+        assert_eq!(interval.current(), Duration::from_secs(15));
+        for _ in 0..10 {
+            interval.update(1);
+        }
+        assert_eq!(interval.current(), Duration::from_secs(1));
+
+        interval.update(0);
+        assert_eq!(interval.current(), Duration::from_secs(2));
+        for _ in 0..10 {
+            interval.update(0);
+        }
+        assert_eq!(interval.current(), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_interval_is_fixed_without_adaptive_checks() {
+        let mut interval = TopologyCheckInterval::new(Duration::from_secs(60), None);
+        interval.update(5);
+        interval.update(0);
+        assert_eq!(interval.current(), Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,111 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::RedisError;
+
+/// The outcomes of the periodic topology checks, as returned by
+/// [`ClusterConnection::topology_check_stats`](super::ClusterConnection::topology_check_stats).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TopologyCheckStats {
+    /// When a check last succeeded, or `None` if none succeeded yet.
+    pub last_success: Option<SystemTime>,
+    /// The number of checks that failed since the last successful one.
+    pub consecutive_failures: u32,
+    /// The error of the last check, if it failed.
+    pub last_error: Option<RedisError>,
+}
+
+/// Tracks the outcomes of the periodic topology checks, and whether they have been failing for longer than the
+/// threshold that is set by
+/// [`ClusterClientBuilder::topology_check_failure_threshold`](crate::cluster::ClusterClientBuilder::topology_check_failure_threshold).
+#[derive(Default)]
+pub(crate) struct TopologyCheckStatus {
+    stats: TopologyCheckStats,
+    failing_since: Option<Instant>,
+    reported: bool,
+}
+
+impl TopologyCheckStatus {
+    pub(crate) fn stats(&self) -> TopologyCheckStats {
+        self.stats.clone()
+    }
+
+    pub(crate) fn record_success(&mut self) {
+        self.stats = TopologyCheckStats {
+            last_success: Some(SystemTime::now()),
+            consecutive_failures: 0,
+            last_error: None,
+        };
+        self.failing_since = None;
+        self.reported = false;
+    }
+
+    /// Records a failed check, and returns for how long the checks have been failing if that's longer than
+    /// `threshold` for the first time since they last succeeded.
+    pub(crate) fn record_failure(
+        &mut self,
+        err: RedisError,
+        now: Instant,
+        threshold: Option<Duration>,
+    ) -> Option<Duration> {
+        self.stats.consecutive_failures = self.stats.consecutive_failures.saturating_add(1);
+        self.stats.last_error = Some(err);
+        let failing_for = now.saturating_duration_since(*self.failing_since.get_or_insert(now));
+        let threshold = threshold?;
+        if self.reported || failing_for <= threshold {
+            return None;
+        }
+        self.reported = true;
+        Some(failing_for)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ErrorKind;
+
+    #[test]
+    fn test_failures_are_reported_once_they_exceed_the_threshold() {
+        let mut status = TopologyCheckStatus::default();
+        let err = || RedisError::from((ErrorKind::IoError, "check failed"));
+        let threshold = Some(Duration::from_secs(10));
+        let start = Instant::now();
+
+        assert_eq!(status.record_failure(err(), start, threshold), None);
+        assert_eq!(
+            status.record_failure(err(), start + Duration::from_secs(10), threshold),
+            None
+        );
+        assert_eq!(
+            status.record_failure(err(), start + Duration::from_secs(11), threshold),
+            Some(Duration::from_secs(11))
+        );
+        assert_eq!(
+            status.record_failure(err(), start + Duration::from_secs(20), threshold),
+            None
+        );
+        let stats = status.stats();
+        assert_eq!(stats.consecutive_failures, 4);
+        assert_eq!(stats.last_error, Some(err()));
+        assert_eq!(stats.last_success, None);
+
+        status.record_success();
+        let stats = status.stats();
+        assert_eq!(stats.consecutive_failures, 0);
+        assert_eq!(stats.last_error, None);
+        assert!(stats.last_success.is_some());
+
+        // A new streak of failures is reported again, and never without a threshold.
+        assert_eq!(status.record_failure(err(), start, threshold), None);
+        assert_eq!(
+            status.record_failure(err(), start + Duration::from_secs(11), threshold),
+            Some(Duration::from_secs(11))
+        );
+        status.record_success();
+        assert_eq!(status.record_failure(err(), start, None), None);
+        assert_eq!(
+            status.record_failure(err(), start + Duration::from_secs(60), None),
+            None
+        );
+    }
+}
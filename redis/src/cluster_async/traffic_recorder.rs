@@ -0,0 +1,656 @@
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tracing::warn;
+
+use crate::aio::ConnectionLike;
+use crate::cluster_routing::{command_keys, Routable, SlotAddr};
+use crate::cluster_topology::{get_slot, SLOT_SIZE};
+use crate::types::{ErrorKind, RedisResult, Value};
+use crate::Cmd;
+
+const MAGIC: &[u8; 4] = b"RRTL";
+const FORMAT_VERSION: u8 = 1;
+// The number of records that can wait for the log's writer before new ones are dropped.
+const QUEUE_CAPACITY: usize = 4096;
+
+/// An argument of a recorded command.
+///
+/// The commands are redacted when they're recorded: the command's name, its subcommand and the keywords that the
+/// command is known to take are kept, keys are replaced by their slots, and all the other arguments, including
+/// numbers, are replaced by their lengths.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordedArg {
+    /// An argument that was kept as is.
+    Plain(Vec<u8>),
+    /// A key, of which only the slot was kept.
+    Key {
+        /// The slot of the key.
+        slot: u16,
+    },
+    /// An argument that was redacted.
+    Redacted {
+        /// The length of the argument.
+        len: u32,
+    },
+}
+
+/// How a recorded command was routed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordedRouting {
+    /// To a random node.
+    Random,
+    /// To a node of a slot.
+    Slot {
+        /// The slot of the command.
+        slot: u16,
+        /// Which of the slot's nodes the command was routed to.
+        slot_addr: SlotAddr,
+    },
+    /// To an explicitly given node.
+    Address,
+    /// To the node of a `MOVED` or `ASK` redirection.
+    Redirect {
+        /// Whether the redirection was an `ASK`, rather than a `MOVED`.
+        ask: bool,
+    },
+    /// To all the nodes.
+    AllNodes,
+    /// To all the primaries.
+    AllPrimaries,
+    /// To the nodes of the slots of the command's keys.
+    MultiSlot,
+}
+
+/// A routed command, as recorded by a [`TrafficRecorder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrafficRecord {
+    /// When the command was routed.
+    pub started_at: SystemTime,
+    /// How long the command took, including the connection to its node.
+    pub elapsed: Duration,
+    /// The redacted arguments of the command.
+    pub args: Vec<RecordedArg>,
+    /// The routing of the command.
+    pub routing: RecordedRouting,
+    /// The address of the node that the command was sent to, if it was sent to a single node that was found.
+    pub node: Option<String>,
+    /// The outcome of the command, or the kind of the error that it failed with.
+    pub outcome: Result<(), ErrorKind>,
+}
+
+impl TrafficRecord {
+    pub(crate) fn redact_args(cmd: &Cmd) -> Vec<RecordedArg> {
+        let keys = command_keys(cmd);
+        // `Routable::command` returns the command with its subcommand for the commands that have subcommands.
+        let name = cmd.command().unwrap_or_default();
+        let name_len = if name.contains(&b' ') { 2 } else { 1 };
+        let keywords = command_keywords(&name);
+        cmd.args_iter()
+            .enumerate()
+            .map(|(idx, arg)| {
+                let arg = match arg {
+                    crate::cmd::Arg::Simple(arg) => arg,
+                    crate::cmd::Arg::Cursor => return RecordedArg::Plain(b"0".to_vec()),
+                };
+                if idx < name_len {
+                    RecordedArg::Plain(arg.to_vec())
+                } else if keys.contains(&arg) {
+                    RecordedArg::Key {
+                        slot: get_slot(arg),
+                    }
+                } else if let Some(keyword) = keywords
+                    .iter()
+                    .find(|keyword| keyword.eq_ignore_ascii_case(arg))
+                {
+                    RecordedArg::Plain(keyword.to_vec())
+                } else {
+                    RecordedArg::Redacted {
+                        len: arg.len() as u32,
+                    }
+                }
+            })
+            .collect()
+    }
+
+    fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let started_at = self
+            .started_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        writer.write_all(&(started_at.as_micros() as u64).to_le_bytes())?;
+        writer.write_all(&(self.elapsed.as_micros() as u64).to_le_bytes())?;
+        match self.routing {
+            RecordedRouting::Random => writer.write_all(&[0])?,
+            RecordedRouting::Slot { slot, slot_addr } => {
+                writer.write_all(&[1])?;
+                writer.write_all(&slot.to_le_bytes())?;
+                writer.write_all(&[slot_addr_to_byte(slot_addr)])?;
+            }
+            RecordedRouting::Address => writer.write_all(&[2])?,
+            RecordedRouting::Redirect { ask } => writer.write_all(&[3, ask as u8])?,
+            RecordedRouting::AllNodes => writer.write_all(&[4])?,
+            RecordedRouting::AllPrimaries => writer.write_all(&[5])?,
+            RecordedRouting::MultiSlot => writer.write_all(&[6])?,
+        }
+        match &self.node {
+            Some(node) => {
+                writer.write_all(&[1])?;
+                write_bytes(writer, node.as_bytes())?;
+            }
+            None => writer.write_all(&[0])?,
+        }
+        match self.outcome {
+            Ok(()) => writer.write_all(&[0])?,
+            Err(kind) => {
+                writer.write_all(&[1])?;
+                writer.write_all(&kind.code().to_le_bytes())?;
+            }
+        }
+        writer.write_all(&(self.args.len() as u32).to_le_bytes())?;
+        for arg in &self.args {
+            match arg {
+                RecordedArg::Plain(arg) => {
+                    writer.write_all(&[0])?;
+                    write_bytes(writer, arg)?;
+                }
+                RecordedArg::Key { slot } => {
+                    writer.write_all(&[1])?;
+                    writer.write_all(&slot.to_le_bytes())?;
+                }
+                RecordedArg::Redacted { len } => {
+                    writer.write_all(&[2])?;
+                    writer.write_all(&len.to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Returns `None` at the end of the log.
+    fn read_from(reader: &mut impl Read) -> io::Result<Option<Self>> {
+        let mut started_at = [0; 8];
+        match reader.read_exact(&mut started_at) {
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            result => result?,
+        }
+        let started_at = UNIX_EPOCH + Duration::from_micros(u64::from_le_bytes(started_at));
+        let elapsed = Duration::from_micros(read_u64(reader)?);
+        let routing = match read_u8(reader)? {
+            0 => RecordedRouting::Random,
+            1 => RecordedRouting::Slot {
+                slot: read_u16(reader)?,
+                slot_addr: slot_addr_from_byte(read_u8(reader)?)?,
+            },
+            2 => RecordedRouting::Address,
+            3 => RecordedRouting::Redirect {
+                ask: read_u8(reader)? != 0,
+            },
+            4 => RecordedRouting::AllNodes,
+            5 => RecordedRouting::AllPrimaries,
+            6 => RecordedRouting::MultiSlot,
+            tag => return Err(invalid_data("routing", tag)),
+        };
+        let node = match read_u8(reader)? {
+            0 => None,
+            _ => Some(
+                String::from_utf8(read_bytes(reader)?)
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?,
+            ),
+        };
+        let outcome = match read_u8(reader)? {
+            0 => Ok(()),
+            _ => {
+                let code = read_u32(reader)?;
+                Err(ErrorKind::from_code(code).ok_or_else(|| invalid_data("error code", code))?)
+            }
+        };
+        let arg_count = read_u32(reader)?;
+        let args = (0..arg_count)
+            .map(|_| match read_u8(reader)? {
+                0 => Ok(RecordedArg::Plain(read_bytes(reader)?)),
+                1 => Ok(RecordedArg::Key {
+                    slot: read_u16(reader)?,
+                }),
+                2 => Ok(RecordedArg::Redacted {
+                    len: read_u32(reader)?,
+                }),
+                tag => Err(invalid_data("argument", tag)),
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Some(TrafficRecord {
+            started_at,
+            elapsed,
+            args,
+            routing,
+            node,
+            outcome,
+        }))
+    }
+}
+
+/// Records the commands that a cluster connection routes to a compact binary log, as set by
+/// [`ClusterClientBuilder::traffic_recorder`](crate::cluster::ClusterClientBuilder::traffic_recorder).
+///
+/// Each attempt to send a command is recorded, with its redacted arguments (see [`RecordedArg`]), its routing, the
+/// node that it was sent to, how long it took and its outcome, so a command that was redirected or retried is
+/// recorded once per attempt. The commands of pipelines aren't recorded.
+///
+/// The records are written by a dedicated thread, so that writing the log doesn't block the connection. A record
+/// is dropped if too many are waiting for the thread, and a failure to write the log is logged, neither of which
+/// fails the commands. The thread flushes the log and stops once the recorder and all its clones are dropped.
+///
+/// The log can be read back with [`read_traffic_log`], and replayed with a [`TrafficReplayer`].
+#[derive(Clone)]
+pub struct TrafficRecorder {
+    sender: SyncSender<WriterMessage>,
+}
+
+enum WriterMessage {
+    Record(Box<TrafficRecord>),
+    Flush(mpsc::Sender<io::Result<()>>),
+}
+
+impl TrafficRecorder {
+    /// Creates a recorder that writes the log to `writer`.
+    pub fn new(writer: impl Write + Send + 'static) -> io::Result<Self> {
+        let mut writer = writer;
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[FORMAT_VERSION])?;
+        let (sender, receiver) = mpsc::sync_channel(QUEUE_CAPACITY);
+        std::thread::Builder::new()
+            .name("redis-traffic-recorder".to_string())
+            .spawn(move || write_records(writer, receiver))?;
+        Ok(Self { sender })
+    }
+
+    /// Creates a recorder that writes the log to a new file at `path`, replacing the file if it exists.
+    pub fn create_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::new(BufWriter::new(std::fs::File::create(path)?))
+    }
+
+    /// Waits for the records that were recorded before the call to be written, and flushes the log's writer.
+    pub fn flush(&self) -> io::Result<()> {
+        let (sender, receiver) = mpsc::channel();
+        let stopped = || io::Error::new(io::ErrorKind::BrokenPipe, "The log's writer stopped");
+        self.sender
+            .send(WriterMessage::Flush(sender))
+            .map_err(|_| stopped())?;
+        receiver.recv().map_err(|_| stopped())?
+    }
+
+    pub(crate) fn record(&self, record: TrafficRecord) {
+        match self
+            .sender
+            .try_send(WriterMessage::Record(Box::new(record)))
+        {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                warn!("Dropped a routed command's record, since the log's writer is behind")
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                warn!("Dropped a routed command's record, since the log's writer stopped")
+            }
+        }
+    }
+}
+
+fn write_records(mut writer: impl Write, receiver: Receiver<WriterMessage>) {
+    for message in receiver {
+        match message {
+            WriterMessage::Record(record) => {
+                if let Err(err) = record.write_to(&mut writer) {
+                    warn!("Failed to record a routed command: {err}");
+                }
+            }
+            WriterMessage::Flush(sender) => {
+                let _ = sender.send(writer.flush());
+            }
+        }
+    }
+    if let Err(err) = writer.flush() {
+        warn!("Failed to flush the routed traffic log: {err}");
+    }
+}
+
+/// Reads the records of a log that was written by a [`TrafficRecorder`].
+pub fn read_traffic_log(mut reader: impl Read) -> io::Result<Vec<TrafficRecord>> {
+    let mut header = [0; 5];
+    reader.read_exact(&mut header)?;
+    if &header[..4] != MAGIC || header[4] != FORMAT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Not a routed traffic log of a supported version",
+        ));
+    }
+    std::iter::from_fn(|| TrafficRecord::read_from(&mut reader).transpose()).collect()
+}
+
+/// Re-issues recorded commands, for example against a test cluster to reproduce a routing bug.
+///
+/// The keys of the recorded commands are replaced by keys of the same slots, and the redacted arguments by
+/// placeholders of the same lengths, so the replayed commands are routed like the recorded ones, but they may fail
+/// where the recorded ones succeeded, for example if a redacted argument had to be a number.
+pub struct TrafficReplayer {
+    keys_by_slot: Vec<Vec<u8>>,
+}
+
+impl TrafficReplayer {
+    /// Creates a replayer.
+    pub fn new() -> Self {
+        let mut keys_by_slot = vec![Vec::new(); SLOT_SIZE as usize];
+        let mut missing = keys_by_slot.len();
+        let mut candidates = 0u64..;
+        while missing > 0 {
+            let key = format!("replay:{}", candidates.next().unwrap()).into_bytes();
+            let entry = &mut keys_by_slot[get_slot(&key) as usize];
+            if entry.is_empty() {
+                *entry = key;
+                missing -= 1;
+            }
+        }
+        Self { keys_by_slot }
+    }
+
+    /// Returns the command that replays `record`.
+    pub fn command(&self, record: &TrafficRecord) -> Cmd {
+        let mut cmd = Cmd::new();
+        for arg in &record.args {
+            match arg {
+                RecordedArg::Plain(arg) => cmd.arg(&arg[..]),
+                RecordedArg::Key { slot } => cmd.arg(&self.keys_by_slot[*slot as usize][..]),
+                RecordedArg::Redacted { len } => cmd.arg(vec![b'x'; *len as usize]),
+            };
+        }
+        cmd
+    }
+
+    /// Re-issues the commands of `records` on `connection` one after the other, and returns their results.
+    ///
+    /// The commands are routed by `connection`, rather than by their recorded routings.
+    pub async fn replay<C>(
+        &self,
+        records: &[TrafficRecord],
+        connection: &mut C,
+    ) -> Vec<RedisResult<Value>>
+    where
+        C: ConnectionLike,
+    {
+        let mut results = Vec::with_capacity(records.len());
+        for record in records {
+            results.push(connection.req_packed_command(&self.command(record)).await);
+        }
+        results
+    }
+}
+
+impl Default for TrafficReplayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Returns the keywords that `command`, as returned by `Routable::command`, is known to take, which are the only
+// arguments other than the command's name that are recorded as they are.
+fn command_keywords(command: &[u8]) -> &'static [&'static [u8]] {
+    match command {
+        b"SET" => &[
+            b"NX", b"XX", b"GET", b"EX", b"PX", b"EXAT", b"PXAT", b"KEEPTTL",
+        ],
+        b"GETEX" => &[b"EX", b"PX", b"EXAT", b"PXAT", b"PERSIST"],
+        b"EXPIRE" | b"PEXPIRE" | b"EXPIREAT" | b"PEXPIREAT" => &[b"NX", b"XX", b"GT", b"LT"],
+        b"ZADD" => &[b"NX", b"XX", b"GT", b"LT", b"CH", b"INCR"],
+        b"ZRANGE" => &[b"BYSCORE", b"BYLEX", b"REV", b"LIMIT", b"WITHSCORES"],
+        b"ZRANGEBYSCORE" | b"ZREVRANGEBYSCORE" => &[b"WITHSCORES", b"LIMIT"],
+        b"ZRANGEBYLEX" | b"ZREVRANGEBYLEX" => &[b"LIMIT"],
+        b"ZUNIONSTORE" | b"ZINTERSTORE" | b"ZUNION" | b"ZINTER" => &[
+            b"WEIGHTS",
+            b"AGGREGATE",
+            b"SUM",
+            b"MIN",
+            b"MAX",
+            b"WITHSCORES",
+        ],
+        b"SCAN" | b"SSCAN" | b"HSCAN" | b"ZSCAN" => &[b"MATCH", b"COUNT", b"TYPE"],
+        b"XADD" => &[b"NOMKSTREAM", b"MAXLEN", b"MINID", b"LIMIT"],
+        b"XTRIM" => &[b"MAXLEN", b"MINID", b"LIMIT"],
+        b"XREAD" => &[b"COUNT", b"BLOCK", b"STREAMS"],
+        b"XREADGROUP" => &[b"GROUP", b"COUNT", b"BLOCK", b"NOACK", b"STREAMS"],
+        b"XCLAIM" => &[b"IDLE", b"TIME", b"RETRYCOUNT", b"FORCE", b"JUSTID"],
+        b"XAUTOCLAIM" => &[b"COUNT", b"JUSTID"],
+        b"XPENDING" => &[b"IDLE"],
+        b"LPOS" => &[b"RANK", b"COUNT", b"MAXLEN"],
+        b"LINSERT" => &[b"BEFORE", b"AFTER"],
+        b"LMOVE" | b"BLMOVE" => &[b"LEFT", b"RIGHT"],
+        b"BITCOUNT" | b"BITPOS" => &[b"BYTE", b"BIT"],
+        b"SORT" | b"SORT_RO" => &[b"BY", b"LIMIT", b"GET", b"ASC", b"DESC", b"ALPHA", b"STORE"],
+        b"RESTORE" => &[b"REPLACE", b"ABSTTL", b"IDLETIME", b"FREQ"],
+        b"COPY" => &[b"DB", b"REPLACE"],
+        b"MIGRATE" => &[b"COPY", b"REPLACE", b"AUTH", b"AUTH2", b"KEYS"],
+        b"GEOADD" => &[b"NX", b"XX", b"CH"],
+        b"GEOSEARCH" | b"GEOSEARCHSTORE" => &[
+            b"FROMMEMBER",
+            b"FROMLONLAT",
+            b"BYRADIUS",
+            b"BYBOX",
+            b"M",
+            b"KM",
+            b"FT",
+            b"MI",
+            b"ASC",
+            b"DESC",
+            b"COUNT",
+            b"ANY",
+            b"WITHCOORD",
+            b"WITHDIST",
+            b"WITHHASH",
+            b"STOREDIST",
+        ],
+        b"FLUSHALL" | b"FLUSHDB" | b"SCRIPT FLUSH" | b"FUNCTION FLUSH" => &[b"ASYNC", b"SYNC"],
+        b"CLIENT KILL" => &[
+            b"ID", b"TYPE", b"USER", b"ADDR", b"LADDR", b"SKIPME", b"MAXAGE",
+        ],
+        b"CLUSTER SETSLOT" => &[b"IMPORTING", b"MIGRATING", b"NODE", b"STABLE"],
+        _ => &[],
+    }
+}
+
+fn slot_addr_to_byte(slot_addr: SlotAddr) -> u8 {
+    match slot_addr {
+        SlotAddr::Master => 0,
+        SlotAddr::ReplicaOptional => 1,
+        SlotAddr::ReplicaRequired => 2,
+        SlotAddr::Nearest => 3,
+    }
+}
+
+fn slot_addr_from_byte(byte: u8) -> io::Result<SlotAddr> {
+    match byte {
+        0 => Ok(SlotAddr::Master),
+        1 => Ok(SlotAddr::ReplicaOptional),
+        2 => Ok(SlotAddr::ReplicaRequired),
+        3 => Ok(SlotAddr::Nearest),
+        _ => Err(invalid_data("slot address", byte)),
+    }
+}
+
+fn invalid_data(what: &str, value: impl std::fmt::Display) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid {what} in the routed traffic log: {value}"),
+    )
+}
+
+fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(bytes)
+}
+
+fn read_bytes(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![0; read_u32(reader)? as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut bytes = [0; 1];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+fn read_u16(reader: &mut impl Read) -> io::Result<u16> {
+    let mut bytes = [0; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_commands_are_redacted() {
+        let args = TrafficRecord::redact_args(
+            cmd("SET")
+                .arg("user:{42}")
+                .arg("secret value")
+                .arg("EX")
+                .arg(10),
+        );
+        assert_eq!(
+            args,
+            vec![
+                RecordedArg::Plain(b"SET".to_vec()),
+                RecordedArg::Key {
+                    slot: get_slot(b"user:{42}")
+                },
+                RecordedArg::Redacted { len: 12 },
+                RecordedArg::Plain(b"EX".to_vec()),
+                RecordedArg::Redacted { len: 2 },
+            ]
+        );
+
+        let args = TrafficRecord::redact_args(cmd("config").arg("set").arg("maxmemory").arg("1mb"));
+        assert_eq!(
+            args,
+            vec![
+                RecordedArg::Plain(b"config".to_vec()),
+                RecordedArg::Plain(b"set".to_vec()),
+                RecordedArg::Redacted { len: 9 },
+                RecordedArg::Redacted { len: 3 },
+            ]
+        );
+
+        // Integers and uppercase arguments are only kept if they're keywords of the command.
+        let args = TrafficRecord::redact_args(
+            cmd("ZADD")
+                .arg("scores")
+                .arg("gt")
+                .arg(12345)
+                .arg("SECRET")
+                .arg("CH"),
+        );
+        assert_eq!(
+            args,
+            vec![
+                RecordedArg::Plain(b"ZADD".to_vec()),
+                RecordedArg::Key {
+                    slot: get_slot(b"scores")
+                },
+                RecordedArg::Plain(b"GT".to_vec()),
+                RecordedArg::Redacted { len: 5 },
+                RecordedArg::Redacted { len: 6 },
+                RecordedArg::Plain(b"CH".to_vec()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_log_round_trip() {
+        let records = vec![
+            TrafficRecord {
+                started_at: UNIX_EPOCH + Duration::from_micros(1_700_000_000_000_000),
+                elapsed: Duration::from_micros(250),
+                args: TrafficRecord::redact_args(cmd("GET").arg("foo")),
+                routing: RecordedRouting::Slot {
+                    slot: get_slot(b"foo"),
+                    slot_addr: SlotAddr::ReplicaOptional,
+                },
+                node: Some("node1:6379".to_string()),
+                outcome: Ok(()),
+            },
+            TrafficRecord {
+                started_at: UNIX_EPOCH + Duration::from_micros(1_700_000_000_000_300),
+                elapsed: Duration::from_micros(1_000),
+                args: TrafficRecord::redact_args(&cmd("FLUSHALL")),
+                routing: RecordedRouting::AllPrimaries,
+                node: None,
+                outcome: Err(ErrorKind::IoError),
+            },
+        ];
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let recorder = TrafficRecorder::new(SharedBuffer(log.clone())).unwrap();
+        for record in &records {
+            recorder.record(record.clone());
+        }
+        recorder.flush().unwrap();
+
+        let log = log.lock().unwrap();
+        assert_eq!(read_traffic_log(&log[..]).unwrap(), records);
+        assert!(read_traffic_log(&log[1..]).is_err());
+    }
+
+    #[test]
+    fn test_replayed_commands_are_routed_to_the_recorded_slots() {
+        let recorded = cmd("MSET")
+            .arg("{user}:1")
+            .arg("a")
+            .arg("{user}:2")
+            .arg("b")
+            .clone();
+        let record = TrafficRecord {
+            started_at: UNIX_EPOCH,
+            elapsed: Duration::ZERO,
+            args: TrafficRecord::redact_args(&recorded),
+            routing: RecordedRouting::Random,
+            node: None,
+            outcome: Ok(()),
+        };
+
+        let replayed = TrafficReplayer::new().command(&record);
+        let keys = command_keys(&replayed);
+        assert_eq!(keys.len(), 2);
+        for key in keys {
+            assert_eq!(get_slot(key), get_slot(b"{user}"));
+        }
+        assert_eq!(replayed.arg_idx(2), Some(&b"x"[..]));
+    }
+
+    struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
@@ -1,4 +1,6 @@
+use crate::cluster_routing::Routable;
 use crate::cluster_slotmap::ReadFromReplicaStrategy;
+use crate::cluster_topology::{AddressResolver, TopologyCommand};
 #[cfg(feature = "cluster-async")]
 use crate::cluster_topology::{
     DEFAULT_SLOTS_REFRESH_MAX_JITTER_MILLI, DEFAULT_SLOTS_REFRESH_WAIT_DURATION,
@@ -7,7 +9,10 @@ use crate::connection::{ConnectionAddr, ConnectionInfo, IntoConnectionInfo};
 use crate::types::{ErrorKind, ProtocolVersion, RedisError, RedisResult};
 use crate::{cluster, cluster::TlsMode};
 use crate::{PubSubSubscriptionInfo, PushInfo};
-use rand::Rng;
+use rand::{seq::SliceRandom, Rng};
+#[cfg(feature = "cluster-async")]
+use std::collections::HashMap;
+use std::collections::HashSet;
 #[cfg(feature = "cluster-async")]
 use std::ops::Add;
 use std::time::Duration;
@@ -18,11 +23,18 @@ use crate::tls::TlsConnParams;
 #[cfg(not(feature = "tls-rustls"))]
 use crate::connection::TlsConnParams;
 
+#[cfg(feature = "cluster-async")]
+use crate::caching::{self, CacheConfig, CachedConnection};
 #[cfg(feature = "cluster-async")]
 use crate::cluster_async;
 
 #[cfg(feature = "tls-rustls")]
-use crate::tls::{retrieve_tls_certificates, TlsCertificates};
+use crate::tls::{
+    retrieve_tls_certificates, CallbackCertVerifier, ServerCertVerifierCallback, TlsCertificates,
+    TlsResumptionStats, TlsSessionCache, DEFAULT_TLS_SESSION_CACHE_SIZE,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use tokio::sync::mpsc;
 
@@ -37,12 +49,79 @@ struct BuilderParams {
     tls: Option<TlsMode>,
     #[cfg(feature = "tls-rustls")]
     certs: Option<TlsCertificates>,
+    #[cfg(feature = "tls-rustls")]
+    tls_session_resumption: bool,
+    #[cfg(feature = "tls-rustls")]
+    tls_alpn_protocols: Vec<Vec<u8>>,
+    #[cfg(feature = "tls-rustls")]
+    tls_server_cert_verifier: Option<Arc<CallbackCertVerifier>>,
     retries_configuration: RetryParams,
     connection_timeout: Option<Duration>,
+    topology_command: TopologyCommand,
+    address_resolver: Option<AddressResolver>,
+    transport_mapping: Option<cluster::TransportMapping>,
+    strict_routing: bool,
+    command_filter: Option<CommandFilter>,
+    #[cfg(feature = "traffic-recorder")]
+    traffic_recorder: Option<cluster_async::TrafficRecorder>,
+    shuffle_initial_nodes: bool,
+    rotate_initial_nodes: bool,
     #[cfg(feature = "cluster-async")]
     topology_checks_interval: Option<Duration>,
     #[cfg(feature = "cluster-async")]
+    adaptive_topology_checks: Option<cluster_async::AdaptiveTopologyChecks>,
+    #[cfg(feature = "cluster-async")]
+    topology_check_failure_threshold: Option<Duration>,
+    #[cfg(feature = "cluster-async")]
     slots_refresh_rate_limit: SlotsRefreshRateLimit,
+    #[cfg(feature = "cluster-async")]
+    shared_resources: Option<cluster_async::SharedResources>,
+    #[cfg(feature = "cluster-async")]
+    pubsub_health_check_interval: Option<Duration>,
+    #[cfg(feature = "cluster-async")]
+    pubsub_dead_connection_callback: Option<cluster_async::PubSubDeadConnectionCallback>,
+    #[cfg(feature = "cluster-async")]
+    health_check_freshness: Option<Duration>,
+    #[cfg(feature = "cluster-async")]
+    health_check_probe: cluster_async::HealthCheckProbe,
+    #[cfg(feature = "cluster-async")]
+    node_id_cache_ttl: Option<Duration>,
+    #[cfg(feature = "cluster-async")]
+    initial_slots_refresh: cluster_async::InitialSlotsRefresh,
+    #[cfg(feature = "cluster-async")]
+    min_initial_connections: cluster_async::MinInitialConnections,
+    #[cfg(feature = "cluster-async")]
+    node_metadata_provider: Option<cluster_async::NodeMetadataProvider>,
+    #[cfg(feature = "cluster-async")]
+    cache_config: Option<CacheConfig>,
+    #[cfg(feature = "cluster-async")]
+    pipeline_chunking: crate::PipelineChunking,
+    #[cfg(feature = "cluster-async")]
+    split_cross_slot_pipelines: bool,
+    #[cfg(feature = "cluster-async")]
+    slot_stats: bool,
+    #[cfg(feature = "cluster-async")]
+    unpause_paused_nodes: bool,
+    #[cfg(feature = "cluster-async")]
+    flush_policy: crate::aio::FlushPolicy,
+    #[cfg(feature = "cluster-async")]
+    connection_pool_size: usize,
+    #[cfg(feature = "cluster-async")]
+    tenant_weights: HashMap<String, u32>,
+    #[cfg(feature = "cluster-async")]
+    max_concurrent_connects: Option<usize>,
+    #[cfg(feature = "cluster-async")]
+    request_timeout: Option<Duration>,
+    #[cfg(feature = "cluster-async")]
+    max_pending_requests: Option<usize>,
+    #[cfg(feature = "cluster-async")]
+    max_argument_size: Option<usize>,
+    #[cfg(feature = "cluster-async")]
+    max_reply_size: Option<usize>,
+    #[cfg(feature = "cluster-async")]
+    node_overrides: HashMap<String, cluster_async::NodeOverride>,
+    #[cfg(feature = "cluster-async")]
+    clock: cluster_async::SharedClock,
     client_name: Option<String>,
     response_timeout: Option<Duration>,
     protocol: ProtocolVersion,
@@ -56,11 +135,17 @@ pub(crate) struct RetryParams {
     min_wait_time: u64,
     exponent_base: u64,
     factor: u64,
+    pub(crate) max_reconnect_attempts: u32,
+    #[cfg(feature = "cluster-async")]
+    pub(crate) circuit_breaker: Option<cluster_async::CircuitBreakerParams>,
+    #[cfg(feature = "cluster-async")]
+    pub(crate) reconnect_backoff: Option<cluster_async::ReconnectBackoffParams>,
 }
 
 impl Default for RetryParams {
     fn default() -> Self {
         const DEFAULT_RETRIES: u32 = 16;
+        const DEFAULT_MAX_RECONNECT_ATTEMPTS: u32 = 3;
         const DEFAULT_MAX_RETRY_WAIT_TIME: u64 = 655360;
         const DEFAULT_MIN_RETRY_WAIT_TIME: u64 = 1280;
         const DEFAULT_EXPONENT_BASE: u64 = 2;
@@ -71,6 +156,11 @@ impl Default for RetryParams {
             min_wait_time: DEFAULT_MIN_RETRY_WAIT_TIME,
             exponent_base: DEFAULT_EXPONENT_BASE,
             factor: DEFAULT_FACTOR,
+            max_reconnect_attempts: DEFAULT_MAX_RECONNECT_ATTEMPTS,
+            #[cfg(feature = "cluster-async")]
+            circuit_breaker: None,
+            #[cfg(feature = "cluster-async")]
+            reconnect_backoff: None,
         }
     }
 }
@@ -122,6 +212,80 @@ impl SlotsRefreshRateLimit {
         self.interval_duration.add(duration_jitter)
     }
 }
+
+/// The commands that a cluster client is allowed to send, as set by
+/// [`ClusterClientBuilder::command_filter`].
+///
+/// The commands are matched by their names, case insensitively. A name can also be a command with its subcommand,
+/// such as `"CONFIG SET"`, to match only that subcommand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandFilter {
+    /// Only the listed commands are allowed.
+    Allow(HashSet<String>),
+    /// All the commands except the listed ones are allowed.
+    Deny(HashSet<String>),
+}
+
+impl CommandFilter {
+    /// Creates a filter that allows only the given commands.
+    pub fn allow<I, S>(commands: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        CommandFilter::Allow(Self::command_names(commands))
+    }
+
+    /// Creates a filter that allows all the commands except the given ones.
+    pub fn deny<I, S>(commands: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        CommandFilter::Deny(Self::command_names(commands))
+    }
+
+    fn command_names<I, S>(commands: I) -> HashSet<String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        commands
+            .into_iter()
+            .map(|command| command.as_ref().trim().to_ascii_uppercase())
+            .collect()
+    }
+
+    /// Returns a [`CommandNotAllowed`](ErrorKind::CommandNotAllowed) error if `routable`'s command isn't allowed.
+    pub(crate) fn check<R>(&self, routable: &R) -> RedisResult<()>
+    where
+        R: Routable + ?Sized,
+    {
+        // `Routable::command` returns the command with its subcommand for the commands that have subcommands.
+        let full_name = routable
+            .command()
+            .map(|name| String::from_utf8_lossy(&name).into_owned())
+            .unwrap_or_default();
+        let base_name = full_name.split(' ').next().unwrap_or_default();
+        let listed = |names: &HashSet<String>| {
+            names.contains(base_name) || names.contains(full_name.as_str())
+        };
+        let allowed = match self {
+            CommandFilter::Allow(names) => listed(names),
+            CommandFilter::Deny(names) => !listed(names),
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err((
+                ErrorKind::CommandNotAllowed,
+                "The command isn't allowed by the client's command filter",
+                full_name,
+            )
+                .into())
+        }
+    }
+}
 /// Redis cluster specific parameters.
 #[derive(Default, Clone)]
 #[doc(hidden)]
@@ -137,16 +301,140 @@ pub struct ClusterParams {
     #[cfg(feature = "cluster-async")]
     pub(crate) topology_checks_interval: Option<Duration>,
     #[cfg(feature = "cluster-async")]
+    pub(crate) adaptive_topology_checks: Option<cluster_async::AdaptiveTopologyChecks>,
+    #[cfg(feature = "cluster-async")]
+    pub(crate) topology_check_failure_threshold: Option<Duration>,
+    #[cfg(feature = "cluster-async")]
     pub(crate) slots_refresh_rate_limit: SlotsRefreshRateLimit,
+    #[cfg(feature = "cluster-async")]
+    pub(crate) shared_resources: Option<cluster_async::SharedResources>,
+    #[cfg(feature = "cluster-async")]
+    pub(crate) pubsub_health_check_interval: Option<Duration>,
+    #[cfg(feature = "cluster-async")]
+    pub(crate) pubsub_dead_connection_callback: Option<cluster_async::PubSubDeadConnectionCallback>,
+    /// How recent a successful response on a connection must be for the health checks to skip its `PING`.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) health_check_freshness: Option<Duration>,
+    /// The command that the health checks send to probe a connection, and the validation of its response.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) health_check_probe: cluster_async::HealthCheckProbe,
+    #[cfg(feature = "cluster-async")]
+    pub(crate) node_id_cache_ttl: Option<Duration>,
+    #[cfg(feature = "cluster-async")]
+    pub(crate) initial_slots_refresh: cluster_async::InitialSlotsRefresh,
+    #[cfg(feature = "cluster-async")]
+    pub(crate) min_initial_connections: cluster_async::MinInitialConnections,
+    #[cfg(feature = "cluster-async")]
+    pub(crate) node_metadata_provider: Option<cluster_async::NodeMetadataProvider>,
+    /// The configuration of the client-side cache. When set, the user connections enable `CLIENT TRACKING`.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) cache_config: Option<CacheConfig>,
+    /// The limits above which the pipelines that are routed to a single node are split into several chunks.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) pipeline_chunking: crate::PipelineChunking,
+    /// Whether non-atomic pipelines whose commands are in several slots are split by slot instead of failing.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) split_cross_slot_pipelines: bool,
+    /// Whether the requests to each slot are counted.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) slot_stats: bool,
+    /// Whether `CLIENT UNPAUSE` is sent to the nodes that are detected as paused.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) unpause_paused_nodes: bool,
+    /// When the node connections flush the requests that they write.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) flush_policy: crate::aio::FlushPolicy,
+    /// The number of user connections that are opened to each node. Always at least 1.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) connection_pool_size: usize,
+    /// The weights of the tenants in the scheduling of the requests, by their tags.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) tenant_weights: Arc<HashMap<String, u32>>,
+    /// Limits the concurrent connection attempts of all the connections of the client, when set.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) connect_limiter: Option<Arc<cluster_async::ConnectLimiter>>,
+    /// The time in which a request must complete, including its retries, redirects and the waits for reconnects, or
+    /// `None` for no limit. It can be overridden per request.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) request_timeout: Option<Duration>,
+    /// The maximal number of requests that wait to be sent, above which new requests fail with
+    /// [`ErrorKind::TryAgain`](crate::ErrorKind::TryAgain), or `None` for no limit.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) max_pending_requests: Option<usize>,
+    /// The size above which the arguments of the commands fail without being sent, or `None` for no limit.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) max_argument_size: Option<usize>,
+    /// The size above which the replies of the nodes fail, or `None` for no limit.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) max_reply_size: Option<usize>,
+    /// What an external source knows about the nodes, by their addresses, shared by all the connections of the
+    /// client.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) node_overrides: Arc<cluster_async::NodeOverrides>,
+    /// The source of time of the timeouts, the retry waits, the backoffs and the periodic checks.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) clock: cluster_async::SharedClock,
     pub(crate) tls_params: Option<TlsConnParams>,
+    /// A session cache kept across reconnects, used to resume TLS sessions with the nodes.
+    #[cfg(feature = "tls-rustls")]
+    pub(crate) tls_session_cache: Option<Arc<TlsSessionCache>>,
     pub(crate) client_name: Option<String>,
     pub(crate) connection_timeout: Duration,
     pub(crate) response_timeout: Duration,
+    /// The command that is sent to the nodes to discover the cluster's topology.
+    pub(crate) topology_command: TopologyCommand,
+    /// Rewrites the addresses of the nodes in the topology responses before they're connected to.
+    pub(crate) address_resolver: Option<AddressResolver>,
+    /// Maps the addresses of the nodes to the transports that are used to connect to them.
+    pub(crate) transport_mapping: Option<cluster::TransportMapping>,
+    /// Whether the commands whose routing is unknown are rejected, instead of being sent to a random node.
+    pub(crate) strict_routing: bool,
+    /// The commands that are allowed to be sent, if they're restricted.
+    pub(crate) command_filter: Option<CommandFilter>,
+    /// Records the commands that are routed by the async cluster connections.
+    #[cfg(feature = "traffic-recorder")]
+    pub(crate) traffic_recorder: Option<cluster_async::TrafficRecorder>,
     pub(crate) protocol: ProtocolVersion,
     pub(crate) pubsub_subscriptions: Option<PubSubSubscriptionInfo>,
 }
 
 impl ClusterParams {
+    /// Returns the address that the connections to `node` should connect to, if it's mapped to a custom transport.
+    pub(crate) fn mapped_transport(&self, node: &str) -> Option<ConnectionAddr> {
+        self.transport_mapping
+            .as_ref()
+            .and_then(|transport_mapping| transport_mapping(node))
+    }
+
+    /// Returns an error if the command filter doesn't allow `routable`'s command, or if one of its arguments is
+    /// larger than the maximal argument size.
+    pub(crate) fn check_command_allowed<R>(&self, routable: &R) -> RedisResult<()>
+    where
+        R: Routable + ?Sized,
+    {
+        if let Some(command_filter) = &self.command_filter {
+            command_filter.check(routable)?;
+        }
+        #[cfg(feature = "cluster-async")]
+        if let Some(max_argument_size) = self.max_argument_size {
+            let too_large = (0..)
+                .map_while(|idx| routable.arg_idx(idx))
+                .find(|arg| arg.len() > max_argument_size);
+            if let Some(arg) = too_large {
+                return Err((
+                    ErrorKind::ValueTooLarge,
+                    "An argument of the command is larger than the maximal argument size",
+                    format!(
+                        "{} bytes, above the limit of {max_argument_size} bytes",
+                        arg.len()
+                    ),
+                )
+                    .into());
+            }
+        }
+        Ok(())
+    }
+
     fn from(value: BuilderParams) -> RedisResult<Self> {
         #[cfg(not(feature = "tls-rustls"))]
         let tls_params = None;
@@ -155,9 +443,55 @@ impl ClusterParams {
         let tls_params = {
             let retrieved_tls_params = value.certs.clone().map(retrieve_tls_certificates);
 
-            retrieved_tls_params.transpose()?
+            let mut tls_params = retrieved_tls_params.transpose()?;
+            if !value.tls_alpn_protocols.is_empty() || value.tls_server_cert_verifier.is_some() {
+                let tls_params = tls_params.get_or_insert_with(Default::default);
+                tls_params.alpn_protocols = value.tls_alpn_protocols;
+                tls_params.server_cert_verifier = value.tls_server_cert_verifier;
+            }
+            tls_params
         };
 
+        // Shared resources come with their own TLS session cache, which is shared with the other clients.
+        #[cfg(all(feature = "tls-rustls", feature = "cluster-async"))]
+        let shared_tls_session_cache = value
+            .shared_resources
+            .as_ref()
+            .map(|shared_resources| shared_resources.tls_session_cache());
+        #[cfg(all(feature = "tls-rustls", not(feature = "cluster-async")))]
+        let shared_tls_session_cache = None;
+        #[cfg(feature = "tls-rustls")]
+        let tls_session_cache = shared_tls_session_cache.or_else(|| {
+            value
+                .tls_session_resumption
+                .then(|| Arc::new(TlsSessionCache::new(DEFAULT_TLS_SESSION_CACHE_SIZE)))
+        });
+
+        #[cfg(feature = "cluster-async")]
+        if let cluster_async::MinInitialConnections::Fraction(fraction) =
+            value.min_initial_connections
+        {
+            if !(fraction > 0.0 && fraction <= 1.0) {
+                return Err(RedisError::from((
+                    ErrorKind::InvalidClientConfig,
+                    "The minimal fraction of initial connections must be in (0, 1].",
+                    fraction.to_string(),
+                )));
+            }
+        }
+        #[cfg(feature = "cluster-async")]
+        if let Some(cluster_async::AdaptiveTopologyChecks { min, max }) =
+            value.adaptive_topology_checks
+        {
+            if min.is_zero() || min > max {
+                return Err(RedisError::from((
+                    ErrorKind::InvalidClientConfig,
+                    "The minimal interval of the topology checks must be positive and at most the maximal one.",
+                    format!("{min:?} - {max:?}"),
+                )));
+            }
+        }
+
         Ok(Self {
             password: value.password,
             username: value.username,
@@ -165,11 +499,74 @@ impl ClusterParams {
             tls: value.tls,
             retry_params: value.retries_configuration,
             connection_timeout: value.connection_timeout.unwrap_or(Duration::MAX),
+            topology_command: value.topology_command,
+            address_resolver: value.address_resolver,
+            transport_mapping: value.transport_mapping,
+            strict_routing: value.strict_routing,
+            command_filter: value.command_filter,
+            #[cfg(feature = "traffic-recorder")]
+            traffic_recorder: value.traffic_recorder,
             #[cfg(feature = "cluster-async")]
             topology_checks_interval: value.topology_checks_interval,
             #[cfg(feature = "cluster-async")]
+            adaptive_topology_checks: value.adaptive_topology_checks,
+            #[cfg(feature = "cluster-async")]
+            topology_check_failure_threshold: value.topology_check_failure_threshold,
+            #[cfg(feature = "cluster-async")]
             slots_refresh_rate_limit: value.slots_refresh_rate_limit,
+            #[cfg(feature = "cluster-async")]
+            shared_resources: value.shared_resources,
+            #[cfg(feature = "cluster-async")]
+            pubsub_health_check_interval: value.pubsub_health_check_interval,
+            #[cfg(feature = "cluster-async")]
+            pubsub_dead_connection_callback: value.pubsub_dead_connection_callback,
+            #[cfg(feature = "cluster-async")]
+            health_check_freshness: value.health_check_freshness,
+            #[cfg(feature = "cluster-async")]
+            health_check_probe: value.health_check_probe,
+            #[cfg(feature = "cluster-async")]
+            node_id_cache_ttl: value.node_id_cache_ttl,
+            #[cfg(feature = "cluster-async")]
+            initial_slots_refresh: value.initial_slots_refresh,
+            #[cfg(feature = "cluster-async")]
+            min_initial_connections: value.min_initial_connections,
+            #[cfg(feature = "cluster-async")]
+            node_metadata_provider: value.node_metadata_provider,
+            #[cfg(feature = "cluster-async")]
+            cache_config: value.cache_config,
+            #[cfg(feature = "cluster-async")]
+            pipeline_chunking: value.pipeline_chunking,
+            #[cfg(feature = "cluster-async")]
+            split_cross_slot_pipelines: value.split_cross_slot_pipelines,
+            #[cfg(feature = "cluster-async")]
+            slot_stats: value.slot_stats,
+            #[cfg(feature = "cluster-async")]
+            unpause_paused_nodes: value.unpause_paused_nodes,
+            #[cfg(feature = "cluster-async")]
+            flush_policy: value.flush_policy,
+            #[cfg(feature = "cluster-async")]
+            connection_pool_size: value.connection_pool_size.max(1),
+            #[cfg(feature = "cluster-async")]
+            tenant_weights: Arc::new(value.tenant_weights),
+            #[cfg(feature = "cluster-async")]
+            connect_limiter: value
+                .max_concurrent_connects
+                .map(|permits| Arc::new(cluster_async::ConnectLimiter::new(permits))),
+            #[cfg(feature = "cluster-async")]
+            request_timeout: value.request_timeout,
+            #[cfg(feature = "cluster-async")]
+            max_pending_requests: value.max_pending_requests,
+            #[cfg(feature = "cluster-async")]
+            max_argument_size: value.max_argument_size,
+            #[cfg(feature = "cluster-async")]
+            max_reply_size: value.max_reply_size,
+            #[cfg(feature = "cluster-async")]
+            node_overrides: Arc::new(cluster_async::NodeOverrides::new(value.node_overrides)),
+            #[cfg(feature = "cluster-async")]
+            clock: value.clock,
             tls_params,
+            #[cfg(feature = "tls-rustls")]
+            tls_session_cache,
             client_name: value.client_name,
             response_timeout: value.response_timeout.unwrap_or(Duration::MAX),
             protocol: value.protocol,
@@ -225,6 +622,8 @@ impl ClusterClientBuilder {
             }
         };
 
+        let shuffle_initial_nodes = self.builder_params.shuffle_initial_nodes;
+        let rotate_initial_nodes = self.builder_params.rotate_initial_nodes;
         let mut cluster_params = ClusterParams::from(self.builder_params)?;
         let password = if cluster_params.password.is_none() {
             cluster_params
@@ -257,13 +656,21 @@ impl ClusterClientBuilder {
             };
         }
 
+        // Reject Unix sockets upfront, even when mixed with TCP nodes: Redis's cluster commands return only
+        // the nodes' IP and port, so a Unix socket node can't be matched against the discovered topology.
+        if let Some(node) = initial_nodes
+            .iter()
+            .find(|node| matches!(node.addr, ConnectionAddr::Unix(_)))
+        {
+            return Err(RedisError::from((
+                ErrorKind::InvalidClientConfig,
+                "This library cannot use unix socket because Redis's cluster command returns only cluster's IP and port.",
+                node.addr.to_string(),
+            )));
+        }
+
         let mut nodes = Vec::with_capacity(initial_nodes.len());
         for mut node in initial_nodes {
-            if let ConnectionAddr::Unix(_) = node.addr {
-                return Err(RedisError::from((ErrorKind::InvalidClientConfig,
-                                             "This library cannot use unix socket because Redis's cluster command returns only cluster's IP and port.")));
-            }
-
             if password.is_some() && node.redis.password != *password {
                 return Err(RedisError::from((
                     ErrorKind::InvalidClientConfig,
@@ -290,10 +697,14 @@ impl ClusterClientBuilder {
             node.redis.protocol = cluster_params.protocol;
             nodes.push(node);
         }
+        if shuffle_initial_nodes {
+            nodes.shuffle(&mut rand::thread_rng());
+        }
 
         Ok(ClusterClient {
             initial_nodes: nodes,
             cluster_params,
+            next_seed: rotate_initial_nodes.then(Default::default),
         })
     }
 
@@ -341,6 +752,62 @@ impl ClusterClientBuilder {
         self
     }
 
+    /// Sets the maximal number of attempts to reconnect to a node after its connection failed, for the sync
+    /// [`ClusterConnection`](crate::cluster::ClusterConnection). The attempts are spaced by the same wait time as
+    /// the retries, and once all of them fail, the request fails with the last reconnect error.
+    ///
+    /// Defaults to 3. The async connection reconnects in the background, and isn't affected by this setting.
+    pub fn max_reconnect_attempts(mut self, attempts: u32) -> ClusterClientBuilder {
+        self.builder_params
+            .retries_configuration
+            .max_reconnect_attempts = attempts.max(1);
+        self
+    }
+
+    /// Enables a circuit breaker per node, which stops retry storms against a node that keeps failing.
+    ///
+    /// Each node has a budget of `failure_budget` failures, which regains a failure every `refill_interval`. A
+    /// request to the node that fails because of the connection, such as a disconnect or a timeout, uses the
+    /// budget, but error replies don't. Once the budget is exhausted, the node's circuit opens, and the requests that
+    /// are routed to it fail fast with [`ErrorKind::CircuitOpen`](crate::ErrorKind::CircuitOpen) instead of being
+    /// sent and retried, until `cooldown` expires and the budget is restored.
+    ///
+    /// Disabled by default.
+    #[cfg(feature = "cluster-async")]
+    pub fn circuit_breaker(
+        mut self,
+        failure_budget: u32,
+        refill_interval: Duration,
+        cooldown: Duration,
+    ) -> ClusterClientBuilder {
+        self.builder_params.retries_configuration.circuit_breaker =
+            Some(cluster_async::CircuitBreakerParams {
+                failure_budget: failure_budget.max(1),
+                refill_interval,
+                cooldown,
+            });
+        self
+    }
+
+    /// Enables a backoff between the attempts to reconnect to each node, so that a node that stays down isn't
+    /// reconnected to on every health check and every request that's routed to it.
+    ///
+    /// After a node fails to connect, it's reconnected to only after a wait that's drawn uniformly between zero and
+    /// `base` times 2 to the power of the number of consecutive failures minus one, capped by `max`. Until then, the
+    /// reconnects to the node are skipped, and the requests that need a new connection to it fail with the error of
+    /// the last attempt. A successful connection resets the backoff.
+    ///
+    /// The backoff is tracked by the node's address, and applies to the reconnects after connection errors, to the
+    /// health checks and to the connections that the slot refreshes open, but not to the initial connections.
+    ///
+    /// Disabled by default.
+    #[cfg(feature = "cluster-async")]
+    pub fn reconnect_backoff(mut self, base: Duration, max: Duration) -> ClusterClientBuilder {
+        self.builder_params.retries_configuration.reconnect_backoff =
+            Some(cluster_async::ReconnectBackoffParams { base, max });
+        self
+    }
+
     /// Sets TLS mode for the new ClusterClient.
     ///
     /// It is extracted from the first node of initial_nodes if not set.
@@ -372,6 +839,49 @@ impl ClusterClientBuilder {
         self
     }
 
+    /// Sets the ALPN protocols offered by the new ClusterClient's TLS connections, in order of preference.
+    #[cfg(feature = "tls-rustls")]
+    pub fn tls_alpn_protocols(mut self, protocols: Vec<Vec<u8>>) -> ClusterClientBuilder {
+        self.builder_params.tls_alpn_protocols = protocols;
+        self
+    }
+
+    /// Sets a callback that verifies the nodes' TLS certificates, e.g. for certificate pinning or private PKIs.
+    ///
+    /// The callback replaces the verification of the certificate chain against the root certificates. It receives the
+    /// node's end-entity certificate, the intermediate certificates and the server name, and returns an error message
+    /// if the certificate shouldn't be trusted. The handshake signatures are still verified against the certificate.
+    #[cfg(feature = "tls-rustls")]
+    pub fn tls_server_cert_verifier(
+        mut self,
+        callback: impl Fn(
+                &rustls_pki_types::CertificateDer<'_>,
+                &[rustls_pki_types::CertificateDer<'_>],
+                &rustls_pki_types::ServerName<'_>,
+            ) -> Result<(), String>
+            + Send
+            + Sync
+            + 'static,
+    ) -> ClusterClientBuilder {
+        let callback: Box<ServerCertVerifierCallback> = Box::new(callback);
+        self.builder_params.tls_server_cert_verifier =
+            Some(Arc::new(CallbackCertVerifier::new(callback)));
+        self
+    }
+
+    /// Enables TLS session resumption for the new ClusterClient (default is disabled).
+    ///
+    /// When enabled, TLS sessions are cached per node across reconnects, so that reconnecting to a node, e.g. after
+    /// a failover, can resume the previous session instead of performing a full handshake.
+    /// The cache's effectiveness can be checked with [`ClusterClient::tls_resumption_stats`].
+    ///
+    /// Clients configured with [`shared_resources`](Self::shared_resources) always resume sessions using the shared cache.
+    #[cfg(feature = "tls-rustls")]
+    pub fn tls_session_resumption(mut self, enabled: bool) -> ClusterClientBuilder {
+        self.builder_params.tls_session_resumption = enabled;
+        self
+    }
+
     /// Enables reading from replicas for all new connections (default is disabled).
     ///
     /// If enabled, then read queries will go to the replica nodes & write queries will go to the
@@ -381,6 +891,18 @@ impl ClusterClientBuilder {
         self
     }
 
+    /// Enables reading from replicas for all new connections, choosing for each slot the replica with the lowest
+    /// latency.
+    ///
+    /// The latency of each node is the moving average of the round-trip times of the pings that the
+    /// [periodic topology checks](Self::periodic_topology_checks) send over the management connections. Until the
+    /// latencies of a slot's replicas are measured, and with the sync cluster, which doesn't measure them, the reads
+    /// go to the replicas in a round robin manner.
+    pub fn read_from_replicas_by_latency(mut self) -> ClusterClientBuilder {
+        self.builder_params.read_from_replicas = ReadFromReplicaStrategy::LowestLatency;
+        self
+    }
+
     /// Enables periodic topology checks for this client.
     ///
     /// If enabled, periodic topology checks will be executed at the configured intervals to examine whether there
@@ -393,6 +915,42 @@ impl ClusterClientBuilder {
         self
     }
 
+    /// Adapts the interval of the [periodic topology checks](Self::periodic_topology_checks) to how stable the
+    /// topology is, between `min_interval` and `max_interval`.
+    ///
+    /// The checks start at the configured interval, clamped to the bounds. The interval is halved after every check
+    /// that follows `MOVED` or `ASK` redirections or connection errors, so that topology changes are caught quickly,
+    /// and doubled after every check that follows a period without them. It has no effect unless periodic topology
+    /// checks are enabled.
+    ///
+    /// Building the client fails if `min_interval` is zero or greater than `max_interval`.
+    #[cfg(feature = "cluster-async")]
+    pub fn adaptive_topology_checks(
+        mut self,
+        min_interval: Duration,
+        max_interval: Duration,
+    ) -> ClusterClientBuilder {
+        self.builder_params.adaptive_topology_checks =
+            Some(cluster_async::AdaptiveTopologyChecks {
+                min: min_interval,
+                max: max_interval,
+            });
+        self
+    }
+
+    /// Sends a [`TopologyEvent::TopologyChecksFailing`](cluster_async::TopologyEvent::TopologyChecksFailing) to
+    /// the receivers of [`ClusterConnection::topology_updates`](cluster_async::ClusterConnection::topology_updates)
+    /// once the [periodic topology checks](Self::periodic_topology_checks) have been failing for longer than
+    /// `threshold`, so that a cluster whose topology can't be checked anymore doesn't go unnoticed.
+    ///
+    /// The event is sent once per streak of failed checks. The outcomes of the checks are also available from
+    /// [`ClusterConnection::topology_check_stats`](cluster_async::ClusterConnection::topology_check_stats).
+    #[cfg(feature = "cluster-async")]
+    pub fn topology_check_failure_threshold(mut self, threshold: Duration) -> ClusterClientBuilder {
+        self.builder_params.topology_check_failure_threshold = Some(threshold);
+        self
+    }
+
     /// Sets the rate limit for slot refresh operations in the cluster.
     ///
     /// This method configures the interval duration between consecutive slot
@@ -428,6 +986,334 @@ impl ClusterClientBuilder {
         self
     }
 
+    /// Sets resources that are shared with other clients, such as the DNS cache and the TLS session cache.
+    ///
+    /// The connections and the cluster state of each client remain isolated. See
+    /// [`SharedResources`](cluster_async::SharedResources) for more details.
+    #[cfg(feature = "cluster-async")]
+    pub fn shared_resources(
+        mut self,
+        shared_resources: cluster_async::SharedResources,
+    ) -> ClusterClientBuilder {
+        self.builder_params.shared_resources = Some(shared_resources);
+        self
+    }
+
+    /// Enables periodic health checks of the connections that hold pubsub subscriptions.
+    ///
+    /// If enabled, a `PING` is sent at the configured intervals on each node's connection that holds subscriptions,
+    /// which is allowed while subscribed when using RESP3. A connection that doesn't respond within the interval is
+    /// considered dead, and is reconnected and resubscribed, so that half-open connections that silently stopped
+    /// delivering messages are detected.
+    #[cfg(feature = "cluster-async")]
+    pub fn pubsub_health_check_interval(mut self, interval: Duration) -> ClusterClientBuilder {
+        self.builder_params.pubsub_health_check_interval = Some(interval);
+        self
+    }
+
+    /// Sets a callback that is called with the node's address whenever the pubsub health checks find a dead
+    /// connection, before it is reconnected and resubscribed.
+    #[cfg(feature = "cluster-async")]
+    pub fn pubsub_dead_connection_callback(
+        mut self,
+        callback: impl Fn(&str) + Send + Sync + 'static,
+    ) -> ClusterClientBuilder {
+        self.builder_params.pubsub_dead_connection_callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Lets the health checks of the connections rely on their traffic: a connection that received a successful
+    /// response within `freshness` is considered healthy without sending it a `PING`, which cuts the load of the
+    /// checks on busy connections. Only the connections that were idle for longer are pinged.
+    ///
+    /// This applies to the checks of the connections when they're refreshed, and to the pubsub health checks. The
+    /// latency measurements of the periodic topology checks always send a `PING`. By default, the connections are
+    /// always pinged.
+    #[cfg(feature = "cluster-async")]
+    pub fn health_check_freshness(mut self, freshness: Duration) -> ClusterClientBuilder {
+        self.builder_params.health_check_freshness = Some(freshness);
+        self
+    }
+
+    /// Sets the command that the health checks send to probe the connections, and the validation of its response,
+    /// for services that rate-limit or log `PING`. For example, `HealthCheckProbe::echo("healthcheck-id")` probes the
+    /// connections with `ECHO healthcheck-id`.
+    ///
+    /// The probe is used when the connections are created and refreshed, by the pubsub health checks and by the
+    /// latency measurements of the periodic topology checks. By default, the connections are probed with `PING`.
+    #[cfg(feature = "cluster-async")]
+    pub fn health_check_probe(
+        mut self,
+        probe: cluster_async::HealthCheckProbe,
+    ) -> ClusterClientBuilder {
+        self.builder_params.health_check_probe = probe;
+        self
+    }
+
+    /// Enables tracking of the nodes' ids, as reported by `CLUSTER MYID`.
+    ///
+    /// If enabled, the id of each node is queried in the background when its address joins the topology, and
+    /// queried again after slot refreshes once it's older than `ttl`. The ids allow distinguishing between a known
+    /// node that moved to a new address and a new node, and are available through
+    /// [`ClusterConnection::node_ids`](cluster_async::ClusterConnection::node_ids). If the id at an address changes,
+    /// the connections to the address are recreated, and a
+    /// [`TopologyEvent::NodeReplaced`](cluster_async::TopologyEvent::NodeReplaced) is sent.
+    #[cfg(feature = "cluster-async")]
+    pub fn node_id_cache_ttl(mut self, ttl: Duration) -> ClusterClientBuilder {
+        self.builder_params.node_id_cache_ttl = Some(ttl);
+        self
+    }
+
+    /// Sets how long creating a connection waits for the initial slot refresh.
+    ///
+    /// By default, creating a connection waits until the topology of the cluster was queried, which depends on the
+    /// slowest node. See [`InitialSlotsRefresh`](cluster_async::InitialSlotsRefresh) for the other options.
+    #[cfg(feature = "cluster-async")]
+    pub fn initial_slots_refresh(
+        mut self,
+        initial_slots_refresh: cluster_async::InitialSlotsRefresh,
+    ) -> ClusterClientBuilder {
+        self.builder_params.initial_slots_refresh = initial_slots_refresh;
+        self
+    }
+
+    /// Sets how many of the initial nodes must be connected to for a connection to be created.
+    ///
+    /// By default, connecting to a single initial node is enough, which can hide misconfigured initial nodes. If the
+    /// minimum isn't met, the returned error contains the errors of the initial nodes that couldn't be connected to.
+    #[cfg(feature = "cluster-async")]
+    pub fn min_initial_connections(
+        mut self,
+        min_initial_connections: cluster_async::MinInitialConnections,
+    ) -> ClusterClientBuilder {
+        self.builder_params.min_initial_connections = min_initial_connections;
+        self
+    }
+
+    /// Sets a callback that is called with the address and the IP of each node when it's discovered, and returns
+    /// user metadata to attach to the node, such as its rack, availability zone or weight.
+    ///
+    /// The metadata is kept while the node is reconnected, and is available through
+    /// [`ClusterConnection::node_metadata`](cluster_async::ClusterConnection::node_metadata) and the
+    /// [`TopologyEvent::NodeAdded`](cluster_async::TopologyEvent::NodeAdded) events. It isn't used by the client's
+    /// own routing, so placement logic that depends on it has to choose the node and route the command to it
+    /// explicitly.
+    #[cfg(feature = "cluster-async")]
+    pub fn node_metadata_provider(
+        mut self,
+        provider: impl Fn(&str, Option<std::net::IpAddr>) -> cluster_async::NodeMetadata
+            + Send
+            + Sync
+            + 'static,
+    ) -> ClusterClientBuilder {
+        self.builder_params.node_metadata_provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Sets the configuration of the client-side cache.
+    ///
+    /// If set, every user connection to the nodes enables `CLIENT TRACKING`, and
+    /// [`ClusterClient::get_cached_async_connection`] returns connections that serve `GET` and `MGET` from the cache.
+    /// See the [`caching`](crate::caching) module.
+    #[cfg(feature = "cluster-async")]
+    pub fn cache(mut self, config: CacheConfig) -> ClusterClientBuilder {
+        self.builder_params.cache_config = Some(config);
+        self
+    }
+
+    /// Sets the limits above which the pipelines that are sent to a single node, such as the ones that are sent
+    /// with [`cluster_async::ClusterConnection::route_pipeline`], are split into several chunks. See
+    /// [`PipelineChunking`](crate::PipelineChunking).
+    #[cfg(feature = "cluster-async")]
+    pub fn pipeline_chunking(mut self, chunking: crate::PipelineChunking) -> ClusterClientBuilder {
+        self.builder_params.pipeline_chunking = chunking;
+        self
+    }
+
+    /// Sets whether the non-atomic pipelines whose keys are in several slots are split into a sub-pipeline per slot,
+    /// instead of failing with a [`CrossSlot`](crate::ErrorKind::CrossSlot) error.
+    ///
+    /// The sub-pipelines are sent concurrently and retried on their own, e.g. on `MOVED` and `ASK` redirections, and
+    /// their replies are returned in the order of the pipeline's commands. Since the sub-pipelines are executed
+    /// independently, the commands of different slots may be executed in any order. Atomic pipelines are never split.
+    ///
+    /// Defaults to `false`.
+    #[cfg(feature = "cluster-async")]
+    pub fn split_cross_slot_pipelines(mut self, enabled: bool) -> ClusterClientBuilder {
+        self.builder_params.split_cross_slot_pipelines = enabled;
+        self
+    }
+
+    /// Sets whether the requests to each slot are counted, to find the hot slots that drive an imbalance between the
+    /// nodes without running `MONITOR` on the servers. The counts are sampled with
+    /// [`ClusterConnection::sample_slot_stats`](cluster_async::ClusterConnection::sample_slot_stats).
+    ///
+    /// Each request is counted once in the slot that it's routed by, so a pipeline counts as a single request, and a
+    /// command whose keys are in several slots counts once in each of them. Retries aren't counted. Requests that
+    /// aren't routed by a slot aren't counted.
+    ///
+    /// Defaults to `false`.
+    #[cfg(feature = "cluster-async")]
+    pub fn slot_stats(mut self, enabled: bool) -> ClusterClientBuilder {
+        self.builder_params.slot_stats = enabled;
+        self
+    }
+
+    /// Sets whether `CLIENT UNPAUSE` is sent to the nodes that are detected as paused, e.g. by an operator's
+    /// `CLIENT PAUSE` that was left behind.
+    ///
+    /// The latency measurements of the [periodic topology checks](Self::periodic_topology_checks) detect a node as
+    /// paused when its probe times out over a connection that is still open. The reads from replicas avoid the
+    /// paused replicas regardless of this setting, until their probes succeed again. If enabled, `CLIENT UNPAUSE` is
+    /// sent over the node's management connection once it's detected as paused, where the server's ACLs permit it.
+    /// See also [`ClusterConnection::client_unpause`](cluster_async::ClusterConnection::client_unpause).
+    ///
+    /// Defaults to `false`.
+    #[cfg(feature = "cluster-async")]
+    pub fn unpause_paused_nodes(mut self, enabled: bool) -> ClusterClientBuilder {
+        self.builder_params.unpause_paused_nodes = enabled;
+        self
+    }
+
+    /// Sets when the connections to the nodes flush the requests that they write. See
+    /// [`FlushPolicy`](crate::aio::FlushPolicy).
+    #[cfg(feature = "cluster-async")]
+    pub fn flush_policy(mut self, policy: crate::aio::FlushPolicy) -> ClusterClientBuilder {
+        self.builder_params.flush_policy = policy;
+        self
+    }
+
+    /// Sets the number of user connections that are opened to each node. The requests to a node are distributed
+    /// between its connections in a round-robin manner, and the connections are health-checked and refreshed
+    /// together. The management connection of each node isn't part of the pool.
+    ///
+    /// Defaults to 1.
+    #[cfg(feature = "cluster-async")]
+    pub fn connection_pool_size(mut self, size: usize) -> ClusterClientBuilder {
+        self.builder_params.connection_pool_size = size.max(1);
+        self
+    }
+
+    /// Sets the weight of a tenant in the scheduling of the requests, for connections that are shared by several
+    /// tenants, for example in multi-tenant proxies.
+    ///
+    /// The requests are tagged with a tenant by sending them through
+    /// [`ClusterConnection::with_tenant`](cluster_async::ClusterConnection::with_tenant). The driver task takes turns
+    /// between the tenants with queued requests, and in each turn a tenant may start up to its weight of requests,
+    /// so a tenant with many queued requests can't starve the others. Untagged requests are scheduled as a tenant
+    /// of their own. The weight of a tenant defaults to 1, and a weight of 0 is treated as 1.
+    #[cfg(feature = "cluster-async")]
+    pub fn tenant_weight(mut self, tenant: impl Into<String>, weight: u32) -> ClusterClientBuilder {
+        self.builder_params
+            .tenant_weights
+            .insert(tenant.into(), weight);
+        self
+    }
+
+    /// Sets the maximal number of connection attempts that run at the same time, across all the nodes and all the
+    /// connections of the client. Connection attempts above the limit wait for a running attempt to finish, so
+    /// reconnecting after a mass disconnect doesn't open a socket to every node at once. This applies to the initial
+    /// connections, the reconnects and the connections that are opened by topology refreshes.
+    ///
+    /// The state of the limiter is returned by
+    /// [`ClusterConnection::connect_limiter_stats`](cluster_async::ClusterConnection::connect_limiter_stats).
+    /// A limit of 0 is treated as 1. Defaults to no limit.
+    #[cfg(feature = "cluster-async")]
+    pub fn max_concurrent_connects(mut self, permits: usize) -> ClusterClientBuilder {
+        self.builder_params.max_concurrent_connects = Some(permits);
+        self
+    }
+
+    /// Sets the time in which a request must complete, including its retries, its redirects and the waits for
+    /// reconnects and slot refreshes, before it fails with [`ErrorKind::Timeout`](crate::ErrorKind::Timeout).
+    /// Unlike the response timeout, which limits each attempt, a request that times out isn't retried. It can be
+    /// overridden per request with
+    /// [`ClusterConnection::route_command_with_options`](cluster_async::ClusterConnection::route_command_with_options).
+    ///
+    /// Defaults to no limit.
+    #[cfg(feature = "cluster-async")]
+    pub fn request_timeout(mut self, timeout: Duration) -> ClusterClientBuilder {
+        self.builder_params.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the maximal number of requests that wait to be sent, for example while the nodes they're routed to are
+    /// reconnected to. Above the limit, new requests fail immediately with
+    /// [`ErrorKind::TryAgain`](crate::ErrorKind::TryAgain) instead of being queued, so that an outage doesn't let the
+    /// queue grow without bound. Retries of requests that were already queued aren't limited.
+    ///
+    /// The depth of the queue is returned by
+    /// [`ClusterConnection::pending_requests_stats`](cluster_async::ClusterConnection::pending_requests_stats).
+    /// Defaults to no limit.
+    #[cfg(feature = "cluster-async")]
+    pub fn max_pending_requests(mut self, max_pending_requests: usize) -> ClusterClientBuilder {
+        self.builder_params.max_pending_requests = Some(max_pending_requests);
+        self
+    }
+
+    /// Sets the size above which an argument of a command fails the command with
+    /// [`ErrorKind::ValueTooLarge`](crate::ErrorKind::ValueTooLarge), before it's sent to the nodes. This protects
+    /// shared clusters from values that are accidentally huge. The commands of a pipeline are checked before any of
+    /// them is sent.
+    ///
+    /// Defaults to no limit.
+    #[cfg(feature = "cluster-async")]
+    pub fn max_argument_size(mut self, max_bytes: usize) -> ClusterClientBuilder {
+        self.builder_params.max_argument_size = Some(max_bytes);
+        self
+    }
+
+    /// Sets the size above which a reply of a node fails the command with
+    /// [`ErrorKind::ValueTooLarge`](crate::ErrorKind::ValueTooLarge), as it's received. A reply that exceeds the
+    /// limit before it's fully received closes the connection to the node, since the rest of the reply can't be
+    /// skipped without reading it. See
+    /// [`MultiplexedConnection::set_max_reply_size`](crate::aio::MultiplexedConnection::set_max_reply_size).
+    ///
+    /// Defaults to no limit.
+    #[cfg(feature = "cluster-async")]
+    pub fn max_reply_size(mut self, max_bytes: usize) -> ClusterClientBuilder {
+        self.builder_params.max_reply_size = Some(max_bytes);
+        self
+    }
+
+    /// Seeds what an external source, such as the service discovery of the platform, knows about the node at
+    /// `address` before the cluster reports it: its role, its availability zone and its weight.
+    ///
+    /// The overridden role replaces the role that the cluster reports whenever the slots are refreshed, and each
+    /// replaced role is sent as a [`TopologyEvent::NodeRoleConflict`](cluster_async::TopologyEvent::NodeRoleConflict)
+    /// to the receivers of
+    /// [`ClusterConnection::topology_updates`](cluster_async::ClusterConnection::topology_updates). The availability
+    /// zone and the weight are merged into the metadata that is returned by
+    /// [`ClusterConnection::node_metadata`](cluster_async::ClusterConnection::node_metadata), over the metadata of the
+    /// [`node_metadata_provider`](Self::node_metadata_provider). The overrides can be changed at runtime with
+    /// [`ClusterConnection::set_node_override`](cluster_async::ClusterConnection::set_node_override).
+    #[cfg(feature = "cluster-async")]
+    pub fn node_override(
+        mut self,
+        address: impl Into<String>,
+        node_override: cluster_async::NodeOverride,
+    ) -> ClusterClientBuilder {
+        self.builder_params
+            .node_overrides
+            .insert(address.into(), node_override);
+        self
+    }
+
+    /// Sets the source of time of the connections, which is used by the request timeouts, the waits between
+    /// retries, the reconnect backoffs, the circuit breakers, the throttling of slot refreshes, the periodic checks
+    /// and the freshness window of the health checks. The connection and response timeouts of the node connections
+    /// keep using the runtime's timers.
+    ///
+    /// Tests that run with a paused tokio clock can use [`TokioClock`](cluster_async::TokioClock), or a clock of
+    /// their own, to advance the time of the client without waiting.
+    ///
+    /// Defaults to [`SystemClock`](cluster_async::SystemClock).
+    #[cfg(feature = "cluster-async")]
+    pub fn clock(mut self, clock: impl cluster_async::Clock + 'static) -> ClusterClientBuilder {
+        self.builder_params.clock = cluster_async::SharedClock::new(clock);
+        self
+    }
+
     /// Enables timing out on slow connection time.
     ///
     /// If enabled, the cluster will only wait the given time on each connection attempt to each node.
@@ -436,6 +1322,113 @@ impl ClusterClientBuilder {
         self
     }
 
+    /// Sets the command that is sent to the nodes to discover the cluster's topology (default is `CLUSTER SLOTS`).
+    ///
+    /// With [`TopologyCommand::ClusterShards`], the replicas that the nodes report as loading or failed aren't
+    /// routed to, and the nodes that don't support `CLUSTER SHARDS`, such as the ones before Redis 7.0, are queried
+    /// with `CLUSTER SLOTS` instead.
+    pub fn topology_command(mut self, topology_command: TopologyCommand) -> ClusterClientBuilder {
+        self.builder_params.topology_command = topology_command;
+        self
+    }
+
+    /// Sets a callback that rewrites the addresses of the nodes in the topology responses, for deployments whose
+    /// nodes announce addresses that the client can't reach, such as internal IPs behind a NAT.
+    ///
+    /// The callback is called with each `host:port` address of the `CLUSTER SLOTS` or `CLUSTER SHARDS` responses,
+    /// and returns the address to connect to instead, for example a NAT'd hostname. The rewritten addresses are the
+    /// nodes' addresses in the slot map. The addresses of `MOVED` and `ASK` redirects aren't rewritten, but a `MOVED`
+    /// redirect triggers a refresh of the slots, after which the node is reached at its rewritten address.
+    pub fn address_resolver(
+        mut self,
+        resolver: impl Fn(&str) -> String + Send + Sync + 'static,
+    ) -> ClusterClientBuilder {
+        self.builder_params.address_resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Sets a callback that maps the address of a node to the address that its connections connect to, for clusters
+    /// that are reached through local proxies or Unix sockets.
+    ///
+    /// The callback is called with the `host:port` address of a node, as given by the initial nodes or announced by
+    /// the topology, whenever a connection to the node is created, and returns the address to connect to instead,
+    /// for example a [`ConnectionAddr::Unix`] socket, or `None` to connect to the node's address. The nodes are still
+    /// identified by their `host:port` addresses, so the initial nodes can't be Unix sockets themselves, but they
+    /// can be mapped to ones. Mapped nodes aren't resolved through DNS.
+    pub fn transport_mapping(
+        mut self,
+        mapping: impl Fn(&str) -> Option<ConnectionAddr> + Send + Sync + 'static,
+    ) -> ClusterClientBuilder {
+        self.builder_params.transport_mapping = Some(Arc::new(mapping));
+        self
+    }
+
+    /// Sets whether the commands whose routing is unknown fail with a [`ClientError`](ErrorKind::ClientError), instead
+    /// of being sent to a random node, to catch data placement bugs with module commands or with commands that are
+    /// newer than this library.
+    ///
+    /// The routing is unknown for commands that can't be routed by their arguments, such as `SCAN` or a key-based
+    /// command without a key, and for module commands, whose names contain a dot, since it isn't known which of
+    /// their arguments are keys. Such commands can still be sent with an explicit routing, for example with
+    /// [`ClusterConnection::route_command`](cluster_async::ClusterConnection::route_command).
+    ///
+    /// Defaults to `false`.
+    pub fn strict_routing(mut self, enabled: bool) -> ClusterClientBuilder {
+        self.builder_params.strict_routing = enabled;
+        self
+    }
+
+    /// Sets the commands that the client is allowed to send, such as a denylist of `KEYS`, `FLUSHALL` and `DEBUG`,
+    /// for clients that are embedded in services whose users shouldn't be able to run any command, when the server's
+    /// ACLs can't be relied on.
+    ///
+    /// The commands that the filter doesn't allow fail with a [`CommandNotAllowed`](ErrorKind::CommandNotAllowed)
+    /// error without being sent. A pipeline fails as a whole if any of its commands isn't allowed. The commands that
+    /// the client sends on its own, such as the topology discovery and the health checks, aren't filtered.
+    ///
+    /// By default, all the commands are allowed.
+    pub fn command_filter(mut self, filter: CommandFilter) -> ClusterClientBuilder {
+        self.builder_params.command_filter = Some(filter);
+        self
+    }
+
+    /// Sets a recorder of the commands that the async cluster connections route, to reproduce routing bugs by
+    /// replaying the recorded commands against a test cluster. See
+    /// [`TrafficRecorder`](cluster_async::TrafficRecorder) for what's recorded.
+    ///
+    /// By default, the commands aren't recorded.
+    #[cfg(feature = "traffic-recorder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "traffic-recorder")))]
+    pub fn traffic_recorder(
+        mut self,
+        recorder: cluster_async::TrafficRecorder,
+    ) -> ClusterClientBuilder {
+        self.builder_params.traffic_recorder = Some(recorder);
+        self
+    }
+
+    /// Sets whether the initial nodes are shuffled when the client is built, so that a fleet of clients that are
+    /// configured with the same seeds doesn't always start the discovery of the topology from the same seed.
+    ///
+    /// Defaults to `false`, in which case the initial nodes are used in the order that they were given.
+    pub fn shuffle_initial_nodes(mut self, enabled: bool) -> ClusterClientBuilder {
+        self.builder_params.shuffle_initial_nodes = enabled;
+        self
+    }
+
+    /// Sets whether each connection that the client creates starts from the next initial node, so that the first
+    /// `CLUSTER SLOTS` queries of the client's connections are spread between the seeds.
+    ///
+    /// The sync cluster connection queries the first initial node that it can connect to, in order, so the rotation
+    /// moves the first query to the next seed. The async cluster connection connects to all the initial nodes and
+    /// queries random ones, so the rotation only changes the order in which they're connected to.
+    ///
+    /// Defaults to `false`.
+    pub fn rotate_initial_nodes(mut self, enabled: bool) -> ClusterClientBuilder {
+        self.builder_params.rotate_initial_nodes = enabled;
+        self
+    }
+
     /// Enables timing out on slow responses.
     ///
     /// If enabled, the cluster will only wait the given time to each response from each node.
@@ -482,6 +1475,8 @@ impl ClusterClientBuilder {
 pub struct ClusterClient {
     initial_nodes: Vec<ConnectionInfo>,
     cluster_params: ClusterParams,
+    // The index of the initial node that the next connection starts from, if the initial nodes are rotated.
+    next_seed: Option<Arc<AtomicUsize>>,
 }
 
 impl ClusterClient {
@@ -507,6 +1502,16 @@ impl ClusterClient {
         ClusterClientBuilder::new(initial_nodes)
     }
 
+    /// Returns the initial nodes in the order that the next connection uses them.
+    fn initial_nodes_for_connection(&self) -> Vec<ConnectionInfo> {
+        let mut nodes = self.initial_nodes.clone();
+        if let Some(next_seed) = &self.next_seed {
+            let first = next_seed.fetch_add(1, Ordering::Relaxed) % nodes.len();
+            nodes.rotate_left(first);
+        }
+        nodes
+    }
+
     /// Creates new connections to Redis Cluster nodes and returns a
     /// [`cluster::ClusterConnection`].
     ///
@@ -519,7 +1524,7 @@ impl ClusterClient {
     ) -> RedisResult<cluster::ClusterConnection> {
         cluster::ClusterConnection::new(
             self.cluster_params.clone(),
-            self.initial_nodes.clone(),
+            self.initial_nodes_for_connection(),
             push_sender,
         )
     }
@@ -536,13 +1541,68 @@ impl ClusterClient {
         push_sender: Option<mpsc::UnboundedSender<PushInfo>>,
     ) -> RedisResult<cluster_async::ClusterConnection> {
         cluster_async::ClusterConnection::new(
-            &self.initial_nodes,
+            &self.initial_nodes_for_connection(),
             self.cluster_params.clone(),
             push_sender,
         )
         .await
     }
 
+    /// Creates new connections to Redis Cluster nodes and returns a [`cluster_async::ClusterConnection`] that serves
+    /// `GET` and `MGET` from a client-side cache, which is invalidated by the nodes with `CLIENT TRACKING`.
+    ///
+    /// The cache is configured by [`ClusterClientBuilder::cache`], or has the default configuration otherwise.
+    /// Invalidations are push messages, so the client must use RESP3. The push messages are forwarded to
+    /// `push_sender` once the cache handled them.
+    ///
+    /// # Errors
+    ///
+    /// An error is returned if the client doesn't use RESP3, or if there is a failure while creating connections or
+    /// slots.
+    #[cfg(feature = "cluster-async")]
+    pub async fn get_cached_async_connection(
+        &self,
+        push_sender: Option<mpsc::UnboundedSender<PushInfo>>,
+    ) -> RedisResult<CachedConnection<cluster_async::ClusterConnection>> {
+        self.get_cached_async_generic_connection(push_sender).await
+    }
+
+    #[doc(hidden)]
+    #[cfg(feature = "cluster-async")]
+    pub async fn get_cached_async_generic_connection<C>(
+        &self,
+        push_sender: Option<mpsc::UnboundedSender<PushInfo>>,
+    ) -> RedisResult<CachedConnection<cluster_async::ClusterConnection<C>>>
+    where
+        C: crate::aio::ConnectionLike
+            + cluster_async::Connect
+            + Clone
+            + Send
+            + Sync
+            + Unpin
+            + 'static,
+    {
+        if self.cluster_params.protocol != ProtocolVersion::RESP3 {
+            return Err(RedisError::from((
+                ErrorKind::InvalidClientConfig,
+                "Client-side caching requires RESP3",
+            )));
+        }
+        let mut cluster_params = self.cluster_params.clone();
+        let config = cluster_params
+            .cache_config
+            .get_or_insert_with(CacheConfig::default)
+            .clone();
+        let (cache, push_sender) = caching::create_cache(config, push_sender);
+        let connection = cluster_async::ClusterConnection::new(
+            &self.initial_nodes_for_connection(),
+            cluster_params,
+            Some(push_sender),
+        )
+        .await?;
+        Ok(CachedConnection::new(connection, cache))
+    }
+
     #[doc(hidden)]
     pub fn get_generic_connection<C>(
         &self,
@@ -553,7 +1613,7 @@ impl ClusterClient {
     {
         cluster::ClusterConnection::new(
             self.cluster_params.clone(),
-            self.initial_nodes.clone(),
+            self.initial_nodes_for_connection(),
             push_sender,
         )
     }
@@ -563,6 +1623,25 @@ impl ClusterClient {
     pub async fn get_async_generic_connection<C>(
         &self,
     ) -> RedisResult<cluster_async::ClusterConnection<C>>
+    where
+        C: crate::aio::ConnectionLike
+            + cluster_async::Connect
+            + Clone
+            + Send
+            + Sync
+            + Unpin
+            + 'static,
+    {
+        self.get_async_generic_connection_with_push_sender(None)
+            .await
+    }
+
+    #[doc(hidden)]
+    #[cfg(feature = "cluster-async")]
+    pub async fn get_async_generic_connection_with_push_sender<C>(
+        &self,
+        push_sender: Option<mpsc::UnboundedSender<PushInfo>>,
+    ) -> RedisResult<cluster_async::ClusterConnection<C>>
     where
         C: crate::aio::ConnectionLike
             + cluster_async::Connect
@@ -573,13 +1652,23 @@ impl ClusterClient {
             + 'static,
     {
         cluster_async::ClusterConnection::new(
-            &self.initial_nodes,
+            &self.initial_nodes_for_connection(),
             self.cluster_params.clone(),
-            None,
+            push_sender,
         )
         .await
     }
 
+    /// Returns the TLS session resumption statistics of the client's connections,
+    /// or `None` if TLS session resumption isn't enabled.
+    #[cfg(feature = "tls-rustls")]
+    pub fn tls_resumption_stats(&self) -> Option<TlsResumptionStats> {
+        self.cluster_params
+            .tls_session_cache
+            .as_ref()
+            .map(|session_cache| session_cache.stats())
+    }
+
     /// Use `new()`.
     #[deprecated(since = "0.22.0", note = "Use new()")]
     pub fn open<T: IntoConnectionInfo>(initial_nodes: Vec<T>) -> RedisResult<ClusterClient> {
@@ -594,7 +1683,10 @@ mod tests {
         DEFAULT_SLOTS_REFRESH_MAX_JITTER_MILLI, DEFAULT_SLOTS_REFRESH_WAIT_DURATION,
     };
 
-    use super::{ClusterClient, ClusterClientBuilder, ConnectionInfo, IntoConnectionInfo};
+    use super::{
+        ClusterClient, ClusterClientBuilder, CommandFilter, ConnectionInfo, IntoConnectionInfo,
+    };
+    use crate::{cmd, ErrorKind};
 
     fn get_connection_data() -> Vec<ConnectionInfo> {
         vec![
@@ -651,6 +1743,47 @@ mod tests {
         assert_eq!(client.cluster_params.username, Some("user1".to_string()));
     }
 
+    #[test]
+    fn rotate_initial_nodes_between_connections() {
+        let client = ClusterClientBuilder::new(get_connection_data())
+            .rotate_initial_nodes(true)
+            .build()
+            .unwrap();
+        let first_node = || client.initial_nodes_for_connection()[0].addr.to_string();
+        assert_eq!(first_node(), "127.0.0.1:6379");
+        assert_eq!(first_node(), "127.0.0.1:6378");
+        assert_eq!(first_node(), "127.0.0.1:6377");
+        assert_eq!(first_node(), "127.0.0.1:6379");
+
+        let client = ClusterClient::new(get_connection_data()).unwrap();
+        assert_eq!(
+            client.initial_nodes_for_connection()[0].addr.to_string(),
+            "127.0.0.1:6379"
+        );
+        assert_eq!(
+            client.initial_nodes_for_connection()[0].addr.to_string(),
+            "127.0.0.1:6379"
+        );
+    }
+
+    #[test]
+    fn shuffle_initial_nodes_keeps_all_the_nodes() {
+        let client = ClusterClientBuilder::new(get_connection_data())
+            .shuffle_initial_nodes(true)
+            .build()
+            .unwrap();
+        let mut addresses: Vec<String> = client
+            .initial_nodes
+            .iter()
+            .map(|node| node.addr.to_string())
+            .collect();
+        addresses.sort();
+        assert_eq!(
+            addresses,
+            vec!["127.0.0.1:6377", "127.0.0.1:6378", "127.0.0.1:6379"]
+        );
+    }
+
     #[test]
     fn give_different_password_by_initial_nodes() {
         let result = ClusterClient::new(vec![
@@ -688,6 +1821,90 @@ mod tests {
         assert!(client.is_err())
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn give_unix_initial_nodes() {
+        let result = ClusterClient::new(vec!["redis+unix:///tmp/redis.sock"]);
+        assert_eq!(
+            result.err().unwrap().kind(),
+            crate::ErrorKind::InvalidClientConfig
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn give_unix_initial_nodes_mixed_with_tcp() {
+        let result = ClusterClient::new(vec![
+            "redis://127.0.0.1:6379",
+            "redis+unix:///tmp/redis.sock",
+        ]);
+        let err = result.err().unwrap();
+        assert_eq!(err.kind(), crate::ErrorKind::InvalidClientConfig);
+        assert_eq!(err.detail(), Some("/tmp/redis.sock"));
+    }
+
+    #[cfg(feature = "cluster-async")]
+    #[test]
+    fn give_invalid_adaptive_topology_checks() {
+        let build = |min, max| {
+            ClusterClientBuilder::new(get_connection_data())
+                .periodic_topology_checks(std::time::Duration::from_secs(10))
+                .adaptive_topology_checks(min, max)
+                .build()
+        };
+        assert!(build(
+            std::time::Duration::from_secs(1),
+            std::time::Duration::from_secs(60)
+        )
+        .is_ok());
+        for (min, max) in [
+            (
+                std::time::Duration::ZERO,
+                std::time::Duration::from_secs(60),
+            ),
+            (
+                std::time::Duration::from_secs(60),
+                std::time::Duration::from_secs(1),
+            ),
+        ] {
+            assert_eq!(
+                build(min, max).err().unwrap().kind(),
+                crate::ErrorKind::InvalidClientConfig
+            );
+        }
+    }
+
+    #[cfg(feature = "tls-rustls")]
+    #[test]
+    fn give_tls_alpn_protocols_and_verifier() {
+        let client = ClusterClientBuilder::new(get_connection_data())
+            .tls_alpn_protocols(vec![b"redis".to_vec()])
+            .tls_server_cert_verifier(|_, _, _| Ok(()))
+            .build()
+            .unwrap();
+        let tls_params = client.cluster_params.tls_params.unwrap();
+        assert_eq!(tls_params.alpn_protocols, vec![b"redis".to_vec()]);
+        assert!(tls_params.server_cert_verifier.is_some());
+    }
+
+    #[cfg(feature = "tls-rustls")]
+    #[test]
+    fn give_tls_session_resumption() {
+        let client = ClusterClientBuilder::new(get_connection_data())
+            .build()
+            .unwrap();
+        assert!(client.tls_resumption_stats().is_none());
+
+        let client = ClusterClientBuilder::new(get_connection_data())
+            .tls_session_resumption(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            client.tls_resumption_stats(),
+            Some(crate::TlsResumptionStats::default())
+        );
+    }
+
     #[cfg(feature = "cluster-async")]
     #[test]
     fn give_slots_refresh_rate_limit_configurations() {
@@ -733,4 +1950,27 @@ mod tests {
             DEFAULT_SLOTS_REFRESH_MAX_JITTER_MILLI
         );
     }
+
+    #[test]
+    fn command_filter_matches_commands_and_subcommands_case_insensitively() {
+        let deny = CommandFilter::deny(["keys", "FlushAll", "config set"]);
+        assert!(deny.check(cmd("GET").arg("foo")).is_ok());
+        assert!(deny
+            .check(cmd("CONFIG").arg("GET").arg("maxmemory"))
+            .is_ok());
+        let err = deny.check(cmd("KEYS").arg("*")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CommandNotAllowed);
+        assert_eq!(err.detail(), Some("KEYS"));
+        let err = deny
+            .check(cmd("config").arg("set").arg("maxmemory").arg("1"))
+            .unwrap_err();
+        assert_eq!(err.detail(), Some("CONFIG SET"));
+        assert!(deny.check(&cmd("flushall")).is_err());
+
+        let allow = CommandFilter::allow(["GET", "CLIENT INFO"]);
+        assert!(allow.check(cmd("get").arg("foo")).is_ok());
+        assert!(allow.check(cmd("CLIENT").arg("INFO")).is_ok());
+        assert!(allow.check(cmd("CLIENT").arg("KILL")).is_err());
+        assert!(allow.check(cmd("SET").arg("foo").arg("bar")).is_err());
+    }
 }
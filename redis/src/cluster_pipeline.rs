@@ -1,5 +1,6 @@
-use crate::cluster::ClusterConnection;
+use crate::cluster::{ClusterConnection, Connect};
 use crate::cmd::{cmd, Cmd};
+use crate::connection::ConnectionLike;
 use crate::types::{
     from_owned_redis_value, ErrorKind, FromRedisValue, HashSet, RedisResult, ToRedisArgs, Value,
 };
@@ -103,20 +104,7 @@ impl ClusterPipeline {
     /// ```
     #[inline]
     pub fn query<T: FromRedisValue>(&self, con: &mut ClusterConnection) -> RedisResult<T> {
-        for cmd in &self.commands {
-            let cmd_name = std::str::from_utf8(cmd.arg_idx(0).unwrap_or(b""))
-                .unwrap_or("")
-                .trim()
-                .to_ascii_uppercase();
-
-            if is_illegal_cmd(&cmd_name) {
-                fail!((
-                    UNROUTABLE_ERROR.0,
-                    UNROUTABLE_ERROR.1,
-                    format!("Command '{cmd_name}' can't be executed in a cluster pipeline.")
-                ))
-            }
-        }
+        self.check_commands()?;
 
         from_owned_redis_value(if self.commands.is_empty() {
             Value::Array(vec![])
@@ -141,6 +129,48 @@ impl ClusterPipeline {
     pub fn execute(&self, con: &mut ClusterConnection) {
         self.query::<()>(con).unwrap();
     }
+
+    /// Checks that every command of the pipeline can be routed, without executing any of them, and returns the
+    /// address of the node that each command would be sent to, in the order of the commands:
+    ///
+    /// ```rust,no_run
+    /// # let nodes = vec!["redis://127.0.0.1:6379/"];
+    /// # let client = redis::cluster::ClusterClient::new(nodes).unwrap();
+    /// # let con = client.get_connection(None).unwrap();
+    /// let mut pipe = redis::cluster::cluster_pipe();
+    /// pipe.cmd("SET").arg("key_1").arg(42).cmd("GET").arg("key_2");
+    /// let addresses = pipe.check_routes(&con).unwrap();
+    /// assert_eq!(addresses.len(), 2);
+    /// ```
+    ///
+    /// The routes are resolved by the connection's current slot map, so they may change if the cluster's
+    /// topology changes before the pipeline is executed. Commands that aren't bound to a slot are routed to a
+    /// random node.
+    pub fn check_routes<C>(&self, con: &ClusterConnection<C>) -> RedisResult<Vec<String>>
+    where
+        C: ConnectionLike + Connect,
+    {
+        self.check_commands()?;
+        con.route_pipeline(self)
+    }
+
+    fn check_commands(&self) -> RedisResult<()> {
+        for cmd in &self.commands {
+            let cmd_name = std::str::from_utf8(cmd.arg_idx(0).unwrap_or(b""))
+                .unwrap_or("")
+                .trim()
+                .to_ascii_uppercase();
+
+            if is_illegal_cmd(&cmd_name) {
+                fail!((
+                    UNROUTABLE_ERROR.0,
+                    UNROUTABLE_ERROR.1,
+                    format!("Command '{cmd_name}' can't be executed in a cluster pipeline.")
+                ))
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Shortcut for creating a new cluster pipeline.
@@ -1,10 +1,19 @@
-use std::cmp::min;
+//! The types in this module are part of the crate's public API, and follow its semver guarantees: changing or
+//! removing a type, a variant or a constructor is a breaking change, and is only done in a major release. They
+//! should be built with their variants and their constructors, such as
+//! [`Route::for_key`](crate::cluster_routing::Route::for_key),
+//! [`RoutingInfo::all_primaries`](crate::cluster_routing::RoutingInfo::all_primaries) or
+//! [`MultipleNodeRoutingInfo::for_keys`](crate::cluster_routing::MultipleNodeRoutingInfo::for_keys), rather than
+//! with any hidden item.
+
+use std::cmp::{max, min};
 use std::collections::HashMap;
+use std::time::Duration;
 
 use crate::cluster_topology::get_slot;
 use crate::cmd::{Arg, Cmd};
 use crate::types::Value;
-use crate::{ErrorKind, RedisResult};
+use crate::{ErrorKind, RedisError, RedisResult};
 use std::iter::Once;
 
 #[derive(Clone)]
@@ -23,12 +32,14 @@ pub enum LogicalAggregateOp {
 
 /// Numerical aggreagting operators.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
 pub enum AggregateOp {
     /// Choose minimal value
     Min,
     /// Sum all values
     Sum,
-    // Max, omitted due to dead code warnings. ATM this value isn't constructed anywhere
+    /// Choose maximal value
+    Max,
 }
 
 /// Policy defining how to combine multiple responses into one.
@@ -45,8 +56,12 @@ pub enum ResponsePolicy {
     /// Aggregate success results according to a numeric operator. Return error on any failed request or on a response that isn't an integer.
     Aggregate(AggregateOp),
     /// Aggregate array responses into a single array. Return error on any failed request or on a response that isn't an array.
+    /// When the request was split by [`MultipleNodeRoutingInfo::MultiSlot`], the combined array holds the results in the
+    /// order of the original command's keys.
     CombineArrays,
-    /// Handling is not defined by the Redis standard. Will receive a special case
+    /// Handling is not defined by the Redis standard. Will receive a special case.
+    /// The responses are returned as a map from the nodes' addresses to their responses, in which the nodes that
+    /// failed have a [`Value::Error`]. Returns an error if all requests fail.
     Special,
 }
 
@@ -75,6 +90,32 @@ pub enum SingleNodeRoutingInfo {
     },
 }
 
+impl SingleNodeRoutingInfo {
+    /// Routes to the node that serves the slot of `key`.
+    pub fn for_key(key: &[u8], slot_addr: SlotAddr) -> Self {
+        SingleNodeRoutingInfo::SpecificNode(Route::for_key(key, slot_addr))
+    }
+
+    /// Routes to the node with the given address.
+    pub fn by_address(host: impl Into<String>, port: u16) -> Self {
+        SingleNodeRoutingInfo::ByAddress {
+            host: host.into(),
+            port,
+        }
+    }
+
+    /// Routes a read-only request to the node of its slot that `read_preference` chooses. Routes that must reach the
+    /// primary, and routes that don't depend on a slot, are kept.
+    pub fn with_read_preference(self, read_preference: ReadPreference) -> Self {
+        match self {
+            SingleNodeRoutingInfo::SpecificNode(route) => {
+                SingleNodeRoutingInfo::SpecificNode(route.with_read_preference(read_preference))
+            }
+            routing => routing,
+        }
+    }
+}
+
 impl From<Option<Route>> for SingleNodeRoutingInfo {
     fn from(value: Option<Route>) -> Self {
         value
@@ -91,9 +132,33 @@ pub enum MultipleNodeRoutingInfo {
     /// Route to all primaries in the cluster
     AllMasters,
     /// Instructions for how to split a multi-slot command (e.g. MGET, MSET) into sub-commands. Each tuple is the route for each subcommand, and the indices of the arguments from the original command that should be copied to the subcommand.
+    ///
+    /// [`RoutingInfo::for_routable`] orders the sub-commands by the first appearance of their slots in the command's
+    /// keys, so the same command is always split the same way. The results of the sub-commands are placed back by
+    /// these indices, so a combined result follows the order of the original command's keys, even if some
+    /// sub-commands were retried or redirected.
     MultiSlot(Vec<(Route, Vec<usize>)>),
 }
 
+impl MultipleNodeRoutingInfo {
+    /// Splits a command whose arguments are all keys, such as `MGET` or `DEL`, by the slots of its keys. The
+    /// sub-commands are ordered by the first appearance of their slots in `keys`.
+    pub fn for_keys<K: AsRef<[u8]>>(
+        keys: impl IntoIterator<Item = K>,
+        slot_addr: SlotAddr,
+    ) -> Self {
+        let mut routes: Vec<(Route, Vec<usize>)> = Vec::new();
+        for (index, key) in keys.into_iter().enumerate() {
+            let route = Route::for_key(key.as_ref(), slot_addr);
+            match routes.iter_mut().find(|(existing, _)| *existing == route) {
+                Some((_, indices)) => indices.push(index),
+                None => routes.push((route, vec![index])),
+            }
+        }
+        MultipleNodeRoutingInfo::MultiSlot(routes)
+    }
+}
+
 /// Takes a routable and an iterator of indices, which is assued to be created from`MultipleNodeRoutingInfo::MultiSlot`,
 /// and returns a command with the arguments matching the indices.
 pub fn command_for_multi_slot_indices<'a, 'b>(
@@ -112,39 +177,143 @@ where
     new_cmd
 }
 
+// A numeric response, which is aggregated as an integer until a double is met.
+#[derive(Clone, Copy)]
+enum Number {
+    Int(i64),
+    Double(f64),
+}
+
+impl Number {
+    fn from_value(value: Value) -> RedisResult<Self> {
+        match value {
+            Value::Int(int) => Ok(Number::Int(int)),
+            Value::Double(double) => Ok(Number::Double(double)),
+            Value::Boolean(boolean) => Ok(Number::Int(boolean as i64)),
+            _ => Err((
+                ErrorKind::TypeError,
+                "expected array of integers as response",
+            )
+                .into()),
+        }
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::Int(int) => int as f64,
+            Number::Double(double) => double,
+        }
+    }
+
+    fn apply(self, other: Number, op: AggregateOp) -> Number {
+        match (self, other) {
+            (Number::Int(a), Number::Int(b)) => Number::Int(match op {
+                AggregateOp::Min => min(a, b),
+                AggregateOp::Sum => a + b,
+                AggregateOp::Max => max(a, b),
+            }),
+            (a, b) => {
+                let (a, b) = (a.as_f64(), b.as_f64());
+                Number::Double(match op {
+                    AggregateOp::Min => a.min(b),
+                    AggregateOp::Sum => a + b,
+                    AggregateOp::Max => a.max(b),
+                })
+            }
+        }
+    }
+}
+
+/// Aggregates map responses by their keys, in the order in which the keys first appear. The value of each key is
+/// aggregated by `aggregate_key` from its values in all the responses that have the key.
+fn aggregate_maps(
+    values: Vec<Value>,
+    aggregate_key: impl Fn(Vec<Value>) -> RedisResult<Value>,
+) -> RedisResult<Value> {
+    let mut keys: Vec<(Value, Vec<Value>)> = Vec::new();
+    for value in values {
+        let Value::Map(pairs) = value else {
+            return Err((ErrorKind::TypeError, "expected map of values as response").into());
+        };
+        for (key, value) in pairs {
+            match keys.iter_mut().find(|(existing, _)| *existing == key) {
+                Some((_, key_values)) => key_values.push(value),
+                None => keys.push((key, vec![value])),
+            }
+        }
+    }
+    keys.into_iter()
+        .map(|(key, values)| Ok((key, aggregate_key(values)?)))
+        .collect::<RedisResult<_>>()
+        .map(Value::Map)
+}
+
 /// Aggreagte numeric responses.
+///
+/// Integers, RESP3 doubles and RESP3 booleans, which count as 0 and 1, are aggregated together, and the result is a
+/// double if one of the responses is a double. RESP3 map responses are aggregated by their keys.
 pub fn aggregate(values: Vec<Value>, op: AggregateOp) -> RedisResult<Value> {
+    if values.iter().any(|value| matches!(value, Value::Map(_))) {
+        return aggregate_maps(values, |values| aggregate(values, op));
+    }
     let initial_value = match op {
-        AggregateOp::Min => i64::MAX,
-        AggregateOp::Sum => 0,
+        AggregateOp::Min => Number::Int(i64::MAX),
+        AggregateOp::Sum => Number::Int(0),
+        AggregateOp::Max => Number::Int(i64::MIN),
     };
     let result = values.into_iter().try_fold(initial_value, |acc, curr| {
-        let int = match curr {
-            Value::Int(int) => int,
-            _ => {
-                return RedisResult::Err(
-                    (
-                        ErrorKind::TypeError,
-                        "expected array of integers as response",
-                    )
-                        .into(),
-                );
-            }
-        };
-        let acc = match op {
-            AggregateOp::Min => min(acc, int),
-            AggregateOp::Sum => acc + int,
-        };
-        Ok(acc)
+        Ok::<_, RedisError>(acc.apply(Number::from_value(curr)?, op))
     })?;
-    Ok(Value::Int(result))
+    Ok(match result {
+        Number::Int(int) => Value::Int(int),
+        Number::Double(double) => Value::Double(double),
+    })
+}
+
+// Returns the truth of a response of a logical aggregation, and whether it's a RESP3 boolean.
+fn truth(value: Value) -> RedisResult<(bool, bool)> {
+    match value {
+        Value::Int(int) => Ok((int > 0, false)),
+        Value::Boolean(boolean) => Ok((boolean, true)),
+        _ => Err((
+            ErrorKind::TypeError,
+            "expected array of integers as response",
+        )
+            .into()),
+    }
+}
+
+fn logical_value(result: bool, as_boolean: bool) -> Value {
+    if as_boolean {
+        Value::Boolean(result)
+    } else {
+        Value::Int(result as i64)
+    }
 }
 
 /// Aggreagte numeric responses by a boolean operator.
+///
+/// The responses are arrays whose elements are aggregated by their index, or RESP3 maps whose values are
+/// aggregated by their keys. The elements are integers, which are true when they're positive, or RESP3 booleans, in
+/// which case the results are booleans too.
 pub fn logical_aggregate(values: Vec<Value>, op: LogicalAggregateOp) -> RedisResult<Value> {
     let initial_value = match op {
         LogicalAggregateOp::And => true,
     };
+    if values.iter().any(|value| matches!(value, Value::Map(_))) {
+        return aggregate_maps(values, |values| {
+            let mut as_boolean = false;
+            let result = values.into_iter().try_fold(initial_value, |acc, value| {
+                let (truth, is_boolean) = truth(value)?;
+                as_boolean |= is_boolean;
+                Ok::<_, RedisError>(match op {
+                    LogicalAggregateOp::And => acc && truth,
+                })
+            })?;
+            Ok(logical_value(result, as_boolean))
+        });
+    }
+    let mut as_boolean = false;
     let results = values.into_iter().try_fold(Vec::new(), |acc, curr| {
         let values = match curr {
             Value::Array(values) => values,
@@ -164,18 +333,10 @@ pub fn logical_aggregate(values: Vec<Value>, op: LogicalAggregateOp) -> RedisRes
             acc
         };
         for (index, value) in values.into_iter().enumerate() {
-            let int = match value {
-                Value::Int(int) => int,
-                _ => {
-                    return Err((
-                        ErrorKind::TypeError,
-                        "expected array of integers as response",
-                    )
-                        .into());
-                }
-            };
+            let (truth, is_boolean) = truth(value)?;
+            as_boolean |= is_boolean;
             acc[index] = match op {
-                LogicalAggregateOp::And => acc[index] && (int > 0),
+                LogicalAggregateOp::And => acc[index] && truth,
             };
         }
         Ok(acc)
@@ -183,11 +344,43 @@ pub fn logical_aggregate(values: Vec<Value>, op: LogicalAggregateOp) -> RedisRes
     Ok(Value::Array(
         results
             .into_iter()
-            .map(|result| Value::Int(result as i64))
+            .map(|result| logical_value(result, as_boolean))
             .collect(),
     ))
 }
 
+/// Maps the results of a command that was sent to multiple nodes by the nodes' addresses, with the errors of the
+/// nodes that failed as [`Value::Error`]s, so that the successes of the other nodes aren't lost.
+///
+/// Fails if all the nodes failed, or with the error of a request that failed before a node was chosen for it.
+pub(crate) fn map_results_by_node<A: AsRef<str>>(
+    results: Vec<(Option<A>, RedisResult<Value>)>,
+) -> RedisResult<Value> {
+    if results.iter().all(|(_, result)| result.is_err()) {
+        if let Some((_, Err(err))) = results.into_iter().next() {
+            return Err(err);
+        }
+        return Ok(Value::Map(vec![]));
+    }
+    results
+        .into_iter()
+        .map(|(addr, result)| match addr {
+            Some(addr) => Ok((
+                Value::BulkString(addr.as_ref().as_bytes().to_vec()),
+                result.unwrap_or_else(Value::Error),
+            )),
+            None => Err(result.err().unwrap_or_else(|| {
+                (
+                    ErrorKind::ClientError,
+                    "A response has no node to map it to",
+                )
+                    .into()
+            })),
+        })
+        .collect::<RedisResult<Vec<_>>>()
+        .map(Value::Map)
+}
+
 /// Aggreagte arrau responses into a single array.
 pub fn combine_array_results(values: Vec<Value>) -> RedisResult<Value> {
     let mut results = Vec::new();
@@ -208,31 +401,45 @@ pub fn combine_array_results(values: Vec<Value>) -> RedisResult<Value> {
 /// into a single array. `sorting_order` defines the order of the results in the returned array -
 /// for each array of results, `sorting_order` should contain a matching array with the indices of
 /// the results in the final array.
+///
+/// Since each result is placed by the indices of its own sub-command, the combined array follows the order of the
+/// original command's keys, regardless of the order in which the sub-commands completed, were retried or were
+/// redirected. A result whose length doesn't match its indices returns an error.
 pub(crate) fn combine_and_sort_array_results<'a>(
     values: Vec<Value>,
     sorting_order: impl ExactSizeIterator<Item = &'a Vec<usize>>,
 ) -> RedisResult<Value> {
-    let mut results = Vec::new();
-    results.resize(
-        values.iter().fold(0, |acc, value| match value {
-            Value::Array(values) => values.len() + acc,
-            _ => 0,
-        }),
-        Value::Nil,
-    );
-    assert_eq!(values.len(), sorting_order.len());
+    if values.len() != sorting_order.len() {
+        return Err((
+            ErrorKind::ClientError,
+            "Mismatch between the number of results and sub-commands",
+            format!(
+                "{} results for {} sub-commands",
+                values.len(),
+                sorting_order.len()
+            ),
+        )
+            .into());
+    }
 
+    let mut results = Vec::new();
     for (key_indices, value) in sorting_order.into_iter().zip(values) {
-        match value {
-            Value::Array(values) => {
-                assert_eq!(values.len(), key_indices.len());
-                for (index, value) in key_indices.iter().zip(values) {
-                    results[*index] = value;
-                }
-            }
-            _ => {
-                return Err((ErrorKind::TypeError, "expected array of values as response").into());
+        let Value::Array(values) = value else {
+            return Err((ErrorKind::TypeError, "expected array of values as response").into());
+        };
+        if values.len() != key_indices.len() {
+            return Err((
+                ErrorKind::TypeError,
+                "Mismatch between the number of values and keys in a sub-command response",
+                format!("{} values for {} keys", values.len(), key_indices.len()),
+            )
+                .into());
+        }
+        for (index, value) in key_indices.iter().zip(values) {
+            if *index >= results.len() {
+                results.resize(*index + 1, Value::Nil);
             }
+            results[*index] = value;
         }
     }
 
@@ -256,6 +463,9 @@ fn get_route(is_readonly: bool, key: &[u8]) -> Route {
 /// `MultipleNodeRoutingInfo::MultiSlot` contains the routes for each sub-command, and
 /// the indices in the final combined result for each result from the sub-command.
 ///
+/// The sub-commands are ordered by the first appearance of their slots in the command's keys, and the indices of
+/// each sub-command are in increasing order.
+///
 /// If all keys are routed to the same slot, there's no need to split the command,
 /// so a single node routing info will be returned.
 fn multi_shard<R>(
@@ -268,12 +478,17 @@ where
     R: Routable + ?Sized,
 {
     let is_readonly = is_readonly_cmd(cmd);
-    let mut routes = HashMap::new();
+    // The routes are kept in the order of the first appearance of their slots, so that the split is deterministic.
+    let mut routes: Vec<(Route, Vec<usize>)> = Vec::new();
+    let mut route_positions = HashMap::new();
     let mut key_index = 0;
     while let Some(key) = routable.arg_idx(first_key_index + key_index) {
         let route = get_route(is_readonly, key);
-        let entry = routes.entry(route);
-        let keys = entry.or_insert(Vec::new());
+        let position = *route_positions.entry(route).or_insert_with(|| {
+            routes.push((route, Vec::new()));
+            routes.len() - 1
+        });
+        let keys = &mut routes[position].1;
         keys.push(key_index);
 
         if has_values {
@@ -284,7 +499,6 @@ where
         key_index += 1;
     }
 
-    let mut routes: Vec<(Route, Vec<usize>)> = routes.into_iter().collect();
     Some(if routes.len() == 1 {
         RoutingInfo::SingleNode(SingleNodeRoutingInfo::SpecificNode(routes.pop().unwrap().0))
     } else {
@@ -310,9 +524,8 @@ impl ResponsePolicy {
             | b"CLIENT SETINFO" | b"CONFIG SET" | b"CONFIG RESETSTAT" | b"CONFIG REWRITE"
             | b"FLUSHALL" | b"FLUSHDB" | b"FUNCTION DELETE" | b"FUNCTION FLUSH"
             | b"FUNCTION LOAD" | b"FUNCTION RESTORE" | b"MEMORY PURGE" | b"MSET" | b"PING"
-            | b"SCRIPT FLUSH" | b"SCRIPT LOAD" | b"SLOWLOG RESET" | b"UNWATCH" | b"WATCH" => {
-                Some(ResponsePolicy::AllSucceeded)
-            }
+            | b"SCRIPT FLUSH" | b"SCRIPT LOAD" | b"SLOWLOG RESET" | b"SUNSUBSCRIBE"
+            | b"UNWATCH" | b"WATCH" => Some(ResponsePolicy::AllSucceeded),
 
             b"KEYS" | b"MGET" | b"SLOWLOG GET" => Some(ResponsePolicy::CombineArrays),
 
@@ -347,6 +560,7 @@ enum RouteBy {
     SecondArg,
     SecondArgAfterKeyCount,
     SecondArgSlot,
+    ShardChannel,
     StreamsIndex,
     ThirdArgAfterKeyCount,
     Undefined,
@@ -389,7 +603,7 @@ fn base_routing(cmd: &[u8]) -> RouteBy {
         b"MSET" => RouteBy::MultiShardWithValues,
 
         // TODO - special handling - b"SCAN"
-        b"SCAN" | b"SHUTDOWN" | b"SLAVEOF" | b"REPLICAOF" => RouteBy::Undefined,
+        b"SCAN" | b"SHUTDOWN" | b"SLAVEOF" | b"REPLICAOF" | b"FAILOVER" => RouteBy::Undefined,
 
         b"BLMPOP" | b"BZMPOP" | b"EVAL" | b"EVALSHA" | b"EVALSHA_RO" | b"EVAL_RO" | b"FCALL"
         | b"FCALL_RO" => RouteBy::ThirdArgAfterKeyCount,
@@ -408,7 +622,8 @@ fn base_routing(cmd: &[u8]) -> RouteBy {
         | b"OBJECT ENCODING"
         | b"OBJECT FREQ"
         | b"OBJECT IDLETIME"
-        | b"OBJECT REFCOUNT" => RouteBy::SecondArg,
+        | b"OBJECT REFCOUNT"
+        | b"DEBUG OBJECT" => RouteBy::SecondArg,
 
         b"LMPOP" | b"SINTERCARD" | b"ZDIFF" | b"ZINTER" | b"ZINTERCARD" | b"ZMPOP" | b"ZUNION" => {
             RouteBy::SecondArgAfterKeyCount
@@ -416,6 +631,8 @@ fn base_routing(cmd: &[u8]) -> RouteBy {
 
         b"XREAD" | b"XREADGROUP" => RouteBy::StreamsIndex,
 
+        b"SPUBLISH" | b"SSUBSCRIBE" | b"SUNSUBSCRIBE" => RouteBy::ShardChannel,
+
         // keyless commands with more arguments, whose arguments might be wrongly taken to be keys.
         // TODO - double check these, in order to find better ways to route some of them.
         b"ACL DRYRUN"
@@ -484,11 +701,60 @@ fn base_routing(cmd: &[u8]) -> RouteBy {
         | b"CLUSTER GETKEYSINSLOT"
         | b"CLUSTER SETSLOT" => RouteBy::SecondArgSlot,
 
+        // The other DEBUG subcommands don't take keys.
+        cmd if cmd.starts_with(b"DEBUG ") => RouteBy::Random,
+
         _ => RouteBy::FirstKey,
     }
 }
 
 impl RoutingInfo {
+    /// Routes to a random node.
+    pub fn random() -> Self {
+        RoutingInfo::SingleNode(SingleNodeRoutingInfo::Random)
+    }
+
+    /// Routes to the node that serves the slot of `key`.
+    pub fn for_key(key: &[u8], slot_addr: SlotAddr) -> Self {
+        RoutingInfo::SingleNode(SingleNodeRoutingInfo::for_key(key, slot_addr))
+    }
+
+    /// Routes to the node with the given address.
+    pub fn by_address(host: impl Into<String>, port: u16) -> Self {
+        RoutingInfo::SingleNode(SingleNodeRoutingInfo::by_address(host, port))
+    }
+
+    /// Routes to all the primaries, and combines their responses by `response_policy`. Without a policy, the
+    /// responses are returned per node.
+    pub fn all_primaries(response_policy: Option<ResponsePolicy>) -> Self {
+        RoutingInfo::MultiNode((MultipleNodeRoutingInfo::AllMasters, response_policy))
+    }
+
+    /// Routes to all the nodes, and combines their responses by `response_policy`. Without a policy, the
+    /// responses are returned per node.
+    pub fn all_nodes(response_policy: Option<ResponsePolicy>) -> Self {
+        RoutingInfo::MultiNode((MultipleNodeRoutingInfo::AllNodes, response_policy))
+    }
+
+    /// Routes a read-only request to the nodes of its slots that `read_preference` chooses, including each
+    /// sub-command of a multi-slot request. Routes that must reach the primaries, and routes that don't depend on
+    /// a slot, are kept.
+    pub fn with_read_preference(self, read_preference: ReadPreference) -> Self {
+        match self {
+            RoutingInfo::SingleNode(routing) => {
+                RoutingInfo::SingleNode(routing.with_read_preference(read_preference))
+            }
+            RoutingInfo::MultiNode((MultipleNodeRoutingInfo::MultiSlot(routes), policy)) => {
+                let routes = routes
+                    .into_iter()
+                    .map(|(route, indices)| (route.with_read_preference(read_preference), indices))
+                    .collect();
+                RoutingInfo::MultiNode((MultipleNodeRoutingInfo::MultiSlot(routes), policy))
+            }
+            routing => routing,
+        }
+    }
+
     /// Returns true if the `cmd` should be routed to all nodes.
     pub fn is_all_nodes(cmd: &[u8]) -> bool {
         matches!(base_routing(cmd), RouteBy::AllNodes)
@@ -505,9 +771,49 @@ impl RoutingInfo {
             | RouteBy::StreamsIndex
             | RouteBy::MultiShardNoValues
             | RouteBy::MultiShardWithValues => true,
-            RouteBy::AllNodes | RouteBy::AllPrimaries | RouteBy::Random | RouteBy::Undefined => {
-                false
+            // Shard channels are hashed to slots like keys, but they aren't keys.
+            RouteBy::ShardChannel
+            | RouteBy::AllNodes
+            | RouteBy::AllPrimaries
+            | RouteBy::Random
+            | RouteBy::Undefined => false,
+        }
+    }
+
+    /// Returns the routing info for `r` if its routing is known, or an error otherwise, as required by
+    /// [`ClusterClientBuilder::strict_routing`](crate::cluster::ClusterClientBuilder::strict_routing).
+    ///
+    /// The routing is unknown for commands that can't be routed by their arguments, such as `SCAN` or a key-based
+    /// command without a key, and for module commands, whose names contain a dot, since it isn't known which of
+    /// their arguments are keys.
+    pub(crate) fn for_routable_strict<R>(r: &R) -> RedisResult<RoutingInfo>
+    where
+        R: Routable + ?Sized,
+    {
+        let unknown = || -> RedisResult<RoutingInfo> {
+            let name = r
+                .command()
+                .map(|cmd| String::from_utf8_lossy(&cmd).into_owned())
+                .unwrap_or_default();
+            Err((
+                ErrorKind::ClientError,
+                "The routing of the command is unknown, and strict routing is enabled",
+                name,
+            )
+                .into())
+        };
+        match r.command() {
+            // Commands that aren't in the routing table are routed by their first argument, or randomly without one.
+            Some(cmd)
+                if matches!(base_routing(&cmd), RouteBy::FirstKey) && r.arg_idx(1).is_none() =>
+            {
+                unknown()
             }
+            Some(cmd) if !cmd.contains(&b'.') => match Self::for_routable(r) {
+                Some(routing) => Ok(routing),
+                None => unknown(),
+            },
+            _ => unknown(),
         }
     }
 
@@ -542,11 +848,14 @@ impl RoutingInfo {
                 if key_count == 0 {
                     Some(RoutingInfo::SingleNode(SingleNodeRoutingInfo::Random))
                 } else {
-                    r.arg_idx(3).map(|key| RoutingInfo::for_key(cmd, key))
+                    r.arg_idx(3)
+                        .map(|key| RoutingInfo::for_command_key(cmd, key))
                 }
             }
 
-            RouteBy::SecondArg => r.arg_idx(2).map(|key| RoutingInfo::for_key(cmd, key)),
+            RouteBy::SecondArg => r
+                .arg_idx(2)
+                .map(|key| RoutingInfo::for_command_key(cmd, key)),
 
             RouteBy::SecondArgAfterKeyCount => {
                 let key_count = r
@@ -556,14 +865,15 @@ impl RoutingInfo {
                 if key_count == 0 {
                     Some(RoutingInfo::SingleNode(SingleNodeRoutingInfo::Random))
                 } else {
-                    r.arg_idx(2).map(|key| RoutingInfo::for_key(cmd, key))
+                    r.arg_idx(2)
+                        .map(|key| RoutingInfo::for_command_key(cmd, key))
                 }
             }
 
             RouteBy::StreamsIndex => {
                 let streams_position = r.position(b"STREAMS")?;
                 r.arg_idx(streams_position + 1)
-                    .map(|key| RoutingInfo::for_key(cmd, key))
+                    .map(|key| RoutingInfo::for_command_key(cmd, key))
             }
 
             RouteBy::SecondArgSlot => r
@@ -577,8 +887,23 @@ impl RoutingInfo {
                     )))
                 }),
 
+            // Shard channels are served by the primary that owns their slot, even when reading from replicas.
+            // Without channels, `SUNSUBSCRIBE` unsubscribes from all the shard channels, on all the primaries.
+            RouteBy::ShardChannel => match r.arg_idx(1) {
+                Some(channel) => Some(RoutingInfo::SingleNode(
+                    SingleNodeRoutingInfo::SpecificNode(Route::new(
+                        get_slot(channel),
+                        SlotAddr::Master,
+                    )),
+                )),
+                None => Some(RoutingInfo::MultiNode((
+                    MultipleNodeRoutingInfo::AllMasters,
+                    ResponsePolicy::for_command(cmd),
+                ))),
+            },
+
             RouteBy::FirstKey => match r.arg_idx(1) {
-                Some(key) => Some(RoutingInfo::for_key(cmd, key)),
+                Some(key) => Some(RoutingInfo::for_command_key(cmd, key)),
                 None => Some(RoutingInfo::SingleNode(SingleNodeRoutingInfo::Random)),
             },
 
@@ -586,7 +911,7 @@ impl RoutingInfo {
         }
     }
 
-    fn for_key(cmd: &[u8], key: &[u8]) -> RoutingInfo {
+    fn for_command_key(cmd: &[u8], key: &[u8]) -> RoutingInfo {
         RoutingInfo::SingleNode(SingleNodeRoutingInfo::SpecificNode(get_route(
             is_readonly_cmd(cmd),
             key,
@@ -595,13 +920,99 @@ impl RoutingInfo {
 }
 
 /// Returns true if the given `routable` represents a readonly command.
-pub fn is_readonly(routable: &impl Routable) -> bool {
+pub fn is_readonly<R>(routable: &R) -> bool
+where
+    R: Routable + ?Sized,
+{
     match routable.command() {
         Some(cmd) => is_readonly_cmd(cmd.as_slice()),
         None => false,
     }
 }
 
+/// Returns how long the given `routable` blocks the connection that it's sent on, or `None` if it isn't a blocking
+/// command. Commands that block until they're served, or whose timeout can't be parsed, return [`Duration::MAX`].
+pub(crate) fn blocking_timeout<R>(routable: &R) -> Option<Duration>
+where
+    R: Routable + ?Sized,
+{
+    // The timeouts of the list and sorted set commands are in seconds, and the timeouts of the stream commands are
+    // in milliseconds.
+    let (timeout, unit) = match routable.command()?.as_slice() {
+        b"BLPOP" | b"BRPOP" | b"BRPOPLPUSH" | b"BLMOVE" | b"BZPOPMIN" | b"BZPOPMAX" => {
+            let last = (1..)
+                .take_while(|idx| routable.arg_idx(*idx).is_some())
+                .last()?;
+            (routable.arg_idx(last), 1.0)
+        }
+        b"BLMPOP" | b"BZMPOP" => (routable.arg_idx(1), 1.0),
+        b"XREAD" | b"XREADGROUP" => {
+            let streams = routable.position(b"STREAMS");
+            let block = routable
+                .position(b"BLOCK")
+                .filter(|block| streams.map_or(true, |streams| *block < streams))?;
+            (routable.arg_idx(block + 1), 0.001)
+        }
+        _ => return None,
+    };
+    let secs = timeout
+        .and_then(|timeout| std::str::from_utf8(timeout).ok())
+        .and_then(|timeout| timeout.parse::<f64>().ok())
+        .map(|timeout| timeout * unit);
+    Some(match secs {
+        // Durations above a year are as good as blocking until the command is served.
+        Some(secs) if secs > 0.0 && secs < 365.0 * 24.0 * 3600.0 => Duration::from_secs_f64(secs),
+        _ => Duration::MAX,
+    })
+}
+
+/// Returns the keys accessed by the given `routable`, in the order in which they appear in the command.
+///
+/// The keys are located using the same table that is used for routing commands in cluster mode, so keyless
+/// commands return an empty list, and commands that are routed by their first key only return that key.
+pub fn command_keys<R>(routable: &R) -> Vec<&[u8]>
+where
+    R: Routable + ?Sized,
+{
+    let Some(cmd) = routable.command() else {
+        return Vec::new();
+    };
+    let args_from = |start: usize| (start..).map_while(|idx| routable.arg_idx(idx));
+    let args_after_key_count = |key_count_idx: usize| {
+        let key_count = routable
+            .arg_idx(key_count_idx)
+            .and_then(|x| std::str::from_utf8(x).ok())
+            .and_then(|x| x.parse::<usize>().ok())
+            .unwrap_or(0);
+        args_from(key_count_idx + 1).take(key_count).collect()
+    };
+
+    match base_routing(&cmd) {
+        RouteBy::FirstKey => routable.arg_idx(1).into_iter().collect(),
+        RouteBy::SecondArg if cmd == b"BITOP" => args_from(2).collect(),
+        RouteBy::SecondArg => routable.arg_idx(2).into_iter().collect(),
+        RouteBy::SecondArgAfterKeyCount => args_after_key_count(1),
+        RouteBy::ThirdArgAfterKeyCount => args_after_key_count(2),
+        RouteBy::StreamsIndex => {
+            let Some(streams_position) = routable.position(b"STREAMS") else {
+                return Vec::new();
+            };
+            // The keys are followed by the same number of IDs
+            let streams_args: Vec<_> = args_from(streams_position + 1).collect();
+            let key_count = streams_args.len() / 2;
+            streams_args.into_iter().take(key_count).collect()
+        }
+        RouteBy::MultiShardNoValues => args_from(1).collect(),
+        RouteBy::MultiShardWithValues => args_from(1).step_by(2).collect(),
+        RouteBy::SecondArgSlot
+        | RouteBy::ShardChannel
+        | RouteBy::AllNodes
+        | RouteBy::AllPrimaries
+        | RouteBy::Random
+        | RouteBy::Undefined => Vec::new(),
+    }
+}
+
 /// Returns `true` if the given `cmd` is a readonly command.
 pub fn is_readonly_cmd(cmd: &[u8]) -> bool {
     matches!(
@@ -717,7 +1128,7 @@ pub trait Routable {
         let mut primary_command = match primary_command.as_slice() {
             b"XGROUP" | b"OBJECT" | b"SLOWLOG" | b"FUNCTION" | b"MODULE" | b"COMMAND"
             | b"PUBSUB" | b"CONFIG" | b"MEMORY" | b"XINFO" | b"CLIENT" | b"ACL" | b"SCRIPT"
-            | b"CLUSTER" | b"LATENCY" => primary_command,
+            | b"CLUSTER" | b"LATENCY" | b"DEBUG" => primary_command,
             _ => {
                 return Some(primary_command);
             }
@@ -818,6 +1229,7 @@ impl Slot {
 
 /// What type of node should a request be routed to, assuming read from replica is enabled.
 #[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+#[non_exhaustive]
 pub enum SlotAddr {
     /// The request must be routed to primary node
     Master,
@@ -827,13 +1239,38 @@ pub enum SlotAddr {
     /// The request must be routed to replica node, if one exists.
     /// For example, by user requested routing.
     ReplicaRequired,
+    /// The request may be routed to the node of the slot, primary or replica, with the lowest latency.
+    /// For example, by a [`ReadPreference::Nearest`] request.
+    Nearest,
+}
+
+/// Which node of a slot should serve a read-only request, regardless of the connection's read from replicas setting.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Hash)]
+pub enum ReadPreference {
+    /// Read from the primary of the slot.
+    Primary,
+    /// Read from a replica of the slot, or from the primary if the slot has no connected replica.
+    Replica,
+    /// Read from the node of the slot with the lowest latency. The latencies of the nodes are measured by the
+    /// periodic topology checks of the async cluster connection, so until they are measured, the primary is used.
+    Nearest,
+}
+
+impl From<ReadPreference> for SlotAddr {
+    fn from(value: ReadPreference) -> Self {
+        match value {
+            ReadPreference::Primary => SlotAddr::Master,
+            ReadPreference::Replica => SlotAddr::ReplicaRequired,
+            ReadPreference::Nearest => SlotAddr::Nearest,
+        }
+    }
 }
 
 /// This is just a simplified version of [`Slot`],
 /// which stores only the master and [optional] replica
 /// to avoid the need to choose a replica each time
 /// a command is executed
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Clone)]
 pub(crate) struct SlotAddrs {
     pub(crate) primary: String,
     pub(crate) replicas: Vec<String>,
@@ -869,6 +1306,11 @@ impl Route {
         Self(slot, slot_addr)
     }
 
+    /// Returns the route to the slot of `key`, which respects hash tags.
+    pub fn for_key(key: &[u8], slot_addr: SlotAddr) -> Self {
+        Self(get_slot(key), slot_addr)
+    }
+
     /// Returns the slot number of the route.
     pub fn slot(&self) -> u16 {
         self.0
@@ -878,15 +1320,26 @@ impl Route {
     pub fn slot_addr(&self) -> SlotAddr {
         self.1
     }
+
+    // Only the routes of read-only requests may be served by replicas, so the other routes are kept.
+    fn with_read_preference(self, read_preference: ReadPreference) -> Self {
+        match self.1 {
+            SlotAddr::Master => self,
+            _ => Self(self.0, read_preference.into()),
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        command_for_multi_slot_indices, AggregateOp, MultipleNodeRoutingInfo, ResponsePolicy,
-        Route, RoutingInfo, SingleNodeRoutingInfo, SlotAddr,
+        aggregate, blocking_timeout, command_for_multi_slot_indices, command_keys, is_readonly,
+        logical_aggregate, AggregateOp, LogicalAggregateOp, MultipleNodeRoutingInfo,
+        ReadPreference, ResponsePolicy, Route, RoutingInfo, SingleNodeRoutingInfo, SlotAddr,
+    };
+    use crate::{
+        cluster_topology::slot, cmd, parser::parse_redis_value, ErrorKind, RedisError, Value,
     };
-    use crate::{cluster_topology::slot, cmd, parser::parse_redis_value, Value};
     use core::panic;
 
     #[test]
@@ -911,6 +1364,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_strict_routing_rejects_commands_with_unknown_routing() {
+        assert_eq!(
+            RoutingInfo::for_routable_strict(cmd("GET").arg("foo")).unwrap(),
+            RoutingInfo::for_routable(cmd("GET").arg("foo")).unwrap()
+        );
+        assert_eq!(
+            RoutingInfo::for_routable_strict(&cmd("TIME")).unwrap(),
+            RoutingInfo::SingleNode(SingleNodeRoutingInfo::Random)
+        );
+
+        for unknown in [
+            cmd("SCAN").arg(0).clone(),
+            cmd("GET"),
+            cmd("JSON.GET").arg("foo").clone(),
+        ] {
+            let err = RoutingInfo::for_routable_strict(&unknown).unwrap_err();
+            assert_eq!(err.kind(), crate::ErrorKind::ClientError);
+        }
+    }
+
     #[test]
     fn test_routing_info() {
         let mut test_cmds = vec![];
@@ -1012,6 +1486,7 @@ mod tests {
             cmd("SHUTDOWN"),
             cmd("SLAVEOF"),
             cmd("REPLICAOF"),
+            cmd("FAILOVER"),
         ] {
             assert_eq!(
                 RoutingInfo::for_routable(&cmd),
@@ -1126,6 +1601,91 @@ mod tests {
             ]).unwrap()), Some(RoutingInfo::SingleNode(SingleNodeRoutingInfo::SpecificNode(Route(slot, SlotAddr::Master)))) if slot == 5210));
     }
 
+    #[test]
+    fn test_routing_info_constructors() {
+        assert_eq!(
+            RoutingInfo::for_key(b"foo", SlotAddr::Master),
+            RoutingInfo::for_routable(cmd("SET").arg("foo").arg(1)).unwrap()
+        );
+        assert_eq!(
+            RoutingInfo::all_primaries(Some(ResponsePolicy::AllSucceeded)),
+            RoutingInfo::for_routable(&cmd("FLUSHALL")).unwrap()
+        );
+        assert_eq!(
+            RoutingInfo::by_address("host", 6379),
+            RoutingInfo::SingleNode(SingleNodeRoutingInfo::ByAddress {
+                host: "host".to_string(),
+                port: 6379
+            })
+        );
+        assert_eq!(
+            MultipleNodeRoutingInfo::for_keys(
+                ["foo", "bar", "{foo}baz"],
+                SlotAddr::ReplicaOptional
+            ),
+            MultipleNodeRoutingInfo::MultiSlot(vec![
+                (Route::new(12182, SlotAddr::ReplicaOptional), vec![0, 2]),
+                (Route::new(5061, SlotAddr::ReplicaOptional), vec![1]),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_read_preference_only_changes_replica_routes() {
+        let get = RoutingInfo::for_routable(cmd("GET").arg("foo")).unwrap();
+        assert_eq!(
+            get.with_read_preference(ReadPreference::Nearest),
+            RoutingInfo::for_key(b"foo", SlotAddr::Nearest)
+        );
+
+        let set = RoutingInfo::for_routable(cmd("SET").arg("foo").arg(1)).unwrap();
+        assert_eq!(
+            set.clone().with_read_preference(ReadPreference::Replica),
+            set
+        );
+
+        let mget = RoutingInfo::for_routable(cmd("MGET").arg("foo").arg("bar")).unwrap();
+        assert_eq!(
+            mget.with_read_preference(ReadPreference::Primary),
+            RoutingInfo::MultiNode((
+                MultipleNodeRoutingInfo::MultiSlot(vec![
+                    (Route::new(12182, SlotAddr::Master), vec![0]),
+                    (Route::new(5061, SlotAddr::Master), vec![1]),
+                ]),
+                Some(ResponsePolicy::CombineArrays)
+            ))
+        );
+
+        let random = RoutingInfo::random();
+        assert_eq!(
+            random.clone().with_read_preference(ReadPreference::Replica),
+            random
+        );
+    }
+
+    #[test]
+    fn test_shard_channel_routing() {
+        for command in ["SPUBLISH", "SSUBSCRIBE", "SUNSUBSCRIBE"] {
+            let mut shard_cmd = cmd(command);
+            shard_cmd.arg("foo").arg("message");
+            assert_eq!(
+                RoutingInfo::for_routable(&shard_cmd),
+                Some(RoutingInfo::SingleNode(
+                    SingleNodeRoutingInfo::SpecificNode(Route::new(12182, SlotAddr::Master))
+                )),
+                "{command}"
+            );
+        }
+
+        assert_eq!(
+            RoutingInfo::for_routable(&cmd("SUNSUBSCRIBE")),
+            Some(RoutingInfo::MultiNode((
+                MultipleNodeRoutingInfo::AllMasters,
+                Some(ResponsePolicy::AllSucceeded)
+            )))
+        );
+    }
+
     #[test]
     fn test_multi_shard() {
         let mut cmd = cmd("DEL");
@@ -1161,6 +1721,109 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_multi_shard_routes_are_ordered_by_first_key_appearance() {
+        let mut cmd = cmd("MSET");
+        cmd.arg("baz")
+            .arg(1)
+            .arg("foo")
+            .arg(2)
+            .arg("{baz}a")
+            .arg(3)
+            .arg("bar")
+            .arg(4);
+        let routing = RoutingInfo::for_routable(&cmd);
+
+        assert_eq!(
+            routing,
+            Some(RoutingInfo::MultiNode((
+                MultipleNodeRoutingInfo::MultiSlot(vec![
+                    (Route(4813, SlotAddr::Master), vec![0, 1, 4, 5]),
+                    (Route(12182, SlotAddr::Master), vec![2, 3]),
+                    (Route(5061, SlotAddr::Master), vec![6, 7]),
+                ]),
+                Some(ResponsePolicy::AllSucceeded)
+            )))
+        );
+    }
+
+    // Splits a command with `keys` and the given values, answers each sub-command with its own arguments, and
+    // returns the combined result.
+    fn combine_split_command(name: &str, args: &[String]) -> Option<Vec<Value>> {
+        let mut original_cmd = cmd(name);
+        for arg in args {
+            original_cmd.arg(arg);
+        }
+        let Some(RoutingInfo::MultiNode((MultipleNodeRoutingInfo::MultiSlot(routes), _))) =
+            RoutingInfo::for_routable(&original_cmd)
+        else {
+            return None;
+        };
+        let responses = routes
+            .iter()
+            .map(|(_, indices)| {
+                let sub_cmd = command_for_multi_slot_indices(&original_cmd, indices.iter());
+                Value::Array(
+                    sub_cmd
+                        .args_iter()
+                        .skip(1)
+                        .map(|arg| match arg {
+                            crate::Arg::Simple(arg) => Value::BulkString(arg.to_vec()),
+                            crate::Arg::Cursor => unreachable!(),
+                        })
+                        .collect(),
+                )
+            })
+            .collect();
+        match super::combine_and_sort_array_results(
+            responses,
+            routes.iter().map(|(_, indices)| indices),
+        ) {
+            Ok(Value::Array(values)) => Some(values),
+            result => panic!("unexpected result: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn test_split_and_combined_results_keep_the_order_of_the_arguments() {
+        fn prop(keys: Vec<String>) -> bool {
+            let to_values = |args: &[String]| -> Vec<Value> {
+                args.iter()
+                    .map(|arg| Value::BulkString(arg.as_bytes().to_vec()))
+                    .collect()
+            };
+            let mget_keeps_order = combine_split_command("MGET", &keys)
+                .map_or(true, |combined| combined == to_values(&keys));
+
+            let pairs: Vec<String> = keys
+                .iter()
+                .enumerate()
+                .flat_map(|(index, key)| [key.clone(), index.to_string()])
+                .collect();
+            let mset_keeps_order = combine_split_command("MSET", &pairs)
+                .map_or(true, |combined| combined == to_values(&pairs));
+
+            mget_keeps_order && mset_keeps_order
+        }
+        quickcheck::quickcheck(prop as fn(Vec<String>) -> bool);
+    }
+
+    #[test]
+    fn test_combining_results_with_mismatched_lengths_returns_an_error() {
+        let res1 = Value::Array(vec![Value::Nil, Value::Okay]);
+        let res2 = Value::Array(vec![Value::Int(1)]);
+
+        let result =
+            super::combine_and_sort_array_results(vec![res1.clone()], [vec![0], vec![1]].iter());
+        assert!(result.is_err());
+
+        let result = super::combine_and_sort_array_results(
+            vec![res1, res2],
+            [vec![0, 2], vec![1, 3]].iter(),
+        );
+        assert_eq!(result.unwrap_err().kind(), crate::ErrorKind::TypeError);
+    }
+
     #[test]
     fn test_command_creation_for_multi_shard() {
         let mut original_cmd = cmd("DEL");
@@ -1172,13 +1835,13 @@ mod tests {
         let routing = RoutingInfo::for_routable(&original_cmd);
         let expected = [vec![0], vec![1, 3], vec![2]];
 
-        let mut indices: Vec<_> = match routing {
+        let indices: Vec<_> = match routing {
             Some(RoutingInfo::MultiNode((MultipleNodeRoutingInfo::MultiSlot(vec), _))) => {
                 vec.into_iter().map(|(_, indices)| indices).collect()
             }
             _ => panic!("unexpected routing: {routing:?}"),
         };
-        indices.sort_by(|prev, next| prev.iter().next().unwrap().cmp(next.iter().next().unwrap())); // sorting because the `for_routable` doesn't return values in a consistent order between runs.
+        assert_eq!(indices, expected);
 
         for (index, indices) in indices.into_iter().enumerate() {
             let cmd = command_for_multi_slot_indices(&original_cmd, indices.iter());
@@ -1233,4 +1896,280 @@ mod tests {
             ])
         );
     }
+
+    #[test]
+    fn test_mapping_results_by_node_keeps_the_successes_of_partial_failures() {
+        let results = super::map_results_by_node(vec![
+            (Some("node1:6379"), Ok(Value::Okay)),
+            (
+                Some("node2:6379"),
+                Err((ErrorKind::IoError, "connection closed").into()),
+            ),
+        ]);
+        assert_eq!(
+            results.unwrap(),
+            Value::Map(vec![
+                (Value::BulkString(b"node1:6379".to_vec()), Value::Okay),
+                (
+                    Value::BulkString(b"node2:6379".to_vec()),
+                    Value::Error((ErrorKind::IoError, "connection closed").into())
+                ),
+            ])
+        );
+
+        let err = super::map_results_by_node(vec![
+            (
+                Some("node1:6379"),
+                Err(RedisError::from((ErrorKind::BusyLoadingError, "loading"))),
+            ),
+            (
+                Some("node2:6379"),
+                Err((ErrorKind::IoError, "connection closed").into()),
+            ),
+        ])
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::BusyLoadingError);
+
+        let err = super::map_results_by_node(vec![
+            (Some("node1:6379"), Ok(Value::Okay)),
+            (None, Err((ErrorKind::IoError, "no connection").into())),
+        ])
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::IoError);
+    }
+
+    #[test]
+    fn test_is_readonly_classification() {
+        assert!(is_readonly(cmd("GET").arg("foo")));
+        assert!(is_readonly(cmd("xinfo").arg("stream").arg("foo")));
+        assert!(!is_readonly(cmd("SET").arg("foo").arg("bar")));
+        assert!(!is_readonly(cmd("XGROUP").arg("CREATE").arg("foo")));
+        assert!(!is_readonly(&cmd("")));
+    }
+
+    #[test]
+    fn test_command_keys() {
+        let keys = |cmd: &crate::Cmd| -> Vec<Vec<u8>> {
+            command_keys(cmd).into_iter().map(|k| k.to_vec()).collect()
+        };
+        let bytes = |keys: &[&str]| -> Vec<Vec<u8>> {
+            keys.iter().map(|k| k.as_bytes().to_vec()).collect()
+        };
+
+        assert_eq!(keys(cmd("GET").arg("foo")), bytes(&["foo"]));
+        assert_eq!(
+            keys(cmd("MGET").arg("foo").arg("bar")),
+            bytes(&["foo", "bar"])
+        );
+        assert_eq!(
+            keys(cmd("MSET").arg("foo").arg(1).arg("bar").arg(2)),
+            bytes(&["foo", "bar"])
+        );
+        assert_eq!(
+            keys(
+                cmd("EVAL")
+                    .arg("script")
+                    .arg(2)
+                    .arg("foo")
+                    .arg("bar")
+                    .arg("arg")
+            ),
+            bytes(&["foo", "bar"])
+        );
+        assert_eq!(
+            keys(cmd("ZUNION").arg(2).arg("foo").arg("bar").arg("WITHSCORES")),
+            bytes(&["foo", "bar"])
+        );
+        assert_eq!(
+            keys(
+                cmd("XREAD")
+                    .arg("COUNT")
+                    .arg(2)
+                    .arg("STREAMS")
+                    .arg("foo")
+                    .arg("bar")
+                    .arg(0)
+                    .arg(0)
+            ),
+            bytes(&["foo", "bar"])
+        );
+        assert_eq!(
+            keys(cmd("BITOP").arg("AND").arg("dest").arg("foo").arg("bar")),
+            bytes(&["dest", "foo", "bar"])
+        );
+        assert_eq!(
+            keys(cmd("XGROUP").arg("CREATE").arg("foo").arg("group").arg("$")),
+            bytes(&["foo"])
+        );
+        assert!(keys(cmd("PING").arg("foo")).is_empty());
+        assert!(keys(&cmd("INFO")).is_empty());
+        assert!(keys(cmd("CLUSTER").arg("SETSLOT").arg(1)).is_empty());
+        assert_eq!(keys(cmd("DEBUG").arg("OBJECT").arg("foo")), bytes(&["foo"]));
+        assert!(keys(cmd("DEBUG").arg("SLEEP").arg(1)).is_empty());
+    }
+
+    #[test]
+    fn test_blocking_timeout() {
+        use std::time::Duration;
+
+        assert_eq!(blocking_timeout(cmd("GET").arg("foo")), None);
+        assert_eq!(
+            blocking_timeout(cmd("BLPOP").arg("foo").arg("bar").arg(2)),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            blocking_timeout(cmd("brpoplpush").arg("foo").arg("bar").arg("0.5")),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            blocking_timeout(cmd("BZMPOP").arg(3).arg(1).arg("foo").arg("MIN")),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            blocking_timeout(cmd("BLPOP").arg("foo").arg(0)),
+            Some(Duration::MAX)
+        );
+        assert_eq!(
+            blocking_timeout(
+                cmd("XREAD")
+                    .arg("COUNT")
+                    .arg(1)
+                    .arg("BLOCK")
+                    .arg(1500)
+                    .arg("STREAMS")
+                    .arg("foo")
+                    .arg("$")
+            ),
+            Some(Duration::from_millis(1500))
+        );
+        // A key named `block` isn't the `BLOCK` option.
+        assert_eq!(
+            blocking_timeout(cmd("XREAD").arg("STREAMS").arg("block").arg("$")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_aggregate_integers_doubles_and_booleans() {
+        let sum = |values| aggregate(values, AggregateOp::Sum).unwrap();
+        let min = |values| aggregate(values, AggregateOp::Min).unwrap();
+
+        assert_eq!(sum(vec![Value::Int(1), Value::Int(2)]), Value::Int(3));
+        assert_eq!(min(vec![Value::Int(3), Value::Int(2)]), Value::Int(2));
+        assert_eq!(
+            sum(vec![Value::Double(1.5), Value::Double(2.0)]),
+            Value::Double(3.5)
+        );
+        assert_eq!(
+            min(vec![Value::Double(1.5), Value::Double(-2.5)]),
+            Value::Double(-2.5)
+        );
+        // A double turns the result into a double.
+        assert_eq!(
+            sum(vec![Value::Int(1), Value::Double(0.5)]),
+            Value::Double(1.5)
+        );
+        assert_eq!(
+            min(vec![Value::Double(4.5), Value::Int(3)]),
+            Value::Double(3.0)
+        );
+        assert_eq!(
+            sum(vec![
+                Value::Boolean(true),
+                Value::Boolean(false),
+                Value::Boolean(true)
+            ]),
+            Value::Int(2)
+        );
+        assert_eq!(
+            min(vec![Value::Boolean(true), Value::Int(3)]),
+            Value::Int(1)
+        );
+        let max = |values| aggregate(values, AggregateOp::Max).unwrap();
+        assert_eq!(max(vec![Value::Int(3), Value::Int(-2)]), Value::Int(3));
+        assert_eq!(
+            max(vec![Value::Double(1.5), Value::Int(1)]),
+            Value::Double(1.5)
+        );
+
+        let err = aggregate(vec![Value::Int(1), Value::Okay], AggregateOp::Sum).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TypeError);
+    }
+
+    #[test]
+    fn test_aggregate_maps_by_their_keys() {
+        let key = |key: &str| Value::BulkString(key.as_bytes().to_vec());
+        let values = vec![
+            Value::Map(vec![
+                (key("a"), Value::Int(1)),
+                (key("b"), Value::Double(1.5)),
+            ]),
+            Value::Map(vec![(key("c"), Value::Int(7)), (key("a"), Value::Int(4))]),
+        ];
+        assert_eq!(
+            aggregate(values.clone(), AggregateOp::Sum).unwrap(),
+            Value::Map(vec![
+                (key("a"), Value::Int(5)),
+                (key("b"), Value::Double(1.5)),
+                (key("c"), Value::Int(7)),
+            ])
+        );
+        assert_eq!(
+            aggregate(values, AggregateOp::Min).unwrap(),
+            Value::Map(vec![
+                (key("a"), Value::Int(1)),
+                (key("b"), Value::Double(1.5)),
+                (key("c"), Value::Int(7)),
+            ])
+        );
+
+        let err = aggregate(vec![Value::Map(vec![]), Value::Int(1)], AggregateOp::Sum).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TypeError);
+    }
+
+    #[test]
+    fn test_logical_aggregate_integers_booleans_and_maps() {
+        let and = |values| logical_aggregate(values, LogicalAggregateOp::And).unwrap();
+
+        assert_eq!(
+            and(vec![
+                Value::Array(vec![Value::Int(1), Value::Int(1), Value::Int(0)]),
+                Value::Array(vec![Value::Int(1), Value::Int(0), Value::Int(1)]),
+            ]),
+            Value::Array(vec![Value::Int(1), Value::Int(0), Value::Int(0)])
+        );
+        assert_eq!(
+            and(vec![
+                Value::Array(vec![Value::Boolean(true), Value::Boolean(true)]),
+                Value::Array(vec![Value::Boolean(true), Value::Boolean(false)]),
+            ]),
+            Value::Array(vec![Value::Boolean(true), Value::Boolean(false)])
+        );
+
+        let key = |key: &str| Value::BulkString(key.as_bytes().to_vec());
+        assert_eq!(
+            and(vec![
+                Value::Map(vec![
+                    (key("a"), Value::Boolean(true)),
+                    (key("b"), Value::Boolean(true))
+                ]),
+                Value::Map(vec![(key("b"), Value::Boolean(false))]),
+            ]),
+            Value::Map(vec![
+                (key("a"), Value::Boolean(true)),
+                (key("b"), Value::Boolean(false)),
+            ])
+        );
+        assert_eq!(
+            and(vec![Value::Map(vec![(key("a"), Value::Int(2))])]),
+            Value::Map(vec![(key("a"), Value::Int(1))])
+        );
+
+        let err = logical_aggregate(
+            vec![Value::Array(vec![Value::Double(1.0)])],
+            LogicalAggregateOp::And,
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TypeError);
+    }
 }
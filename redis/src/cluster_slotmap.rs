@@ -13,6 +13,19 @@ pub(crate) struct SlotMapValue {
     pub(crate) latest_used_replica: AtomicUsize,
 }
 
+impl Clone for SlotMapValue {
+    fn clone(&self) -> Self {
+        Self {
+            start: self.start,
+            addrs: self.addrs.clone(),
+            latest_used_replica: AtomicUsize::new(
+                self.latest_used_replica
+                    .load(std::sync::atomic::Ordering::Relaxed),
+            ),
+        }
+    }
+}
+
 impl SlotMapValue {
     fn from_slot(slot: Slot) -> Self {
         Self {
@@ -28,9 +41,12 @@ pub(crate) enum ReadFromReplicaStrategy {
     #[default]
     AlwaysFromPrimary,
     RoundRobin,
+    /// Reads from the replica with the lowest average latency, as measured by the periodic topology checks of the
+    /// async cluster.
+    LowestLatency,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub(crate) struct SlotMap {
     pub(crate) slots: BTreeMap<u16, SlotMapValue>,
     read_from_replica: ReadFromReplicaStrategy,
@@ -46,7 +62,8 @@ fn get_address_from_slot(
     }
     match read_from_replica {
         ReadFromReplicaStrategy::AlwaysFromPrimary => slot.addrs.primary.as_str(),
-        ReadFromReplicaStrategy::RoundRobin => {
+        // The sync cluster doesn't measure the latencies of the nodes.
+        ReadFromReplicaStrategy::RoundRobin | ReadFromReplicaStrategy::LowestLatency => {
             let index = slot
                 .latest_used_replica
                 .fetch_add(1, std::sync::atomic::Ordering::Relaxed)
@@ -144,6 +161,103 @@ impl SlotMap {
             .collect()
     }
 
+    // Returns the primaries and replicas of all the slot ranges that the given address serves.
+    pub(crate) fn shard_addresses(&self, node_address: &str) -> HashSet<&str> {
+        self.slots
+            .values()
+            .filter(|slot_value| {
+                slot_value
+                    .addrs
+                    .into_iter()
+                    .any(|addr| addr == node_address)
+            })
+            .flat_map(|slot_value| slot_value.addrs.into_iter().map(String::as_str))
+            .collect()
+    }
+
+    /// Replaces the slot ranges that are served by any of `shard_addresses` with the ones of `new_slot_map`.
+    /// The other ranges must be identical in both maps, otherwise the topology changed beyond the shard, and
+    /// false is returned without changing the map.
+    pub(crate) fn replace_shard(
+        &mut self,
+        shard_addresses: &HashSet<String>,
+        new_slot_map: SlotMap,
+    ) -> bool {
+        let in_shard = |slot_value: &SlotMapValue| {
+            slot_value
+                .addrs
+                .into_iter()
+                .any(|addr| shard_addresses.contains(addr))
+        };
+        let outside_shard = |(end, slot_value): (&u16, &SlotMapValue)| {
+            (!in_shard(slot_value)).then_some((*end, slot_value.start))
+        };
+        let unchanged = self
+            .slots
+            .iter()
+            .filter_map(|slot| outside_shard(slot).map(|range| (range, &slot.1.addrs)))
+            .eq(new_slot_map
+                .slots
+                .iter()
+                .filter_map(|slot| outside_shard(slot).map(|range| (range, &slot.1.addrs))));
+        if !unchanged {
+            return false;
+        }
+
+        self.slots.retain(|_, slot_value| !in_shard(slot_value));
+        self.slots.extend(
+            new_slot_map
+                .slots
+                .into_iter()
+                .filter(|(_, slot_value)| in_shard(slot_value)),
+        );
+        true
+    }
+
+    /// Points `slot` to `primary`, as after a `MOVED` redirection, splitting the range that the slot belonged to.
+    /// The slot takes the replicas of another range of `primary` if there is one, since the replicas of its previous
+    /// primary don't serve it anymore.
+    pub(crate) fn update_slot_primary(&mut self, slot: u16, primary: &str) {
+        let range = self
+            .slots
+            .range(slot..)
+            .next()
+            .filter(|(_, slot_value)| slot_value.start <= slot)
+            .map(|(end, slot_value)| (*end, slot_value.clone()));
+        if let Some((end, slot_value)) = range {
+            if slot_value.addrs.primary == primary {
+                return;
+            }
+            self.slots.remove(&end);
+            if slot_value.start < slot {
+                self.slots.insert(slot - 1, slot_value.clone());
+            }
+            if slot < end {
+                self.slots.insert(
+                    end,
+                    SlotMapValue {
+                        start: slot + 1,
+                        ..slot_value
+                    },
+                );
+            }
+        }
+        let replicas = self
+            .slots
+            .values()
+            .find(|slot_value| slot_value.addrs.primary == primary)
+            .map(|slot_value| slot_value.addrs.replicas.clone())
+            .unwrap_or_default();
+        self.slots.insert(
+            slot,
+            SlotMapValue {
+                start: slot,
+                addrs: SlotAddrs::new(primary.to_string(), replicas),
+                latest_used_replica: AtomicUsize::new(0),
+            },
+        );
+    }
+
     pub(crate) fn get_node_address_for_slot(
         &self,
         slot: u16,
@@ -432,4 +546,103 @@ mod tests {
             (2001..3001).collect::<Vec<u16>>()
         );
     }
+
+    #[test]
+    fn test_replace_shard() {
+        let mut slot_map = get_slot_map(ReadFromReplicaStrategy::AlwaysFromPrimary);
+        let mut shard: Vec<_> = slot_map
+            .shard_addresses("replica2:6379")
+            .into_iter()
+            .collect();
+        shard.sort();
+        assert_eq!(shard, vec!["node2:6379", "replica2:6379", "replica3:6379"]);
+        let shard: HashSet<String> = shard.into_iter().map(String::from).collect();
+
+        // replica2 was promoted, and node2 became its replica.
+        let promoted = |first_primary: &str| {
+            let shard_slot = |start, end| {
+                Slot::new(
+                    start,
+                    end,
+                    "replica2:6379".to_owned(),
+                    vec!["node2:6379".to_owned(), "replica3:6379".to_owned()],
+                )
+            };
+            SlotMap::new(
+                vec![
+                    Slot::new(
+                        1,
+                        1000,
+                        first_primary.to_owned(),
+                        vec!["replica1:6379".to_owned()],
+                    ),
+                    shard_slot(1002, 2000),
+                    Slot::new(
+                        2001,
+                        3000,
+                        "node3:6379".to_owned(),
+                        vec![
+                            "replica4:6379".to_owned(),
+                            "replica5:6379".to_owned(),
+                            "replica6:6379".to_owned(),
+                        ],
+                    ),
+                    shard_slot(3001, 4000),
+                ],
+                ReadFromReplicaStrategy::AlwaysFromPrimary,
+            )
+        };
+        assert!(slot_map.replace_shard(&shard, promoted("node1:6379")));
+        assert_eq!(
+            slot_map.get_node_address_for_slot(1500, SlotAddr::Master),
+            Some("replica2:6379".to_owned())
+        );
+        assert_eq!(
+            slot_map.get_node_address_for_slot(3500, SlotAddr::Master),
+            Some("replica2:6379".to_owned())
+        );
+        assert_eq!(
+            slot_map.get_node_address_for_slot(500, SlotAddr::Master),
+            Some("node1:6379".to_owned())
+        );
+
+        // A change outside the shard isn't applied.
+        let mut slot_map = get_slot_map(ReadFromReplicaStrategy::AlwaysFromPrimary);
+        assert!(!slot_map.replace_shard(&shard, promoted("node4:6379")));
+        assert_eq!(
+            slot_map.get_node_address_for_slot(1500, SlotAddr::Master),
+            Some("node2:6379".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_slot_map_update_slot_primary() {
+        let mut slot_map = get_slot_map(ReadFromReplicaStrategy::RoundRobin);
+        slot_map.update_slot_primary(500, "node3:6379");
+
+        let primary =
+            |slot_map: &SlotMap, slot| slot_map.get_node_address_for_slot(slot, SlotAddr::Master);
+        assert_eq!(primary(&slot_map, 499), Some("node1:6379".to_owned()));
+        assert_eq!(primary(&slot_map, 500), Some("node3:6379".to_owned()));
+        assert_eq!(primary(&slot_map, 501), Some("node1:6379".to_owned()));
+        assert_eq!(primary(&slot_map, 1), Some("node1:6379".to_owned()));
+        assert_eq!(primary(&slot_map, 1000), Some("node1:6379".to_owned()));
+        // The slot is read from the replicas of its new primary.
+        assert_eq!(
+            slot_map.get_node_address_for_slot(500, SlotAddr::ReplicaOptional),
+            Some("replica4:6379".to_owned())
+        );
+
+        // The edges of a range, an unknown primary and an uncovered slot.
+        slot_map.update_slot_primary(1002, "node4:6379");
+        slot_map.update_slot_primary(1001, "node1:6379");
+        assert_eq!(primary(&slot_map, 1001), Some("node1:6379".to_owned()));
+        assert_eq!(primary(&slot_map, 1002), Some("node4:6379".to_owned()));
+        assert_eq!(primary(&slot_map, 1003), Some("node2:6379".to_owned()));
+        assert_eq!(
+            slot_map.get_node_address_for_slot(1002, SlotAddr::ReplicaOptional),
+            Some("node4:6379".to_owned())
+        );
+        assert_eq!(slot_map.slots.len(), 8);
+    }
 }
@@ -5,15 +5,15 @@ use crate::cluster::get_connection_addr;
 use crate::cluster_client::SlotsRefreshRateLimit;
 use crate::cluster_routing::Slot;
 use crate::cluster_slotmap::{ReadFromReplicaStrategy, SlotMap};
-use crate::{cluster::TlsMode, ErrorKind, RedisError, RedisResult, Value};
+use crate::{cluster::TlsMode, Cmd, ErrorKind, RedisError, RedisResult, Value};
 #[cfg(all(feature = "cluster-async", not(feature = "tokio-comp")))]
 use async_std::sync::RwLock;
 use derivative::Derivative;
 use std::collections::{hash_map::DefaultHasher, HashMap};
 use std::hash::{Hash, Hasher};
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant};
 #[cfg(all(feature = "cluster-async", feature = "tokio-comp"))]
 use tokio::sync::RwLock;
 
@@ -42,7 +42,7 @@ pub(crate) struct SlotRefreshState {
     /// Indicates if a slot refresh is currently in progress
     pub(crate) in_progress: AtomicBool,
     /// The last slot refresh run timestamp
-    pub(crate) last_run: Arc<RwLock<Option<SystemTime>>>,
+    pub(crate) last_run: Arc<RwLock<Option<Instant>>>,
     pub(crate) rate_limiter: SlotsRefreshRateLimit,
 }
 
@@ -55,6 +55,26 @@ impl SlotRefreshState {
             rate_limiter,
         }
     }
+
+    /// Marks a slot refresh as in progress, unless one already is. The returned guard marks the refresh as done
+    /// when it's dropped, so a refresh that panicked or was cancelled doesn't block the following ones.
+    pub(crate) fn try_start(&self) -> Option<SlotRefreshGuard<'_>> {
+        self.in_progress
+            .compare_exchange(false, true, Ordering::Relaxed, Ordering::Relaxed)
+            .ok()
+            .map(|_| SlotRefreshGuard(&self.in_progress))
+    }
+}
+
+/// Marks the slot refresh that created it as done when dropped.
+#[cfg(feature = "cluster-async")]
+pub(crate) struct SlotRefreshGuard<'a>(&'a AtomicBool);
+
+#[cfg(feature = "cluster-async")]
+impl Drop for SlotRefreshGuard<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
 }
 
 #[derive(Derivative)]
@@ -64,12 +84,108 @@ pub(crate) struct TopologyView {
     pub(crate) hash_value: TopologyHash,
     #[derivative(PartialEq = "ignore")]
     pub(crate) nodes_count: u16,
+    /// The addresses of the nodes that returned this view.
+    #[derivative(PartialEq = "ignore")]
+    supporting_nodes: Vec<String>,
     #[derivative(PartialEq = "ignore")]
     slots_and_count: (u16, Vec<Slot>),
 }
 
+impl std::fmt::Display for TopologyView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (count, slots) = &self.slots_and_count;
+        let mut primaries: Vec<&str> = slots.iter().map(|slot| slot.master.as_str()).collect();
+        primaries.sort_unstable();
+        primaries.dedup();
+        write!(
+            f,
+            "view {:x} returned by {:?}: {} slots in {} ranges, primaries {:?}",
+            self.hash_value,
+            self.supporting_nodes,
+            // The count doesn't include the last slot of each range.
+            *count as usize + slots.len(),
+            slots.len(),
+            primaries
+        )
+    }
+}
+
+// Describes the views that the nodes disagreed on, starting with the most common ones, and the nodes whose views
+// couldn't be parsed.
+fn describe_disagreement(mut views: Vec<&TopologyView>, unparsable_nodes: &[&str]) -> String {
+    views.sort_by_key(|view| std::cmp::Reverse(view.nodes_count));
+    let mut description = views
+        .iter()
+        .map(|view| view.to_string())
+        .collect::<Vec<_>>()
+        .join("; ");
+    if !unparsable_nodes.is_empty() {
+        description.push_str(&format!(
+            "; unparsable views returned by {unparsable_nodes:?}"
+        ));
+    }
+    description
+}
+
+// The CRC16 polynomial that Redis Cluster uses for key slots (CRC-16/XMODEM).
+const CRC16_POLY: u16 = 0x1021;
+
+// `CRC16_TABLES[k][b]` is the CRC16 of the byte `b` followed by `k` zero bytes, so that 8 bytes are hashed by
+// looking up each of them in its own table (slice-by-8), instead of one byte after the other.
+static CRC16_TABLES: [[u16; 256]; 8] = crc16_tables();
+
+const fn crc16_tables() -> [[u16; 256]; 8] {
+    let mut tables = [[0u16; 256]; 8];
+    let mut byte = 0;
+    while byte < 256 {
+        let mut crc = (byte as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ CRC16_POLY
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        tables[0][byte] = crc;
+        byte += 1;
+    }
+    let mut table = 1;
+    while table < 8 {
+        let mut byte = 0;
+        while byte < 256 {
+            let previous = tables[table - 1][byte];
+            tables[table][byte] = (previous << 8) ^ tables[0][(previous >> 8) as usize];
+            byte += 1;
+        }
+        table += 1;
+    }
+    tables
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    let [t0, t1, t2, t3, t4, t5, t6, t7] = &CRC16_TABLES;
+    let mut crc: u16 = 0;
+    let mut chunks = bytes.chunks_exact(8);
+    for chunk in &mut chunks {
+        crc = t7[(chunk[0] ^ (crc >> 8) as u8) as usize]
+            ^ t6[(chunk[1] ^ crc as u8) as usize]
+            ^ t5[chunk[2] as usize]
+            ^ t4[chunk[3] as usize]
+            ^ t3[chunk[4] as usize]
+            ^ t2[chunk[5] as usize]
+            ^ t1[chunk[6] as usize]
+            ^ t0[chunk[7] as usize];
+    }
+    for byte in chunks.remainder() {
+        crc = (crc << 8) ^ t0[(*byte ^ (crc >> 8) as u8) as usize];
+    }
+    crc
+}
+
 pub(crate) fn slot(key: &[u8]) -> u16 {
-    crc16::State::<crc16::XMODEM>::calculate(key) % SLOT_SIZE
+    crc16(key) % SLOT_SIZE
 }
 
 fn get_hashtag(key: &[u8]) -> Option<&[u8]> {
@@ -103,13 +219,208 @@ pub fn get_slot(key: &[u8]) -> u16 {
     slot(key)
 }
 
-// Parse slot data from raw redis value.
+/// The command that is sent to the nodes to discover the cluster's topology, as set by
+/// [`ClusterClientBuilder::topology_command`](crate::cluster::ClusterClientBuilder::topology_command).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TopologyCommand {
+    /// `CLUSTER SLOTS`, which all the cluster versions support. This is the default.
+    #[default]
+    ClusterSlots,
+    /// `CLUSTER SHARDS`, which Redis supports since 7.0. It reports the health of the nodes, so the replicas that are
+    /// loading or failed aren't routed to. The nodes that don't support it are queried with `CLUSTER SLOTS` instead.
+    ClusterShards,
+}
+
+impl TopologyCommand {
+    pub(crate) fn cmd(self) -> Cmd {
+        let mut cmd = Cmd::new();
+        match self {
+            TopologyCommand::ClusterSlots => cmd.arg("CLUSTER").arg("SLOTS"),
+            TopologyCommand::ClusterShards => cmd.arg("CLUSTER").arg("SHARDS"),
+        };
+        cmd
+    }
+
+    /// Returns true if a node that failed to answer the command with `err` should be queried with `CLUSTER SLOTS`
+    /// instead, which is the case of the servers that don't know `CLUSTER SHARDS`.
+    pub(crate) fn should_fall_back(self, err: &RedisError) -> bool {
+        self == TopologyCommand::ClusterShards && err.kind() == ErrorKind::ResponseError
+    }
+}
+
+fn text(value: &Value) -> Option<String> {
+    match value {
+        Value::BulkString(bytes) => Some(String::from_utf8_lossy(bytes).into_owned()),
+        Value::SimpleString(text) => Some(text.clone()),
+        Value::VerbatimString { text, .. } => Some(text.clone()),
+        _ => None,
+    }
+}
+
+// The fields of a map, which RESP2 replies as an array of alternating keys and values.
+fn map_fields(value: &Value) -> Vec<(String, &Value)> {
+    match value {
+        Value::Map(fields) => fields
+            .iter()
+            .filter_map(|(key, value)| Some((text(key)?, value)))
+            .collect(),
+        Value::Array(items) => items
+            .chunks_exact(2)
+            .filter_map(|pair| Some((text(&pair[0])?, &pair[1])))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// The shards of a `CLUSTER SHARDS` reply are maps, while the ranges of a `CLUSTER SLOTS` reply start with a slot.
+fn is_cluster_shards_reply(raw_slot_resp: &Value) -> bool {
+    match raw_slot_resp {
+        Value::Array(items) => match items.first() {
+            Some(Value::Map(_)) => true,
+            Some(Value::Array(shard)) => shard.first().and_then(text).is_some(),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+struct ShardNode {
+    address: String,
+    is_primary: bool,
+    is_online: bool,
+}
+
+fn parse_shard_node(
+    node: &Value,
+    tls: Option<TlsMode>,
+    addr_of_answering_node: &str,
+) -> Option<ShardNode> {
+    let fields: HashMap<String, &Value> = map_fields(node).into_iter().collect();
+    let field_text = |name: &str| fields.get(name).and_then(|value| text(value));
+    let field_int = |name: &str| match fields.get(name) {
+        Some(Value::Int(value)) => Some(*value),
+        _ => None,
+    };
+    // As with `CLUSTER SLOTS`, an empty endpoint stands for the answering node, and "?" for an unknown one.
+    let host = match field_text("endpoint")
+        .or_else(|| field_text("hostname"))
+        .or_else(|| field_text("ip"))
+    {
+        Some(host) if host == "?" => return None,
+        Some(host) if !host.is_empty() => host,
+        _ if addr_of_answering_node.is_empty() => return None,
+        _ => addr_of_answering_node.to_string(),
+    };
+    let port = tls
+        .and_then(|_| field_int("tls-port"))
+        .or_else(|| field_int("port"))?;
+    let role = field_text("role")?;
+    Some(ShardNode {
+        address: get_connection_addr(host, port as u16, tls, None).to_string(),
+        is_primary: role == "master" || role == "primary",
+        // Servers that don't report the health of the nodes only report healthy ones.
+        is_online: field_text("health").map_or(true, |health| health == "online"),
+    })
+}
+
+// Parses a `CLUSTER SHARDS` reply. The replicas that aren't online are left out, so they aren't routed to.
+fn parse_and_count_shards(
+    raw_shards_resp: &Value,
+    tls: Option<TlsMode>,
+    addr_of_answering_node: &str,
+) -> RedisResult<(u16, Vec<Slot>)> {
+    let mut slots = Vec::new();
+    let mut count = 0;
+    let shards = match raw_shards_resp {
+        Value::Array(shards) => shards.as_slice(),
+        _ => &[],
+    };
+    for shard in shards {
+        let fields: HashMap<String, &Value> = map_fields(shard).into_iter().collect();
+        let ranges: Vec<u16> = match fields.get("slots") {
+            Some(Value::Array(ranges)) => ranges
+                .iter()
+                .filter_map(|slot| match slot {
+                    Value::Int(slot) => Some(*slot as u16),
+                    _ => text(slot).and_then(|slot| slot.parse().ok()),
+                })
+                .collect(),
+            _ => continue,
+        };
+        let nodes: Vec<ShardNode> = match fields.get("nodes") {
+            Some(Value::Array(nodes)) => nodes
+                .iter()
+                .filter_map(|node| parse_shard_node(node, tls, addr_of_answering_node))
+                .collect(),
+            _ => continue,
+        };
+        // After a failover, the failed primary may still be reported with the primary role.
+        let Some(primary) = nodes
+            .iter()
+            .filter(|node| node.is_primary)
+            .min_by_key(|node| !node.is_online)
+        else {
+            continue;
+        };
+        let mut replicas: Vec<String> = nodes
+            .iter()
+            .filter(|node| !node.is_primary && node.is_online)
+            .map(|node| node.address.clone())
+            .collect();
+        replicas.sort_unstable();
+        for range in ranges.chunks_exact(2) {
+            let (start, end) = (range[0], range[1]);
+            count += end - start;
+            slots.push(Slot::new(
+                start,
+                end,
+                primary.address.clone(),
+                replicas.clone(),
+            ));
+        }
+    }
+    if slots.is_empty() {
+        return Err(RedisError::from((
+            ErrorKind::ResponseError,
+            "Error parsing shards: No healthy node found",
+            format!("Raw shards response: {:?}", raw_shards_resp),
+        )));
+    }
+
+    Ok((count, slots))
+}
+
+/// A callback that rewrites the addresses of the nodes in the topology responses, as set by
+/// [`ClusterClientBuilder::address_resolver`](crate::cluster::ClusterClientBuilder::address_resolver).
+pub(crate) type AddressResolver = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// Rewrites the addresses of the parsed slots with `resolver`.
+pub(crate) fn resolve_addresses(
+    (count, mut slots): (u16, Vec<Slot>),
+    resolver: Option<&AddressResolver>,
+) -> (u16, Vec<Slot>) {
+    if let Some(resolver) = resolver {
+        for slot in slots.iter_mut() {
+            slot.master = resolver(&slot.master);
+            for replica in slot.replicas.iter_mut() {
+                *replica = resolver(replica);
+            }
+            slot.replicas.sort_unstable();
+        }
+    }
+    (count, slots)
+}
+
+// Parse slot data from raw redis value, which is either a `CLUSTER SLOTS` or a `CLUSTER SHARDS` reply.
 pub(crate) fn parse_and_count_slots(
     raw_slot_resp: &Value,
     tls: Option<TlsMode>,
     // The DNS address of the node from which `raw_slot_resp` was received.
     addr_of_answering_node: &str,
 ) -> RedisResult<(u16, Vec<Slot>)> {
+    if is_cluster_shards_reply(raw_slot_resp) {
+        return parse_and_count_shards(raw_slot_resp, tls, addr_of_answering_node);
+    }
     // Parse response.
     let mut slots = Vec::with_capacity(2);
     let mut count = 0;
@@ -199,10 +510,33 @@ pub(crate) fn parse_and_count_slots(
     Ok((count, slots))
 }
 
-fn calculate_hash<T: Hash>(t: &T) -> u64 {
-    let mut s = DefaultHasher::new();
-    t.hash(&mut s);
-    s.finish()
+// Host names are case insensitive, and may be fully qualified with a trailing dot.
+fn canonical_address(address: &str) -> String {
+    let (host, port) = address.rsplit_once(':').unwrap_or((address, ""));
+    format!("{}:{port}", host.trim_end_matches('.').to_ascii_lowercase())
+}
+
+/// Hashes the canonical form of a parsed topology, so that views that differ only in the order of their slot ranges
+/// or of their replicas, or in the form of their addresses, have the same hash.
+fn calculate_hash(slots_and_count: &(u16, Vec<Slot>)) -> TopologyHash {
+    let (count, slots) = slots_and_count;
+    let mut hasher = DefaultHasher::new();
+    count.hash(&mut hasher);
+    let mut slots: Vec<&Slot> = slots.iter().collect();
+    slots.sort_unstable_by_key(|slot| slot.start);
+    for slot in slots {
+        slot.start.hash(&mut hasher);
+        slot.end.hash(&mut hasher);
+        canonical_address(&slot.master).hash(&mut hasher);
+        let mut replicas: Vec<String> = slot
+            .replicas
+            .iter()
+            .map(|replica| canonical_address(replica))
+            .collect();
+        replicas.sort_unstable();
+        replicas.hash(&mut hasher);
+    }
+    hasher.finish()
 }
 
 pub(crate) fn calculate_topology<'a>(
@@ -211,27 +545,38 @@ pub(crate) fn calculate_topology<'a>(
     tls_mode: Option<TlsMode>,
     num_of_queried_nodes: usize,
     read_from_replica: ReadFromReplicaStrategy,
+    address_resolver: Option<&AddressResolver>,
 ) -> RedisResult<(SlotMap, TopologyHash)> {
     let mut hash_view_map = HashMap::new();
+    let mut unparsable_nodes = Vec::new();
     for (host, view) in topology_views {
-        if let Ok(slots_and_count) = parse_and_count_slots(view, tls_mode, host) {
-            let hash_value = calculate_hash(&slots_and_count);
-            let topology_entry = hash_view_map.entry(hash_value).or_insert(TopologyView {
-                hash_value,
-                nodes_count: 0,
-                slots_and_count,
-            });
-            topology_entry.nodes_count += 1;
+        match parse_and_count_slots(view, tls_mode, host)
+            .map(|slots_and_count| resolve_addresses(slots_and_count, address_resolver))
+        {
+            Ok(slots_and_count) => {
+                let hash_value = calculate_hash(&slots_and_count);
+                let topology_entry = hash_view_map.entry(hash_value).or_insert(TopologyView {
+                    hash_value,
+                    nodes_count: 0,
+                    supporting_nodes: Vec::new(),
+                    slots_and_count,
+                });
+                topology_entry.nodes_count += 1;
+                topology_entry.supporting_nodes.push(host.to_string());
+            }
+            Err(_) => unparsable_nodes.push(host),
         }
     }
     let mut non_unique_max_node_count = false;
-    let mut vec_iter = hash_view_map.into_values();
+    let views: Vec<TopologyView> = hash_view_map.into_values().collect();
+    let mut vec_iter = views.iter();
     let mut most_frequent_topology = match vec_iter.next() {
         Some(view) => view,
         None => {
             return Err(RedisError::from((
                 ErrorKind::ResponseError,
                 "No topology views found",
+                describe_disagreement(Vec::new(), &unparsable_nodes),
             )));
         }
     };
@@ -259,7 +604,13 @@ pub(crate) fn calculate_topology<'a>(
         }
     }
 
-    let parse_and_built_result = |most_frequent_topology: TopologyView| {
+    let most_frequent_hash = most_frequent_topology.hash_value;
+    let most_frequent_nodes_count = most_frequent_topology.nodes_count;
+    let parse_and_built_result = |views: Vec<TopologyView>| {
+        let most_frequent_topology = views
+            .into_iter()
+            .find(|view| view.hash_value == most_frequent_hash)
+            .unwrap();
         let slots_data = most_frequent_topology.slots_and_count.1;
 
         Ok((
@@ -272,23 +623,25 @@ pub(crate) fn calculate_topology<'a>(
         // More than a single most frequent view was found
         // If we reached the last retry, or if we it's a 2-nodes cluster, we'll return a view with the highest slot coverage, and that is one of most agreed on views.
         if curr_retry >= DEFAULT_NUMBER_OF_REFRESH_SLOTS_RETRIES || num_of_queried_nodes < 3 {
-            return parse_and_built_result(most_frequent_topology);
+            return parse_and_built_result(views);
         }
         return Err(RedisError::from((
             ErrorKind::ResponseError,
             "Slot refresh error: Failed to obtain a majority in topology views",
+            describe_disagreement(views.iter().collect(), &unparsable_nodes),
         )));
     }
 
     // The rate of agreement of the topology view is determined by assessing the number of nodes that share this view out of the total number queried
-    let agreement_rate = most_frequent_topology.nodes_count as f32 / num_of_queried_nodes as f32;
+    let agreement_rate = most_frequent_nodes_count as f32 / num_of_queried_nodes as f32;
     const MIN_AGREEMENT_RATE: f32 = 0.2;
     if agreement_rate >= MIN_AGREEMENT_RATE {
-        parse_and_built_result(most_frequent_topology)
+        parse_and_built_result(views)
     } else {
         Err(RedisError::from((
             ErrorKind::ResponseError,
             "Slot refresh error: The accuracy of the topology view is too low",
+            describe_disagreement(views.iter().collect(), &unparsable_nodes),
         )))
     }
 }
@@ -298,6 +651,29 @@ mod tests {
     use super::*;
     use crate::cluster_routing::SlotAddrs;
 
+    #[test]
+    fn test_crc16_matches_the_bytewise_crc16() {
+        assert_eq!(crc16(b"123456789"), 0x31c3);
+        let bytes: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        for start in 0..9 {
+            for end in (start..bytes.len()).step_by(7) {
+                let key = &bytes[start..end];
+                assert_eq!(
+                    crc16(key),
+                    ::crc16::State::<::crc16::XMODEM>::calculate(key),
+                    "{key:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_slot() {
+        assert_eq!(get_slot(b"foo"), 12182);
+        assert_eq!(get_slot(b"{user1000}.following"), get_slot(b"user1000"));
+        assert_eq!(get_slot(b""), 0);
+    }
+
     #[test]
     fn test_get_hashtag() {
         assert_eq!(get_hashtag(&b"foo{bar}baz"[..]), Some(&b"bar"[..]));
@@ -414,6 +790,143 @@ mod tests {
         assert_eq!(slots[0].master(), "node:6379");
     }
 
+    fn bulk(text: &str) -> Value {
+        Value::BulkString(text.as_bytes().to_vec())
+    }
+
+    fn shard_node(endpoint: &str, port: i64, role: &str, health: &str) -> Value {
+        Value::Array(vec![
+            bulk("id"),
+            bulk(&format!("{endpoint}-{port}")),
+            bulk("port"),
+            Value::Int(port),
+            bulk("endpoint"),
+            bulk(endpoint),
+            bulk("role"),
+            bulk(role),
+            bulk("health"),
+            bulk(health),
+        ])
+    }
+
+    fn shard_value(ranges: &[(u16, u16)], nodes: Vec<Value>) -> Value {
+        Value::Array(vec![
+            bulk("slots"),
+            Value::Array(
+                ranges
+                    .iter()
+                    .flat_map(|(start, end)| [Value::Int(*start as i64), Value::Int(*end as i64)])
+                    .collect(),
+            ),
+            bulk("nodes"),
+            Value::Array(nodes),
+        ])
+    }
+
+    #[test]
+    fn parse_shards_leaves_out_the_replicas_that_are_not_online() {
+        let view = Value::Array(vec![
+            shard_value(
+                &[(0, 4000), (8001, 10000)],
+                vec![
+                    shard_node("replica1_1", 6379, "replica", "loading"),
+                    shard_node("primary1", 6379, "master", "online"),
+                    shard_node("replica1_2", 6379, "replica", "online"),
+                ],
+            ),
+            shard_value(
+                &[(4001, 8000)],
+                vec![
+                    // The previous primary, which failed over.
+                    shard_node("primary2", 6379, "master", "failed"),
+                    shard_node("replica2_1", 6379, "master", "online"),
+                ],
+            ),
+        ]);
+
+        let (slot_count, slots) = parse_and_count_slots(&view, None, "node").unwrap();
+        assert_eq!(slot_count, 4000 + 1999 + 3999);
+        assert_eq!(
+            slots
+                .iter()
+                .map(|slot| (slot.start(), slot.end(), slot.master(), slot.replicas()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    0,
+                    4000,
+                    "primary1:6379",
+                    vec!["replica1_2:6379".to_string()]
+                ),
+                (
+                    8001,
+                    10000,
+                    "primary1:6379",
+                    vec!["replica1_2:6379".to_string()]
+                ),
+                (4001, 8000, "replica2_1:6379", vec![]),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_shards_and_slots_of_the_same_topology_returns_the_same_hash() {
+        let slots_view = Value::Array(vec![
+            slot_value_with_replicas(0, 8000, vec![("", 6379), ("replica1", 6379)]),
+            slot_value(8001, 16383, "primary2", 6379),
+        ]);
+        // RESP3 replies the shards and the nodes as maps.
+        let node_map = |endpoint: &str, role: &str| {
+            Value::Map(vec![
+                (bulk("port"), Value::Int(6379)),
+                (bulk("endpoint"), bulk(endpoint)),
+                (bulk("role"), bulk(role)),
+                (bulk("health"), bulk("online")),
+            ])
+        };
+        let shards_view = Value::Array(vec![
+            Value::Map(vec![
+                (
+                    bulk("slots"),
+                    Value::Array(vec![Value::Int(8001), Value::Int(16383)]),
+                ),
+                (
+                    bulk("nodes"),
+                    Value::Array(vec![node_map("primary2", "master")]),
+                ),
+            ]),
+            Value::Map(vec![
+                (
+                    bulk("slots"),
+                    Value::Array(vec![Value::Int(0), Value::Int(8000)]),
+                ),
+                (
+                    bulk("nodes"),
+                    Value::Array(vec![
+                        node_map("replica1", "replica"),
+                        node_map("", "master"),
+                    ]),
+                ),
+            ]),
+        ]);
+
+        let slots = parse_and_count_slots(&slots_view, None, "primary1").unwrap();
+        let shards = parse_and_count_slots(&shards_view, None, "primary1").unwrap();
+        assert_eq!(shards.1[1].master(), "primary1:6379");
+        assert_eq!(calculate_hash(&slots), calculate_hash(&shards));
+    }
+
+    #[test]
+    fn topology_command_falls_back_only_from_cluster_shards_on_response_errors() {
+        let unknown_command: RedisError =
+            (ErrorKind::ResponseError, "unknown subcommand 'SHARDS'").into();
+        let io_error: RedisError = std::io::Error::from(std::io::ErrorKind::BrokenPipe).into();
+
+        assert!(TopologyCommand::ClusterShards.should_fall_back(&unknown_command));
+        assert!(!TopologyCommand::ClusterShards.should_fall_back(&io_error));
+        assert!(!TopologyCommand::ClusterSlots.should_fall_back(&unknown_command));
+    }
+
     #[test]
     fn should_parse_and_hash_regardless_of_missing_host_name_and_replicas_order() {
         let view1 = Value::Array(vec![
@@ -465,6 +978,28 @@ mod tests {
         assert!(replicas_check);
     }
 
+    #[test]
+    fn parse_slots_with_different_ranges_order_and_address_form_returns_the_same_hash() {
+        let view1 = Value::Array(vec![
+            slot_value_with_replicas(0, 8000, vec![("node1", 6379), ("replica1", 6379)]),
+            slot_value(8001, 16383, "node2", 6379),
+        ]);
+        let view2 = Value::Array(vec![
+            slot_value(8001, 16383, "NODE2.", 6379),
+            slot_value_with_replicas(0, 8000, vec![("Node1", 6379), ("replica1.", 6379)]),
+        ]);
+        let view3 = Value::Array(vec![
+            slot_value(0, 8000, "node1", 6379),
+            slot_value_with_replicas(8001, 16383, vec![("node2", 6379), ("replica1", 6379)]),
+        ]);
+
+        let res1 = parse_and_count_slots(&view1, None, "node1").unwrap();
+        let res2 = parse_and_count_slots(&view2, None, "node1").unwrap();
+        let res3 = parse_and_count_slots(&view3, None, "node1").unwrap();
+        assert_eq!(calculate_hash(&res1), calculate_hash(&res2));
+        assert_ne!(calculate_hash(&res1), calculate_hash(&res3));
+    }
+
     enum ViewType {
         SingleNodeViewFullCoverage,
         SingleNodeViewMissingSlots,
@@ -518,6 +1053,7 @@ mod tests {
             None,
             queried_nodes,
             ReadFromReplicaStrategy::AlwaysFromPrimary,
+            None,
         )
         .unwrap();
         let res: Vec<_> = topology_view.values().collect();
@@ -541,10 +1077,49 @@ mod tests {
             None,
             queried_nodes,
             ReadFromReplicaStrategy::AlwaysFromPrimary,
+            None,
         );
         assert!(topology_view.is_err());
     }
 
+    #[test]
+    fn test_topology_calculator_no_majority_error_describes_the_views() {
+        let topology_results = vec![
+            get_view(&ViewType::SingleNodeViewFullCoverage),
+            get_view(&ViewType::TwoNodesViewFullCoverage),
+            get_view(&ViewType::TwoNodesViewMissingSlots),
+            ("fifth", Value::Array(vec![])),
+        ];
+        let err = calculate_topology(
+            topology_results.iter().map(|(addr, value)| (*addr, value)),
+            1,
+            None,
+            4,
+            ReadFromReplicaStrategy::AlwaysFromPrimary,
+            None,
+        )
+        .unwrap_err();
+        let detail = err.detail().unwrap();
+        assert!(
+            detail.contains(
+                r#"returned by ["first"]: 16384 slots in 1 ranges, primaries ["node1:6379"]"#
+            ),
+            "{detail}"
+        );
+        assert!(
+            detail.contains(r#"returned by ["third"]: 16384 slots in 2 ranges, primaries ["node1:6379", "node2:6380"]"#),
+            "{detail}"
+        );
+        assert!(
+            detail.contains(r#"returned by ["fourth"]: 15384 slots in 2 ranges"#),
+            "{detail}"
+        );
+        assert!(
+            detail.contains(r#"unparsable views returned by ["fifth"]"#),
+            "{detail}"
+        );
+    }
+
     #[test]
     fn test_topology_calculator_3_nodes_queried_no_majority_last_retry_success() {
         // 3 nodes queried:: No majority, last retry, should get the view that has a full slot coverage
@@ -560,6 +1135,7 @@ mod tests {
             None,
             queried_nodes,
             ReadFromReplicaStrategy::AlwaysFromPrimary,
+            None,
         )
         .unwrap();
         let res: Vec<_> = topology_view.values().collect();
@@ -583,6 +1159,7 @@ mod tests {
             None,
             queried_nodes,
             ReadFromReplicaStrategy::AlwaysFromPrimary,
+            None,
         )
         .unwrap();
         let res: Vec<_> = topology_view.values().collect();
@@ -607,6 +1184,7 @@ mod tests {
             None,
             queried_nodes,
             ReadFromReplicaStrategy::AlwaysFromPrimary,
+            None,
         )
         .unwrap();
         let res: Vec<_> = topology_view.values().collect();
@@ -631,6 +1209,7 @@ mod tests {
             None,
             queried_nodes,
             ReadFromReplicaStrategy::AlwaysFromPrimary,
+            None,
         )
         .unwrap();
         let res: Vec<_> = topology_view.values().collect();
@@ -267,6 +267,171 @@ where
     Ok(())
 }
 
+/// Arguments at least this long are written from the command's own buffer, instead of being copied next to their
+/// length prefix.
+pub(crate) const BORROWED_ARG_MIN_LEN: usize = 1024;
+
+/// The most slices that are passed to a single vectored write, which stays below the `IOV_MAX` of the platforms.
+pub(crate) const MAX_SLICES_PER_WRITE: usize = 64;
+
+/// The frame of one or more commands, split into slices that are written with vectored writes. The headers, the
+/// length prefixes and the short arguments are staged together, while the long arguments are borrowed from the
+/// commands, so that they aren't copied before they're sent.
+///
+/// The synchronous connection and the [`aio::Connection`](crate::aio::Connection) send their commands and async
+/// pipelines as frames. The multiplexed connection hands each request to its driver task as an owned buffer, which
+/// the driver writes with vectored writes too, instead of copying it into a write buffer.
+#[derive(Default)]
+pub(crate) struct CommandFrame<'a> {
+    staged: Vec<u8>,
+    parts: Vec<FramePart<'a>>,
+    // The start of the staged bytes that aren't in `parts` yet.
+    staged_start: usize,
+}
+
+enum FramePart<'a> {
+    Staged(std::ops::Range<usize>),
+    Borrowed(&'a [u8]),
+}
+
+impl<'a> CommandFrame<'a> {
+    pub(crate) fn new(cmd: &'a Cmd) -> Self {
+        let mut frame = CommandFrame::default();
+        frame.push_command(cmd);
+        frame
+    }
+
+    /// Appends a command to the frame.
+    pub(crate) fn push_command(&mut self, cmd: &'a Cmd) {
+        let args = cmd.args_iter();
+        let cursor = cmd.cursor.unwrap_or(0);
+        let mut buf = ::itoa::Buffer::new();
+
+        self.staged.push(b'*');
+        self.staged
+            .extend_from_slice(buf.format(args.len()).as_bytes());
+        self.staged.extend_from_slice(b"\r\n");
+        for item in args {
+            let mut cursor_bytes = ::itoa::Buffer::new();
+            let bytes = match item {
+                Arg::Cursor => cursor_bytes.format(cursor).as_bytes(),
+                Arg::Simple(val) => val,
+            };
+            self.staged.push(b'$');
+            self.staged
+                .extend_from_slice(buf.format(bytes.len()).as_bytes());
+            self.staged.extend_from_slice(b"\r\n");
+            match item {
+                Arg::Simple(val) if val.len() >= BORROWED_ARG_MIN_LEN => {
+                    self.parts
+                        .push(FramePart::Staged(self.staged_start..self.staged.len()));
+                    self.parts.push(FramePart::Borrowed(val));
+                    self.staged_start = self.staged.len();
+                }
+                _ => self.staged.extend_from_slice(bytes),
+            }
+            self.staged.extend_from_slice(b"\r\n");
+        }
+    }
+
+    /// Appends a command that is already packed to the frame.
+    #[cfg(feature = "aio")]
+    pub(crate) fn push_packed_command(&mut self, packed: &[u8]) {
+        self.staged.extend_from_slice(packed);
+    }
+
+    /// Returns the slices of the frame, in the order they're sent.
+    pub(crate) fn slices(&self) -> Vec<&[u8]> {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                FramePart::Staged(range) => &self.staged[range.clone()],
+                FramePart::Borrowed(bytes) => *bytes,
+            })
+            .chain(Some(&self.staged[self.staged_start..]).filter(|rest| !rest.is_empty()))
+            .collect()
+    }
+}
+
+/// The slices that remain to be written by a sequence of vectored writes.
+struct VectoredCursor<'a, 'b> {
+    slices: &'b [&'a [u8]],
+    // The number of bytes of the first slice that were already written.
+    offset: usize,
+}
+
+impl<'a, 'b> VectoredCursor<'a, 'b> {
+    fn new(slices: &'b [&'a [u8]]) -> Self {
+        let mut cursor = VectoredCursor { slices, offset: 0 };
+        // Skips the leading empty slices, so that a write of zero bytes means that the writer can't make progress.
+        cursor.advance(0);
+        cursor
+    }
+
+    fn is_done(&self) -> bool {
+        self.slices.is_empty()
+    }
+
+    fn io_slices(&self) -> Vec<io::IoSlice<'a>> {
+        let mut slices = self.slices.iter().take(MAX_SLICES_PER_WRITE);
+        let first = slices
+            .next()
+            .map(|first| io::IoSlice::new(&first[self.offset..]));
+        first
+            .into_iter()
+            .chain(slices.map(|slice| io::IoSlice::new(slice)))
+            .collect()
+    }
+
+    fn advance(&mut self, mut written: usize) {
+        while let Some(first) = self.slices.first() {
+            let remaining = first.len() - self.offset;
+            if written < remaining {
+                self.offset += written;
+                return;
+            }
+            written -= remaining;
+            self.slices = &self.slices[1..];
+            self.offset = 0;
+        }
+    }
+}
+
+/// Writes all the slices with vectored writes, resuming after the partial writes.
+pub(crate) fn write_all_vectored(
+    writer: &mut (impl ?Sized + io::Write),
+    slices: &[&[u8]],
+) -> io::Result<()> {
+    let mut cursor = VectoredCursor::new(slices);
+    while !cursor.is_done() {
+        match writer.write_vectored(&cursor.io_slices()) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(written) => cursor.advance(written),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// Writes all the slices with vectored writes, resuming after the partial writes.
+#[cfg(feature = "aio")]
+pub(crate) async fn write_all_vectored_async<W>(writer: &mut W, slices: &[&[u8]]) -> io::Result<()>
+where
+    W: ::tokio::io::AsyncWrite + Unpin + ?Sized,
+{
+    use ::tokio::io::AsyncWriteExt;
+
+    let mut cursor = VectoredCursor::new(slices);
+    while !cursor.is_done() {
+        match writer.write_vectored(&cursor.io_slices()).await? {
+            0 => return Err(io::ErrorKind::WriteZero.into()),
+            written => cursor.advance(written),
+        }
+    }
+    Ok(())
+}
+
 impl RedisWrite for Cmd {
     fn write_arg(&mut self, arg: &[u8]) {
         self.data.extend_from_slice(arg);
@@ -545,8 +710,26 @@ impl Cmd {
         })
     }
 
+    // Returns whether the command subscribes to channels or patterns, or unsubscribes from them.
+    #[cfg(feature = "aio")]
+    pub(crate) fn is_subscription(&self) -> bool {
+        let Some(name) = self.arg_idx(0) else {
+            return false;
+        };
+        [
+            &b"SUBSCRIBE"[..],
+            b"PSUBSCRIBE",
+            b"SSUBSCRIBE",
+            b"UNSUBSCRIBE",
+            b"PUNSUBSCRIBE",
+            b"SUNSUBSCRIBE",
+        ]
+        .iter()
+        .any(|command| name.eq_ignore_ascii_case(command))
+    }
+
     // Get a reference to the argument at `idx`
-    #[cfg(feature = "cluster")]
+    #[cfg(any(feature = "cluster", feature = "aio"))]
     pub(crate) fn arg_idx(&self, idx: usize) -> Option<&[u8]> {
         if idx >= self.args.len() {
             return None;
@@ -641,11 +824,14 @@ pub fn pipe() -> Pipeline {
 }
 
 #[cfg(test)]
-#[cfg(feature = "cluster")]
 mod tests {
+    #[cfg(any(feature = "cluster", feature = "aio"))]
     use super::Cmd;
+    use super::{cmd, write_all_vectored, CommandFrame};
+    use std::io;
 
     #[test]
+    #[cfg(feature = "cluster")]
     fn test_cmd_arg_idx() {
         let mut c = Cmd::new();
         assert_eq!(c.arg_idx(0), None);
@@ -660,4 +846,138 @@ mod tests {
         assert_eq!(c.arg_idx(3), None);
         assert_eq!(c.arg_idx(4), None);
     }
+
+    // Accepts at most `max_write` bytes per call, to exercise the resumption after partial writes.
+    struct ShortWriter {
+        written: Vec<u8>,
+        max_write: usize,
+    }
+
+    impl io::Write for ShortWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let len = buf.len().min(self.max_write);
+            self.written.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+            let mut total = 0;
+            for buf in bufs {
+                let len = buf.len().min(self.max_write - total);
+                self.written.extend_from_slice(&buf[..len]);
+                total += len;
+                if total == self.max_write {
+                    break;
+                }
+            }
+            Ok(total)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "aio")]
+    impl ::tokio::io::AsyncWrite for ShortWriter {
+        fn poll_write(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            buf: &[u8],
+        ) -> std::task::Poll<io::Result<usize>> {
+            std::task::Poll::Ready(io::Write::write(self.get_mut(), buf))
+        }
+
+        fn poll_write_vectored(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+            bufs: &[io::IoSlice<'_>],
+        ) -> std::task::Poll<io::Result<usize>> {
+            std::task::Poll::Ready(io::Write::write_vectored(self.get_mut(), bufs))
+        }
+
+        fn is_write_vectored(&self) -> bool {
+            true
+        }
+
+        fn poll_flush(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(
+            self: std::pin::Pin<&mut Self>,
+            _cx: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    #[test]
+    fn test_command_frame_matches_packed_command() {
+        let big = vec![b'x'; 4096];
+        let mut c = cmd("SET");
+        c.arg("key").arg(&big[..]).arg("").arg(&big[..]);
+
+        let frame = CommandFrame::new(&c);
+        let slices = frame.slices();
+        assert_eq!(slices.len(), 5);
+        assert_eq!(slices.concat(), c.get_packed_command());
+
+        let mut writer = ShortWriter {
+            written: Vec::new(),
+            max_write: 7,
+        };
+        write_all_vectored(&mut writer, &slices).unwrap();
+        assert_eq!(writer.written, c.get_packed_command());
+    }
+
+    #[test]
+    #[cfg(feature = "aio")]
+    fn test_pipeline_frame_resumes_partial_async_writes() {
+        use futures_util::FutureExt;
+
+        let big = vec![b'x'; 4096];
+        let mut pipeline = super::pipe();
+        pipeline
+            .atomic()
+            .set("key", &big[..])
+            .get("key")
+            .set("other", &big[..]);
+
+        let frame = pipeline.frame();
+        let slices = frame.slices();
+        assert_eq!(slices.len(), 5);
+        assert_eq!(slices.concat(), pipeline.get_packed_pipeline());
+
+        let mut writer = ShortWriter {
+            written: Vec::new(),
+            max_write: 7,
+        };
+        super::write_all_vectored_async(&mut writer, &slices)
+            .now_or_never()
+            .unwrap()
+            .unwrap();
+        assert_eq!(writer.written, pipeline.get_packed_pipeline());
+    }
+
+    #[test]
+    #[cfg(feature = "aio")]
+    fn test_cmd_is_subscription() {
+        assert!(cmd("subscribe").arg("channel").is_subscription());
+        assert!(cmd("SUNSUBSCRIBE").is_subscription());
+        assert!(!cmd("PUBLISH").arg("channel").is_subscription());
+        assert!(!Cmd::new().is_subscription());
+    }
+
+    #[test]
+    fn test_command_frame_encodes_cursor() {
+        let mut c = cmd("SCAN");
+        c.cursor_arg(42).arg("COUNT").arg(10);
+
+        let frame = CommandFrame::new(&c);
+        assert_eq!(frame.slices().concat(), c.get_packed_command());
+    }
 }
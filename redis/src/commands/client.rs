@@ -0,0 +1,117 @@
+use crate::types::{from_redis_value, ErrorKind, FromRedisValue, HashMap, RedisResult, Value};
+
+/// A client connection of a node, as described by a line of `CLIENT LIST` or by `CLIENT INFO`.
+///
+/// The commonly used fields are parsed, and all the fields of the line are available in `fields`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClientInfo {
+    /// The unique id of the client on the node.
+    pub id: u64,
+    /// The address and port of the client.
+    pub addr: String,
+    /// The address and port of the node that the client is connected to.
+    pub laddr: String,
+    /// The name that the client set with `CLIENT SETNAME`, or an empty string.
+    pub name: String,
+    /// The total duration of the connection, in seconds.
+    pub age: u64,
+    /// The idle time of the connection, in seconds.
+    pub idle: u64,
+    /// The flags of the client, such as `N` for a normal client, or `S` for a replica.
+    pub flags: String,
+    /// The current database of the client.
+    pub db: i64,
+    /// The last command that the client sent.
+    pub cmd: String,
+    /// The authenticated user of the client.
+    pub user: String,
+    /// All the fields of the line, by their names.
+    pub fields: HashMap<String, String>,
+}
+
+impl ClientInfo {
+    /// Parses a line of `CLIENT LIST`, made of space-separated `name=value` fields.
+    pub(crate) fn from_line(line: &str) -> RedisResult<Self> {
+        let fields: HashMap<String, String> = line
+            .split(' ')
+            .filter_map(|field| field.split_once('='))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        if !fields.contains_key("id") {
+            fail!((
+                ErrorKind::TypeError,
+                "Cannot parse CLIENT LIST reply",
+                format!("missing id in `{line}`")
+            ));
+        }
+        let string_field = |name: &str| fields.get(name).cloned().unwrap_or_default();
+        let int_field = |name: &str| -> RedisResult<i64> {
+            fields.get(name).map_or(Ok(0), |value| {
+                value.parse().map_err(|_| {
+                    (
+                        ErrorKind::TypeError,
+                        "Cannot parse CLIENT LIST reply",
+                        format!("invalid {name} `{value}`"),
+                    )
+                        .into()
+                })
+            })
+        };
+        Ok(ClientInfo {
+            id: int_field("id")? as u64,
+            addr: string_field("addr"),
+            laddr: string_field("laddr"),
+            name: string_field("name"),
+            age: int_field("age")? as u64,
+            idle: int_field("idle")? as u64,
+            flags: string_field("flags"),
+            db: int_field("db")?,
+            cmd: string_field("cmd"),
+            user: string_field("user"),
+            fields,
+        })
+    }
+
+    /// Parses the reply of `CLIENT LIST`, with a line per client.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) fn from_list(list: &str) -> RedisResult<Vec<Self>> {
+        list.lines()
+            .filter(|line| !line.is_empty())
+            .map(Self::from_line)
+            .collect()
+    }
+}
+
+impl FromRedisValue for ClientInfo {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let line: String = from_redis_value(v)?;
+        Self::from_line(line.trim_end())
+    }
+}
+
+#[cfg(all(test, feature = "cluster-async"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_list_parsing() {
+        let clients = ClientInfo::from_list(
+            "id=3 addr=127.0.0.1:50188 laddr=127.0.0.1:6379 fd=8 name=worker age=12 idle=0 flags=N db=0 cmd=client|list user=default\n\
+             id=4 addr=127.0.0.1:50190 laddr=127.0.0.1:6379 fd=9 name= age=1 idle=1 flags=S db=0 cmd=replconf user=default\n",
+        )
+        .unwrap();
+        assert_eq!(clients.len(), 2);
+        assert_eq!(clients[0].id, 3);
+        assert_eq!(clients[0].name, "worker");
+        assert_eq!(clients[0].cmd, "client|list");
+        assert_eq!(clients[0].fields["fd"], "8");
+        assert_eq!(clients[1].name, "");
+        assert_eq!(clients[1].flags, "S");
+    }
+
+    #[test]
+    fn test_client_list_parsing_fails_without_id() {
+        let err = ClientInfo::from_list("addr=127.0.0.1:50188 age=1\n").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TypeError);
+    }
+}
@@ -7,6 +7,8 @@ use crate::cluster_routing::SlotAddr;
 use crate::cluster_topology::SLOT_SIZE;
 use crate::{cmd, from_redis_value, Cmd, ErrorKind, RedisError, RedisResult, Value};
 use async_trait::async_trait;
+use futures::future;
+use std::collections::HashSet;
 use std::sync::Arc;
 use strum_macros::Display;
 
@@ -24,6 +26,14 @@ use strum_macros::Display;
 /// The [[`ScanState`]] struct represents the state of a scan operation in a Redis cluster.
 /// It holds information about the current scan state, including the cursor position, scanned slots map,
 /// address being scanned, and address's epoch.
+///
+/// When the scan of an address completes, only the slots that the address owned during the whole scan of the address
+/// are marked as scanned. The slots that moved to or from the address in the meantime, which are detected by a
+/// change of the topology hash or of the address's epoch, and the slots that are still being migrated, are scanned
+/// again from their current owners, so no key is missed during resharding.
+///
+/// The [`ClusterScanCursor`] struct holds a [`ScanStateRC`] for each shard of the cluster, so that the scan
+/// of all the shards is sent concurrently, with the [`ClusterScanOptions`] of the scan.
 
 const BITS_PER_U64: usize = u64::BITS as usize;
 const NUM_OF_SLOTS: usize = SLOT_SIZE as usize;
@@ -70,6 +80,48 @@ impl ClusterScanArgs {
     }
 }
 
+/// The options of a scan of all the shards of the cluster.
+#[derive(Debug, Clone, Default)]
+pub struct ClusterScanOptions {
+    match_pattern: Option<String>,
+    count: Option<usize>,
+    object_type: Option<ObjectType>,
+}
+
+impl ClusterScanOptions {
+    /// Creates options that scan all the keys.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only return the keys that match the given pattern.
+    pub fn match_pattern(mut self, pattern: &str) -> Self {
+        self.match_pattern = Some(pattern.to_string());
+        self
+    }
+
+    /// Hint the amount of keys that each node returns per `SCAN` call.
+    pub fn count(mut self, count: usize) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    /// Only return the keys that hold the given type.
+    pub fn object_type(mut self, object_type: ObjectType) -> Self {
+        self.object_type = Some(object_type);
+        self
+    }
+
+    fn scan_args(&self, scan_state_cursor: ScanStateRC) -> ClusterScanArgs {
+        ClusterScanArgs::new(
+            scan_state_cursor,
+            self.match_pattern.clone(),
+            self.count,
+            self.object_type.clone(),
+        )
+    }
+}
+
 #[derive(PartialEq, Debug, Clone, Default)]
 pub enum ScanStateStage {
     #[default]
@@ -128,6 +180,29 @@ impl ScanStateRC {
     }
 }
 
+/// The opaque cursor of a scan of all the shards of the cluster.
+///
+/// The cursor holds the scan state of each shard that wasn't fully scanned yet. Each state tracks the slots of its
+/// shard and the epoch of the node that is scanned, so the scan of a shard follows its slots to the nodes that own
+/// them after a failover or a slot migration.
+#[derive(Debug, Clone, Default)]
+pub struct ClusterScanCursor {
+    node_states: Vec<ScanStateRC>,
+    status: ScanStateStage,
+}
+
+impl ClusterScanCursor {
+    /// Creates a cursor that starts a new scan.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns `true` if all the shards were scanned.
+    pub fn is_finished(&self) -> bool {
+        self.status == ScanStateStage::Finished
+    }
+}
+
 /// This trait defines the methods for interacting with a Redis cluster during scanning.
 #[async_trait]
 pub(crate) trait ClusterInScan {
@@ -141,6 +216,15 @@ pub(crate) trait ClusterInScan {
     /// Retrieves the slots assigned to a given address in the cluster.
     async fn get_slots_of_address(&self, address: &str) -> Vec<u16>;
 
+    /// Retrieves the addresses of all the primaries in the cluster.
+    async fn get_primary_addresses(&self) -> Vec<String>;
+
+    /// Retrieves the hash of the current topology, which changes whenever the slot ownership changes.
+    async fn get_topology_hash(&self) -> u64;
+
+    /// Retrieves the slots that are known to be migrating between nodes.
+    async fn get_migrating_slots(&self) -> HashSet<u16>;
+
     /// Routes a Redis command to a specific address in the cluster.
     async fn route_command(&self, cmd: Cmd, address: &str) -> RedisResult<Value>;
 
@@ -166,6 +250,10 @@ pub(crate) struct ScanState {
     pub(crate) address_in_scan: String,
     // epoch represent the version of the address, when a failover happens or slots migrate in the epoch will be updated to +1
     address_epoch: u64,
+    // the slots that the address owned when its scan started
+    address_slots_map: SlotsBitsArray,
+    // the hash of the topology when the scan of the address started
+    topology_hash: u64,
     // the status of the scan operation
     scan_status: ScanStateStage,
 }
@@ -179,6 +267,8 @@ impl ScanState {
     /// * `scanned_slots_map` - The scanned slots map.
     /// * `address_in_scan` - The address being scanned.
     /// * `address_epoch` - The epoch of the address being scanned.
+    /// * `address_slots_map` - The slots that the address owned when its scan started.
+    /// * `topology_hash` - The hash of the topology when the scan of the address started.
     /// * `scan_status` - The status of the scan operation.
     ///
     /// # Returns
@@ -189,6 +279,8 @@ impl ScanState {
         scanned_slots_map: SlotsBitsArray,
         address_in_scan: String,
         address_epoch: u64,
+        address_slots_map: SlotsBitsArray,
+        topology_hash: u64,
         scan_status: ScanStateStage,
     ) -> Self {
         Self {
@@ -196,6 +288,8 @@ impl ScanState {
             scanned_slots_map,
             address_in_scan,
             address_epoch,
+            address_slots_map,
+            topology_hash,
             scan_status,
         }
     }
@@ -206,10 +300,37 @@ impl ScanState {
             scanned_slots_map: [0; BITS_ARRAY_SIZE],
             address_in_scan: String::new(),
             address_epoch: 0,
+            address_slots_map: [0; BITS_ARRAY_SIZE],
+            topology_hash: 0,
             scan_status: ScanStateStage::Finished,
         }
     }
 
+    /// Start the scan of an address from a 0 cursor.
+    /// The epoch of the address, the slots it owns and the topology hash are recorded, so that the slots that
+    /// moved while the address was scanned can be detected when its scan completes.
+    async fn start_address_scan<C: ClusterInScan + ?Sized>(
+        connection: &C,
+        scanned_slots_map: SlotsBitsArray,
+        address: String,
+    ) -> ScanState {
+        let address_epoch = connection.get_address_epoch(&address).await.unwrap_or(0);
+        let mut address_slots_map: SlotsBitsArray = [0; BITS_ARRAY_SIZE];
+        for slot in connection.get_slots_of_address(&address).await {
+            set_slot(&mut address_slots_map, slot);
+        }
+        let topology_hash = connection.get_topology_hash().await;
+        ScanState::new(
+            0,
+            scanned_slots_map,
+            address,
+            address_epoch,
+            address_slots_map,
+            topology_hash,
+            ScanStateStage::InProgress,
+        )
+    }
+
     /// Initialize a new scan operation.
     /// This method creates a new scan state with the cursor set to 0, the scanned slots map initialized to 0,
     /// and the address set to the address associated with slot 0.
@@ -217,16 +338,28 @@ impl ScanState {
     /// If the address epoch cannot be retrieved, the method returns an error.
     async fn initiate_scan<C: ClusterInScan + ?Sized>(connection: &C) -> RedisResult<ScanState> {
         let new_scanned_slots_map: SlotsBitsArray = [0; BITS_ARRAY_SIZE];
-        let new_cursor = 0;
         let address = connection.get_address_by_slot(0).await?;
-        let address_epoch = connection.get_address_epoch(&address).await.unwrap_or(0);
-        Ok(ScanState::new(
-            new_cursor,
-            new_scanned_slots_map,
-            address,
-            address_epoch,
-            ScanStateStage::InProgress,
-        ))
+        Ok(ScanState::start_address_scan(connection, new_scanned_slots_map, address).await)
+    }
+
+    /// Initialize the scan of the shard of a primary.
+    /// The slots that the shard doesn't own are marked as scanned, so that the scan only follows the slots of the
+    /// shard, even after they move to other nodes.
+    async fn initiate_shard_scan<C: ClusterInScan + ?Sized>(
+        connection: &C,
+        primary: &str,
+    ) -> RedisResult<ScanState> {
+        let mut scanned_slots_map: SlotsBitsArray = [u64::MAX; BITS_ARRAY_SIZE];
+        for slot in connection.get_slots_of_address(primary).await {
+            let slot_index = slot as usize / BITS_PER_U64;
+            let slot_bit = slot as usize % BITS_PER_U64;
+            scanned_slots_map[slot_index] &= !(1 << slot_bit);
+        }
+        let state = ScanState {
+            scanned_slots_map,
+            ..ScanState::create_finished_state()
+        };
+        state.creating_state_without_slot_changes(connection).await
     }
 
     /// Get the next slot to be scanned based on the scanned slots map.
@@ -249,38 +382,26 @@ impl ScanState {
     }
 
     /// Update the scan state without updating the scanned slots map.
-    /// This method is used when the address epoch has changed, and we can't determine which slots are new.
-    /// In this case, we skip updating the scanned slots map and only update the address and cursor.
+    /// This method starts the scan of the address that owns the next slot that wasn't scanned, and only updates the
+    /// address and cursor.
     async fn creating_state_without_slot_changes<C: ClusterInScan + ?Sized>(
         &self,
         connection: &C,
     ) -> RedisResult<ScanState> {
         let next_slot = self.get_next_slot(&self.scanned_slots_map).unwrap_or(0);
-        let new_address = if next_slot == END_OF_SCAN {
+        if next_slot == END_OF_SCAN {
             return Ok(ScanState::create_finished_state());
-        } else {
-            connection.get_address_by_slot(next_slot).await
-        };
-        match new_address {
-            Ok(address) => {
-                let new_epoch = connection.get_address_epoch(&address).await.unwrap_or(0);
-                Ok(ScanState::new(
-                    0,
-                    self.scanned_slots_map,
-                    address,
-                    new_epoch,
-                    ScanStateStage::InProgress,
-                ))
-            }
-            Err(err) => Err(err),
         }
+        let address = connection.get_address_by_slot(next_slot).await?;
+        Ok(ScanState::start_address_scan(connection, self.scanned_slots_map, address).await)
     }
 
     /// Update the scan state and get the next address to scan.
     /// This method is called when the cursor reaches 0, indicating that the current address has been scanned.
     /// This method updates the scan state based on the scanned slots map and retrieves the next address to scan.
-    /// If the address epoch has changed, the method skips updating the scanned slots map and only updates the address and cursor.
-    /// If the address epoch has not changed, the method updates the scanned slots map with the slots owned by the address.
+    /// If neither the topology hash nor the address epoch changed, the slots owned by the address are marked as scanned.
+    /// Otherwise, only the slots that the address owned both when its scan started and now are marked as scanned.
+    /// The slots that are still being migrated are never marked as scanned, since some of their keys might have moved.
     /// The method returns the new scan state with the updated cursor, scanned slots map, address, and epoch.
     async fn create_updated_scan_state_for_completed_address<C: ClusterInScan + ?Sized>(
         &mut self,
@@ -288,50 +409,40 @@ impl ScanState {
     ) -> RedisResult<ScanState> {
         let _ = connection.refresh_if_topology_changed().await;
         let mut scanned_slots_map = self.scanned_slots_map;
-        // If the address epoch changed it mean that some slots in the address are new, so we cant know which slots been there from the beginning and which are new, or out and in later.
-        // In this case we will skip updating the scanned_slots_map and will just update the address and the cursor
         let new_address_epoch = connection
             .get_address_epoch(&self.address_in_scan)
             .await
             .unwrap_or(0);
-        if new_address_epoch != self.address_epoch {
-            return self.creating_state_without_slot_changes(connection).await;
-        }
-        // If epoch wasn't changed, the slots owned by the address after the refresh are all valid as slots that been scanned
-        // So we will update the scanned_slots_map with the slots owned by the address
-        let slots_scanned = connection.get_slots_of_address(&self.address_in_scan).await;
-        for slot in slots_scanned {
-            let slot_index = slot as usize / BITS_PER_U64;
-            let slot_bit = slot as usize % BITS_PER_U64;
-            scanned_slots_map[slot_index] |= 1 << slot_bit;
-        }
-        // Get the next address to scan and its param base on the next slot set to 0 in the scanned_slots_map
-        let next_slot = self.get_next_slot(&scanned_slots_map).unwrap_or(0);
-        let new_address = if next_slot == END_OF_SCAN {
-            return Ok(ScanState::create_finished_state());
-        } else {
-            connection.get_address_by_slot(next_slot).await
-        };
-        match new_address {
-            Ok(new_address) => {
-                let new_epoch = connection
-                    .get_address_epoch(&new_address)
-                    .await
-                    .unwrap_or(0);
-                let new_cursor = 0;
-                Ok(ScanState::new(
-                    new_cursor,
-                    scanned_slots_map,
-                    new_address,
-                    new_epoch,
-                    ScanStateStage::InProgress,
-                ))
+        let topology_changed = new_address_epoch != self.address_epoch
+            || connection.get_topology_hash().await != self.topology_hash;
+        let migrating_slots = connection.get_migrating_slots().await;
+        for slot in connection.get_slots_of_address(&self.address_in_scan).await {
+            // If the topology changed, the slots that moved into the address during its scan might have been only
+            // partially scanned.
+            if topology_changed && !is_slot_set(&self.address_slots_map, slot) {
+                continue;
+            }
+            if migrating_slots.contains(&slot) {
+                continue;
             }
-            Err(err) => Err(err),
+            set_slot(&mut scanned_slots_map, slot);
         }
+        let state = ScanState {
+            scanned_slots_map,
+            ..ScanState::create_finished_state()
+        };
+        state.creating_state_without_slot_changes(connection).await
     }
 }
 
+fn set_slot(slots_map: &mut SlotsBitsArray, slot: u16) {
+    slots_map[slot as usize / BITS_PER_U64] |= 1 << (slot as usize % BITS_PER_U64);
+}
+
+fn is_slot_set(slots_map: &SlotsBitsArray, slot: u16) -> bool {
+    slots_map[slot as usize / BITS_PER_U64] & (1 << (slot as usize % BITS_PER_U64)) != 0
+}
+
 // Implement the [`ClusterInScan`] trait for [`InnerCore`] of async cluster connection.
 #[async_trait]
 impl<C> ClusterInScan for Core<C>
@@ -366,14 +477,24 @@ where
     async fn get_slots_of_address(&self, address: &str) -> Vec<u16> {
         self.as_ref().get_slots_of_address(address).await
     }
+    async fn get_primary_addresses(&self) -> Vec<String> {
+        self.as_ref().get_primary_addresses().await
+    }
+    async fn get_topology_hash(&self) -> u64 {
+        self.conn_lock.read().await.get_current_topology_hash()
+    }
+    async fn get_migrating_slots(&self) -> HashSet<u16> {
+        self.as_ref().get_migrating_slots()
+    }
     async fn route_command(&self, cmd: Cmd, address: &str) -> RedisResult<Value> {
         let routing = InternalRoutingInfo::SingleNode(InternalSingleNodeRouting::ByAddress(
             address.to_string(),
         ));
         let core = self.to_owned();
-        let response = ClusterConnInner::<C>::try_cmd_request(Arc::new(cmd), routing, core)
-            .await
-            .map_err(|err| err.1)?;
+        let response =
+            ClusterConnInner::<C>::try_cmd_request(Arc::new(cmd), routing, core, None, None)
+                .await
+                .map_err(|err| err.1)?;
         match response {
             Response::Single(value) => Ok(value),
             _ => Err(RedisError::from((
@@ -471,16 +592,84 @@ where
         return Ok((ScanStateRC::create_finished(), new_keys));
     }
 
-    scan_state = ScanState::new(
-        new_cursor,
-        scan_state.scanned_slots_map,
-        scan_state.address_in_scan,
-        scan_state.address_epoch,
-        ScanStateStage::InProgress,
-    );
+    scan_state = ScanState {
+        cursor: new_cursor,
+        scan_status: ScanStateStage::InProgress,
+        ..scan_state
+    };
     Ok((ScanStateRC::from_scan_state(scan_state), new_keys))
 }
 
+/// Perform a scan operation on all the shards of the cluster.
+/// This function sends the next `SCAN` of every shard that wasn't fully scanned concurrently, using [`cluster_scan`]
+/// for each shard. The function returns the new cursor and the keys that all the shards returned.
+/// If the scan of any shard fails, an error is returned, and the scan can be resumed from the given cursor.
+/// The shards that succeeded then return some of their keys again.
+///
+/// # Arguments
+/// * `core` - The connection to the Redis cluster.
+/// * `cursor` - The cursor of the scan, [`ClusterScanCursor::new`] for a new scan.
+/// * `options` - The options of the scan.
+pub(crate) async fn cluster_scan_all_shards<C>(
+    core: C,
+    cursor: ClusterScanCursor,
+    options: ClusterScanOptions,
+) -> RedisResult<(ClusterScanCursor, Vec<Value>)>
+where
+    C: ClusterInScan + Clone,
+{
+    let node_states = match cursor.status {
+        ScanStateStage::Finished => return Ok((cursor, Vec::new())),
+        ScanStateStage::InProgress => cursor.node_states,
+        ScanStateStage::Initiating => {
+            // The slots that no shard owns would otherwise be marked as scanned by all the shards.
+            if !core.are_all_slots_covered().await {
+                return Err(RedisError::from((
+                    ErrorKind::NotAllSlotsCovered,
+                    "Not all slots are covered by the cluster, please check the cluster configuration",
+                )));
+            }
+            let mut node_states = Vec::new();
+            for primary in core.get_primary_addresses().await {
+                let state = ScanState::initiate_shard_scan(&core, &primary).await?;
+                if state.scan_status != ScanStateStage::Finished {
+                    node_states.push(ScanStateRC::from_scan_state(state));
+                }
+            }
+            node_states
+        }
+    };
+
+    let results = future::join_all(
+        node_states
+            .into_iter()
+            .map(|state| cluster_scan(core.clone(), options.scan_args(state))),
+    )
+    .await;
+    let mut keys = Vec::new();
+    let mut remaining_states = Vec::new();
+    for result in results {
+        let (state, mut node_keys) = result?;
+        keys.append(&mut node_keys);
+        if !state.is_finished() {
+            remaining_states.push(state);
+        }
+    }
+
+    let status = if remaining_states.is_empty() {
+        ScanStateStage::Finished
+    } else {
+        ScanStateStage::InProgress
+    };
+    Ok((
+        ClusterScanCursor {
+            node_states: remaining_states,
+            status,
+        },
+        keys,
+    ))
+}
+
 // Send the scan command to the address in the scan_state
 async fn send_scan<C>(
     scan_state: &ScanState,
@@ -541,14 +730,8 @@ where
         .unwrap_or(0);
     let address = core.get_address_by_slot(next_slot).await?;
 
-    let new_epoch = core.get_address_epoch(&address).await.unwrap_or(0);
-    let scan_state = &ScanState::new(
-        0,
-        scan_state.scanned_slots_map,
-        address,
-        new_epoch,
-        ScanStateStage::InProgress,
-    );
+    let scan_state =
+        &ScanState::start_address_scan(core, scan_state.scanned_slots_map, address).await;
     let res = (
         send_scan(scan_state, core, match_pattern, count, object_type).await,
         scan_state.clone(),
@@ -574,6 +757,8 @@ mod tests {
             scanned_slots_map: [0; BITS_ARRAY_SIZE],
             address_in_scan: String::from("address1"),
             address_epoch: 1,
+            address_slots_map: [0; BITS_ARRAY_SIZE],
+            topology_hash: 0,
             scan_status: ScanStateStage::InProgress,
         };
 
@@ -590,6 +775,8 @@ mod tests {
             scanned_slots_map,
             address_in_scan: String::from("address1"),
             address_epoch: 1,
+            address_slots_map: [0; BITS_ARRAY_SIZE],
+            topology_hash: 0,
             scan_status: ScanStateStage::InProgress,
         };
         let next_slot = scan_state.get_next_slot(&scanned_slots_map);
@@ -602,6 +789,8 @@ mod tests {
             scanned_slots_map,
             address_in_scan: String::from("address1"),
             address_epoch: 1,
+            address_slots_map: [0; BITS_ARRAY_SIZE],
+            topology_hash: 0,
             scan_status: ScanStateStage::InProgress,
         };
         let next_slot = scan_state.get_next_slot(&scanned_slots_map);
@@ -625,6 +814,15 @@ mod tests {
                 vec![0, 1, 2]
             }
         }
+        async fn get_primary_addresses(&self) -> Vec<String> {
+            vec!["mock_address".to_string()]
+        }
+        async fn get_topology_hash(&self) -> u64 {
+            0
+        }
+        async fn get_migrating_slots(&self) -> HashSet<u16> {
+            HashSet::new()
+        }
         async fn route_command(&self, _: Cmd, _: &str) -> RedisResult<Value> {
             unimplemented!()
         }
@@ -653,6 +851,8 @@ mod tests {
             scanned_slots_map: [0; BITS_ARRAY_SIZE],
             address_in_scan: "".to_string(),
             address_epoch: 0,
+            address_slots_map: [0; BITS_ARRAY_SIZE],
+            topology_hash: 0,
             scan_status: ScanStateStage::InProgress,
         };
         // Test when all first bits of each u6 are set to 1, the next slots should be 1
@@ -701,6 +901,8 @@ mod tests {
             [0; BITS_ARRAY_SIZE],
             "address".to_string(),
             0,
+            [0; BITS_ARRAY_SIZE],
+            0,
             ScanStateStage::InProgress,
         );
         let scanned_slots_map = scan_state.scanned_slots_map;
@@ -713,4 +915,104 @@ mod tests {
         assert_eq!(updated_scan_state.address_in_scan, "mock_address");
         assert_eq!(updated_scan_state.address_epoch, 0);
     }
+
+    #[tokio::test]
+    async fn test_initiate_shard_scan_only_leaves_the_shard_slots_unscanned() {
+        let connection = MockConnection;
+        let scan_state = ScanState::initiate_shard_scan(&connection, "mock_address")
+            .await
+            .unwrap();
+
+        let mut expected_map: SlotsBitsArray = [u64::MAX; BITS_ARRAY_SIZE];
+        expected_map[0] = !0b111000;
+        assert_eq!(scan_state.scanned_slots_map, expected_map);
+        assert_eq!(
+            scan_state.get_next_slot(&scan_state.scanned_slots_map),
+            Some(3)
+        );
+        assert_eq!(scan_state.cursor, 0);
+        assert_eq!(scan_state.address_in_scan, "mock_address");
+        assert_eq!(scan_state.scan_status, ScanStateStage::InProgress);
+    }
+
+    // A mock connection whose address owns the slots 3, 4 and 5 after a resharding.
+    struct ReshardedMockConnection {
+        topology_hash: u64,
+        migrating_slots: HashSet<u16>,
+    }
+    #[async_trait]
+    impl ClusterInScan for ReshardedMockConnection {
+        async fn refresh_if_topology_changed(&self) {}
+        async fn get_address_by_slot(&self, _slot: u16) -> RedisResult<String> {
+            Ok("mock_address".to_string())
+        }
+        async fn get_address_epoch(&self, _address: &str) -> Result<u64, RedisError> {
+            Ok(0)
+        }
+        async fn get_slots_of_address(&self, _address: &str) -> Vec<u16> {
+            vec![3, 4, 5]
+        }
+        async fn get_primary_addresses(&self) -> Vec<String> {
+            vec!["mock_address".to_string()]
+        }
+        async fn get_topology_hash(&self) -> u64 {
+            self.topology_hash
+        }
+        async fn get_migrating_slots(&self) -> HashSet<u16> {
+            self.migrating_slots.clone()
+        }
+        async fn route_command(&self, _: Cmd, _: &str) -> RedisResult<Value> {
+            unimplemented!()
+        }
+        async fn are_all_slots_covered(&self) -> bool {
+            true
+        }
+    }
+
+    // A state of an address that owned the slots 2, 3 and 4 when its scan started, under the topology hash 1.
+    fn state_of_completed_address() -> ScanState {
+        let mut address_slots_map = [0; BITS_ARRAY_SIZE];
+        address_slots_map[0] = 0b11100;
+        ScanState::new(
+            0,
+            [0; BITS_ARRAY_SIZE],
+            "mock_address".to_string(),
+            0,
+            address_slots_map,
+            1,
+            ScanStateStage::InProgress,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_completed_address_marks_only_the_slots_it_owned_during_the_whole_scan() {
+        let connection = ReshardedMockConnection {
+            topology_hash: 2,
+            migrating_slots: HashSet::new(),
+        };
+        let updated_scan_state = state_of_completed_address()
+            .create_updated_scan_state_for_completed_address(&connection)
+            .await
+            .unwrap();
+
+        // Slot 5 moved in during the scan, so it's scanned again.
+        assert_eq!(updated_scan_state.scanned_slots_map[0], 0b11000);
+        assert_eq!(updated_scan_state.address_slots_map[0], 0b111000);
+        assert_eq!(updated_scan_state.topology_hash, 2);
+    }
+
+    #[tokio::test]
+    async fn test_completed_address_does_not_mark_migrating_slots() {
+        let connection = ReshardedMockConnection {
+            topology_hash: 1,
+            migrating_slots: HashSet::from([4]),
+        };
+        let updated_scan_state = state_of_completed_address()
+            .create_updated_scan_state_for_completed_address(&connection)
+            .await
+            .unwrap();
+
+        // The topology didn't change, so all the slots of the address are scanned, except for the migrating one.
+        assert_eq!(updated_scan_state.scanned_slots_map[0], 0b101000);
+    }
 }
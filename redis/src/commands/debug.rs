@@ -0,0 +1,79 @@
+use super::ObjectEncoding;
+use crate::types::{from_redis_value, ErrorKind, FromRedisValue, HashMap, RedisResult, Value};
+
+/// Low level information about a key, as returned by `DEBUG OBJECT`.
+///
+/// The commonly used fields are parsed, and all the fields of the reply are available in `fields`.
+#[derive(Debug, Clone)]
+pub struct DebugObject {
+    /// The number of references to the value.
+    pub refcount: i64,
+    /// The internal representation of the value.
+    pub encoding: ObjectEncoding,
+    /// The length in bytes of the value when serialized to an RDB file.
+    pub serialized_length: i64,
+    /// The number of seconds since the key was last accessed.
+    pub lru_seconds_idle: i64,
+    /// All the fields of the reply, by their names.
+    pub fields: HashMap<String, String>,
+}
+
+impl FromRedisValue for DebugObject {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let reply: String = from_redis_value(v)?;
+        // The reply looks like `Value at:0x7f... refcount:1 encoding:embstr serializedlength:4 ...`
+        let fields: HashMap<String, String> = reply
+            .split_whitespace()
+            .filter_map(|field| field.split_once(':'))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        let int_field = |name: &str| -> RedisResult<i64> {
+            match fields.get(name).map(|value| value.parse::<i64>()) {
+                None => Ok(0),
+                Some(Ok(value)) => Ok(value),
+                Some(Err(_)) => fail!((
+                    ErrorKind::TypeError,
+                    "Cannot parse DEBUG OBJECT reply",
+                    format!("invalid `{name}` field in `{reply}`")
+                )),
+            }
+        };
+        let encoding = match fields.get("encoding") {
+            Some(encoding) => from_redis_value(&Value::BulkString(encoding.as_bytes().to_vec()))?,
+            None => fail!((
+                ErrorKind::TypeError,
+                "Cannot parse DEBUG OBJECT reply",
+                reply
+            )),
+        };
+        Ok(DebugObject {
+            refcount: int_field("refcount")?,
+            encoding,
+            serialized_length: int_field("serializedlength")?,
+            lru_seconds_idle: int_field("lru_seconds_idle")?,
+            fields,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_debug_object_parsing() {
+        let reply = Value::SimpleString(
+            "Value at:0x7f36f7c0f260 refcount:1 encoding:embstr serializedlength:4 lru:1015 lru_seconds_idle:12"
+                .to_string(),
+        );
+        let object: DebugObject = from_redis_value(&reply).unwrap();
+        assert_eq!(object.refcount, 1);
+        assert_eq!(object.encoding, ObjectEncoding::EmbStr);
+        assert_eq!(object.serialized_length, 4);
+        assert_eq!(object.lru_seconds_idle, 12);
+        assert_eq!(object.fields["lru"], "1015");
+
+        let reply = Value::SimpleString("Value at:0x0 refcount:one encoding:int".to_string());
+        assert!(from_redis_value::<DebugObject>(&reply).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::types::{from_redis_value, ErrorKind, FromRedisValue, HashMap, RedisResult, Value};
+
+/// The internal representation of a key's value, as returned by `OBJECT ENCODING`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObjectEncoding {
+    /// A string, stored as a raw string.
+    Raw,
+    /// A string that represents a 64-bit signed integer.
+    Int,
+    /// A short string, embedded in the object's allocation.
+    EmbStr,
+    /// A space-efficient representation of small lists, hashes and sorted sets.
+    Listpack,
+    /// The predecessor of `Listpack`, used before Redis 7.0.
+    Ziplist,
+    /// A linked list of listpacks or ziplists, used for large lists.
+    Quicklist,
+    /// A linked list, used before Redis 3.2.
+    LinkedList,
+    /// A hash table, used for large hashes and sets.
+    HashTable,
+    /// A set of integers.
+    IntSet,
+    /// A skip list, used for large sorted sets.
+    SkipList,
+    /// A stream.
+    Stream,
+    /// An encoding that isn't known to this crate.
+    Other(String),
+}
+
+impl FromRedisValue for ObjectEncoding {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let encoding: String = from_redis_value(v)?;
+        Ok(match encoding.as_str() {
+            "raw" => ObjectEncoding::Raw,
+            "int" => ObjectEncoding::Int,
+            "embstr" => ObjectEncoding::EmbStr,
+            "listpack" => ObjectEncoding::Listpack,
+            "ziplist" => ObjectEncoding::Ziplist,
+            "quicklist" => ObjectEncoding::Quicklist,
+            "linkedlist" => ObjectEncoding::LinkedList,
+            "hashtable" => ObjectEncoding::HashTable,
+            "intset" => ObjectEncoding::IntSet,
+            "skiplist" => ObjectEncoding::SkipList,
+            "stream" => ObjectEncoding::Stream,
+            _ => ObjectEncoding::Other(encoding),
+        })
+    }
+}
+
+/// The memory usage report of a node, as returned by `MEMORY STATS`.
+///
+/// The commonly used fields are parsed, and all the fields of the reply are available in `fields`.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStats {
+    /// Peak memory consumed by the server, in bytes.
+    pub peak_allocated: i64,
+    /// Total number of bytes allocated by the server.
+    pub total_allocated: i64,
+    /// Initial amount of memory consumed by the server at startup, in bytes.
+    pub startup_allocated: i64,
+    /// Size in bytes of the replication backlog.
+    pub replication_backlog: i64,
+    /// Total size in bytes of all replicas' overheads.
+    pub clients_replicas: i64,
+    /// Total size in bytes of all clients' overheads.
+    pub clients_normal: i64,
+    /// The sum of all overheads, in bytes.
+    pub overhead_total: i64,
+    /// The total number of keys stored across all databases.
+    pub keys_count: i64,
+    /// The ratio between the net memory usage and the number of keys.
+    pub keys_bytes_per_key: i64,
+    /// The size in bytes of the dataset.
+    pub dataset_bytes: i64,
+    /// The percentage of the dataset out of the net memory usage.
+    pub dataset_percentage: f64,
+    /// The percentage of the current memory usage out of the peak memory usage.
+    pub peak_percentage: f64,
+    /// The ratio between the memory used by the process and the memory allocated by the server.
+    pub fragmentation: f64,
+    /// All the fields of the reply, by their names.
+    pub fields: HashMap<String, Value>,
+}
+
+impl FromRedisValue for MemoryStats {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let fields: HashMap<String, Value> = from_redis_value(v)?;
+        if fields.is_empty() {
+            fail!((ErrorKind::TypeError, "Cannot parse MEMORY STATS reply"));
+        }
+        let int_field =
+            |name: &str| -> RedisResult<i64> { fields.get(name).map_or(Ok(0), from_redis_value) };
+        let float_field =
+            |name: &str| -> RedisResult<f64> { fields.get(name).map_or(Ok(0.0), from_redis_value) };
+        Ok(MemoryStats {
+            peak_allocated: int_field("peak.allocated")?,
+            total_allocated: int_field("total.allocated")?,
+            startup_allocated: int_field("startup.allocated")?,
+            replication_backlog: int_field("replication.backlog")?,
+            clients_replicas: int_field("clients.slaves")?,
+            clients_normal: int_field("clients.normal")?,
+            overhead_total: int_field("overhead.total")?,
+            keys_count: int_field("keys.count")?,
+            keys_bytes_per_key: int_field("keys.bytes-per-key")?,
+            dataset_bytes: int_field("dataset.bytes")?,
+            dataset_percentage: float_field("dataset.percentage")?,
+            peak_percentage: float_field("peak.percentage")?,
+            fragmentation: float_field("fragmentation")?,
+            fields,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_redis_value;
+
+    #[test]
+    fn test_object_encoding_parsing() {
+        let parse = |encoding: &str| -> ObjectEncoding {
+            from_redis_value(&Value::BulkString(encoding.as_bytes().to_vec())).unwrap()
+        };
+        assert_eq!(parse("embstr"), ObjectEncoding::EmbStr);
+        assert_eq!(parse("listpack"), ObjectEncoding::Listpack);
+        assert_eq!(parse("future"), ObjectEncoding::Other("future".to_string()));
+    }
+
+    #[test]
+    fn test_memory_stats_parsing() {
+        let value = parse_redis_value(
+            b"*8\r\n$14\r\npeak.allocated\r\n:1024\r\n$18\r\ndataset.percentage\r\n$4\r\n42.5\r\n$4\r\ndb.0\r\n*2\r\n$23\r\noverhead.hashtable.main\r\n:72\r\n$13\r\nfragmentation\r\n$3\r\n1.5\r\n",
+        )
+        .unwrap();
+        let stats: MemoryStats = from_redis_value(&value).unwrap();
+        assert_eq!(stats.peak_allocated, 1024);
+        assert_eq!(stats.dataset_percentage, 42.5);
+        assert_eq!(stats.fragmentation, 1.5);
+        assert_eq!(stats.keys_count, 0);
+        assert!(stats.fields.contains_key("db.0"));
+    }
+}
@@ -22,6 +22,20 @@ pub(crate) mod cluster_scan;
 #[cfg(feature = "cluster-async")]
 pub use cluster_scan::ObjectType;
 
+#[cfg(feature = "cluster-async")]
+pub use cluster_scan::{ClusterScanCursor, ClusterScanOptions};
+
+mod memory;
+pub use memory::{MemoryStats, ObjectEncoding};
+
+mod client;
+pub use client::ClientInfo;
+
+#[cfg(feature = "debug-commands")]
+mod debug;
+#[cfg(feature = "debug-commands")]
+pub use debug::DebugObject;
+
 #[cfg(feature = "json")]
 pub use json::JsonCommands;
 
@@ -878,6 +892,8 @@ implement_commands! {
     // Object commands
 
     /// Returns the encoding of a key.
+    ///
+    /// The reply can be parsed into an [`ObjectEncoding`].
     fn object_encoding<K: ToRedisArgs>(key: K) {
         cmd("OBJECT").arg("ENCODING").arg(key)
     }
@@ -897,6 +913,58 @@ implement_commands! {
         cmd("OBJECT").arg("REFCOUNT").arg(key)
     }
 
+    // Memory commands
+
+    /// Returns the number of bytes that a key and its value take in memory.
+    /// For nested data types, `samples` sets the number of sampled nested values,
+    /// where 0 samples all of them.
+    ///
+    /// ```text
+    /// MEMORY USAGE <key> [SAMPLES <count>]
+    /// ```
+    fn memory_usage<K: ToRedisArgs>(key: K, samples: Option<usize>) {
+        cmd("MEMORY")
+            .arg("USAGE")
+            .arg(key)
+            .arg(samples.map(|samples| ("SAMPLES", samples)))
+    }
+
+    // Debug commands
+
+    /// Blocks the server for the given number of seconds, which is useful for simulating slow nodes in tests.
+    /// In cluster mode, use `route_command` in order to choose the node that sleeps.
+    ///
+    /// ```text
+    /// DEBUG SLEEP <seconds>
+    /// ```
+    #[cfg(feature = "debug-commands")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "debug-commands")))]
+    fn debug_sleep<>(seconds: f64) {
+        cmd("DEBUG").arg("SLEEP").arg(seconds)
+    }
+
+    /// Returns low level information about a key, which can be parsed into a [`DebugObject`].
+    ///
+    /// ```text
+    /// DEBUG OBJECT <key>
+    /// ```
+    #[cfg(feature = "debug-commands")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "debug-commands")))]
+    fn debug_object<K: ToRedisArgs>(key: K) {
+        cmd("DEBUG").arg("OBJECT").arg(key)
+    }
+
+    /// Enables or disables TCP_QUICKACK on the server's side of the connection.
+    ///
+    /// ```text
+    /// DEBUG QUICKACK <0|1>
+    /// ```
+    #[cfg(feature = "debug-commands")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "debug-commands")))]
+    fn debug_quickack<>(enabled: bool) {
+        cmd("DEBUG").arg("QUICKACK").arg(if enabled { 1 } else { 0 })
+    }
+
     // ACL commands
 
     /// When Redis is configured to use an ACL file (with the aclfile
@@ -1406,6 +1474,53 @@ implement_commands! {
     }
 
 
+    /// Transfer ownership of the pending stream entries that were idle for at least
+    /// `min_idle_time` milliseconds, scanning the pending entries list from `start`.
+    /// Each call returns the `id` from which the next call should continue the scan.
+    ///
+    /// ```no_run
+    /// use redis::{Connection,Commands,RedisResult};
+    /// use redis::streams::{StreamAutoClaimOptions, StreamAutoClaimReply};
+    /// let client = redis::Client::open("redis://127.0.0.1/0").unwrap();
+    /// let mut con = client.get_connection(None).unwrap();
+    ///
+    /// // Claim up to 10 entries of "k1" that were idle for 60 seconds,
+    /// // from group "g1", for consumer "c2"
+    ///
+    /// let opts = StreamAutoClaimOptions::default().count(10);
+    /// let results: RedisResult<StreamAutoClaimReply> =
+    ///     con.xautoclaim_options("k1", "g1", "c2", 60000, "0-0", opts);
+    /// ```
+    ///
+    /// ```text
+    /// XAUTOCLAIM <key> <group> <consumer> <min-idle-time> <start>
+    ///     [COUNT <count>] [JUSTID]
+    /// ```
+    #[cfg(feature = "streams")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "streams")))]
+    fn xautoclaim_options<
+        K: ToRedisArgs,
+        G: ToRedisArgs,
+        C: ToRedisArgs,
+        MIT: ToRedisArgs,
+        S: ToRedisArgs
+    >(
+        key: K,
+        group: G,
+        consumer: C,
+        min_idle_time: MIT,
+        start: S,
+        options: streams::StreamAutoClaimOptions
+    ) {
+        cmd("XAUTOCLAIM")
+            .arg(key)
+            .arg(group)
+            .arg(consumer)
+            .arg(min_idle_time)
+            .arg(start)
+            .arg(options)
+    }
+
     /// Deletes a list of `id`s for a given stream `key`.
     ///
     /// ```text
@@ -1,13 +1,13 @@
 use std::collections::{HashSet, VecDeque};
 use std::fmt;
-use std::io::{self, Write};
+use std::io;
 use std::net::{self, SocketAddr, TcpStream, ToSocketAddrs};
 use std::ops::DerefMut;
 use std::path::PathBuf;
 use std::str::{from_utf8, FromStr};
 use std::time::Duration;
 
-use crate::cmd::{cmd, pipe, Cmd};
+use crate::cmd::{cmd, pipe, write_all_vectored, Cmd, CommandFrame};
 use crate::parser::Parser;
 use crate::pipeline::Pipeline;
 use crate::types::{
@@ -767,10 +767,12 @@ impl ActualConnection {
         })
     }
 
-    pub fn send_bytes(&mut self, bytes: &[u8]) -> RedisResult<Value> {
+    /// Sends the slices one after the other, with vectored writes.
+    pub fn send_slices(&mut self, slices: &[&[u8]]) -> RedisResult<Value> {
         match *self {
             ActualConnection::Tcp(ref mut connection) => {
-                let res = connection.reader.write_all(bytes).map_err(RedisError::from);
+                let res =
+                    write_all_vectored(&mut connection.reader, slices).map_err(RedisError::from);
                 match res {
                     Err(e) => {
                         if e.is_unrecoverable_error() {
@@ -783,7 +785,8 @@ impl ActualConnection {
             }
             #[cfg(all(feature = "tls-native-tls", not(feature = "tls-rustls")))]
             ActualConnection::TcpNativeTls(ref mut connection) => {
-                let res = connection.reader.write_all(bytes).map_err(RedisError::from);
+                let res =
+                    write_all_vectored(&mut connection.reader, slices).map_err(RedisError::from);
                 match res {
                     Err(e) => {
                         if e.is_unrecoverable_error() {
@@ -796,7 +799,8 @@ impl ActualConnection {
             }
             #[cfg(feature = "tls-rustls")]
             ActualConnection::TcpRustls(ref mut connection) => {
-                let res = connection.reader.write_all(bytes).map_err(RedisError::from);
+                let res =
+                    write_all_vectored(&mut connection.reader, slices).map_err(RedisError::from);
                 match res {
                     Err(e) => {
                         if e.is_unrecoverable_error() {
@@ -809,7 +813,8 @@ impl ActualConnection {
             }
             #[cfg(unix)]
             ActualConnection::Unix(ref mut connection) => {
-                let result = connection.sock.write_all(bytes).map_err(RedisError::from);
+                let result =
+                    write_all_vectored(&mut connection.sock, slices).map_err(RedisError::from);
                 match result {
                     Err(e) => {
                         if e.is_unrecoverable_error() {
@@ -902,8 +907,18 @@ pub(crate) fn create_rustls_config(
         root_store.add(cert)?;
     }
 
+    let session_store = tls_params
+        .as_ref()
+        .and_then(|tls_params| tls_params.session_store.clone());
+    let server_cert_verifier = tls_params
+        .as_ref()
+        .and_then(|tls_params| tls_params.server_cert_verifier.clone());
+    let alpn_protocols = tls_params
+        .as_ref()
+        .map(|tls_params| tls_params.alpn_protocols.clone())
+        .unwrap_or_default();
     let config = rustls::ClientConfig::builder();
-    let config = if let Some(tls_params) = tls_params {
+    let mut config = if let Some(tls_params) = tls_params {
         let config_builder =
             config.with_root_certificates(tls_params.root_cert_store.unwrap_or(root_store));
 
@@ -929,6 +944,15 @@ pub(crate) fn create_rustls_config(
             .with_root_certificates(root_store)
             .with_no_client_auth()
     };
+    if let Some(session_store) = session_store {
+        config.resumption = rustls::client::Resumption::store(session_store);
+    }
+    if let Some(server_cert_verifier) = server_cert_verifier {
+        config
+            .dangerous()
+            .set_certificate_verifier(server_cert_verifier);
+    }
+    config.alpn_protocols = alpn_protocols;
 
     match (insecure, cfg!(feature = "tls-rustls-insecure")) {
         #[cfg(feature = "tls-rustls-insecure")]
@@ -1331,7 +1355,11 @@ impl Connection {
     }
 
     fn send_bytes(&mut self, bytes: &[u8]) -> RedisResult<Value> {
-        let result = self.con.send_bytes(bytes);
+        self.send_slices(&[bytes])
+    }
+
+    fn send_slices(&mut self, slices: &[&[u8]]) -> RedisResult<Value> {
+        let result = self.con.send_slices(slices);
         if self.protocol != ProtocolVersion::RESP2 {
             if let Err(e) = &result {
                 if e.is_connection_dropped() {
@@ -1350,12 +1378,12 @@ impl Connection {
 impl ConnectionLike for Connection {
     /// Sends a [Cmd] into the TCP socket and reads a single response from it.
     fn req_command(&mut self, cmd: &Cmd) -> RedisResult<Value> {
-        let pcmd = cmd.get_packed_command();
         if self.pubsub {
             self.exit_pubsub()?;
         }
 
-        self.send_bytes(&pcmd)?;
+        let frame = CommandFrame::new(cmd);
+        self.send_slices(&frame.slices())?;
         if cmd.is_no_response() {
             return Ok(Value::Nil);
         }
@@ -1392,6 +1420,10 @@ impl ConnectionLike for Connection {
         offset: usize,
         count: usize,
     ) -> RedisResult<Vec<Value>> {
+        // An empty pipeline has no replies to wait for.
+        if cmd.is_empty() {
+            return Ok(vec![]);
+        }
         if self.pubsub {
             self.exit_pubsub()?;
         }
@@ -0,0 +1,397 @@
+//! Defines typed views of the reply of the `INFO` command.
+//!
+//! The reply of `INFO` is a text of `name:value` lines, grouped into sections by `# Section` header lines.
+//! [`InfoParser`] turns it into an [`Info`], which exposes the commonly used fields of the server, clients,
+//! memory, replication and keyspace sections as typed values, and keeps all the fields of the reply by section.
+//!
+//! ```rust,no_run
+//! # fn do_something() -> redis::RedisResult<()> {
+//! # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+//! # let mut con = client.get_connection(None).unwrap();
+//! let info: redis::info::Info = redis::cmd("INFO").query(&mut con)?;
+//! if info.replication.role == Some(redis::info::ReplicationRole::Primary) {
+//!     println!("{:?} clients are connected", info.clients.connected_clients);
+//! }
+//! # Ok(()) }
+//! ```
+
+use std::str::FromStr;
+
+use crate::types::{from_redis_value, ErrorKind, FromRedisValue, HashMap, RedisResult, Value};
+
+type Fields = HashMap<String, String>;
+
+/// The role of a node, as reported by the replication section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ReplicationRole {
+    /// The node is a primary.
+    Primary,
+    /// The node replicates a primary.
+    Replica,
+}
+
+/// The `server` section of the reply.
+#[derive(Debug, Clone, Default)]
+pub struct ServerSection {
+    /// The version of the server.
+    pub redis_version: Option<String>,
+    /// The name of the server implementation, reported by Valkey.
+    pub server_name: Option<String>,
+    /// The mode of the server: `standalone`, `sentinel` or `cluster`.
+    pub redis_mode: Option<String>,
+    /// The operating system that hosts the server.
+    pub os: Option<String>,
+    /// The PID of the server process.
+    pub process_id: Option<u64>,
+    /// The TCP port the server listens on.
+    pub tcp_port: Option<u16>,
+    /// The number of seconds since the server started.
+    pub uptime_in_seconds: Option<u64>,
+    /// The availability zone the server was configured with, if any.
+    pub availability_zone: Option<String>,
+}
+
+/// The `clients` section of the reply.
+#[derive(Debug, Clone, Default)]
+pub struct ClientsSection {
+    /// The number of client connections, excluding the connections from replicas.
+    pub connected_clients: Option<u64>,
+    /// The number of clients pending on a blocking call.
+    pub blocked_clients: Option<u64>,
+    /// The number of clients being tracked.
+    pub tracking_clients: Option<u64>,
+    /// The value of the `maxclients` configuration.
+    pub maxclients: Option<u64>,
+}
+
+/// The `memory` section of the reply.
+#[derive(Debug, Clone, Default)]
+pub struct MemorySection {
+    /// Total number of bytes allocated by the server.
+    pub used_memory: Option<u64>,
+    /// Number of bytes that the operating system allocated to the server.
+    pub used_memory_rss: Option<u64>,
+    /// Peak memory consumed by the server, in bytes.
+    pub used_memory_peak: Option<u64>,
+    /// The value of the `maxmemory` configuration, in bytes.
+    pub maxmemory: Option<u64>,
+    /// The value of the `maxmemory-policy` configuration.
+    pub maxmemory_policy: Option<String>,
+    /// The ratio between `used_memory_rss` and `used_memory`.
+    pub mem_fragmentation_ratio: Option<f64>,
+}
+
+/// A replica, as listed in the replication section of its primary.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplicaInfo {
+    /// The IP address of the replica.
+    pub ip: String,
+    /// The port of the replica.
+    pub port: u16,
+    /// The replication state of the replica, for example `online`.
+    pub state: String,
+    /// The replication offset of the replica.
+    pub offset: i64,
+    /// The number of seconds since the last interaction with the replica.
+    pub lag: i64,
+}
+
+/// The `replication` section of the reply.
+#[derive(Debug, Clone, Default)]
+pub struct ReplicationSection {
+    /// The role of the node.
+    pub role: Option<ReplicationRole>,
+    /// The number of connected replicas.
+    pub connected_replicas: Option<u64>,
+    /// The replicas of the node, if it's a primary.
+    pub replicas: Vec<ReplicaInfo>,
+    /// The host of the primary, if the node is a replica.
+    pub primary_host: Option<String>,
+    /// The port of the primary, if the node is a replica.
+    pub primary_port: Option<u16>,
+    /// The status of the link to the primary, `up` or `down`, if the node is a replica.
+    pub primary_link_status: Option<String>,
+    /// The replication offset of the node.
+    pub primary_repl_offset: Option<i64>,
+}
+
+/// The statistics of a single database, as listed in the `keyspace` section of the reply.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyspaceInfo {
+    /// The number of keys in the database.
+    pub keys: u64,
+    /// The number of keys with an expiration.
+    pub expires: u64,
+    /// The average time to live of the keys with an expiration, in milliseconds.
+    pub avg_ttl: u64,
+}
+
+/// The parsed reply of the `INFO` command.
+///
+/// Sections and fields that are missing from the reply, for example because only some of the sections were
+/// requested, are left empty.
+#[derive(Debug, Clone, Default)]
+pub struct Info {
+    /// The `server` section.
+    pub server: ServerSection,
+    /// The `clients` section.
+    pub clients: ClientsSection,
+    /// The `memory` section.
+    pub memory: MemorySection,
+    /// The `replication` section.
+    pub replication: ReplicationSection,
+    /// The `keyspace` section, by database index.
+    pub keyspace: HashMap<u16, KeyspaceInfo>,
+    /// All the fields of the reply, by their lowercase section names and then by their names.
+    pub sections: HashMap<String, HashMap<String, String>>,
+}
+
+/// Parses the reply of the `INFO` command into an [`Info`].
+///
+/// Fields that are present but can't be parsed into their expected types cause a `TypeError`.
+pub struct InfoParser;
+
+impl InfoParser {
+    /// Parses the text of an `INFO` reply.
+    pub fn parse(info: &str) -> RedisResult<Info> {
+        let mut sections: HashMap<String, Fields> = HashMap::new();
+        let mut section = String::new();
+        for line in info.lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('#') {
+                section = header.trim().to_lowercase();
+                continue;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                sections
+                    .entry(section.clone())
+                    .or_default()
+                    .insert(name.to_string(), value.to_string());
+            }
+        }
+
+        let empty = Fields::new();
+        let section = |name: &str| sections.get(name).unwrap_or(&empty);
+        Ok(Info {
+            server: parse_server(section("server"))?,
+            clients: parse_clients(section("clients"))?,
+            memory: parse_memory(section("memory"))?,
+            replication: parse_replication(section("replication"))?,
+            keyspace: parse_keyspace(section("keyspace"))?,
+            sections,
+        })
+    }
+}
+
+impl FromRedisValue for Info {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let info: String = from_redis_value(v)?;
+        InfoParser::parse(&info)
+    }
+}
+
+fn invalid_field<T>(name: &str, value: &str) -> RedisResult<T> {
+    fail!((
+        ErrorKind::TypeError,
+        "Cannot parse INFO reply",
+        format!("invalid `{name}` field: `{value}`")
+    ))
+}
+
+fn field<T: FromStr>(fields: &Fields, name: &str) -> RedisResult<Option<T>> {
+    match fields.get(name) {
+        None => Ok(None),
+        Some(value) => match value.parse() {
+            Ok(parsed) => Ok(Some(parsed)),
+            Err(_) => invalid_field(name, value),
+        },
+    }
+}
+
+/// Parses a `name=value,name=value` field, such as the keyspace and replica fields.
+fn subfields(value: &str) -> Fields {
+    value
+        .split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+fn required_subfield<T: FromStr + Default>(
+    name: &str,
+    value: &str,
+    fields: &Fields,
+    subfield: &str,
+) -> RedisResult<T> {
+    match field(fields, subfield) {
+        Ok(parsed) => Ok(parsed.unwrap_or_default()),
+        Err(_) => invalid_field(name, value),
+    }
+}
+
+fn parse_server(fields: &Fields) -> RedisResult<ServerSection> {
+    Ok(ServerSection {
+        redis_version: field(fields, "redis_version")?,
+        server_name: field(fields, "server_name")?,
+        redis_mode: field(fields, "redis_mode")?,
+        os: field(fields, "os")?,
+        process_id: field(fields, "process_id")?,
+        tcp_port: field(fields, "tcp_port")?,
+        uptime_in_seconds: field(fields, "uptime_in_seconds")?,
+        availability_zone: field::<String>(fields, "availability_zone")?
+            .filter(|zone| !zone.is_empty()),
+    })
+}
+
+fn parse_clients(fields: &Fields) -> RedisResult<ClientsSection> {
+    Ok(ClientsSection {
+        connected_clients: field(fields, "connected_clients")?,
+        blocked_clients: field(fields, "blocked_clients")?,
+        tracking_clients: field(fields, "tracking_clients")?,
+        maxclients: field(fields, "maxclients")?,
+    })
+}
+
+fn parse_memory(fields: &Fields) -> RedisResult<MemorySection> {
+    Ok(MemorySection {
+        used_memory: field(fields, "used_memory")?,
+        used_memory_rss: field(fields, "used_memory_rss")?,
+        used_memory_peak: field(fields, "used_memory_peak")?,
+        maxmemory: field(fields, "maxmemory")?,
+        maxmemory_policy: field(fields, "maxmemory_policy")?,
+        mem_fragmentation_ratio: field(fields, "mem_fragmentation_ratio")?,
+    })
+}
+
+fn parse_replication(fields: &Fields) -> RedisResult<ReplicationSection> {
+    let role = match fields.get("role").map(String::as_str) {
+        None => None,
+        Some("master") => Some(ReplicationRole::Primary),
+        Some("slave") => Some(ReplicationRole::Replica),
+        Some(role) => return invalid_field("role", role),
+    };
+
+    // Replicas are listed as `slave0:ip=...,port=...,state=online,offset=...,lag=0`.
+    let mut replicas = Vec::new();
+    let mut index = 0;
+    while let Some(value) = fields.get(&format!("slave{index}")) {
+        let name = format!("slave{index}");
+        let replica = subfields(value);
+        replicas.push(ReplicaInfo {
+            ip: replica.get("ip").cloned().unwrap_or_default(),
+            port: required_subfield(&name, value, &replica, "port")?,
+            state: replica.get("state").cloned().unwrap_or_default(),
+            offset: required_subfield(&name, value, &replica, "offset")?,
+            lag: required_subfield(&name, value, &replica, "lag")?,
+        });
+        index += 1;
+    }
+
+    Ok(ReplicationSection {
+        role,
+        connected_replicas: field(fields, "connected_slaves")?,
+        replicas,
+        primary_host: field(fields, "master_host")?,
+        primary_port: field(fields, "master_port")?,
+        primary_link_status: field(fields, "master_link_status")?,
+        primary_repl_offset: field(fields, "master_repl_offset")?,
+    })
+}
+
+fn parse_keyspace(fields: &Fields) -> RedisResult<HashMap<u16, KeyspaceInfo>> {
+    let mut keyspace = HashMap::new();
+    for (name, value) in fields {
+        let Some(db) = name.strip_prefix("db").and_then(|db| db.parse().ok()) else {
+            return invalid_field(name, value);
+        };
+        let db_fields = subfields(value);
+        keyspace.insert(
+            db,
+            KeyspaceInfo {
+                keys: required_subfield(name, value, &db_fields, "keys")?,
+                expires: required_subfield(name, value, &db_fields, "expires")?,
+                avg_ttl: required_subfield(name, value, &db_fields, "avg_ttl")?,
+            },
+        );
+    }
+    Ok(keyspace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INFO: &str = "# Server\r\n\
+        redis_version:7.2.4\r\n\
+        redis_mode:standalone\r\n\
+        process_id:42\r\n\
+        tcp_port:6379\r\n\
+        availability_zone:us-east-1a\r\n\
+        \r\n\
+        # Clients\r\n\
+        connected_clients:3\r\n\
+        blocked_clients:0\r\n\
+        \r\n\
+        # Memory\r\n\
+        used_memory:1048576\r\n\
+        maxmemory_policy:noeviction\r\n\
+        mem_fragmentation_ratio:1.25\r\n\
+        \r\n\
+        # Replication\r\n\
+        role:master\r\n\
+        connected_slaves:1\r\n\
+        slave0:ip=10.0.0.2,port=6380,state=online,offset=1234,lag=0\r\n\
+        master_repl_offset:1234\r\n\
+        \r\n\
+        # Keyspace\r\n\
+        db0:keys=10,expires=2,avg_ttl=3000\r\n\
+        db3:keys=1,expires=0,avg_ttl=0\r\n";
+
+    #[test]
+    fn test_info_parsing() {
+        let info = InfoParser::parse(INFO).unwrap();
+        assert_eq!(info.server.redis_version.as_deref(), Some("7.2.4"));
+        assert_eq!(info.server.tcp_port, Some(6379));
+        assert_eq!(info.server.availability_zone.as_deref(), Some("us-east-1a"));
+        assert_eq!(info.server.uptime_in_seconds, None);
+        assert_eq!(info.clients.connected_clients, Some(3));
+        assert_eq!(info.memory.used_memory, Some(1048576));
+        assert_eq!(info.memory.mem_fragmentation_ratio, Some(1.25));
+        assert_eq!(info.replication.role, Some(ReplicationRole::Primary));
+        assert_eq!(
+            info.replication.replicas,
+            vec![ReplicaInfo {
+                ip: "10.0.0.2".to_string(),
+                port: 6380,
+                state: "online".to_string(),
+                offset: 1234,
+                lag: 0,
+            }]
+        );
+        assert_eq!(info.keyspace.len(), 2);
+        assert_eq!(info.keyspace[&0].keys, 10);
+        assert_eq!(info.keyspace[&3].expires, 0);
+        assert_eq!(info.sections["memory"]["maxmemory_policy"], "noeviction");
+    }
+
+    #[test]
+    fn test_info_parsing_of_partial_and_invalid_replies() {
+        let info: Info = from_redis_value(&Value::BulkString(
+            b"# Replication\r\nrole:slave\r\nmaster_host:10.0.0.1\r\nmaster_port:6379\r\n".to_vec(),
+        ))
+        .unwrap();
+        assert_eq!(info.replication.role, Some(ReplicationRole::Replica));
+        assert_eq!(info.replication.primary_port, Some(6379));
+        assert!(info.keyspace.is_empty());
+        assert_eq!(info.clients.connected_clients, None);
+
+        let err = InfoParser::parse("# Clients\r\nconnected_clients:many\r\n").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TypeError);
+        let err = InfoParser::parse("# Keyspace\r\ndb0:keys=ten\r\n").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::TypeError);
+    }
+}
@@ -63,6 +63,9 @@
 //! * `tokio-comp`: enables support for tokio (optional)
 //! * `connection-manager`: enables support for automatic reconnection (optional)
 //! * `keep-alive`: enables keep-alive option on socket by means of `socket2` crate (optional)
+//! * `serde`: enables serialization of the async cluster's topology snapshots (optional)
+//! * `routing-test-support`: enables generators and invariant checks of cluster routing for property-based tests (optional)
+//! * `traffic-recorder`: enables recording the commands that the async cluster routes, and replaying them (optional)
 //!
 //! ## Connection Parameters
 //!
@@ -366,7 +369,8 @@ assert_eq!(result, Ok(("foo".to_string(), b"bar".to_vec())));
 pub use crate::client::Client;
 pub use crate::cmd::{cmd, pack_command, pipe, Arg, Cmd, Iter};
 pub use crate::commands::{
-    Commands, ControlFlow, Direction, LposOptions, PubSubCommands, SetOptions,
+    ClientInfo, Commands, ControlFlow, Direction, LposOptions, MemoryStats, ObjectEncoding,
+    PubSubCommands, SetOptions,
 };
 pub use crate::connection::{
     parse_redis_url, transaction, Connection, ConnectionAddr, ConnectionInfo, ConnectionLike,
@@ -375,6 +379,9 @@ pub use crate::connection::{
 };
 pub use crate::parser::{parse_redis_value, Parser};
 pub use crate::pipeline::Pipeline;
+#[cfg(feature = "aio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "aio")))]
+pub use crate::pipeline::PipelineChunking;
 pub use push_manager::{PushInfo, PushManager};
 
 #[cfg(feature = "script")]
@@ -441,6 +448,8 @@ pub use crate::commands::JsonAsyncCommands;
 #[cfg_attr(docsrs, doc(cfg(feature = "geospatial")))]
 pub mod geo;
 
+pub mod info;
+
 #[cfg(feature = "cluster")]
 #[cfg_attr(docsrs, doc(cfg(feature = "cluster")))]
 pub mod cluster;
@@ -455,6 +464,13 @@ pub use crate::commands::ScanStateRC;
 #[cfg(feature = "cluster-async")]
 pub use crate::commands::ObjectType;
 
+#[cfg(feature = "cluster-async")]
+pub use crate::commands::{ClusterScanCursor, ClusterScanOptions};
+
+#[cfg(feature = "debug-commands")]
+#[cfg_attr(docsrs, doc(cfg(feature = "debug-commands")))]
+pub use crate::commands::DebugObject;
+
 #[cfg(feature = "cluster")]
 mod cluster_client;
 
@@ -462,6 +478,10 @@ mod cluster_client;
 pub mod testing {
     #[cfg(feature = "cluster")]
     pub use crate::cluster_client::ClusterParams;
+
+    #[cfg(feature = "routing-test-support")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "routing-test-support")))]
+    pub mod routing;
 }
 
 #[cfg(feature = "cluster")]
@@ -489,11 +509,18 @@ pub mod cluster_async;
 #[cfg(feature = "sentinel")]
 pub mod sentinel;
 
+#[cfg(feature = "aio")]
+#[cfg_attr(docsrs, doc(cfg(feature = "aio")))]
+pub mod caching;
+
 #[cfg(feature = "tls-rustls")]
 mod tls;
 
 #[cfg(feature = "tls-rustls")]
-pub use crate::tls::{ClientTlsConfig, TlsCertificates};
+pub use crate::tls::{
+    ClientTlsConfig, ServerCertVerifierCallback, TlsCertificates, TlsResumptionStats,
+    DEFAULT_TLS_SESSION_CACHE_SIZE,
+};
 
 mod client;
 mod cmd;
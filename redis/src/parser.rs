@@ -307,20 +307,45 @@ mod aio_support {
     use super::*;
 
     use bytes::{Buf, BytesMut};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
     use tokio::io::AsyncRead;
     use tokio_util::codec::{Decoder, Encoder};
 
     #[derive(Default)]
     pub struct ValueCodec {
         state: AnySendSyncPartialState,
+        // The bytes of the reply that is being parsed, which were already removed from the buffer.
+        partial_len: usize,
+        max_reply_size: Option<Arc<AtomicUsize>>,
     }
 
     impl ValueCodec {
+        /// Creates a codec that fails the replies that are larger than `max_reply_size`, which can be changed while
+        /// the codec is used. A reply that is complete when it's found to be too large is replaced by a
+        /// [`ValueTooLarge`](ErrorKind::ValueTooLarge) error. A reply that is still being received when it exceeds
+        /// the limit fails the stream instead, because the rest of it can't be told apart from the next replies
+        /// without buffering it.
+        pub(crate) fn with_max_reply_size(max_reply_size: Arc<AtomicUsize>) -> Self {
+            Self {
+                max_reply_size: Some(max_reply_size),
+                ..Default::default()
+            }
+        }
+
         fn decode_stream(
             &mut self,
             bytes: &mut BytesMut,
             eof: bool,
         ) -> RedisResult<Option<RedisResult<Value>>> {
+            let max_reply_size = self
+                .max_reply_size
+                .as_ref()
+                .map_or(usize::MAX, |max_reply_size| {
+                    max_reply_size.load(Ordering::Relaxed)
+                });
             let (opt, removed_len) = {
                 let buffer = &bytes[..];
                 let mut stream =
@@ -342,13 +367,35 @@ mod aio_support {
             };
 
             bytes.advance(removed_len);
+            let reply_len = self.partial_len + removed_len;
             match opt {
-                Some(result) => Ok(Some(result.try_into())),
-                None => Ok(None),
+                Some(_) if reply_len > max_reply_size => {
+                    self.partial_len = 0;
+                    Ok(Some(Err(reply_too_large(reply_len, max_reply_size))))
+                }
+                Some(result) => {
+                    self.partial_len = 0;
+                    Ok(Some(result.try_into()))
+                }
+                None if reply_len + bytes.len() > max_reply_size => {
+                    Err(reply_too_large(reply_len + bytes.len(), max_reply_size))
+                }
+                None => {
+                    self.partial_len = reply_len;
+                    Ok(None)
+                }
             }
         }
     }
 
+    fn reply_too_large(reply_len: usize, max_reply_size: usize) -> RedisError {
+        RedisError::from((
+            ErrorKind::ValueTooLarge,
+            "The reply is larger than the maximal reply size",
+            format!("at least {reply_len} bytes, above the limit of {max_reply_size} bytes"),
+        ))
+    }
+
     impl Encoder<Vec<u8>> for ValueCodec {
         type Error = RedisError;
         fn encode(&mut self, item: Vec<u8>, dst: &mut BytesMut) -> Result<(), Self::Error> {
@@ -514,6 +561,29 @@ mod tests {
         assert_eq!(result, Ok(Value::Okay));
     }
 
+    #[cfg(feature = "aio")]
+    #[test]
+    fn decode_fails_the_replies_above_the_max_reply_size() {
+        use std::sync::{atomic::AtomicUsize, Arc};
+        use tokio_util::codec::Decoder;
+        let mut codec = ValueCodec::with_max_reply_size(Arc::new(AtomicUsize::new(10)));
+
+        // A complete reply fails alone, and the next replies are still parsed.
+        let mut bytes = bytes::BytesMut::from(b"$6\r\nfoobar\r\n+OK\r\n".as_slice());
+        let result = codec.decode(&mut bytes).unwrap().unwrap();
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::ValueTooLarge);
+        assert_eq!(codec.decode(&mut bytes), Ok(Some(Ok(Value::Okay))));
+
+        // A reply that exceeds the limit before it's complete fails the stream.
+        let mut bytes = bytes::BytesMut::from(b"*2\r\n$3\r\nfo".as_slice());
+        assert_eq!(codec.decode(&mut bytes), Ok(None));
+        bytes.extend_from_slice(b"o\r\n");
+        assert_eq!(
+            codec.decode(&mut bytes).unwrap_err().kind(),
+            ErrorKind::ValueTooLarge
+        );
+    }
+
     #[test]
     fn parse_nested_error_and_handle_more_inputs() {
         // from https://redis.io/docs/interact/transactions/ -
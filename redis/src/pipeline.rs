@@ -1,5 +1,7 @@
 #![macro_use]
 
+#[cfg(feature = "aio")]
+use crate::cmd::CommandFrame;
 use crate::cmd::{cmd, cmd_len, Cmd};
 use crate::connection::ConnectionLike;
 use crate::types::{
@@ -70,14 +72,51 @@ impl Pipeline {
         self
     }
 
+    /// Returns whether the pipeline is in atomic mode.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) fn is_atomic(&self) -> bool {
+        self.transaction_mode
+    }
+
     /// Returns the encoded pipeline commands.
     pub fn get_packed_pipeline(&self) -> Vec<u8> {
         encode_pipeline(&self.commands, self.transaction_mode)
     }
 
+    /// Returns a pipeline that encodes to `ASKING` followed by this pipeline, so that they're sent in the same
+    /// packet after an `ASK` redirect. The replies of this pipeline are offset by one.
+    #[cfg(feature = "cluster-async")]
+    pub(crate) fn with_asking(&self) -> Pipeline {
+        let mut commands = Vec::with_capacity(self.commands.len() + 3);
+        commands.push(cmd("ASKING"));
+        if self.transaction_mode {
+            commands.push(cmd("MULTI"));
+        }
+        commands.extend(self.commands.iter().cloned());
+        if self.transaction_mode {
+            commands.push(cmd("EXEC"));
+        }
+        Pipeline {
+            commands,
+            transaction_mode: false,
+            ignored_commands: HashSet::new(),
+        }
+    }
+
+    /// Returns the frame of the encoded pipeline commands, which borrows their long arguments.
     #[cfg(feature = "aio")]
-    pub(crate) fn write_packed_pipeline(&self, out: &mut Vec<u8>) {
-        write_pipeline(out, &self.commands, self.transaction_mode)
+    pub(crate) fn frame(&self) -> CommandFrame<'_> {
+        let mut frame = CommandFrame::default();
+        if self.transaction_mode {
+            frame.push_packed_command(&cmd("MULTI").get_packed_command());
+        }
+        for cmd in &self.commands {
+            frame.push_command(cmd);
+        }
+        if self.transaction_mode {
+            frame.push_packed_command(&cmd("EXEC").get_packed_command());
+        }
+        frame
     }
 
     fn execute_pipelined(&self, con: &mut dyn ConnectionLike) -> RedisResult<Value> {
@@ -129,7 +168,7 @@ impl Pipeline {
                 "This connection does not support pipelining."
             ));
         }
-        from_owned_redis_value(if self.commands.is_empty() {
+        from_owned_redis_value(if self.is_empty() {
             Value::Array(vec![])
         } else if self.transaction_mode {
             self.execute_transaction(con)?
@@ -154,9 +193,15 @@ impl Pipeline {
     where
         C: crate::aio::ConnectionLike,
     {
-        let mut resp = con
+        let resp = con
             .req_packed_commands(self, self.commands.len() + 1, 1)
             .await?;
+        self.make_transaction_results(resp)
+    }
+
+    /// Makes the results of the transaction out of the reply of its `EXEC`.
+    #[cfg(feature = "aio")]
+    pub(crate) fn make_transaction_results(&self, mut resp: Vec<Value>) -> RedisResult<Value> {
         match resp.pop() {
             Some(Value::Nil) => Ok(Value::Nil),
             Some(Value::Array(items)) => Ok(self.make_pipeline_results(items)),
@@ -175,7 +220,7 @@ impl Pipeline {
     where
         C: crate::aio::ConnectionLike,
     {
-        let v = if self.commands.is_empty() {
+        let v = if self.is_empty() {
             return from_owned_redis_value(Value::Array(vec![]));
         } else if self.transaction_mode {
             self.execute_transaction_async(con).await?
@@ -205,6 +250,117 @@ impl Pipeline {
     }
 }
 
+/// The limits above which a pipeline is split into several chunks, which are sent one after the other, so that a
+/// big pipeline doesn't exceed the server's query buffer limit (`client-query-buffer-limit`).
+///
+/// The replies of the chunks are stitched together, so that the caller receives the same replies as if the pipeline
+/// was sent at once. If a command fails, the following chunks are still sent, like the following commands of a
+/// pipeline that is sent at once, and the first error is returned. Atomic pipelines are never split, since
+/// `MULTI`/`EXEC` can't span several chunks.
+///
+/// ```rust,no_run
+/// # async fn run() -> redis::RedisResult<()> {
+/// # let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+/// let mut con = client.get_multiplexed_async_connection(None).await?;
+/// con.set_pipeline_chunking(
+///     redis::PipelineChunking::new()
+///         .max_commands(10_000)
+///         .max_bytes(64 * 1024 * 1024),
+/// );
+/// # Ok(()) }
+/// ```
+#[cfg(feature = "aio")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PipelineChunking {
+    max_commands: Option<usize>,
+    max_bytes: Option<usize>,
+}
+
+#[cfg(feature = "aio")]
+impl PipelineChunking {
+    /// Creates limits that never split a pipeline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximal number of commands in a chunk.
+    pub fn max_commands(mut self, max_commands: usize) -> Self {
+        self.max_commands = Some(max_commands.max(1));
+        self
+    }
+
+    /// Sets the maximal encoded size of a chunk, in bytes. A command that is bigger than the limit is sent in a
+    /// chunk of its own.
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Splits the commands of `pipeline` in the range of `offset..offset + count` into chunks that respect the
+    /// limits. Returns `None` if the pipeline doesn't need to be split.
+    pub(crate) fn split(
+        &self,
+        pipeline: &Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> Option<Vec<Pipeline>> {
+        if pipeline.transaction_mode || (self.max_commands.is_none() && self.max_bytes.is_none()) {
+            return None;
+        }
+        let commands = pipeline.commands.get(offset..offset + count)?;
+        let max_commands = self.max_commands.unwrap_or(usize::MAX);
+        let max_bytes = self.max_bytes.unwrap_or(usize::MAX);
+
+        let mut chunks = vec![];
+        let mut chunk = Pipeline::new();
+        let mut chunk_bytes: usize = 0;
+        for cmd in commands {
+            let len = cmd_len(cmd);
+            if !chunk.commands.is_empty()
+                && (chunk.commands.len() == max_commands
+                    || chunk_bytes.saturating_add(len) > max_bytes)
+            {
+                chunks.push(std::mem::take(&mut chunk));
+                chunk_bytes = 0;
+            }
+            chunk.commands.push(cmd.clone());
+            chunk_bytes += len;
+        }
+        if chunks.is_empty() {
+            return None;
+        }
+        chunks.push(chunk);
+        Some(chunks)
+    }
+}
+
+/// Sends the chunks of a pipeline one after the other and stitches their replies together.
+#[cfg(feature = "aio")]
+pub(crate) async fn send_chunks<C>(con: &mut C, chunks: Vec<Pipeline>) -> RedisResult<Vec<Value>>
+where
+    C: crate::aio::ConnectionLike,
+{
+    let mut values = Vec::new();
+    let mut first_err = None;
+    for chunk in chunks {
+        match con
+            .req_packed_commands(&chunk, 0, chunk.commands.len())
+            .await
+        {
+            Ok(chunk_values) => values.extend(chunk_values),
+            // The connection can't deliver the following chunks either.
+            Err(err) if err.is_io_error() || err.is_unrecoverable_error() => return Err(err),
+            Err(err) => {
+                first_err.get_or_insert(err);
+            }
+        }
+    }
+    match first_err {
+        Some(err) => Err(err),
+        None => Ok(values),
+    }
+}
+
 fn encode_pipeline(cmds: &[Cmd], atomic: bool) -> Vec<u8> {
     let mut rv = vec![];
     write_pipeline(&mut rv, cmds, atomic);
@@ -256,6 +412,13 @@ macro_rules! implement_pipeline_commands {
                 self.commands.iter()
             }
 
+            /// Returns true if the pipeline has no commands. Querying an empty pipeline returns an empty result
+            /// without sending anything.
+            #[inline]
+            pub fn is_empty(&self) -> bool {
+                self.commands.is_empty()
+            }
+
             /// Instructs the pipeline to ignore the return value of this command.
             /// It will still be ensured that it is not an error, but any successful
             /// result is just thrown away.  This makes result processing through
@@ -322,3 +485,62 @@ macro_rules! implement_pipeline_commands {
 }
 
 implement_pipeline_commands!(Pipeline);
+
+#[cfg(test)]
+#[cfg(feature = "aio")]
+mod tests {
+    use super::{Pipeline, PipelineChunking};
+    use crate::pipe;
+
+    #[test]
+    #[cfg(feature = "cluster-async")]
+    fn test_with_asking_precedes_the_pipeline_with_asking() {
+        let mut pipeline = pipe();
+        pipeline.get("a").get("b");
+        let asking = crate::cmd("ASKING").get_packed_command();
+        for atomic in [false, true] {
+            if atomic {
+                pipeline.atomic();
+            }
+            assert_eq!(
+                pipeline.with_asking().get_packed_pipeline(),
+                [asking.clone(), pipeline.get_packed_pipeline()].concat()
+            );
+        }
+    }
+
+    #[test]
+    fn test_pipeline_chunking_split() {
+        let mut pipeline = pipe();
+        pipeline
+            .set("a", "x")
+            .set("b", "y".repeat(100))
+            .set("c", "z");
+
+        let chunk_lengths = |pipeline: &Pipeline, chunking: PipelineChunking| {
+            chunking
+                .split(pipeline, 0, 3)
+                .map(|chunks| chunks.iter().map(|chunk| chunk.commands.len()).collect())
+        };
+        assert_eq!(chunk_lengths(&pipeline, PipelineChunking::new()), None);
+        assert_eq!(
+            chunk_lengths(&pipeline, PipelineChunking::new().max_commands(3)),
+            None
+        );
+        assert_eq!(
+            chunk_lengths(&pipeline, PipelineChunking::new().max_commands(2)),
+            Some(vec![2, 1])
+        );
+        // The big command is sent in a chunk of its own.
+        assert_eq!(
+            chunk_lengths(&pipeline, PipelineChunking::new().max_bytes(64)),
+            Some(vec![1, 1, 1])
+        );
+
+        pipeline.atomic();
+        assert_eq!(
+            chunk_lengths(&pipeline, PipelineChunking::new().max_commands(1)),
+            None
+        );
+    }
+}
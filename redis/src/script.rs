@@ -44,6 +44,12 @@ impl Script {
         &self.hash
     }
 
+    pub(crate) fn load_cmd(&self) -> Cmd {
+        let mut cmd = cmd("SCRIPT");
+        cmd.arg("LOAD").arg(self.code.as_bytes());
+        cmd
+    }
+
     /// Creates a script invocation object with a key filled in.
     #[inline]
     pub fn key<T: ToRedisArgs>(&self, key: T) -> ScriptInvocation<'_> {
@@ -207,9 +213,7 @@ impl<'a> ScriptInvocation<'a> {
     }
 
     fn load_cmd(&self) -> Cmd {
-        let mut cmd = cmd("SCRIPT");
-        cmd.arg("LOAD").arg(self.script.code.as_bytes());
-        cmd
+        self.script.load_cmd()
     }
 
     fn estimate_buflen(&self) -> usize {
@@ -6,6 +6,11 @@ use crate::{
 
 use std::io::{Error, ErrorKind};
 
+#[cfg(feature = "aio")]
+mod worker;
+#[cfg(feature = "aio")]
+pub use worker::{StreamWorker, StreamWorkerOptions};
+
 // Stream Maxlen Enum
 
 /// Utility enum for passing `MAXLEN [= or ~] [COUNT]`
@@ -112,6 +117,49 @@ impl ToRedisArgs for StreamClaimOptions {
     }
 }
 
+/// Builder options for [`xautoclaim_options`] command.
+///
+/// [`xautoclaim_options`]: ../trait.Commands.html#method.xautoclaim_options
+///
+#[derive(Default, Debug)]
+pub struct StreamAutoClaimOptions {
+    /// Set `COUNT <count>` cmd arg.
+    count: Option<usize>,
+    /// Set `JUSTID` cmd arg. Be advised: the response
+    /// type changes with this option.
+    justid: bool,
+}
+
+impl StreamAutoClaimOptions {
+    /// Set `COUNT <count>` cmd arg.
+    pub fn count(mut self, n: usize) -> Self {
+        self.count = Some(n);
+        self
+    }
+
+    /// Set `JUSTID` cmd arg to true. Be advised: the response
+    /// type changes with this option.
+    pub fn with_justid(mut self) -> Self {
+        self.justid = true;
+        self
+    }
+}
+
+impl ToRedisArgs for StreamAutoClaimOptions {
+    fn write_redis_args<W>(&self, out: &mut W)
+    where
+        W: ?Sized + RedisWrite,
+    {
+        if let Some(ref count) = self.count {
+            out.write_arg(b"COUNT");
+            out.write_arg(format!("{count}").as_bytes());
+        }
+        if self.justid {
+            out.write_arg(b"JUSTID");
+        }
+    }
+}
+
 /// Argument to `StreamReadOptions`
 /// Represents the Redis `GROUP <groupname> <consumername>` cmd arg.
 /// This option will toggle the cmd from `XREAD` to `XREADGROUP`
@@ -248,6 +296,23 @@ pub struct StreamClaimReply {
     pub ids: Vec<StreamId>,
 }
 
+/// Reply type used with [`xautoclaim_options`] command.
+///
+/// Represents the entries whose ownership was changed, and the cursor to continue scanning from.
+///
+/// [`xautoclaim_options`]: ../trait.Commands.html#method.xautoclaim_options
+///
+#[derive(Default, Debug, Clone)]
+pub struct StreamAutoClaimReply {
+    /// The stream `id` to use as the start of the next call, or `0-0` if the whole pending entries list was scanned.
+    pub next_stream_id: String,
+    /// The entries that were claimed.
+    pub claimed: Vec<StreamId>,
+    /// The `id`s of pending entries that no longer exist in the stream, and were removed from the pending entries list.
+    /// Always empty before Redis 7.0.
+    pub deleted_ids: Vec<String>,
+}
+
 /// Reply type used with [`xpending`] command.
 ///
 /// Data returned here were fetched from the stream without
@@ -507,6 +572,47 @@ impl FromRedisValue for StreamClaimReply {
     }
 }
 
+impl FromRedisValue for StreamAutoClaimReply {
+    fn from_redis_value(v: &Value) -> RedisResult<Self> {
+        let values = match v {
+            Value::Array(values) if values.len() >= 2 => values,
+            _ => fail!((
+                crate::types::ErrorKind::TypeError,
+                "Cannot parse XAUTOCLAIM reply"
+            )),
+        };
+        let next_stream_id = from_redis_value(&values[0])?;
+        let claimed = match &values[1] {
+            Value::Array(entries) => entries
+                .iter()
+                .filter_map(|entry| match entry {
+                    // Entries that were deleted are returned as nil before Redis 7.0.
+                    Value::Nil => None,
+                    Value::Array(_) => Some(StreamId::from_array_value(entry)),
+                    // With JUSTID, only the entries' ids are returned.
+                    _ => Some(from_redis_value(entry).map(|id| StreamId {
+                        id,
+                        ..Default::default()
+                    })),
+                })
+                .collect::<RedisResult<_>>()?,
+            _ => fail!((
+                crate::types::ErrorKind::TypeError,
+                "Cannot parse XAUTOCLAIM reply"
+            )),
+        };
+        let deleted_ids = match values.get(2) {
+            Some(deleted_ids) => from_redis_value(deleted_ids)?,
+            None => Vec::new(),
+        };
+        Ok(StreamAutoClaimReply {
+            next_stream_id,
+            claimed,
+            deleted_ids,
+        })
+    }
+}
+
 type SPRInner = (
     usize,
     Option<String>,
@@ -0,0 +1,235 @@
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use futures_util::{stream, StreamExt};
+use tracing::warn;
+
+use super::{
+    StreamAutoClaimOptions, StreamAutoClaimReply, StreamId, StreamReadOptions, StreamReadReply,
+};
+use crate::{aio::ConnectionLike, AsyncCommands, RedisResult};
+
+/// Options for a [`StreamWorker`].
+#[derive(Debug, Clone)]
+pub struct StreamWorkerOptions {
+    batch_size: usize,
+    block: Option<usize>,
+    min_idle_time: usize,
+    claim_interval: Duration,
+    concurrency: usize,
+}
+
+impl Default for StreamWorkerOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 10,
+            block: Some(1000),
+            min_idle_time: 60000,
+            claim_interval: Duration::from_secs(30),
+            concurrency: 1,
+        }
+    }
+}
+
+impl StreamWorkerOptions {
+    /// Sets the maximum number of entries that are read or claimed in a single batch.
+    pub fn batch_size(mut self, n: usize) -> Self {
+        self.batch_size = n;
+        self
+    }
+
+    /// Sets the time in milliseconds for which `XREADGROUP` blocks when no new entries are available.
+    /// `None` disables blocking.
+    ///
+    /// Be advised that a blocking read holds the connection for its duration, so the worker should
+    /// use a connection that isn't shared with latency sensitive requests.
+    pub fn block(mut self, ms: Option<usize>) -> Self {
+        self.block = ms;
+        self
+    }
+
+    /// Sets the time in milliseconds after which a pending entry of another consumer is considered stale,
+    /// and can be claimed by this worker.
+    pub fn min_idle_time(mut self, ms: usize) -> Self {
+        self.min_idle_time = ms;
+        self
+    }
+
+    /// Sets the interval between `XAUTOCLAIM` scans of the pending entries list.
+    pub fn claim_interval(mut self, interval: Duration) -> Self {
+        self.claim_interval = interval;
+        self
+    }
+
+    /// Sets the maximum number of entries that are handled concurrently.
+    pub fn concurrency(mut self, n: usize) -> Self {
+        self.concurrency = n.max(1);
+        self
+    }
+}
+
+/// A consumer group worker for a single stream.
+///
+/// The worker reads new entries with `XREADGROUP`, periodically steals stale pending entries of other
+/// consumers with `XAUTOCLAIM`, and acknowledges the entries that were handled successfully with `XACK`.
+/// Entries whose handler failed are left pending, so they will be claimed again once they become stale.
+///
+/// All the commands of a worker access the same stream key, so the worker can be used with a cluster
+/// connection, which routes them to the node that owns the stream.
+///
+/// # Example
+/// ```rust,no_run
+/// use redis::streams::{StreamWorker, StreamWorkerOptions};
+///
+/// async fn consume() -> redis::RedisResult<()> {
+///     let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+///     let connection = client.get_multiplexed_async_connection(None).await?;
+///     let mut worker = StreamWorker::new(
+///         connection,
+///         "events",
+///         "group",
+///         "consumer-1",
+///         StreamWorkerOptions::default().concurrency(4),
+///     );
+///     worker
+///         .run(|entry| async move {
+///             println!("handling {}", entry.id);
+///             Ok(())
+///         })
+///         .await
+/// }
+/// ```
+pub struct StreamWorker<C> {
+    connection: C,
+    key: String,
+    group: String,
+    consumer: String,
+    options: StreamWorkerOptions,
+    claim_cursor: String,
+    last_claim: Option<Instant>,
+}
+
+impl<C> StreamWorker<C>
+where
+    C: ConnectionLike + Send,
+{
+    /// Creates a worker that consumes the stream `key` as `consumer` of the consumer group `group`.
+    ///
+    /// The consumer group must already exist.
+    pub fn new(
+        connection: C,
+        key: impl Into<String>,
+        group: impl Into<String>,
+        consumer: impl Into<String>,
+        options: StreamWorkerOptions,
+    ) -> Self {
+        Self {
+            connection,
+            key: key.into(),
+            group: group.into(),
+            consumer: consumer.into(),
+            options,
+            claim_cursor: "0-0".to_string(),
+            last_claim: None,
+        }
+    }
+
+    /// Handles a single batch of entries, and returns the number of entries that were acknowledged.
+    ///
+    /// If the claim interval has passed, stale pending entries are claimed and handled first, and then
+    /// new entries are read and handled.
+    pub async fn process_batch<F, Fut>(&mut self, handler: &F) -> RedisResult<usize>
+    where
+        F: Fn(StreamId) -> Fut,
+        Fut: Future<Output = RedisResult<()>>,
+    {
+        let mut acknowledged = 0;
+        let claim_due = self.last_claim.map_or(true, |last_claim| {
+            last_claim.elapsed() >= self.options.claim_interval
+        });
+        if claim_due {
+            let reply: StreamAutoClaimReply = self
+                .connection
+                .xautoclaim_options(
+                    &self.key,
+                    &self.group,
+                    &self.consumer,
+                    self.options.min_idle_time,
+                    &self.claim_cursor,
+                    StreamAutoClaimOptions::default().count(self.options.batch_size),
+                )
+                .await?;
+            self.claim_cursor = reply.next_stream_id;
+            self.last_claim = Some(Instant::now());
+            acknowledged += self.handle_entries(reply.claimed, handler).await?;
+        }
+
+        let mut read_options = StreamReadOptions::default()
+            .group(&self.group, &self.consumer)
+            .count(self.options.batch_size);
+        if let Some(ms) = self.options.block {
+            read_options = read_options.block(ms);
+        }
+        let reply: Option<StreamReadReply> = self
+            .connection
+            .xread_options(&[&self.key], &[">"], &read_options)
+            .await?;
+        let entries = reply
+            .into_iter()
+            .flat_map(|reply| reply.keys)
+            .flat_map(|key| key.ids)
+            .collect();
+        acknowledged += self.handle_entries(entries, handler).await?;
+        Ok(acknowledged)
+    }
+
+    /// Handles batches of entries until a command fails.
+    pub async fn run<F, Fut>(&mut self, handler: F) -> RedisResult<()>
+    where
+        F: Fn(StreamId) -> Fut,
+        Fut: Future<Output = RedisResult<()>>,
+    {
+        loop {
+            self.process_batch(&handler).await?;
+        }
+    }
+
+    async fn handle_entries<F, Fut>(
+        &mut self,
+        entries: Vec<StreamId>,
+        handler: &F,
+    ) -> RedisResult<usize>
+    where
+        F: Fn(StreamId) -> Fut,
+        Fut: Future<Output = RedisResult<()>>,
+    {
+        if entries.is_empty() {
+            return Ok(0);
+        }
+        let handled_ids: Vec<String> = stream::iter(entries)
+            .map(|entry| async move {
+                let id = entry.id.clone();
+                match handler(entry).await {
+                    Ok(()) => Some(id),
+                    Err(err) => {
+                        warn!("Failed to handle stream entry {id}, leaving it pending. Error: `{err}`");
+                        None
+                    }
+                }
+            })
+            .buffer_unordered(self.options.concurrency)
+            .filter_map(|id| async move { id })
+            .collect()
+            .await;
+        if handled_ids.is_empty() {
+            return Ok(0);
+        }
+        let _: usize = self
+            .connection
+            .xack(&self.key, &self.group, &handled_ids)
+            .await?;
+        Ok(handled_ids.len())
+    }
+}
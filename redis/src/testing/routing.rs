@@ -0,0 +1,243 @@
+//! Generators and invariant checks for the routing of cluster commands, for property-based tests.
+//!
+//! The generators implement [`quickcheck::Arbitrary`], and the checks return a description of the first violation
+//! of their invariant, so they can be used as `quickcheck` properties directly, or from any other test framework.
+//!
+//! ```rust
+//! use quickcheck::quickcheck;
+//! use redis::testing::routing::{check_hash_tag_slot, check_multi_slot_split, HashTaggedKeys, MultiKeyCommand};
+//!
+//! quickcheck(check_hash_tag_slot as fn(HashTaggedKeys) -> Result<(), String>);
+//! quickcheck(check_multi_slot_split as fn(MultiKeyCommand) -> Result<(), String>);
+//! ```
+
+use quickcheck::{Arbitrary, Gen};
+
+use crate::cluster_routing::{
+    combine_and_sort_array_results, command_for_multi_slot_indices, MultipleNodeRoutingInfo,
+    RoutingInfo, SingleNodeRoutingInfo,
+};
+use crate::cluster_topology::get_slot;
+use crate::{cmd, Cmd, Value};
+
+// Few distinct characters, so that the generated keys often share hash tags and slots.
+const KEY_CHARS: &[u8] = b"abcxyz019:";
+
+fn arbitrary_text(g: &mut Gen, max_len: usize) -> Vec<u8> {
+    let len = usize::arbitrary(g) % (max_len + 1);
+    (0..len).map(|_| *g.choose(KEY_CHARS).unwrap()).collect()
+}
+
+/// A key of one of the shapes that affect its slot: a plain key, a key with a hash tag, a key with an empty hash
+/// tag, a key with unbalanced braces, or a binary key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArbitraryKey(pub Vec<u8>);
+
+impl Arbitrary for ArbitraryKey {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let size = g.size();
+        let text = |g: &mut Gen| arbitrary_text(g, size);
+        let key = match u8::arbitrary(g) % 5 {
+            0 => text(g),
+            1 => [text(g), b"{".to_vec(), text(g), b"}".to_vec(), text(g)].concat(),
+            2 => [text(g), b"{}".to_vec(), text(g)].concat(),
+            3 => [text(g), b"{".to_vec(), text(g)].concat(),
+            _ => Vec::<u8>::arbitrary(g),
+        };
+        ArbitraryKey(key)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.0.shrink().map(ArbitraryKey))
+    }
+}
+
+/// Keys that share the same non-empty hash tag, so they must all be in the slot of the tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashTaggedKeys {
+    /// The hash tag, without the braces.
+    pub tag: Vec<u8>,
+    /// The keys, each made of a prefix without braces, the tag in braces, and any suffix.
+    pub keys: Vec<Vec<u8>>,
+}
+
+impl Arbitrary for HashTaggedKeys {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let size = g.size();
+        let mut tag = arbitrary_text(g, size);
+        if tag.is_empty() {
+            tag.push(*g.choose(KEY_CHARS).unwrap());
+        }
+        let count = 1 + usize::arbitrary(g) % size.max(1);
+        let keys = (0..count)
+            .map(|_| {
+                let suffix = ArbitraryKey::arbitrary(g).0;
+                [
+                    arbitrary_text(g, size),
+                    b"{".to_vec(),
+                    tag.clone(),
+                    b"}".to_vec(),
+                    suffix,
+                ]
+                .concat()
+            })
+            .collect();
+        HashTaggedKeys { tag, keys }
+    }
+}
+
+/// A command whose keys may be in several slots, which is split by slot when it's sent to a cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultiKeyCommand {
+    /// The name of the command.
+    pub name: &'static str,
+    /// The arguments of the command, which are keys, or keys followed by their values.
+    pub args: Vec<Vec<u8>>,
+}
+
+impl MultiKeyCommand {
+    const KEYS_ONLY: &'static [&'static str] = &["MGET", "DEL", "EXISTS", "UNLINK", "TOUCH"];
+    const KEYS_AND_VALUES: &'static [&'static str] = &["MSET"];
+
+    /// Returns `true` if each key of the command is followed by its value.
+    pub fn has_values(&self) -> bool {
+        Self::KEYS_AND_VALUES.contains(&self.name)
+    }
+
+    /// Returns the keys of the command.
+    pub fn keys(&self) -> Vec<&[u8]> {
+        let step = if self.has_values() { 2 } else { 1 };
+        self.args.iter().step_by(step).map(Vec::as_slice).collect()
+    }
+
+    /// Builds the command.
+    pub fn to_cmd(&self) -> Cmd {
+        let mut command = cmd(self.name);
+        for arg in &self.args {
+            command.arg(arg.as_slice());
+        }
+        command
+    }
+}
+
+impl Arbitrary for MultiKeyCommand {
+    fn arbitrary(g: &mut Gen) -> Self {
+        let names = [Self::KEYS_ONLY, Self::KEYS_AND_VALUES].concat();
+        let name = *g.choose(&names).unwrap();
+        let count = 1 + usize::arbitrary(g) % g.size().max(1);
+        let mut args = Vec::new();
+        for index in 0..count {
+            args.push(ArbitraryKey::arbitrary(g).0);
+            if Self::KEYS_AND_VALUES.contains(&name) {
+                args.push(format!("value{index}").into_bytes());
+            }
+        }
+        MultiKeyCommand { name, args }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let name = self.name;
+        let step = if self.has_values() { 2 } else { 1 };
+        let pairs: Vec<Vec<Vec<u8>>> = self.args.chunks(step).map(<[_]>::to_vec).collect();
+        Box::new(
+            pairs
+                .shrink()
+                .filter(|pairs| !pairs.is_empty())
+                .map(move |pairs| MultiKeyCommand {
+                    name,
+                    args: pairs.concat(),
+                }),
+        )
+    }
+}
+
+/// Checks that all the keys with the same hash tag are in the slot of the tag.
+pub fn check_hash_tag_slot(keys: HashTaggedKeys) -> Result<(), String> {
+    let tag_slot = get_slot(&keys.tag);
+    for key in &keys.keys {
+        let slot = get_slot(key);
+        if slot != tag_slot {
+            return Err(format!(
+                "key {key:?} is in slot {slot}, but its tag {:?} is in slot {tag_slot}",
+                keys.tag
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the split of a multi-key command by slots: each argument is sent exactly once, each sub-command only holds
+/// keys of its route's slot, the values stay with their keys, and combining the replies of the sub-commands restores
+/// the order of the original arguments.
+pub fn check_multi_slot_split(command: MultiKeyCommand) -> Result<(), String> {
+    let original_cmd = command.to_cmd();
+    let routes = match RoutingInfo::for_routable(&original_cmd) {
+        Some(RoutingInfo::MultiNode((MultipleNodeRoutingInfo::MultiSlot(routes), _))) => routes,
+        Some(RoutingInfo::SingleNode(SingleNodeRoutingInfo::SpecificNode(route))) => {
+            return match command
+                .keys()
+                .iter()
+                .find(|key| get_slot(key) != route.slot())
+            {
+                Some(key) => Err(format!(
+                    "key {key:?} isn't in slot {} of the single route",
+                    route.slot()
+                )),
+                None => Ok(()),
+            };
+        }
+        routing => return Err(format!("unexpected routing {routing:?}")),
+    };
+
+    let mut sent_count = vec![0; command.args.len()];
+    let mut replies = Vec::new();
+    for (route, indices) in &routes {
+        for (position, index) in indices.iter().enumerate() {
+            let Some(count) = sent_count.get_mut(*index) else {
+                return Err(format!("index {index} is out of the arguments"));
+            };
+            *count += 1;
+            let is_key = !command.has_values() || position % 2 == 0;
+            if is_key && get_slot(&command.args[*index]) != route.slot() {
+                return Err(format!(
+                    "key {:?} was sent to slot {}",
+                    command.args[*index],
+                    route.slot()
+                ));
+            }
+            if !is_key && indices[position - 1] + 1 != *index {
+                return Err(format!("value at {index} was separated from its key"));
+            }
+        }
+
+        // Each sub-command is answered with its own arguments.
+        let sub_cmd = command_for_multi_slot_indices(&original_cmd, indices.iter());
+        let reply = (1..=indices.len())
+            .map(|index| Value::BulkString(sub_cmd.arg_idx(index).unwrap_or_default().to_vec()))
+            .collect();
+        replies.push(Value::Array(reply));
+    }
+    if let Some(index) = sent_count.iter().position(|count| *count != 1) {
+        return Err(format!(
+            "argument {index} was sent {} times",
+            sent_count[index]
+        ));
+    }
+
+    let combined =
+        combine_and_sort_array_results(replies, routes.iter().map(|(_, indices)| indices))
+            .map_err(|err| err.to_string())?;
+    let expected = Value::Array(
+        command
+            .args
+            .iter()
+            .map(|arg| Value::BulkString(arg.clone()))
+            .collect(),
+    );
+    if combined != expected {
+        return Err(format!(
+            "combined replies {combined:?} don't match the arguments"
+        ));
+    }
+    Ok(())
+}
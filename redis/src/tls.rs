@@ -1,7 +1,24 @@
+use std::fmt;
 use std::io::{BufRead, Error, ErrorKind as IOErrorKind};
 
-use rustls::RootCertStore;
-use rustls_pki_types::{CertificateDer, PrivateKeyDer};
+#[cfg(feature = "cluster")]
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use rustls::{
+    client::{
+        danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        ClientSessionStore,
+    },
+    crypto::WebPkiSupportedAlgorithms,
+    DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+#[cfg(feature = "cluster")]
+use rustls::{
+    client::{ClientSessionMemoryCache, Tls12ClientSessionValue, Tls13ClientSessionValue},
+    NamedGroup,
+};
+use rustls_pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
 
 use crate::{Client, ConnectionAddr, ConnectionInfo, ErrorKind, RedisError, RedisResult};
 
@@ -53,7 +70,11 @@ pub(crate) fn inner_build_with_tls(
         )));
     };
 
-    Ok(Client { connection_info })
+    Ok(Client {
+        connection_info,
+        #[cfg(feature = "aio")]
+        cache_config: None,
+    })
 }
 
 pub(crate) fn retrieve_tls_certificates(
@@ -110,6 +131,7 @@ pub(crate) fn retrieve_tls_certificates(
     Ok(TlsConnParams {
         client_tls_params,
         root_cert_store,
+        ..Default::default()
     })
 }
 
@@ -135,8 +157,249 @@ impl Clone for ClientTlsParams {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct TlsConnParams {
     pub(crate) client_tls_params: Option<ClientTlsParams>,
     pub(crate) root_cert_store: Option<RootCertStore>,
+    /// When set, TLS sessions are stored in and resumed from this store instead of a per-connection cache.
+    pub(crate) session_store: Option<Arc<dyn ClientSessionStore>>,
+    /// The ALPN protocols offered to the server, in order of preference.
+    pub(crate) alpn_protocols: Vec<Vec<u8>>,
+    /// When set, replaces the verification of the server's certificate chain against the root certificates.
+    pub(crate) server_cert_verifier: Option<Arc<CallbackCertVerifier>>,
+}
+
+/// The signature of a callback used to verify the server's certificate.
+///
+/// The callback receives the server's end-entity certificate, the intermediate certificates and the server name,
+/// and returns an error message if the certificate shouldn't be trusted.
+pub type ServerCertVerifierCallback = dyn Fn(&CertificateDer<'_>, &[CertificateDer<'_>], &ServerName<'_>) -> Result<(), String>
+    + Send
+    + Sync;
+
+/// Verifies the server's certificate using a user-provided callback, e.g. for certificate pinning or private PKIs.
+/// The handshake signatures are still verified, so that the server must own the private key of the accepted certificate.
+pub(crate) struct CallbackCertVerifier {
+    callback: Box<ServerCertVerifierCallback>,
+    supported: WebPkiSupportedAlgorithms,
+}
+
+impl CallbackCertVerifier {
+    #[cfg(feature = "cluster")]
+    pub(crate) fn new(callback: Box<ServerCertVerifierCallback>) -> Self {
+        Self {
+            callback,
+            supported: rustls::crypto::ring::default_provider().signature_verification_algorithms,
+        }
+    }
+}
+
+impl ServerCertVerifier for CallbackCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        (self.callback)(end_entity, intermediates, server_name)
+            .map(|_| ServerCertVerified::assertion())
+            .map_err(rustls::Error::General)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.supported)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.supported)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.supported.supported_schemes()
+    }
+}
+
+impl fmt::Debug for CallbackCertVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallbackCertVerifier").finish()
+    }
+}
+
+/// The default number of TLS sessions kept in a TLS session cache.
+pub const DEFAULT_TLS_SESSION_CACHE_SIZE: usize = 256;
+
+/// Statistics about TLS session resumption.
+///
+/// A lookup is counted whenever a new TLS connection checks the session cache, and a hit is counted when a cached
+/// session was found and offered to the server for resumption.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TlsResumptionStats {
+    /// The number of handshakes that looked up a cached session.
+    pub lookups: u64,
+    /// The number of handshakes that offered a cached session to the server.
+    pub hits: u64,
+}
+
+impl TlsResumptionStats {
+    /// Returns the ratio of hits to lookups, or 0 if no lookups were made.
+    pub fn hit_rate(&self) -> f64 {
+        if self.lookups == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / self.lookups as f64
+    }
+}
+
+/// A TLS session cache that outlives single connections, so that reconnects to the same node can resume their
+/// previous TLS session instead of performing a full handshake.
+/// Sessions are keyed by the server name, so each node has its own entries.
+#[cfg(feature = "cluster")]
+#[derive(Debug)]
+pub(crate) struct TlsSessionCache {
+    sessions: ClientSessionMemoryCache,
+    lookups: AtomicU64,
+    hits: AtomicU64,
+}
+
+#[cfg(feature = "cluster")]
+impl TlsSessionCache {
+    pub(crate) fn new(size: usize) -> Self {
+        Self {
+            sessions: ClientSessionMemoryCache::new(size),
+            lookups: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn stats(&self) -> TlsResumptionStats {
+        TlsResumptionStats {
+            lookups: self.lookups.load(Ordering::Relaxed),
+            hits: self.hits.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(feature = "cluster")]
+impl ClientSessionStore for TlsSessionCache {
+    fn set_kx_hint(&self, server_name: ServerName<'static>, group: NamedGroup) {
+        self.sessions.set_kx_hint(server_name, group)
+    }
+
+    fn kx_hint(&self, server_name: &ServerName<'_>) -> Option<NamedGroup> {
+        self.sessions.kx_hint(server_name)
+    }
+
+    fn set_tls12_session(&self, server_name: ServerName<'static>, value: Tls12ClientSessionValue) {
+        self.sessions.set_tls12_session(server_name, value)
+    }
+
+    // Called by rustls only after `take_tls13_ticket` found no ticket, so the lookup was already counted.
+    fn tls12_session(&self, server_name: &ServerName<'_>) -> Option<Tls12ClientSessionValue> {
+        let session = self.sessions.tls12_session(server_name);
+        if session.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        }
+        session
+    }
+
+    fn remove_tls12_session(&self, server_name: &ServerName<'static>) {
+        self.sessions.remove_tls12_session(server_name)
+    }
+
+    fn insert_tls13_ticket(
+        &self,
+        server_name: ServerName<'static>,
+        value: Tls13ClientSessionValue,
+    ) {
+        self.sessions.insert_tls13_ticket(server_name, value)
+    }
+
+    fn take_tls13_ticket(
+        &self,
+        server_name: &ServerName<'static>,
+    ) -> Option<Tls13ClientSessionValue> {
+        self.lookups.fetch_add(1, Ordering::Relaxed);
+        let ticket = self.sessions.take_tls13_ticket(server_name);
+        if ticket.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        }
+        ticket
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "cluster")]
+    #[test]
+    fn test_callback_verifier_rejects_with_callback_error() {
+        let verifier = CallbackCertVerifier::new(Box::new(|_, _, server_name| match server_name {
+            ServerName::DnsName(name) if name.as_ref() == "pinned" => Ok(()),
+            _ => Err("unexpected server".to_string()),
+        }));
+        let cert = CertificateDer::from(vec![0u8]);
+
+        assert!(verifier
+            .verify_server_cert(
+                &cert,
+                &[],
+                &ServerName::try_from("pinned").unwrap(),
+                &[],
+                UnixTime::now()
+            )
+            .is_ok());
+        assert_eq!(
+            verifier
+                .verify_server_cert(
+                    &cert,
+                    &[],
+                    &ServerName::try_from("other").unwrap(),
+                    &[],
+                    UnixTime::now()
+                )
+                .unwrap_err(),
+            rustls::Error::General("unexpected server".to_string())
+        );
+    }
+
+    #[test]
+    fn test_resumption_stats_hit_rate() {
+        assert_eq!(TlsResumptionStats::default().hit_rate(), 0.0);
+        let stats = TlsResumptionStats {
+            lookups: 4,
+            hits: 1,
+        };
+        assert_eq!(stats.hit_rate(), 0.25);
+    }
+
+    #[cfg(feature = "cluster")]
+    #[test]
+    fn test_session_cache_counts_lookups_and_misses() {
+        let cache = TlsSessionCache::new(DEFAULT_TLS_SESSION_CACHE_SIZE);
+        let server_name = ServerName::try_from("node1").unwrap();
+
+        assert!(cache.take_tls13_ticket(&server_name).is_none());
+        assert!(cache.tls12_session(&server_name).is_none());
+
+        assert_eq!(
+            cache.stats(),
+            TlsResumptionStats {
+                lookups: 1,
+                hits: 0
+            }
+        );
+    }
 }
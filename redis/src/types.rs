@@ -23,11 +23,15 @@ macro_rules! invalid_type_error {
 
 macro_rules! invalid_type_error_inner {
     ($v:expr, $det:expr) => {
-        RedisError::from((
-            ErrorKind::TypeError,
-            "Response was of incompatible type",
-            format!("{:?} (response was {:?})", $det, $v),
-        ))
+        match &$v {
+            // An error in place of a value can't be converted, so the conversion fails with the error itself.
+            Value::Error(err) => err.clone(),
+            v => RedisError::from((
+                ErrorKind::TypeError,
+                "Response was of incompatible type",
+                format!("{:?} (response was {:?})", $det, v),
+            )),
+        }
     };
 }
 
@@ -146,6 +150,111 @@ pub enum ErrorKind {
 
     /// Not all slots are covered by the cluster
     NotAllSlotsCovered,
+
+    /// The client's background driver task has stopped, so it can no longer handle requests.
+    /// The error's detail contains the reason for which the task stopped.
+    DriverStopped,
+
+    /// The request wasn't sent, because the circuit breaker of the node it's routed to is open after too many
+    /// failures. The requests to the node fail fast until the breaker's cooldown expires.
+    CircuitOpen,
+
+    /// The command wasn't sent, because the client's command filter doesn't allow it.
+    /// The error's detail contains the name of the command.
+    CommandNotAllowed,
+
+    /// The request didn't complete within its request timeout. The server might have executed the request anyway.
+    Timeout,
+
+    /// An argument of the command, or the reply to it, is larger than the client's limit. The commands whose
+    /// arguments are too large aren't sent.
+    ValueTooLarge,
+}
+
+/// Stable numeric codes of the error kinds, for language bindings that can't match on [`ErrorKind`].
+///
+/// A kind keeps its code across releases, and the code of a removed kind isn't reused, so the codes may be
+/// hardcoded on the other side of an FFI boundary. New kinds get new codes. The code of `Serialize` is reserved
+/// even without the `json` feature.
+#[cfg(feature = "error-codes")]
+#[cfg_attr(docsrs, doc(cfg(feature = "error-codes")))]
+impl ErrorKind {
+    /// Returns the stable numeric code of the kind.
+    pub fn code(&self) -> u32 {
+        match self {
+            ErrorKind::ResponseError => 1,
+            ErrorKind::ParseError => 2,
+            ErrorKind::AuthenticationFailed => 3,
+            ErrorKind::TypeError => 4,
+            ErrorKind::ExecAbortError => 5,
+            ErrorKind::BusyLoadingError => 6,
+            ErrorKind::NoScriptError => 7,
+            ErrorKind::InvalidClientConfig => 8,
+            ErrorKind::Moved => 9,
+            ErrorKind::Ask => 10,
+            ErrorKind::TryAgain => 11,
+            ErrorKind::ClusterDown => 12,
+            ErrorKind::CrossSlot => 13,
+            ErrorKind::MasterDown => 14,
+            ErrorKind::IoError => 15,
+            ErrorKind::ClientError => 16,
+            ErrorKind::ExtensionError => 17,
+            ErrorKind::ReadOnly => 18,
+            ErrorKind::MasterNameNotFoundBySentinel => 19,
+            ErrorKind::NoValidReplicasFoundBySentinel => 20,
+            ErrorKind::EmptySentinelList => 21,
+            ErrorKind::NotBusy => 22,
+            ErrorKind::ClusterConnectionNotFound => 23,
+            #[cfg(feature = "json")]
+            ErrorKind::Serialize => 24,
+            ErrorKind::RESP3NotSupported => 25,
+            ErrorKind::NotAllSlotsCovered => 26,
+            ErrorKind::DriverStopped => 27,
+            ErrorKind::CircuitOpen => 28,
+            ErrorKind::CommandNotAllowed => 29,
+            ErrorKind::Timeout => 30,
+            ErrorKind::ValueTooLarge => 31,
+        }
+    }
+
+    /// Returns the kind with the given numeric code, if there is one.
+    pub fn from_code(code: u32) -> Option<ErrorKind> {
+        match code {
+            1 => Some(ErrorKind::ResponseError),
+            2 => Some(ErrorKind::ParseError),
+            3 => Some(ErrorKind::AuthenticationFailed),
+            4 => Some(ErrorKind::TypeError),
+            5 => Some(ErrorKind::ExecAbortError),
+            6 => Some(ErrorKind::BusyLoadingError),
+            7 => Some(ErrorKind::NoScriptError),
+            8 => Some(ErrorKind::InvalidClientConfig),
+            9 => Some(ErrorKind::Moved),
+            10 => Some(ErrorKind::Ask),
+            11 => Some(ErrorKind::TryAgain),
+            12 => Some(ErrorKind::ClusterDown),
+            13 => Some(ErrorKind::CrossSlot),
+            14 => Some(ErrorKind::MasterDown),
+            15 => Some(ErrorKind::IoError),
+            16 => Some(ErrorKind::ClientError),
+            17 => Some(ErrorKind::ExtensionError),
+            18 => Some(ErrorKind::ReadOnly),
+            19 => Some(ErrorKind::MasterNameNotFoundBySentinel),
+            20 => Some(ErrorKind::NoValidReplicasFoundBySentinel),
+            21 => Some(ErrorKind::EmptySentinelList),
+            22 => Some(ErrorKind::NotBusy),
+            23 => Some(ErrorKind::ClusterConnectionNotFound),
+            #[cfg(feature = "json")]
+            24 => Some(ErrorKind::Serialize),
+            25 => Some(ErrorKind::RESP3NotSupported),
+            26 => Some(ErrorKind::NotAllSlotsCovered),
+            27 => Some(ErrorKind::DriverStopped),
+            28 => Some(ErrorKind::CircuitOpen),
+            29 => Some(ErrorKind::CommandNotAllowed),
+            30 => Some(ErrorKind::Timeout),
+            31 => Some(ErrorKind::ValueTooLarge),
+            _ => None,
+        }
+    }
 }
 
 #[derive(PartialEq, Debug)]
@@ -355,6 +464,9 @@ pub enum Value {
         /// Remaining data from push message
         data: Vec<Value>,
     },
+    /// An error in place of a value, such as the error of one of the nodes in the per-node results of a command that
+    /// was sent to multiple nodes.
+    Error(RedisError),
 }
 
 /// `VerbatimString`'s format types defined by spec
@@ -598,6 +710,7 @@ impl fmt::Debug for Value {
                 write!(fmt, "verbatim-string({:?},{:?})", format, text)
             }
             Value::BigNumber(ref m) => write!(fmt, "big-number({:?})", m),
+            Value::Error(ref err) => write!(fmt, "error({err:?})"),
         }
     }
 }
@@ -644,6 +757,25 @@ impl PartialEq for RedisError {
     }
 }
 
+// IO errors can't be cloned, so their clones keep only their kind and message.
+impl Clone for RedisError {
+    fn clone(&self) -> RedisError {
+        let repr = match self.repr {
+            ErrorRepr::WithDescription(kind, desc) => ErrorRepr::WithDescription(kind, desc),
+            ErrorRepr::WithDescriptionAndDetail(kind, desc, ref detail) => {
+                ErrorRepr::WithDescriptionAndDetail(kind, desc, detail.clone())
+            }
+            ErrorRepr::ExtensionError(ref code, ref detail) => {
+                ErrorRepr::ExtensionError(code.clone(), detail.clone())
+            }
+            ErrorRepr::IoError(ref err) => {
+                ErrorRepr::IoError(io::Error::new(err.kind(), err.to_string()))
+            }
+        };
+        RedisError { repr }
+    }
+}
+
 impl From<io::Error> for RedisError {
     fn from(err: io::Error) -> RedisError {
         RedisError {
@@ -829,6 +961,13 @@ impl RedisError {
         }
     }
 
+    /// Returns the stable numeric code of the error's kind, see [`ErrorKind::code`].
+    #[cfg(feature = "error-codes")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "error-codes")))]
+    pub fn kind_code(&self) -> u32 {
+        self.kind().code()
+    }
+
     /// Returns the raw error code if available.
     pub fn code(&self) -> Option<&str> {
         match self.kind() {
@@ -881,6 +1020,11 @@ impl RedisError {
             ErrorKind::RESP3NotSupported => "resp3 is not supported by server",
             ErrorKind::ParseError => "parse error",
             ErrorKind::NotAllSlotsCovered => "not all slots are covered",
+            ErrorKind::DriverStopped => "driver stopped",
+            ErrorKind::CircuitOpen => "circuit open",
+            ErrorKind::CommandNotAllowed => "command not allowed",
+            ErrorKind::Timeout => "request timed out",
+            ErrorKind::ValueTooLarge => "value too large",
         }
     }
 
@@ -925,9 +1069,12 @@ impl RedisError {
         }
     }
 
-    /// Returns true if error was caused by I/O time out.
-    /// Note that this may not be accurate depending on platform.
+    /// Returns true if error was caused by I/O time out, or by a request that didn't complete within its request
+    /// timeout. Note that this may not be accurate depending on platform.
     pub fn is_timeout(&self) -> bool {
+        if self.kind() == ErrorKind::Timeout {
+            return true;
+        }
         match self.repr {
             ErrorRepr::IoError(ref err) => matches!(
                 err.kind(),
@@ -1066,6 +1213,11 @@ impl RedisError {
                 _ => RetryMethod::RetryImmediately,
             },
             ErrorKind::NotAllSlotsCovered => RetryMethod::NoRetry,
+            ErrorKind::DriverStopped => RetryMethod::NoRetry,
+            ErrorKind::CircuitOpen => RetryMethod::NoRetry,
+            ErrorKind::CommandNotAllowed => RetryMethod::NoRetry,
+            ErrorKind::Timeout => RetryMethod::NoRetry,
+            ErrorKind::ValueTooLarge => RetryMethod::NoRetry,
         }
     }
 }
@@ -1111,6 +1263,8 @@ pub struct InfoDict {
 /// let role : Option<String> = info.get("role");
 /// # Ok(()) }
 /// ```
+///
+/// For typed access to the commonly used fields, see [`crate::info::InfoParser`].
 impl InfoDict {
     /// Creates a new info dictionary from a string in the response of
     /// the INFO command.  Each line is a key, value pair with the
@@ -1759,6 +1913,10 @@ macro_rules! from_redis_value_for_num_internal {
                 Err(_) => invalid_type_error!(v, "Could not convert from string."),
             },
             Value::Double(val) => Ok(val as $t),
+            Value::BigNumber(ref number) => match number.to_string().parse::<$t>() {
+                Ok(rv) => Ok(rv),
+                Err(_) => invalid_type_error!(v, "Could not convert from big number."),
+            },
             _ => invalid_type_error!(v, "Response type not convertible to numeric."),
         }
     }};
@@ -1821,6 +1979,10 @@ macro_rules! from_redis_value_for_bignum_internal {
                 Ok(rv) => Ok(rv),
                 Err(_) => invalid_type_error!(v, "Could not convert from string."),
             },
+            Value::BigNumber(ref number) => match number.to_string().parse::<$t>() {
+                Ok(rv) => Ok(rv),
+                Err(_) => invalid_type_error!(v, "Could not convert from big number."),
+            },
             _ => invalid_type_error!(v, "Response type not convertible to numeric."),
         }
     }};
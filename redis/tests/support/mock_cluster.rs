@@ -8,9 +8,9 @@ use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr},
     sync::{
         atomic::{AtomicUsize, Ordering},
-        Arc, RwLock,
+        Arc, Mutex, RwLock,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use {
@@ -37,6 +37,8 @@ pub struct MockConnectionBehavior {
     pub connection_id_provider: AtomicUsize,
     pub returned_ip_type: ConnectionIPReturnType,
     pub return_connection_err: ShouldReturnConnectionError,
+    /// Connecting through one of these socket addresses returns a connection error
+    pub unreachable_socket_addrs: Vec<SocketAddr>,
 }
 
 impl MockConnectionBehavior {
@@ -47,6 +49,7 @@ impl MockConnectionBehavior {
             connection_id_provider: AtomicUsize::new(0),
             returned_ip_type: ConnectionIPReturnType::default(),
             return_connection_err: ShouldReturnConnectionError::default(),
+            unreachable_socket_addrs: Vec::new(),
         }
     }
 
@@ -87,6 +90,7 @@ pub fn get_mock_connection_with_port(name: &str, id: usize, port: u16) -> MockCo
         id,
         handler: get_mock_connection_handler(name),
         port,
+        last_response: Default::default(),
     }
 }
 
@@ -118,6 +122,10 @@ pub enum ShouldReturnConnectionError {
     Yes,
     /// Return connection error when the internal index is an odd number
     OnOddIdx(AtomicUsize),
+    /// Always return a connection error, and count the connection attempts
+    YesAndCount(Arc<AtomicUsize>),
+    /// Never complete the connection, like an address that isn't routable, and count the connection attempts
+    HangAndCount(Arc<AtomicUsize>),
 }
 
 #[derive(Clone)]
@@ -125,6 +133,18 @@ pub struct MockConnection {
     pub id: usize,
     pub handler: Handler,
     pub port: u16,
+    /// When the connection, or one of its clones, last received a successful response.
+    pub last_response: Arc<Mutex<Option<Instant>>>,
+}
+
+impl MockConnection {
+    #[cfg(feature = "cluster-async")]
+    fn record_response<T>(&self, result: RedisResult<T>) -> RedisResult<T> {
+        if result.is_ok() {
+            *self.last_response.lock().unwrap() = Some(Instant::now());
+        }
+        result
+    }
 }
 
 #[cfg(feature = "cluster-async")]
@@ -133,7 +153,7 @@ impl cluster_async::Connect for MockConnection {
         info: T,
         _response_timeout: Duration,
         _connection_timeout: Duration,
-        _socket_addr: Option<SocketAddr>,
+        socket_addr: Option<SocketAddr>,
         _push_sender: Option<mpsc::UnboundedSender<PushInfo>>,
     ) -> RedisFuture<'a, (Self, Option<IpAddr>)>
     where
@@ -153,9 +173,21 @@ impl cluster_async::Connect for MockConnection {
             std::io::ErrorKind::ConnectionReset,
             "mock-io-error",
         ))));
+        if matches!(socket_addr, Some(addr) if conn_utils.unreachable_socket_addrs.contains(&addr))
+        {
+            return conn_err;
+        }
         match &conn_utils.return_connection_err {
             ShouldReturnConnectionError::No => {}
             ShouldReturnConnectionError::Yes => return conn_err,
+            ShouldReturnConnectionError::YesAndCount(attempts) => {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                return conn_err;
+            }
+            ShouldReturnConnectionError::HangAndCount(attempts) => {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                return Box::pin(future::pending());
+            }
             ShouldReturnConnectionError::OnOddIdx(curr_idx) => {
                 if curr_idx.fetch_add(1, Ordering::SeqCst) % 2 != 0 {
                     // raise an error on each odd number
@@ -180,10 +212,15 @@ impl cluster_async::Connect for MockConnection {
                     .fetch_add(1, Ordering::SeqCst),
                 handler: conn_utils.get_handler(),
                 port,
+                last_response: Default::default(),
             },
             ip,
         )))
     }
+
+    fn last_successful_response(&self) -> Option<Instant> {
+        *self.last_response.lock().unwrap()
+    }
 }
 
 impl cluster::Connect for MockConnection {
@@ -207,6 +244,7 @@ impl cluster::Connect for MockConnection {
                 .fetch_add(1, Ordering::SeqCst),
             handler: conn_utils.get_handler(),
             port,
+            last_response: Default::default(),
         })
     }
 
@@ -352,18 +390,26 @@ pub fn respond_startup_with_config(
 impl aio::ConnectionLike for MockConnection {
     fn req_packed_command<'a>(&'a mut self, cmd: &'a redis::Cmd) -> RedisFuture<'a, Value> {
         Box::pin(future::ready(
-            (self.handler)(&cmd.get_packed_command(), self.port)
-                .expect_err("Handler did not specify a response"),
+            self.record_response(
+                (self.handler)(&cmd.get_packed_command(), self.port)
+                    .expect_err("Handler did not specify a response"),
+            ),
         ))
     }
 
     fn req_packed_commands<'a>(
         &'a mut self,
-        _pipeline: &'a redis::Pipeline,
-        _offset: usize,
-        _count: usize,
+        pipeline: &'a redis::Pipeline,
+        offset: usize,
+        count: usize,
     ) -> RedisFuture<'a, Vec<Value>> {
-        Box::pin(future::ok(vec![]))
+        let res = (self.handler)(&pipeline.get_packed_pipeline(), self.port)
+            .expect_err("Handler did not specify a response");
+        Box::pin(future::ready(self.record_response(match res {
+            Ok(Value::Array(values)) => Ok(values.into_iter().skip(offset).take(count).collect()),
+            Ok(_) => Err((ErrorKind::ResponseError, "non-array response").into()),
+            Err(err) => Err(err),
+        })))
     }
 
     fn get_db(&self) -> i64 {
@@ -633,6 +633,12 @@ where
             }
             Ok(())
         }
+        Value::Error(ref err) => write!(
+            writer,
+            "-{} {}\r\n",
+            err.code().unwrap_or("ERR"),
+            err.detail().unwrap_or_default()
+        ),
     }
 }
 
@@ -139,6 +139,24 @@ mod basic_async {
         .unwrap();
     }
 
+    #[test]
+    fn test_empty_pipeline() {
+        let ctx = TestContext::new();
+        block_on_all(async move {
+            let mut con = ctx.async_connection().await?;
+
+            let result: Vec<String> = pipe().query_async(&mut con).await?;
+            assert_eq!(result, Vec::<String>::new());
+            assert_eq!(con.req_packed_commands(&pipe(), 0, 0).await?, vec![]);
+
+            // Nothing is sent, so the connection's replies stay in order.
+            let pong: String = cmd("PING").query_async(&mut con).await?;
+            assert_eq!(pong, "PONG");
+            Ok(())
+        })
+        .unwrap();
+    }
+
     #[test]
     fn test_pipeline_transaction() {
         let ctx = TestContext::new();
@@ -192,6 +210,495 @@ mod basic_async {
         .unwrap();
     }
 
+    #[test]
+    fn test_unsolicited_subscription_push_is_not_taken_as_a_reply() {
+        use redis::IntoConnectionInfo;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // A server that pushes an unsolicited `sunsubscribe`, as sent when a shard channel's slot is migrated,
+        // before replying to `GET`.
+        async fn serve(mut stream: tokio::io::DuplexStream) {
+            let mut buffer = vec![0; 1024];
+            loop {
+                let Ok(read) = stream.read(&mut buffer).await else {
+                    return;
+                };
+                if read == 0 {
+                    return;
+                }
+                let requests = &buffer[..read];
+                let contains = |name: &[u8]| requests.windows(name.len()).any(|w| w == name);
+                let mut reply = Vec::new();
+                // `CLIENT SETINFO` is sent as a pipeline of two commands on connection setup.
+                for _ in requests.windows(6).filter(|w| w == b"CLIENT") {
+                    reply.extend_from_slice(b"+OK\r\n");
+                }
+                if contains(b"GET") {
+                    reply.extend_from_slice(
+                        b">3\r\n$12\r\nsunsubscribe\r\n$3\r\nfoo\r\n:0\r\n$3\r\nbar\r\n",
+                    );
+                }
+                if contains(b"SSUBSCRIBE") {
+                    reply.extend_from_slice(b">3\r\n$10\r\nssubscribe\r\n$3\r\nfoo\r\n:1\r\n");
+                }
+                stream.write_all(&reply).await.unwrap();
+            }
+        }
+
+        block_on_all(async move {
+            let (client_stream, server_stream) = tokio::io::duplex(1024);
+            tokio::spawn(serve(server_stream));
+            let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+            let (mut con, driver) = MultiplexedConnection::new(
+                &"redis://127.0.0.1".into_connection_info().unwrap(),
+                client_stream,
+                Some(tx),
+            )
+            .await?;
+            tokio::spawn(driver);
+
+            let value: String = con.get("key").await?;
+            assert_eq!(value, "bar");
+            let push = rx.recv().await.unwrap();
+            assert_eq!(push.kind, PushKind::SUnsubscribe);
+
+            // The subscription commands are still replied by their pushes.
+            let value: Value = cmd("SSUBSCRIBE").arg("foo").query_async(&mut con).await?;
+            assert!(
+                matches!(
+                    value,
+                    Value::Push {
+                        kind: PushKind::SSubscribe,
+                        ..
+                    }
+                ),
+                "{value:?}"
+            );
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_pipeline_chunking_splits_big_pipelines() {
+        use redis::{IntoConnectionInfo, PipelineChunking};
+        use std::sync::{Arc, Mutex};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // A server that replies to each `INCR` with the number of `INCR`s it received so far, and records how many
+        // of them arrived together.
+        async fn serve(mut stream: tokio::io::DuplexStream, batches: Arc<Mutex<Vec<usize>>>) {
+            let mut buffer = vec![0; 4096];
+            let mut counter = 0;
+            loop {
+                let Ok(read) = stream.read(&mut buffer).await else {
+                    return;
+                };
+                if read == 0 {
+                    return;
+                }
+                let requests = &buffer[..read];
+                let mut reply = Vec::new();
+                for _ in requests.windows(6).filter(|w| w == b"CLIENT") {
+                    reply.extend_from_slice(b"+OK\r\n");
+                }
+                let incrs = requests.windows(4).filter(|w| w == b"INCR").count();
+                if incrs > 0 {
+                    batches.lock().unwrap().push(incrs);
+                }
+                for _ in 0..incrs {
+                    counter += 1;
+                    reply.extend_from_slice(format!(":{counter}\r\n").as_bytes());
+                }
+                stream.write_all(&reply).await.unwrap();
+            }
+        }
+
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let server_batches = batches.clone();
+        block_on_all(async move {
+            let (client_stream, server_stream) = tokio::io::duplex(4096);
+            tokio::spawn(serve(server_stream, server_batches));
+            let (mut con, driver) = MultiplexedConnection::new(
+                &"redis://127.0.0.1".into_connection_info().unwrap(),
+                client_stream,
+                None,
+            )
+            .await?;
+            tokio::spawn(driver);
+            con.set_pipeline_chunking(PipelineChunking::new().max_commands(2));
+
+            let mut pipe = redis::pipe();
+            for _ in 0..5 {
+                pipe.incr("key", 1);
+            }
+            pipe.ignore();
+            let values: Vec<i64> = pipe.query_async(&mut con).await?;
+            assert_eq!(values, vec![1, 2, 3, 4]);
+            assert_eq!(*batches.lock().unwrap(), vec![2, 2, 1]);
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_max_outstanding_bytes() {
+        use redis::aio::OutstandingBytesPolicy;
+        use redis::IntoConnectionInfo;
+        use std::sync::Arc;
+        use std::time::Duration;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        use tokio::sync::Semaphore;
+
+        // A server that replies to each `GET` once it's released.
+        async fn serve(mut stream: tokio::io::DuplexStream, released: Arc<Semaphore>) {
+            let mut buffer = vec![0; 1024];
+            loop {
+                let Ok(read) = stream.read(&mut buffer).await else {
+                    return;
+                };
+                if read == 0 {
+                    return;
+                }
+                let requests = &buffer[..read];
+                let mut reply = Vec::new();
+                for _ in requests.windows(6).filter(|w| w == b"CLIENT") {
+                    reply.extend_from_slice(b"+OK\r\n");
+                }
+                for _ in requests.windows(3).filter(|w| w == b"GET") {
+                    released.acquire().await.unwrap().forget();
+                    reply.extend_from_slice(b"$3\r\nbar\r\n");
+                }
+                stream.write_all(&reply).await.unwrap();
+            }
+        }
+
+        async fn wait_for_outstanding_bytes(con: &MultiplexedConnection, bytes: usize) {
+            while con.outstanding_bytes() != bytes {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        }
+
+        block_on_all(async move {
+            let released = Arc::new(Semaphore::new(0));
+            let (client_stream, server_stream) = tokio::io::duplex(1024);
+            tokio::spawn(serve(server_stream, released.clone()));
+            let (mut con, driver) = MultiplexedConnection::new(
+                &"redis://127.0.0.1".into_connection_info().unwrap(),
+                client_stream,
+                None,
+            )
+            .await?;
+            tokio::spawn(driver);
+            let get = cmd("GET").arg("k".repeat(40)).clone();
+            let get_len = get.get_packed_command().len();
+            let spawn_get = |con: &MultiplexedConnection| {
+                let mut con = con.clone();
+                let get = get.clone();
+                tokio::spawn(async move { get.query_async::<_, String>(&mut con).await })
+            };
+
+            con.set_max_outstanding_bytes(get_len * 3 / 2, OutstandingBytesPolicy::Fail);
+            let first = spawn_get(&con);
+            wait_for_outstanding_bytes(&con, get_len).await;
+            let err = get.query_async::<_, String>(&mut con).await.unwrap_err();
+            assert_eq!(err.kind(), redis::ErrorKind::ClientError, "{err:?}");
+            let err = cmd("GET")
+                .arg("k".repeat(100))
+                .query_async::<_, String>(&mut con)
+                .await
+                .unwrap_err();
+            assert_eq!(err.kind(), redis::ErrorKind::ClientError, "{err:?}");
+            released.add_permits(1);
+            assert_eq!(first.await.unwrap()?, "bar");
+            assert_eq!(con.outstanding_bytes(), 0);
+
+            con.set_max_outstanding_bytes(get_len * 3 / 2, OutstandingBytesPolicy::Wait);
+            let first = spawn_get(&con);
+            wait_for_outstanding_bytes(&con, get_len).await;
+            let second = spawn_get(&con);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            assert_eq!(con.outstanding_bytes(), get_len);
+            released.add_permits(2);
+            assert_eq!(first.await.unwrap()?, "bar");
+            assert_eq!(second.await.unwrap()?, "bar");
+            assert_eq!(con.outstanding_bytes(), 0);
+
+            // The wait for the outstanding bytes counts against the response timeout.
+            con.set_response_timeout(Duration::from_millis(200));
+            let first = spawn_get(&con);
+            wait_for_outstanding_bytes(&con, get_len).await;
+            let start = std::time::Instant::now();
+            let second = spawn_get(&con);
+            tokio::time::sleep(Duration::from_millis(150)).await;
+            released.add_permits(1);
+            assert_eq!(first.await.unwrap()?, "bar");
+            let err = second.await.unwrap().unwrap_err();
+            assert!(err.is_timeout(), "{err:?}");
+            assert!(start.elapsed() < Duration::from_millis(300));
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_flush_policy() {
+        use redis::aio::FlushPolicy;
+        use redis::IntoConnectionInfo;
+        use std::pin::Pin;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use std::task::{Context, Poll};
+        use std::time::{Duration, Instant};
+        use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+
+        // A stream that counts the writes to the socket.
+        struct CountingStream {
+            stream: tokio::io::DuplexStream,
+            writes: Arc<AtomicUsize>,
+        }
+
+        impl AsyncRead for CountingStream {
+            fn poll_read(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &mut ReadBuf<'_>,
+            ) -> Poll<std::io::Result<()>> {
+                Pin::new(&mut self.stream).poll_read(cx, buf)
+            }
+        }
+
+        impl AsyncWrite for CountingStream {
+            fn poll_write(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &[u8],
+            ) -> Poll<std::io::Result<usize>> {
+                self.writes.fetch_add(1, Ordering::SeqCst);
+                Pin::new(&mut self.stream).poll_write(cx, buf)
+            }
+
+            fn poll_flush(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<std::io::Result<()>> {
+                Pin::new(&mut self.stream).poll_flush(cx)
+            }
+
+            fn poll_shutdown(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<std::io::Result<()>> {
+                Pin::new(&mut self.stream).poll_shutdown(cx)
+            }
+        }
+
+        async fn serve(mut stream: tokio::io::DuplexStream) {
+            let mut buffer = vec![0; 4096];
+            loop {
+                let Ok(read) = stream.read(&mut buffer).await else {
+                    return;
+                };
+                if read == 0 {
+                    return;
+                }
+                let requests = &buffer[..read];
+                let mut reply = Vec::new();
+                for _ in requests.windows(6).filter(|w| w == b"CLIENT") {
+                    reply.extend_from_slice(b"+OK\r\n");
+                }
+                for _ in requests.windows(4).filter(|w| w == b"PING") {
+                    reply.extend_from_slice(b"+PONG\r\n");
+                }
+                stream.write_all(&reply).await.unwrap();
+            }
+        }
+
+        block_on_all(async move {
+            let (client_stream, server_stream) = tokio::io::duplex(4096);
+            tokio::spawn(serve(server_stream));
+            let writes = Arc::new(AtomicUsize::new(0));
+            let (mut con, driver) = MultiplexedConnection::new(
+                &"redis://127.0.0.1".into_connection_info().unwrap(),
+                CountingStream {
+                    stream: client_stream,
+                    writes: writes.clone(),
+                },
+                None,
+            )
+            .await?;
+            tokio::spawn(driver);
+            let ping_concurrently = |con: &MultiplexedConnection| {
+                let pings = (0..10).map(|_| {
+                    let mut con = con.clone();
+                    async move { cmd("PING").query_async::<_, String>(&mut con).await }
+                });
+                future::try_join_all(pings)
+            };
+
+            // The concurrent requests are written together by default.
+            writes.store(0, Ordering::SeqCst);
+            ping_concurrently(&con).await?;
+            assert_eq!(writes.load(Ordering::SeqCst), 1);
+
+            con.set_flush_policy(FlushPolicy::PerRequest);
+            writes.store(0, Ordering::SeqCst);
+            ping_concurrently(&con).await?;
+            assert_eq!(writes.load(Ordering::SeqCst), 10);
+
+            // Requests that are sent within the interval are written together.
+            con.set_flush_policy(FlushPolicy::Interval(Duration::from_millis(50)));
+            writes.store(0, Ordering::SeqCst);
+            let start = Instant::now();
+            let first = tokio::spawn({
+                let mut con = con.clone();
+                async move { cmd("PING").query_async::<_, String>(&mut con).await }
+            });
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            cmd("PING").query_async::<_, String>(&mut con).await?;
+            first.await.unwrap()?;
+            assert_eq!(writes.load(Ordering::SeqCst), 1);
+            assert!(start.elapsed() >= Duration::from_millis(50));
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn test_multiplexed_connection_resumes_partial_vectored_writes() {
+        use redis::{IntoConnectionInfo, Value};
+        use std::pin::Pin;
+        use std::sync::{Arc, Mutex};
+        use std::task::{Context, Poll};
+        use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf};
+
+        // A stream that writes at most 7 bytes of the slices of each vectored write.
+        struct ShortWriteStream {
+            stream: tokio::io::DuplexStream,
+        }
+
+        impl AsyncRead for ShortWriteStream {
+            fn poll_read(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &mut ReadBuf<'_>,
+            ) -> Poll<std::io::Result<()>> {
+                Pin::new(&mut self.stream).poll_read(cx, buf)
+            }
+        }
+
+        impl AsyncWrite for ShortWriteStream {
+            fn poll_write(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                buf: &[u8],
+            ) -> Poll<std::io::Result<usize>> {
+                let len = buf.len().min(7);
+                Pin::new(&mut self.stream).poll_write(cx, &buf[..len])
+            }
+
+            fn poll_write_vectored(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+                bufs: &[std::io::IoSlice<'_>],
+            ) -> Poll<std::io::Result<usize>> {
+                let bytes: Vec<u8> = bufs
+                    .iter()
+                    .flat_map(|buf| buf.iter())
+                    .copied()
+                    .take(7)
+                    .collect();
+                self.poll_write(cx, &bytes)
+            }
+
+            fn is_write_vectored(&self) -> bool {
+                true
+            }
+
+            fn poll_flush(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<std::io::Result<()>> {
+                Pin::new(&mut self.stream).poll_flush(cx)
+            }
+
+            fn poll_shutdown(
+                mut self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<std::io::Result<()>> {
+                Pin::new(&mut self.stream).poll_shutdown(cx)
+            }
+        }
+
+        // A server that records the commands and replies `OK` to each.
+        async fn serve(mut stream: tokio::io::DuplexStream, commands: Arc<Mutex<Vec<Value>>>) {
+            let mut decoder = combine::stream::Decoder::new();
+            while let Ok(command) = redis::parse_redis_value_async(&mut decoder, &mut stream).await
+            {
+                commands.lock().unwrap().push(command);
+                stream.write_all(b"+OK\r\n").await.unwrap();
+            }
+        }
+
+        block_on_all(async move {
+            let commands = Arc::new(Mutex::new(Vec::new()));
+            let (client_stream, server_stream) = tokio::io::duplex(1024);
+            tokio::spawn(serve(server_stream, commands.clone()));
+            let (con, driver) = MultiplexedConnection::new(
+                &"redis://127.0.0.1".into_connection_info().unwrap(),
+                ShortWriteStream {
+                    stream: client_stream,
+                },
+                None,
+            )
+            .await?;
+            tokio::spawn(driver);
+            commands.lock().unwrap().clear();
+
+            let big = vec![b'x'; 4096];
+            let mut sent = vec![
+                cmd("SET").arg("big").arg(&big[..]).clone(),
+                cmd("SET").arg("small").arg("1").clone(),
+                cmd("SET").arg("both").arg(&big[..]).arg("1").clone(),
+            ];
+            let requests = sent.iter().map(|command| {
+                let mut con = con.clone();
+                let command = command.clone();
+                async move { command.query_async::<_, String>(&mut con).await }
+            });
+            let replies = future::try_join_all(requests).await?;
+            assert_eq!(replies, vec!["OK"; 3]);
+            let mut pipe = redis::pipe();
+            pipe.set("piped", &big[..])
+                .ignore()
+                .set("small", 2)
+                .ignore();
+            pipe.query_async::<_, ()>(&mut con.clone()).await?;
+            sent.push(cmd("SET").arg("piped").arg(&big[..]).clone());
+            sent.push(cmd("SET").arg("small").arg(2).clone());
+
+            let as_value = |command: &redis::Cmd| {
+                Value::Array(
+                    command
+                        .args_iter()
+                        .map(|arg| match arg {
+                            redis::Arg::Simple(arg) => Value::BulkString(arg.to_vec()),
+                            redis::Arg::Cursor => unreachable!(),
+                        })
+                        .collect(),
+                )
+            };
+            let mut expected: Vec<_> = sent.iter().map(as_value).collect();
+            let mut received = commands.lock().unwrap().clone();
+            let key = |value: &Value| format!("{value:?}");
+            expected.sort_by_key(key);
+            received.sort_by_key(key);
+            assert_eq!(received, expected);
+            Ok(())
+        })
+        .unwrap();
+    }
+
     #[test]
     fn test_pipeline_transaction_with_errors() {
         use redis::RedisError;
@@ -221,6 +221,7 @@ mod test_connect_and_check {
             id: user_conn_id,
             handler: get_mock_connection_handler(name),
             port: 6379,
+            last_response: Default::default(),
         };
         let node = AsyncClusterNode::new(async { user_conn }.boxed().shared(), None, Some(ip));
 
@@ -260,6 +261,7 @@ mod test_connect_and_check {
             id: user_conn_id,
             handler: get_mock_connection_handler(name),
             port: 6379,
+            last_response: Default::default(),
         };
         let prev_ip = IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1));
         let node = AsyncClusterNode::new(async { user_conn }.boxed().shared(), None, Some(prev_ip));
@@ -303,6 +305,7 @@ mod test_connect_and_check {
             id: user_conn_id,
             handler: get_mock_connection_handler(name),
             port: 6379,
+            last_response: Default::default(),
         };
         let prev_ip = Some(IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)));
         let node = AsyncClusterNode::new(async { user_conn }.boxed().shared(), None, prev_ip);
@@ -347,11 +350,13 @@ mod test_connect_and_check {
             id: old_user_conn_id,
             handler: get_mock_connection_handler(name),
             port: 6379,
+            last_response: Default::default(),
         };
         let management_conn = MockConnection {
             id: management_conn_id,
             handler: get_mock_connection_handler(name),
             port: 6379,
+            last_response: Default::default(),
         };
 
         let node = AsyncClusterNode::new(
@@ -402,11 +407,13 @@ mod test_connect_and_check {
             id: old_user_conn_id,
             handler: get_mock_connection_handler(name),
             port: 6379,
+            last_response: Default::default(),
         };
         let management_conn = MockConnection {
             id: management_conn_id,
             handler: get_mock_connection_handler(name),
             port: 6379,
+            last_response: Default::default(),
         };
         let prev_ip = Some(IpAddr::V4(Ipv4Addr::new(1, 1, 1, 1)));
         let node = AsyncClusterNode::new(
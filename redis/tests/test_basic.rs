@@ -447,6 +447,10 @@ mod basic {
         let _: () = redis::pipe().cmd("PING").ignore().query(&mut con).unwrap();
 
         let _: () = redis::pipe().query(&mut con).unwrap();
+
+        // Nothing is sent, so the connection's replies stay in order.
+        assert_eq!(con.req_packed_commands(&[], 0, 0), Ok(vec![]));
+        assert_eq!(redis::cmd("PING").query(&mut con), Ok("PONG".to_string()));
     }
 
     #[test]
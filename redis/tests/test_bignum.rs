@@ -29,6 +29,10 @@ where
     let v: RedisResult<T> = FromRedisValue::from_redis_value(&Value::Int(42));
     assert_eq!(v.unwrap(), T::from(42u32));
 
+    let v: RedisResult<T> =
+        FromRedisValue::from_redis_value(&Value::BigNumber(num_bigint::BigInt::from(42)));
+    assert_eq!(v.unwrap(), T::from(42u32));
+
     let v: RedisResult<T> = FromRedisValue::from_redis_value(&Value::Okay);
     assert_eq!(v.unwrap_err().kind(), ErrorKind::TypeError);
 
@@ -10,7 +10,7 @@ mod cluster {
 
     use crate::support::*;
     use redis::{
-        cluster::{cluster_pipe, ClusterClient},
+        cluster::{cluster_pipe, ClusterClient, CommandFilter},
         cmd, parse_redis_value, Commands, ConnectionLike, ErrorKind, ProtocolVersion, RedisError,
         Value,
     };
@@ -271,6 +271,18 @@ mod cluster {
     );
     }
 
+    #[test]
+    fn test_cluster_empty_pipeline_is_not_sent() {
+        let name = "test_cluster_empty_pipeline_is_not_sent";
+
+        let MockEnv { mut connection, .. } = MockEnv::new(name, move |cmd: &[u8], _| {
+            respond_startup(name, cmd)?;
+            panic!("unexpected command {:?}", String::from_utf8_lossy(cmd));
+        });
+
+        assert_eq!(connection.req_packed_commands(&[], 0, 0), Ok(vec![]));
+    }
+
     #[test]
     fn test_cluster_can_connect_to_server_that_sends_cluster_slots_without_host_name() {
         let name = "test_cluster_can_connect_to_server_that_sends_cluster_slots_without_host_name";
@@ -443,6 +455,76 @@ mod cluster {
         assert_eq!(value, Ok(Some(123)));
     }
 
+    #[test]
+    fn test_cluster_command_filter_rejects_denied_commands() {
+        let name = "command_filter_rejects_denied_commands";
+
+        let requests = Arc::new(atomic::AtomicUsize::new(0));
+        let requests_clone = requests.clone();
+        let MockEnv {
+            mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")])
+                .command_filter(CommandFilter::allow(["GET", "SET"])),
+            name,
+            move |cmd: &[u8], _| {
+                respond_startup(name, cmd)?;
+                requests_clone.fetch_add(1, atomic::Ordering::SeqCst);
+                Err(Ok(Value::Okay))
+            },
+        );
+
+        let value = cmd("GET").arg("test").query::<Value>(&mut connection);
+        assert_eq!(value, Ok(Value::Okay));
+        assert_eq!(requests.swap(0, atomic::Ordering::SeqCst), 1);
+
+        let err = cmd("KEYS")
+            .arg("*")
+            .query::<Value>(&mut connection)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::CommandNotAllowed);
+        assert_eq!(err.detail(), Some("KEYS"));
+
+        assert_eq!(requests.load(atomic::Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_cluster_transport_mapping_connects_through_the_mapped_address() {
+        let name = "sync_transport_mapping_connects_through_the_mapped_address";
+        let proxy = "sync_transport_mapping_proxy";
+        let _proxy_handler = MockConnectionBehavior::register_new(
+            proxy,
+            Arc::new(move |cmd: &[u8], port| {
+                respond_startup(name, cmd)?;
+                Err(Ok(Value::BulkString(format!("proxy:{port}").into_bytes())))
+            }),
+        );
+        let MockEnv {
+            mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")]).transport_mapping(
+                move |node| {
+                    let (host, port) = node.rsplit_once(':')?;
+                    (host == name).then(|| {
+                        redis::ConnectionAddr::Tcp(proxy.to_string(), port.parse().unwrap())
+                    })
+                },
+            ),
+            name,
+            move |cmd: &[u8], _| {
+                respond_startup(name, cmd)?;
+                Err(Ok(Value::BulkString(b"direct".to_vec())))
+            },
+        );
+
+        let reply: String = cmd("GET").arg("foo").query(&mut connection).unwrap();
+        assert_eq!(reply, "proxy:6379");
+    }
+
     #[test]
     fn test_cluster_exhaust_retries() {
         let name = "tryagain_exhaust_retries";
@@ -714,6 +796,84 @@ mod cluster {
         assert_eq!(value, Ok(Some(123)));
     }
 
+    #[test]
+    fn test_cluster_surfaces_the_reconnect_error_after_the_last_attempt() {
+        let name = "surfaces_the_reconnect_error_after_the_last_attempt";
+        let reconnect_attempts = Arc::new(AtomicI32::new(0));
+        let disconnected = Arc::new(atomic::AtomicBool::new(false));
+        let MockEnv {
+            mut connection,
+            handler: _handler,
+            ..
+        } = MockEnv::with_client_builder(
+            ClusterClient::builder(vec![&*format!("redis://{name}")])
+                .read_from_replicas()
+                .max_reconnect_attempts(3)
+                .min_retry_wait(0)
+                .max_retry_wait(1),
+            name,
+            {
+                let reconnect_attempts = reconnect_attempts.clone();
+                move |cmd: &[u8], port| {
+                    // Every new connection sends `READONLY`, which fails once the node is disconnected.
+                    if contains_slice(cmd, b"READONLY") && disconnected.load(Ordering::SeqCst) {
+                        reconnect_attempts.fetch_add(1, Ordering::SeqCst);
+                        return Err(Err(RedisError::from(std::io::Error::new(
+                            std::io::ErrorKind::ConnectionRefused,
+                            "mock-connection-refused",
+                        ))));
+                    }
+                    respond_startup_two_nodes(name, cmd)?;
+                    match port {
+                        6379 => {
+                            disconnected.store(true, Ordering::SeqCst);
+                            Err(Err(RedisError::from(std::io::Error::new(
+                                std::io::ErrorKind::ConnectionReset,
+                                "mock-io-error",
+                            ))))
+                        }
+                        _ => panic!("Node should not be called"),
+                    }
+                }
+            },
+        );
+
+        let err = cmd("GET")
+            .arg("test")
+            .query::<Option<i32>>(&mut connection)
+            .unwrap_err();
+
+        assert_eq!(reconnect_attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(err.kind(), ErrorKind::IoError);
+        assert!(err.to_string().contains("Failed to reconnect"), "{err}");
+    }
+
+    #[test]
+    fn test_cluster_pipeline_check_routes_does_not_execute_the_commands() {
+        let name = "pipeline_check_routes";
+        let MockEnv { connection, .. } = MockEnv::new(name, move |cmd: &[u8], _| {
+            respond_startup_two_nodes(name, cmd)?;
+            panic!("Commands should not be executed");
+        });
+
+        let mut pipe = cluster_pipe();
+        pipe.set("foo", 1).get("bar");
+        assert_eq!(
+            pipe.check_routes(&connection).unwrap(),
+            vec![format!("{name}:6380"), format!("{name}:6379")]
+        );
+
+        let err = pipe.cmd("FLUSHALL").check_routes(&connection).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ClientError);
+
+        let err = cluster_pipe()
+            .get("foo")
+            .cmd("SCAN")
+            .check_routes(&connection)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::ClientError);
+    }
+
     #[test]
     fn test_cluster_non_retryable_error_should_not_retry() {
         let name = "node";
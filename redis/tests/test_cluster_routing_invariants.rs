@@ -0,0 +1,34 @@
+#![cfg(feature = "routing-test-support")]
+
+use quickcheck::quickcheck;
+use redis::testing::routing::{
+    check_hash_tag_slot, check_multi_slot_split, ArbitraryKey, HashTaggedKeys, MultiKeyCommand,
+};
+
+quickcheck! {
+    fn same_hash_tag_is_in_the_same_slot(keys: HashTaggedKeys) -> Result<(), String> {
+        check_hash_tag_slot(keys)
+    }
+
+    fn multi_slot_split_keeps_arguments_order(command: MultiKeyCommand) -> Result<(), String> {
+        check_multi_slot_split(command)
+    }
+
+    fn slot_is_in_range(key: ArbitraryKey) -> bool {
+        redis::cluster_topology::get_slot(&key.0) < 16384
+    }
+}
+
+#[test]
+fn test_multi_slot_split_of_keys_with_the_same_tag_is_single_node() {
+    let command = MultiKeyCommand {
+        name: "MSET",
+        args: vec![
+            b"{user}a".to_vec(),
+            b"1".to_vec(),
+            b"b{user}".to_vec(),
+            b"2".to_vec(),
+        ],
+    };
+    assert_eq!(check_multi_slot_split(command), Ok(()));
+}
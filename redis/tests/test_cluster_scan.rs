@@ -4,9 +4,12 @@ mod support;
 #[cfg(test)]
 mod test_cluster_scan_async {
     use crate::support::*;
+    use futures::StreamExt;
     use rand::Rng;
     use redis::cluster_routing::{RoutingInfo, SingleNodeRoutingInfo};
-    use redis::{cmd, from_redis_value, ObjectType, RedisResult, ScanStateRC, Value};
+    use redis::{
+        cmd, from_redis_value, ClusterScanOptions, ObjectType, RedisResult, ScanStateRC, Value,
+    };
     use std::time::Duration;
 
     async fn kill_one_node(
@@ -87,6 +90,47 @@ mod test_cluster_scan_async {
         }
     }
 
+    #[tokio::test]
+    async fn test_async_cluster_scan_all_shards() {
+        let cluster = TestClusterContext::new(3, 0);
+        let mut connection = cluster.async_connection(None).await;
+
+        let mut expected_keys: Vec<String> = Vec::new();
+        for i in 0..100 {
+            let key = format!("key{}", i);
+            let _: () = redis::cmd("SET")
+                .arg(&key)
+                .arg("value")
+                .query_async(&mut connection)
+                .await
+                .unwrap();
+            expected_keys.push(key);
+        }
+        let _: () = redis::cmd("SET")
+            .arg("other")
+            .arg("value")
+            .query_async(&mut connection)
+            .await
+            .unwrap();
+
+        let mut stream = connection.scan(ClusterScanOptions::new().match_pattern("key*"));
+        let mut keys: Vec<String> = Vec::new();
+        while let Some(scan_keys) = stream.next().await {
+            keys.extend(
+                scan_keys
+                    .unwrap()
+                    .into_iter()
+                    .map(|v| from_redis_value::<String>(&v).unwrap()),
+            );
+        }
+        assert!(stream.cursor().is_finished());
+
+        keys.sort();
+        keys.dedup();
+        expected_keys.sort();
+        assert_eq!(keys, expected_keys);
+    }
+
     #[tokio::test] // test cluster scan with slot migration in the middle
     async fn test_async_cluster_scan_with_migration() {
         let cluster = TestClusterContext::new(3, 0);
@@ -0,0 +1,201 @@
+#![allow(unknown_lints, dependency_on_unit_never_type_fallback)]
+#![cfg(feature = "cluster-async")]
+mod support;
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use redis::{cluster_async::replication_group::ReplicationGroupClient, cmd, ErrorKind, Value};
+use support::{
+    contains_slice, modify_mock_connection_behavior, respond_startup, MockConnection,
+    MockConnectionBehavior, ShouldReturnConnectionError,
+};
+
+fn respond_with_name(name: &'static str, cmd: &[u8]) -> Result<(), redis::RedisResult<Value>> {
+    respond_startup(name, cmd)?;
+    if contains_slice(cmd, b"GET") {
+        Err(Ok(Value::BulkString(name.as_bytes().to_vec())))
+    } else if contains_slice(cmd, b"SET") {
+        Err(Ok(Value::SimpleString(name.to_string())))
+    } else {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_replication_group_routes_reads_to_reader_and_writes_to_primary() {
+    let primary = "test_replication_group_routing_primary";
+    let reader = "test_replication_group_routing_reader";
+    let _primary_handle = MockConnectionBehavior::register_new(
+        primary,
+        Arc::new(move |cmd, _| respond_with_name(primary, cmd)),
+    );
+    let _reader_handle = MockConnectionBehavior::register_new(
+        reader,
+        Arc::new(move |cmd, _| respond_with_name(reader, cmd)),
+    );
+
+    let client = ReplicationGroupClient::builder(format!("redis://{primary}"))
+        .reader_endpoint(format!("redis://{reader}"))
+        .build()
+        .unwrap();
+    let mut connection = client
+        .get_async_generic_connection::<MockConnection>()
+        .await
+        .unwrap();
+
+    let write: String = cmd("SET")
+        .arg("foo")
+        .arg("bar")
+        .query_async(&mut connection)
+        .await
+        .unwrap();
+    assert_eq!(write, primary);
+    let read: String = cmd("GET")
+        .arg("foo")
+        .query_async(&mut connection)
+        .await
+        .unwrap();
+    assert_eq!(read, reader);
+}
+
+#[tokio::test]
+async fn test_replication_group_reads_fall_back_to_primary_when_reader_is_unreachable() {
+    let primary = "test_replication_group_fallback_primary";
+    let reader = "test_replication_group_fallback_reader";
+    let _primary_handle = MockConnectionBehavior::register_new(
+        primary,
+        Arc::new(move |cmd, _| respond_with_name(primary, cmd)),
+    );
+    let _reader_handle = MockConnectionBehavior::register_new(
+        reader,
+        Arc::new(move |cmd, _| respond_with_name(reader, cmd)),
+    );
+    modify_mock_connection_behavior(reader, |behavior| {
+        behavior.return_connection_err = ShouldReturnConnectionError::Yes;
+    });
+
+    let client = ReplicationGroupClient::builder(format!("redis://{primary}"))
+        .reader_endpoint(format!("redis://{reader}"))
+        .build()
+        .unwrap();
+    let mut connection = client
+        .get_async_generic_connection::<MockConnection>()
+        .await
+        .unwrap();
+
+    let read: String = cmd("GET")
+        .arg("foo")
+        .query_async(&mut connection)
+        .await
+        .unwrap();
+    assert_eq!(read, primary);
+}
+
+#[tokio::test]
+async fn test_replication_group_reads_dont_wait_for_an_unroutable_reader() {
+    let primary = "test_replication_group_unroutable_primary";
+    let reader = "test_replication_group_unroutable_reader";
+    let reader_broken = Arc::new(AtomicBool::new(false));
+    let handler_reader_broken = reader_broken.clone();
+    let _primary_handle = MockConnectionBehavior::register_new(
+        primary,
+        Arc::new(move |cmd, _| respond_with_name(primary, cmd)),
+    );
+    let _reader_handle = MockConnectionBehavior::register_new(
+        reader,
+        Arc::new(move |cmd, _| {
+            if handler_reader_broken.load(Ordering::Relaxed) && contains_slice(cmd, b"GET") {
+                return Err(Err(
+                    std::io::Error::from(std::io::ErrorKind::BrokenPipe).into()
+                ));
+            }
+            respond_with_name(reader, cmd)
+        }),
+    );
+
+    let client = ReplicationGroupClient::builder(format!("redis://{primary}"))
+        .reader_endpoint(format!("redis://{reader}"))
+        .build()
+        .unwrap();
+    let mut connection = client
+        .get_async_generic_connection::<MockConnection>()
+        .await
+        .unwrap();
+
+    // The reader's connection breaks, and its address no longer routes anywhere.
+    let attempts = Arc::new(AtomicUsize::new(0));
+    reader_broken.store(true, Ordering::Relaxed);
+    modify_mock_connection_behavior(reader, |behavior| {
+        behavior.return_connection_err =
+            ShouldReturnConnectionError::HangAndCount(attempts.clone());
+    });
+
+    for _ in 0..10 {
+        let read = tokio::time::timeout(
+            Duration::from_secs(1),
+            cmd("GET")
+                .arg("foo")
+                .query_async::<_, String>(&mut connection),
+        )
+        .await
+        .expect("The read waited for the reader endpoint")
+        .unwrap();
+        assert_eq!(read, primary);
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+    // A single reconnect is attempted in the background, and the reads don't wait for it.
+    assert_eq!(attempts.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn test_replication_group_reconnects_to_primary_on_readonly_error() {
+    // After a failover, the primary connection may still point to the demoted primary,
+    // which answers writes with READONLY errors until the endpoint is reconnected.
+    let primary = "test_replication_group_readonly_primary";
+    let pings = Arc::new(AtomicUsize::new(0));
+    let writes = Arc::new(AtomicUsize::new(0));
+    let (handler_pings, handler_writes) = (pings.clone(), writes.clone());
+    let _handle = MockConnectionBehavior::register_new(
+        primary,
+        Arc::new(move |cmd, _| {
+            if contains_slice(cmd, b"PING") {
+                handler_pings.fetch_add(1, Ordering::Relaxed);
+            }
+            respond_startup(primary, cmd)?;
+            if contains_slice(cmd, b"SET") && handler_writes.fetch_add(1, Ordering::Relaxed) == 0 {
+                return Err(Err((ErrorKind::ReadOnly, "").into()));
+            }
+            respond_with_name(primary, cmd)
+        }),
+    );
+
+    let client = ReplicationGroupClient::builder(format!("redis://{primary}"))
+        .build()
+        .unwrap();
+    let mut connection = client
+        .get_async_generic_connection::<MockConnection>()
+        .await
+        .unwrap();
+
+    let write: String = cmd("SET")
+        .arg("foo")
+        .arg("bar")
+        .query_async(&mut connection)
+        .await
+        .unwrap();
+    assert_eq!(write, primary);
+    assert_eq!(writes.load(Ordering::Relaxed), 2);
+    assert_eq!(pings.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn test_replication_group_rejects_unix_endpoints() {
+    let result = ReplicationGroupClient::builder("redis+unix:///tmp/redis.sock").build();
+    assert_eq!(result.err().unwrap().kind(), ErrorKind::InvalidClientConfig);
+}
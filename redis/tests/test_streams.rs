@@ -1,7 +1,7 @@
 #![cfg(feature = "streams")]
 
 use redis::streams::*;
-use redis::{Commands, Connection, RedisResult, ToRedisArgs};
+use redis::{from_redis_value, Commands, Connection, RedisResult, ToRedisArgs, Value};
 
 mod support;
 use crate::support::*;
@@ -88,6 +88,63 @@ fn test_cmd_options() {
     let opts = StreamReadOptions::default().noack().block(100).count(200);
 
     assert_args!(&opts, "BLOCK", "100", "COUNT", "200");
+
+    // test autoclaim options
+
+    let empty = StreamAutoClaimOptions::default();
+    assert_eq!(ToRedisArgs::to_redis_args(&empty).len(), 0);
+
+    let opts = StreamAutoClaimOptions::default().count(5).with_justid();
+
+    assert_args!(&opts, "COUNT", "5", "JUSTID");
+}
+
+#[test]
+fn test_autoclaim_reply_parsing() {
+    let entry = |id: &str| {
+        Value::Array(vec![
+            Value::BulkString(id.as_bytes().to_vec()),
+            Value::Array(vec![
+                Value::BulkString(b"h".to_vec()),
+                Value::BulkString(b"w".to_vec()),
+            ]),
+        ])
+    };
+
+    // Redis 7.0 and above also return the deleted ids
+    let reply: StreamAutoClaimReply = from_redis_value(&Value::Array(vec![
+        Value::BulkString(b"0-0".to_vec()),
+        Value::Array(vec![entry("1-0"), entry("2-0")]),
+        Value::Array(vec![Value::BulkString(b"3-0".to_vec())]),
+    ]))
+    .unwrap();
+    assert_eq!(reply.next_stream_id, "0-0");
+    assert_eq!(
+        reply.claimed.iter().map(|e| &e.id[..]).collect::<Vec<_>>(),
+        vec!["1-0", "2-0"]
+    );
+    assert_eq!(reply.claimed[0].get::<String>("h").unwrap(), "w");
+    assert_eq!(reply.deleted_ids, vec!["3-0"]);
+
+    // Redis 6.2 returns deleted entries as nil
+    let reply: StreamAutoClaimReply = from_redis_value(&Value::Array(vec![
+        Value::BulkString(b"4-0".to_vec()),
+        Value::Array(vec![entry("1-0"), Value::Nil]),
+    ]))
+    .unwrap();
+    assert_eq!(reply.next_stream_id, "4-0");
+    assert_eq!(reply.claimed.len(), 1);
+    assert!(reply.deleted_ids.is_empty());
+
+    // JUSTID only returns the ids
+    let reply: StreamAutoClaimReply = from_redis_value(&Value::Array(vec![
+        Value::BulkString(b"0-0".to_vec()),
+        Value::Array(vec![Value::BulkString(b"1-0".to_vec())]),
+        Value::Array(vec![]),
+    ]))
+    .unwrap();
+    assert_eq!(reply.claimed[0].id, "1-0");
+    assert!(reply.claimed[0].is_empty());
 }
 
 #[test]
@@ -492,6 +549,117 @@ fn test_xclaim() {
     assert_eq!(claimed.len(), 10);
 }
 
+#[test]
+fn test_xautoclaim() {
+    // Tests the following commands....
+    // xautoclaim_options
+    let ctx = TestContext::new();
+    if ctx.get_version() < (6, 2, 0) {
+        return;
+    }
+    let mut con = ctx.connection();
+
+    let result: RedisResult<String> = con.xgroup_create_mkstream("k1", "g1", "$");
+    assert!(result.is_ok());
+    xadd_keyrange(&mut con, "k1", 0, 10);
+
+    // check out all the entries with c1, without acking them
+    let read: StreamReadReply = con
+        .xread_options(
+            &["k1"],
+            &[">"],
+            &StreamReadOptions::default().group("g1", "c1"),
+        )
+        .unwrap();
+    assert_eq!(read.keys[0].ids.len(), 10);
+
+    sleep(Duration::from_millis(5));
+
+    // claim the stale entries in two batches
+    let reply: StreamAutoClaimReply = con
+        .xautoclaim_options(
+            "k1",
+            "g1",
+            "c2",
+            4,
+            "0-0",
+            StreamAutoClaimOptions::default().count(6),
+        )
+        .unwrap();
+    assert_eq!(reply.claimed.len(), 6);
+    assert_eq!(reply.claimed[0].id, read.keys[0].ids[0].id);
+    assert_eq!(reply.next_stream_id, read.keys[0].ids[6].id);
+
+    let claimed: Vec<Value> = con
+        .xautoclaim_options(
+            "k1",
+            "g1",
+            "c2",
+            4,
+            &reply.next_stream_id,
+            StreamAutoClaimOptions::default().count(6).with_justid(),
+        )
+        .unwrap();
+    let reply: StreamAutoClaimReply = from_redis_value(&Value::Array(claimed)).unwrap();
+    assert_eq!(reply.claimed.len(), 4);
+    assert_eq!(reply.next_stream_id, "0-0");
+
+    // all the entries are now pending for c2
+    let reply: StreamPendingReply = con.xpending("k1", "g1").unwrap();
+    if let StreamPendingReply::Data(data) = reply {
+        assert_eq!(data.consumers.len(), 1);
+        assert_eq!(data.consumers[0].name, "c2");
+        assert_eq!(data.consumers[0].pending, 10);
+    }
+}
+
+#[test]
+#[cfg(feature = "tokio-comp")]
+fn test_stream_worker_steals_stale_entries() {
+    let ctx = TestContext::new();
+    if ctx.get_version() < (6, 2, 0) {
+        return;
+    }
+    let mut con = ctx.connection();
+
+    let result: RedisResult<String> = con.xgroup_create_mkstream("k1", "g1", "$");
+    assert!(result.is_ok());
+    xadd_keyrange(&mut con, "k1", 0, 4);
+
+    // c1 checks out the entries and never acks them
+    let _: StreamReadReply = con
+        .xread_options(
+            &["k1"],
+            &[">"],
+            &StreamReadOptions::default().group("g1", "c1"),
+        )
+        .unwrap();
+    xadd_keyrange(&mut con, "k1", 0, 2);
+
+    sleep(Duration::from_millis(5));
+
+    let handled = block_on_all(async move {
+        let connection = ctx.async_connection().await.unwrap();
+        let mut worker = StreamWorker::new(
+            connection,
+            "k1",
+            "g1",
+            "c2",
+            StreamWorkerOptions::default()
+                .min_idle_time(4)
+                .block(None)
+                .concurrency(2),
+        );
+        worker.process_batch(&|_entry| async { Ok(()) }).await
+    })
+    .unwrap();
+    // the 4 stale entries of c1 were claimed, and the 2 new entries were read
+    assert_eq!(handled, 6);
+
+    let reply: StreamPendingReply = con.xpending("k1", "g1").unwrap();
+    assert_eq!(reply.count(), 0);
+}
+
 #[test]
 fn test_xdel() {
     // Tests the following commands....
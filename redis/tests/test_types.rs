@@ -77,6 +77,13 @@ mod types {
             let i = parse_mode.parse_redis_value(Value::BulkString("42".into()));
             assert_eq!(i, Ok(42i32));
 
+            let i = parse_mode.parse_redis_value(Value::BigNumber(42.into()));
+            assert_eq!(i, Ok(42i32));
+
+            let bad_i: Result<i32, _> =
+                parse_mode.parse_redis_value(Value::BigNumber(i64::MAX.into()));
+            assert_eq!(bad_i.unwrap_err().kind(), ErrorKind::TypeError);
+
             let bad_i: Result<i32, _> =
                 parse_mode.parse_redis_value(Value::SimpleString("42x".into()));
             assert_eq!(bad_i.unwrap_err().kind(), ErrorKind::TypeError);
@@ -97,6 +104,28 @@ mod types {
         }
     }
 
+    #[test]
+    fn test_error_value() {
+        use redis::{ErrorKind, RedisError, Value};
+
+        let err = RedisError::from((ErrorKind::BusyLoadingError, "Loading", "node1".to_string()));
+        for parse_mode in [RedisParseMode::Owned, RedisParseMode::Ref] {
+            let v: Result<i32, _> = parse_mode.parse_redis_value(Value::Error(err.clone()));
+            assert_eq!(v.unwrap_err().kind(), ErrorKind::BusyLoadingError);
+
+            let v: Result<Option<String>, _> =
+                parse_mode.parse_redis_value(Value::Error(err.clone()));
+            assert_eq!(v.unwrap_err().detail(), Some("node1"));
+
+            let v: Result<Vec<i32>, _> = parse_mode
+                .parse_redis_value(Value::Array(vec![Value::Int(1), Value::Error(err.clone())]));
+            assert_eq!(v.unwrap_err().kind(), ErrorKind::BusyLoadingError);
+
+            let v: Result<Value, _> = parse_mode.parse_redis_value(Value::Error(err.clone()));
+            assert_eq!(v, Ok(Value::Error(err.clone())));
+        }
+    }
+
     #[test]
     fn test_vec() {
         use redis::Value;
@@ -367,6 +396,56 @@ mod types {
         }
     }
 
+    #[cfg(feature = "error-codes")]
+    #[test]
+    fn test_error_kind_codes_are_stable() {
+        use redis::{ErrorKind, RedisError};
+
+        // The codes are part of the FFI contract, so they must never change.
+        let codes = [
+            (ErrorKind::ResponseError, 1),
+            (ErrorKind::ParseError, 2),
+            (ErrorKind::AuthenticationFailed, 3),
+            (ErrorKind::TypeError, 4),
+            (ErrorKind::ExecAbortError, 5),
+            (ErrorKind::BusyLoadingError, 6),
+            (ErrorKind::NoScriptError, 7),
+            (ErrorKind::InvalidClientConfig, 8),
+            (ErrorKind::Moved, 9),
+            (ErrorKind::Ask, 10),
+            (ErrorKind::TryAgain, 11),
+            (ErrorKind::ClusterDown, 12),
+            (ErrorKind::CrossSlot, 13),
+            (ErrorKind::MasterDown, 14),
+            (ErrorKind::IoError, 15),
+            (ErrorKind::ClientError, 16),
+            (ErrorKind::ExtensionError, 17),
+            (ErrorKind::ReadOnly, 18),
+            (ErrorKind::MasterNameNotFoundBySentinel, 19),
+            (ErrorKind::NoValidReplicasFoundBySentinel, 20),
+            (ErrorKind::EmptySentinelList, 21),
+            (ErrorKind::NotBusy, 22),
+            (ErrorKind::ClusterConnectionNotFound, 23),
+            (ErrorKind::RESP3NotSupported, 25),
+            (ErrorKind::NotAllSlotsCovered, 26),
+            (ErrorKind::DriverStopped, 27),
+            (ErrorKind::CircuitOpen, 28),
+            (ErrorKind::CommandNotAllowed, 29),
+            (ErrorKind::Timeout, 30),
+            (ErrorKind::ValueTooLarge, 31),
+        ];
+        for (kind, code) in codes {
+            assert_eq!(kind.code(), code, "{kind:?}");
+            assert_eq!(ErrorKind::from_code(code), Some(kind));
+        }
+        #[cfg(feature = "json")]
+        assert_eq!(ErrorKind::Serialize.code(), 24);
+        assert_eq!(ErrorKind::from_code(0), None);
+
+        let error = RedisError::from((ErrorKind::ClusterDown, "cluster is down"));
+        assert_eq!(error.kind_code(), 12);
+    }
+
     #[cfg(feature = "uuid")]
     #[test]
     fn test_uuid() {